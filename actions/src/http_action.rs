@@ -0,0 +1,209 @@
+use crate::{Action, ActionError, ActionResult, render_event_template};
+use engine_core::event::Event;
+use std::collections::HashMap;
+use std::time::Duration;
+use tracing::info;
+
+/// Sends an HTTP request (e.g. a webhook) when triggered
+#[derive(Debug, Clone)]
+pub struct HttpAction {
+    pub url: String,
+    pub method: String,
+    pub headers: HashMap<String, String>,
+    /// Request body; placeholders such as `{source}` and `{kind}` are filled in by
+    /// [`render_event_template`](crate::render_event_template)
+    pub body: Option<String>,
+    pub timeout: Duration,
+}
+
+impl HttpAction {
+    pub fn new(url: impl Into<String>) -> Self {
+        Self {
+            url: url.into(),
+            method: "POST".to_string(),
+            headers: HashMap::new(),
+            body: None,
+            timeout: Duration::from_secs(10),
+        }
+    }
+
+    pub fn with_method(mut self, method: impl Into<String>) -> Self {
+        self.method = method.into();
+        self
+    }
+
+    pub fn with_header(mut self, key: impl Into<String>, value: impl Into<String>) -> Self {
+        self.headers.insert(key.into(), value.into());
+        self
+    }
+
+    pub fn with_headers(mut self, headers: HashMap<String, String>) -> Self {
+        self.headers.extend(headers);
+        self
+    }
+
+    pub fn with_body(mut self, body: impl Into<String>) -> Self {
+        self.body = Some(body.into());
+        self
+    }
+
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    fn send(&self, body: Option<String>) -> Result<ActionResult, ActionError> {
+        let method =
+            reqwest::Method::from_bytes(self.method.to_uppercase().as_bytes()).map_err(|_| {
+                ActionError::Configuration(format!("Invalid HTTP method: {}", self.method))
+            })?;
+
+        let client = reqwest::blocking::Client::builder()
+            .timeout(self.timeout)
+            .build()
+            .map_err(|e| ActionError::Execution(format!("Failed to create HTTP client: {}", e)))?;
+
+        let mut request = client.request(method, &self.url);
+        for (key, value) in &self.headers {
+            request = request.header(key, value);
+        }
+        if let Some(body) = body {
+            request = request.body(body);
+        }
+
+        let response = request.send().map_err(|e| {
+            if e.is_timeout() {
+                ActionError::Timeout
+            } else {
+                ActionError::Execution(format!("HTTP request to {} failed: {}", self.url, e))
+            }
+        })?;
+
+        let status = response.status();
+        if status.is_success() {
+            info!("HTTP {} {} -> {}", self.method, self.url, status);
+            Ok(ActionResult::Success {
+                message: Some(format!("HTTP {}", status.as_u16())),
+            })
+        } else {
+            let text = response.text().unwrap_or_default();
+            Err(ActionError::Execution(format!(
+                "HTTP {} {} returned {}: {}",
+                self.method, self.url, status, text
+            )))
+        }
+    }
+}
+
+impl Action for HttpAction {
+    fn execute(&self, event: &Event) -> Result<ActionResult, ActionError> {
+        let body = self
+            .body
+            .as_ref()
+            .map(|template| render_event_template(template, event));
+
+        // reqwest's blocking client owns its own runtime and panics if used from
+        // inside a tokio worker, so run the request on a plain thread
+        let action = self.clone();
+        std::thread::spawn(move || action.send(body))
+            .join()
+            .map_err(|_| ActionError::Execution("HTTP request thread panicked".to_string()))?
+    }
+
+    fn description(&self) -> String {
+        format!("HTTP {} {}", self.method, self.url)
+    }
+
+    fn clone_box(&self) -> Box<dyn Action> {
+        Box::new(self.clone())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use engine_core::event::EventKind;
+    use std::io::{Read, Write};
+    use std::net::TcpListener;
+    use std::thread::JoinHandle;
+
+    /// Accept a single request, reply with `status_line`, and hand back the raw request
+    fn serve_once(status_line: &'static str) -> (String, JoinHandle<String>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/hook", listener.local_addr().unwrap());
+
+        let handle = std::thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut request = Vec::new();
+            let mut buf = [0u8; 1024];
+            loop {
+                let n = stream.read(&mut buf).unwrap();
+                request.extend_from_slice(&buf[..n]);
+                let text = String::from_utf8_lossy(&request);
+                if let Some(header_end) = text.find("\r\n\r\n") {
+                    let content_length = text[..header_end]
+                        .lines()
+                        .find_map(|line| {
+                            let (name, value) = line.split_once(':')?;
+                            name.eq_ignore_ascii_case("content-length")
+                                .then(|| value.trim().parse::<usize>().ok())?
+                        })
+                        .unwrap_or(0);
+                    if request.len() >= header_end + 4 + content_length {
+                        break;
+                    }
+                }
+                if n == 0 {
+                    break;
+                }
+            }
+
+            let response = format!("HTTP/1.1 {}\r\nContent-Length: 4\r\n\r\nbody", status_line);
+            stream.write_all(response.as_bytes()).unwrap();
+            String::from_utf8_lossy(&request).to_string()
+        });
+
+        (url, handle)
+    }
+
+    #[test]
+    fn test_http_action_success_with_templated_body() {
+        let (url, server) = serve_once("200 OK");
+        let action = HttpAction::new(url)
+            .with_header("X-Token", "secret")
+            .with_body(r#"{"source": "{source}", "kind": "{kind}"}"#);
+        let event = Event::new(EventKind::TimerTick, "timer_1");
+
+        let result = action.execute(&event);
+        assert!(matches!(result, Ok(ActionResult::Success { .. })));
+
+        let request = server.join().unwrap();
+        assert!(request.starts_with("POST /hook"));
+        assert!(request.to_lowercase().contains("x-token: secret"));
+        assert!(request.ends_with(r#"{"source": "timer_1", "kind": "TimerTick"}"#));
+    }
+
+    #[test]
+    fn test_http_action_server_error() {
+        let (url, server) = serve_once("500 Internal Server Error");
+        let action = HttpAction::new(url).with_method("get");
+        let event = Event::new(EventKind::TimerTick, "test");
+
+        match action.execute(&event) {
+            Err(ActionError::Execution(msg)) => assert!(msg.contains("500")),
+            other => panic!("expected execution error, got {:?}", other),
+        }
+        assert!(server.join().unwrap().starts_with("GET /hook"));
+    }
+
+    #[test]
+    fn test_http_action_invalid_method() {
+        let action = HttpAction::new("http://127.0.0.1:1/").with_method("NOT A METHOD");
+        let event = Event::new(EventKind::TimerTick, "test");
+
+        assert!(matches!(
+            action.execute(&event),
+            Err(ActionError::Configuration(_))
+        ));
+    }
+}
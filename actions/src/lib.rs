@@ -1,11 +1,20 @@
+pub mod http_action;
+pub mod notify_action;
 pub mod script_action;
 
 use engine_core::event::Event;
+use engine_core::log_rotation;
 use std::collections::HashMap;
+use std::io::Read;
 use std::path::PathBuf;
 use std::process::Stdio;
-use tracing::{error, info};
+use std::sync::{Arc, Mutex};
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
+use tracing::{error, info, warn};
 
+pub use http_action::HttpAction;
+pub use notify_action::NotifyAction;
 pub use script_action::{ScriptAction, ScriptErrorBehavior};
 
 pub trait Action: Send + Sync {
@@ -28,8 +37,17 @@ impl std::fmt::Debug for Box<dyn Action> {
 
 #[derive(Debug, Clone)]
 pub enum ActionResult {
-    Success { message: Option<String> },
-    Skipped { reason: String },
+    Success {
+        message: Option<String>,
+    },
+    Skipped {
+        reason: String,
+    },
+    /// Succeeded and asked for another registered action to run next
+    Chain {
+        message: Option<String>,
+        next_action: String,
+    },
 }
 
 #[derive(Debug, Clone)]
@@ -57,21 +75,109 @@ impl From<mlua::Error> for ActionError {
     }
 }
 
+/// Substitute event fields into a user-supplied template.
+///
+/// Supports `{timestamp}` (local time when rendered, RFC 3339), `{source}`,
+/// `{kind}` (the event type, e.g. `WindowFocused`), `{id}` and
+/// `{metadata.<key>}`; metadata keys missing from the event expand to an empty
+/// string.
+pub fn render_event_template(template: &str, event: &Event) -> String {
+    let mut rendered = String::with_capacity(template.len());
+    let mut rest = template;
+
+    // One pass over the template, so substituted values are never expanded again
+    while let Some(pos) = rest.find('{') {
+        rendered.push_str(&rest[..pos]);
+        let after = &rest[pos + 1..];
+        let Some(end) = after.find('}') else {
+            rest = &rest[pos..];
+            break;
+        };
+
+        let placeholder = &after[..end];
+        let value = match placeholder {
+            "timestamp" => Some(chrono::Local::now().to_rfc3339()),
+            "source" => Some(event.source.clone()),
+            "kind" => Some(format!("{:?}", event.kind.tag())),
+            "id" => Some(event.id.to_string()),
+            _ => placeholder
+                .strip_prefix("metadata.")
+                .map(|key| event.metadata.get(key).cloned().unwrap_or_default()),
+        };
+        match value {
+            Some(value) => {
+                rendered.push_str(&value);
+                rest = &after[end + 1..];
+            }
+            // Not a placeholder; keep the brace and carry on after it
+            None => {
+                rendered.push('{');
+                rest = after;
+            }
+        }
+    }
+
+    rendered.push_str(rest);
+    rendered
+}
+
+/// Turn an arbitrary string into a valid environment variable name
+fn sanitize_env_key(key: &str) -> String {
+    key.chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() {
+                c.to_ascii_uppercase()
+            } else {
+                '_'
+            }
+        })
+        .collect()
+}
+
+/// Environment variables describing `event`, injected into spawned commands:
+/// `WEE_EVENT_SOURCE`, `WEE_EVENT_KIND`, `WEE_EVENT_ID`, and `WEE_META_<KEY>`
+/// for each metadata entry.
+pub fn event_env_vars(event: &Event) -> HashMap<String, String> {
+    let mut vars = HashMap::new();
+    vars.insert("WEE_EVENT_SOURCE".to_string(), event.source.clone());
+    vars.insert("WEE_EVENT_KIND".to_string(), format!("{:?}", event.kind));
+    vars.insert("WEE_EVENT_ID".to_string(), event.id.to_string());
+    for (key, value) in &event.metadata {
+        vars.insert(format!("WEE_META_{}", sanitize_env_key(key)), value.clone());
+    }
+    vars
+}
+
 #[derive(Debug, Clone)]
 pub struct ExecuteAction {
     pub command: String,
     pub args: Vec<String>,
     pub working_dir: Option<PathBuf>,
+    pub env: HashMap<String, String>,
     pub timeout_seconds: Option<u64>,
+    pub max_retries: u32,
+    pub retry_backoff: Duration,
+    /// When false, stdout/stderr are discarded instead of captured
+    pub capture_output: bool,
+    /// Captured stdout/stderr beyond this many bytes is dropped
+    pub max_output_bytes: usize,
 }
 
+/// Default cap on captured stdout/stderr per stream
+pub const DEFAULT_MAX_OUTPUT_BYTES: usize = 4096;
+
 impl ExecuteAction {
     pub fn new(command: impl Into<String>) -> Self {
         Self {
             command: command.into(),
             args: Vec::new(),
             working_dir: None,
+            env: HashMap::new(),
             timeout_seconds: Some(30),
+            max_retries: 0,
+            retry_backoff: Duration::ZERO,
+            capture_output: true,
+            max_output_bytes: DEFAULT_MAX_OUTPUT_BYTES,
         }
     }
 
@@ -85,18 +191,48 @@ impl ExecuteAction {
         self
     }
 
+    /// Extra environment variables for the command; these override the injected `WEE_*` ones
+    pub fn with_env(mut self, env: HashMap<String, String>) -> Self {
+        self.env.extend(env);
+        self
+    }
+
     pub fn with_timeout(mut self, seconds: u64) -> Self {
         self.timeout_seconds = Some(seconds);
         self
     }
-}
 
-impl Action for ExecuteAction {
-    fn execute(&self, _event: &Event) -> Result<ActionResult, ActionError> {
+    /// Retry a failed run up to `count` more times, doubling `backoff` after each attempt
+    pub fn with_retries(mut self, count: u32, backoff: Duration) -> Self {
+        self.max_retries = count;
+        self.retry_backoff = backoff;
+        self
+    }
+
+    pub fn with_capture_output(mut self, capture: bool) -> Self {
+        self.capture_output = capture;
+        self
+    }
+
+    pub fn with_max_output_bytes(mut self, max_bytes: usize) -> Self {
+        self.max_output_bytes = max_bytes;
+        self
+    }
+
+    fn run_once(&self, event: &Event) -> Result<ActionResult, ActionError> {
+        let output = || {
+            if self.capture_output {
+                Stdio::piped()
+            } else {
+                Stdio::null()
+            }
+        };
         let mut cmd = std::process::Command::new(&self.command);
         cmd.args(&self.args)
-            .stdout(Stdio::piped())
-            .stderr(Stdio::piped());
+            .envs(event_env_vars(event))
+            .envs(&self.env)
+            .stdout(output())
+            .stderr(output());
 
         if let Some(ref dir) = self.working_dir {
             cmd.current_dir(dir);
@@ -104,29 +240,127 @@ impl Action for ExecuteAction {
 
         info!("Executing: {} {}", self.command, self.args.join(" "));
 
-        let output = cmd
+        let mut child = cmd
             .spawn()
-            .map_err(|e| ActionError::Execution(format!("Failed to spawn process: {}", e)))?
-            .wait_with_output()
-            .map_err(|e| ActionError::Execution(format!("Failed to wait for process: {}", e)))?;
+            .map_err(|e| ActionError::Execution(format!("Failed to spawn process: {}", e)))?;
+        let stdout_reader = read_pipe(child.stdout.take(), self.max_output_bytes);
+        let stderr_reader = read_pipe(child.stderr.take(), self.max_output_bytes);
+        let wait_error = |e: std::io::Error| {
+            ActionError::Execution(format!("Failed to wait for process: {}", e))
+        };
+
+        let status = match self.timeout_seconds {
+            Some(seconds) => {
+                let deadline = Instant::now() + Duration::from_secs(seconds);
+                loop {
+                    if let Some(status) = child.try_wait().map_err(wait_error)? {
+                        break status;
+                    }
+                    if Instant::now() >= deadline {
+                        warn!(
+                            "Command '{}' exceeded {}s timeout, killing it",
+                            self.command, seconds
+                        );
+                        let _ = child.kill();
+                        let _ = child.wait();
+                        return Err(ActionError::Timeout);
+                    }
+                    std::thread::sleep(TIMEOUT_POLL_INTERVAL);
+                }
+            }
+            None => child.wait().map_err(wait_error)?,
+        };
 
-        if output.status.success() {
-            let stdout = String::from_utf8_lossy(&output.stdout);
+        let stdout = join_pipe(stdout_reader);
+        let stderr = join_pipe(stderr_reader);
+
+        if status.success() {
             if !stdout.is_empty() {
                 info!("Command output: {}", stdout.trim());
             }
             Ok(ActionResult::Success {
-                message: Some(stdout.to_string()),
+                message: self.capture_output.then_some(stdout),
             })
         } else {
-            let stderr = String::from_utf8_lossy(&output.stderr);
             Err(ActionError::Execution(format!(
                 "Command failed with exit code {:?}: {}",
-                output.status.code(),
+                status.code(),
                 stderr
             )))
         }
     }
+}
+
+/// How often a running command is polled while waiting for it to finish or time out
+const TIMEOUT_POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+/// Drain a child's pipe on a background thread so a chatty process can't block
+/// on a full pipe. Only the first `max_bytes` are kept; the rest is counted and
+/// reported as a `...[truncated N bytes]` suffix.
+fn read_pipe<R: Read + Send + 'static>(
+    pipe: Option<R>,
+    max_bytes: usize,
+) -> Option<JoinHandle<String>> {
+    pipe.map(|mut pipe| {
+        std::thread::spawn(move || {
+            let mut kept = Vec::new();
+            let mut dropped = 0usize;
+            let mut chunk = [0u8; 8192];
+            loop {
+                let n = match pipe.read(&mut chunk) {
+                    Ok(0) | Err(_) => break,
+                    Ok(n) => n,
+                };
+                let room = max_bytes.saturating_sub(kept.len()).min(n);
+                kept.extend_from_slice(&chunk[..room]);
+                dropped += n - room;
+            }
+
+            let mut text = String::from_utf8_lossy(&kept).into_owned();
+            if dropped > 0 {
+                text.push_str(&format!("...[truncated {} bytes]", dropped));
+            }
+            text
+        })
+    })
+}
+
+fn join_pipe(reader: Option<JoinHandle<String>>) -> String {
+    reader
+        .and_then(|handle| handle.join().ok())
+        .unwrap_or_default()
+}
+
+impl Action for ExecuteAction {
+    fn execute(&self, event: &Event) -> Result<ActionResult, ActionError> {
+        let attempts = self.max_retries + 1;
+        let mut backoff = self.retry_backoff;
+        let mut attempt = 1;
+
+        loop {
+            match self.run_once(event) {
+                Ok(result) => return Ok(result),
+                Err(e) if attempt >= attempts => {
+                    if attempts == 1 {
+                        return Err(e);
+                    }
+                    return Err(ActionError::Execution(format!(
+                        "Giving up after {} attempts: {}",
+                        attempts, e
+                    )));
+                }
+                Err(e) => {
+                    warn!(
+                        "Attempt {}/{} of '{}' failed: {}; retrying in {:?}",
+                        attempt, attempts, self.command, e, backoff
+                    );
+                    std::thread::sleep(backoff);
+                    backoff = backoff.saturating_mul(2);
+                    attempt += 1;
+                }
+            }
+        }
+    }
 
     fn description(&self) -> String {
         format!("Execute: {} {}", self.command, self.args.join(" "))
@@ -158,10 +392,11 @@ impl PowerShellAction {
 }
 
 impl Action for PowerShellAction {
-    fn execute(&self, _event: &Event) -> Result<ActionResult, ActionError> {
+    fn execute(&self, event: &Event) -> Result<ActionResult, ActionError> {
         let mut cmd = std::process::Command::new("powershell.exe");
         cmd.arg("-Command")
             .arg(&self.script)
+            .envs(event_env_vars(event))
             .stdout(Stdio::piped())
             .stderr(Stdio::piped());
 
@@ -254,6 +489,109 @@ impl Action for LogAction {
     }
 }
 
+/// Appends one rendered line per event to a file. Once a line would take the
+/// file past `max_bytes` it is renamed to `path.1` (shifting older files up to
+/// `path.<max_files>`) and a new file started.
+#[derive(Debug, Clone)]
+pub struct FileAppendAction {
+    pub path: PathBuf,
+    /// Line template, rendered with [`render_event_template`]
+    pub format_template: String,
+    pub max_bytes: u64,
+    /// Rotated files to keep; 0 discards the old file on rotation
+    pub max_files: usize,
+}
+
+/// Default line written by [`FileAppendAction`]
+pub const DEFAULT_FILE_APPEND_FORMAT: &str = "{timestamp} [{source}] {kind}";
+
+/// Held while a [`FileAppendAction`] checks the size of, rotates and writes its
+/// file, so lines from concurrent dispatches never interleave and only one of
+/// them rotates
+static FILE_APPEND_LOCK: Mutex<()> = Mutex::new(());
+
+impl FileAppendAction {
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self {
+            path: path.into(),
+            format_template: DEFAULT_FILE_APPEND_FORMAT.to_string(),
+            max_bytes: log_rotation::DEFAULT_MAX_BYTES,
+            max_files: log_rotation::DEFAULT_MAX_FILES,
+        }
+    }
+
+    pub fn with_format(mut self, format_template: impl Into<String>) -> Self {
+        self.format_template = format_template.into();
+        self
+    }
+
+    pub fn with_max_bytes(mut self, max_bytes: u64) -> Self {
+        self.max_bytes = max_bytes;
+        self
+    }
+
+    pub fn with_max_files(mut self, max_files: usize) -> Self {
+        self.max_files = max_files;
+        self
+    }
+
+    fn open(&self) -> Result<std::fs::File, ActionError> {
+        std::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)
+            .map_err(|e| {
+                ActionError::Execution(format!("Failed to open {}: {}", self.path.display(), e))
+            })
+    }
+}
+
+impl Action for FileAppendAction {
+    fn execute(&self, event: &Event) -> Result<ActionResult, ActionError> {
+        use std::io::Write;
+
+        if let Some(parent) = self.path.parent()
+            && !parent.as_os_str().is_empty()
+        {
+            std::fs::create_dir_all(parent).map_err(|e| {
+                ActionError::Execution(format!(
+                    "Failed to create directory {}: {}",
+                    parent.display(),
+                    e
+                ))
+            })?;
+        }
+
+        let mut line = render_event_template(&self.format_template, event);
+        line.push('\n');
+
+        let _guard = FILE_APPEND_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+        let mut file = self.open()?;
+        let size = file.metadata().map(|m| m.len()).unwrap_or(0);
+        if log_rotation::should_rotate(size, line.len(), self.max_bytes) {
+            // Windows won't rename a file that is still open
+            drop(file);
+            log_rotation::rotate_files(&self.path, self.max_files).map_err(|e| {
+                ActionError::Execution(format!("Failed to rotate {}: {}", self.path.display(), e))
+            })?;
+            file = self.open()?;
+        }
+        file.write_all(line.as_bytes()).map_err(|e| {
+            ActionError::Execution(format!("Failed to write {}: {}", self.path.display(), e))
+        })?;
+
+        Ok(ActionResult::Success { message: None })
+    }
+
+    fn description(&self) -> String {
+        format!("Append to file: {}", self.path.display())
+    }
+
+    fn clone_box(&self) -> Box<dyn Action> {
+        Box::new(self.clone())
+    }
+}
+
 pub struct CompositeAction {
     pub actions: Vec<Box<dyn Action>>,
     pub on_error: ErrorBehavior,
@@ -281,6 +619,19 @@ pub enum ErrorBehavior {
     SkipRemaining,
 }
 
+impl std::str::FromStr for ErrorBehavior {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "continue" => Ok(ErrorBehavior::Continue),
+            "stop" => Ok(ErrorBehavior::Stop),
+            "skip_remaining" => Ok(ErrorBehavior::SkipRemaining),
+            _ => Err(format!("Invalid error behavior: {}", s)),
+        }
+    }
+}
+
 impl CompositeAction {
     pub fn new(actions: Vec<Box<dyn Action>>) -> Self {
         Self {
@@ -327,6 +678,331 @@ impl Action for CompositeAction {
     }
 }
 
+/// Runs the inner action once a burst of triggers has been quiet for
+/// `window` (trailing edge), with the event from the last trigger in the
+/// burst; each new trigger restarts the wait.
+///
+/// `execute` never runs the inner action itself and always returns
+/// `ActionResult::Skipped { reason: "debounced" }`. The deferred run happens
+/// on a background thread, where its outcome can only be logged.
+///
+/// Clones share the same timer, so one debounced action registered for
+/// several rules is debounced across all of them.
+#[derive(Debug, Clone)]
+pub struct DebounceAction {
+    pub action: Box<dyn Action>,
+    pub window: Duration,
+    pending: Arc<Mutex<Option<PendingRun>>>,
+}
+
+/// The latest trigger of a burst that is still waiting to go quiet
+#[derive(Debug)]
+struct PendingRun {
+    event: Event,
+    triggered_at: Instant,
+}
+
+impl DebounceAction {
+    pub fn new(action: Box<dyn Action>, window: Duration) -> Self {
+        Self {
+            action,
+            window,
+            pending: Arc::new(Mutex::new(None)),
+        }
+    }
+
+    /// Wait until no trigger has arrived for `window`, then run `action`
+    /// with the last one
+    fn run_when_quiet(
+        action: Box<dyn Action>,
+        window: Duration,
+        pending: Arc<Mutex<Option<PendingRun>>>,
+    ) {
+        let event = loop {
+            let mut guard = pending.lock().unwrap_or_else(|e| e.into_inner());
+            let Some(run) = guard.as_ref() else {
+                return;
+            };
+            let quiet_at = run.triggered_at + window;
+            let now = Instant::now();
+            if now >= quiet_at {
+                break guard.take().map(|run| run.event);
+            }
+            drop(guard);
+            std::thread::sleep(quiet_at - now);
+        };
+
+        let Some(event) = event else {
+            return;
+        };
+        match action.execute(&event) {
+            Ok(_) => info!("Debounced action ran: {}", action.description()),
+            Err(e) => error!("Debounced action failed: {}: {}", action.description(), e),
+        }
+    }
+}
+
+impl Action for DebounceAction {
+    fn execute(&self, event: &Event) -> Result<ActionResult, ActionError> {
+        let mut pending = self.pending.lock().unwrap_or_else(|e| e.into_inner());
+        let waiting = pending.is_some();
+        *pending = Some(PendingRun {
+            event: event.clone(),
+            triggered_at: Instant::now(),
+        });
+
+        if !waiting {
+            let action = self.action.clone();
+            let window = self.window;
+            let pending = self.pending.clone();
+            std::thread::spawn(move || Self::run_when_quiet(action, window, pending));
+        }
+
+        Ok(ActionResult::Skipped {
+            reason: "debounced".to_string(),
+        })
+    }
+
+    fn description(&self) -> String {
+        format!(
+            "{} (debounced {:?})",
+            self.action.description(),
+            self.window
+        )
+    }
+
+    fn clone_box(&self) -> Box<dyn Action> {
+        Box::new(self.clone())
+    }
+}
+
+/// Ensures the inner action never runs concurrently with itself.
+///
+/// Clones share the same lock, so the guarantee holds across every rule the
+/// action is registered for.
+#[derive(Debug, Clone)]
+pub struct SerializeAction {
+    pub action: Box<dyn Action>,
+    pub when_busy: BusyBehavior,
+    lock: Arc<Mutex<()>>,
+}
+
+/// What a [`SerializeAction`] does when triggered while already running
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BusyBehavior {
+    /// Wait for the running execution to finish, then run
+    Queue,
+    /// Return `ActionResult::Skipped` immediately
+    Skip,
+}
+
+impl SerializeAction {
+    pub fn new(action: Box<dyn Action>) -> Self {
+        Self {
+            action,
+            when_busy: BusyBehavior::Queue,
+            lock: Arc::new(Mutex::new(())),
+        }
+    }
+
+    pub fn with_busy_behavior(mut self, when_busy: BusyBehavior) -> Self {
+        self.when_busy = when_busy;
+        self
+    }
+}
+
+impl Action for SerializeAction {
+    fn execute(&self, event: &Event) -> Result<ActionResult, ActionError> {
+        let _guard = match self.when_busy {
+            BusyBehavior::Queue => self.lock.lock().unwrap_or_else(|e| e.into_inner()),
+            BusyBehavior::Skip => match self.lock.try_lock() {
+                Ok(guard) => guard,
+                Err(std::sync::TryLockError::Poisoned(e)) => e.into_inner(),
+                Err(std::sync::TryLockError::WouldBlock) => {
+                    return Ok(ActionResult::Skipped {
+                        reason: "already running".to_string(),
+                    });
+                }
+            },
+        };
+
+        self.action.execute(event)
+    }
+
+    fn description(&self) -> String {
+        format!("{} (serialized)", self.action.description())
+    }
+
+    fn clone_box(&self) -> Box<dyn Action> {
+        Box::new(self.clone())
+    }
+}
+
+/// Where a [`CircuitBreakerAction`] is in its cycle
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CircuitState {
+    /// Running the inner action normally
+    Closed,
+    /// Skipping the inner action until the cooldown has passed
+    Open,
+    /// Cooldown over; the next trigger runs the inner action as a trial
+    HalfOpen,
+}
+
+#[derive(Debug)]
+struct Breaker {
+    consecutive_failures: u32,
+    opened_at: Option<Instant>,
+    cooldown: Duration,
+    trial_running: bool,
+}
+
+/// Stops running the inner action after `failure_threshold` consecutive
+/// failures, skipping it for `cooldown`. After that one trial run is allowed:
+/// success closes the circuit, failure opens it again for twice as long, up
+/// to `max_cooldown`.
+///
+/// Clones share the same state, so the breaker trips across every rule the
+/// action is registered for.
+#[derive(Debug, Clone)]
+pub struct CircuitBreakerAction {
+    pub action: Box<dyn Action>,
+    pub failure_threshold: u32,
+    pub cooldown: Duration,
+    pub max_cooldown: Duration,
+    state: Arc<Mutex<Breaker>>,
+}
+
+impl CircuitBreakerAction {
+    pub fn new(action: Box<dyn Action>) -> Self {
+        let cooldown = Duration::from_secs(30);
+        Self {
+            action,
+            failure_threshold: 5,
+            cooldown,
+            max_cooldown: Duration::from_secs(600),
+            state: Arc::new(Mutex::new(Breaker {
+                consecutive_failures: 0,
+                opened_at: None,
+                cooldown,
+                trial_running: false,
+            })),
+        }
+    }
+
+    pub fn with_failure_threshold(mut self, failure_threshold: u32) -> Self {
+        self.failure_threshold = failure_threshold.max(1);
+        self
+    }
+
+    pub fn with_cooldown(mut self, cooldown: Duration) -> Self {
+        self.cooldown = cooldown;
+        self.state
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .cooldown = cooldown;
+        self
+    }
+
+    pub fn with_max_cooldown(mut self, max_cooldown: Duration) -> Self {
+        self.max_cooldown = max_cooldown;
+        self
+    }
+
+    pub fn state(&self) -> CircuitState {
+        let breaker = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        match breaker.opened_at {
+            None => CircuitState::Closed,
+            Some(opened_at) if opened_at.elapsed() < breaker.cooldown => CircuitState::Open,
+            Some(_) => CircuitState::HalfOpen,
+        }
+    }
+}
+
+/// Ends a half-open trial even if the inner action panics, which then counts
+/// as a failed trial; otherwise the circuit would stay open for good
+struct TrialGuard<'a>(&'a Mutex<Breaker>);
+
+impl Drop for TrialGuard<'_> {
+    fn drop(&mut self) {
+        let mut breaker = self.0.lock().unwrap_or_else(|e| e.into_inner());
+        breaker.trial_running = false;
+        if std::thread::panicking() {
+            breaker.opened_at = Some(Instant::now());
+        }
+    }
+}
+
+impl Action for CircuitBreakerAction {
+    fn execute(&self, event: &Event) -> Result<ActionResult, ActionError> {
+        let trial = {
+            let mut breaker = self.state.lock().unwrap_or_else(|e| e.into_inner());
+            match breaker.opened_at {
+                None => false,
+                Some(opened_at) => {
+                    // Only one trial at a time; everything else waits for its outcome
+                    if opened_at.elapsed() < breaker.cooldown || breaker.trial_running {
+                        return Ok(ActionResult::Skipped {
+                            reason: "circuit open".to_string(),
+                        });
+                    }
+                    breaker.trial_running = true;
+                    true
+                }
+            }
+        };
+
+        let trial_guard = trial.then(|| TrialGuard(&self.state));
+        let result = self.action.execute(event);
+        drop(trial_guard);
+
+        let mut breaker = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        match &result {
+            Ok(_) => {
+                if trial {
+                    info!("Circuit for {} closed", self.action.description());
+                }
+                breaker.consecutive_failures = 0;
+                breaker.opened_at = None;
+                breaker.cooldown = self.cooldown;
+            }
+            Err(_) if trial => {
+                breaker.cooldown = breaker.cooldown.saturating_mul(2).min(self.max_cooldown);
+                breaker.opened_at = Some(Instant::now());
+                warn!(
+                    "Trial of {} failed, circuit open for {:?}",
+                    self.action.description(),
+                    breaker.cooldown
+                );
+            }
+            Err(_) => {
+                breaker.consecutive_failures += 1;
+                if breaker.opened_at.is_none()
+                    && breaker.consecutive_failures >= self.failure_threshold
+                {
+                    breaker.opened_at = Some(Instant::now());
+                    warn!(
+                        "{} failed {} times in a row, circuit open for {:?}",
+                        self.action.description(),
+                        breaker.consecutive_failures,
+                        breaker.cooldown
+                    );
+                }
+            }
+        }
+
+        result
+    }
+
+    fn description(&self) -> String {
+        format!("{} (circuit breaker)", self.action.description())
+    }
+
+    fn clone_box(&self) -> Box<dyn Action> {
+        Box::new(self.clone())
+    }
+}
+
 pub struct ActionExecutor {
     actions: HashMap<String, Box<dyn Action>>,
 }
@@ -342,6 +1018,27 @@ impl ActionExecutor {
         self.actions.insert(name.into(), action);
     }
 
+    /// Remove a registered action, returning whether it existed
+    pub fn unregister(&mut self, name: &str) -> bool {
+        self.actions.remove(name).is_some()
+    }
+
+    pub fn contains(&self, name: &str) -> bool {
+        self.actions.contains_key(name)
+    }
+
+    /// Description of a registered action, if there is one
+    pub fn description(&self, name: &str) -> Option<String> {
+        self.actions.get(name).map(|action| action.description())
+    }
+
+    /// Names of all registered actions, sorted
+    pub fn list(&self) -> Vec<String> {
+        let mut names: Vec<String> = self.actions.keys().cloned().collect();
+        names.sort();
+        names
+    }
+
     pub fn execute(&self, name: &str, event: &Event) -> Result<ActionResult, ActionError> {
         match self.actions.get(name) {
             Some(action) => action.execute(event),
@@ -399,6 +1096,543 @@ mod tests {
         }
     }
 
+    #[cfg(unix)]
+    #[test]
+    fn test_execute_action_retries_until_success() {
+        let dir = tempfile::tempdir().unwrap();
+        let sentinel = dir.path().join("ready");
+        let script = format!(
+            "if [ -f '{0}' ]; then echo ok; else touch '{0}'; exit 1; fi",
+            sentinel.display()
+        );
+        let event = Event::new(EventKind::TimerTick, "test");
+
+        let no_retry = ExecuteAction::new("sh").with_args(vec!["-c".to_string(), script.clone()]);
+        assert!(no_retry.execute(&event).is_err());
+        std::fs::remove_file(&sentinel).unwrap();
+
+        let action = no_retry.with_retries(2, Duration::from_millis(10));
+        let result = action.execute(&event);
+        assert!(matches!(result, Ok(ActionResult::Success { .. })));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_execute_action_reports_attempts() {
+        let action = ExecuteAction::new("sh")
+            .with_args(vec!["-c".to_string(), "exit 3".to_string()])
+            .with_retries(2, Duration::from_millis(1));
+        let event = Event::new(EventKind::TimerTick, "test");
+
+        match action.execute(&event) {
+            Err(ActionError::Execution(msg)) => assert!(msg.contains("after 3 attempts")),
+            other => panic!("expected execution error, got {:?}", other),
+        }
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_execute_action_timeout_kills_command() {
+        let action = ExecuteAction::new("sleep")
+            .with_args(vec!["10".to_string()])
+            .with_timeout(1);
+        let event = Event::new(EventKind::TimerTick, "test");
+
+        let started = Instant::now();
+        let result = action.execute(&event);
+        assert!(matches!(result, Err(ActionError::Timeout)));
+        assert!(started.elapsed() < Duration::from_secs(5));
+    }
+
+    #[test]
+    fn test_render_event_template() {
+        let event = Event::new(EventKind::TimerTick, "timer_1").with_metadata("user", "alice");
+
+        assert_eq!(
+            render_event_template("{kind} from {source} by {metadata.user}", &event),
+            "TimerTick from timer_1 by alice"
+        );
+        assert_eq!(render_event_template("[{metadata.missing}]", &event), "[]");
+        assert_eq!(render_event_template("{id}", &event), event.id.to_string());
+
+        // Only the event type, not its fields
+        let focused = Event::new(
+            EventKind::WindowFocused {
+                hwnd: 1,
+                title: "Notepad".to_string(),
+            },
+            "windows",
+        );
+        assert_eq!(render_event_template("{kind}", &focused), "WindowFocused");
+
+        // Substituted values are never expanded again
+        let looping = Event::new(EventKind::TimerTick, "{metadata.title}")
+            .with_metadata("title", "{metadata.title} - Notepad");
+        assert_eq!(
+            render_event_template("{metadata.title}|{source}", &looping),
+            "{metadata.title} - Notepad|{metadata.title}"
+        );
+        assert_eq!(
+            render_event_template("{not a placeholder} {source", &looping),
+            "{not a placeholder} {source"
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_execute_action_truncates_large_output() {
+        let action = ExecuteAction::new("sh")
+            .with_args(vec![
+                "-c".to_string(),
+                "head -c 100000 /dev/zero | tr '\\0' 'a'".to_string(),
+            ])
+            .with_max_output_bytes(1024);
+        let event = Event::new(EventKind::TimerTick, "test");
+
+        match action.execute(&event) {
+            Ok(ActionResult::Success { message: Some(msg) }) => {
+                assert!(msg.starts_with(&"a".repeat(1024)));
+                assert!(msg.ends_with("...[truncated 98976 bytes]"));
+                assert_eq!(msg.len(), 1024 + "...[truncated 98976 bytes]".len());
+            }
+            other => panic!("expected truncated output, got {:?}", other),
+        }
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_execute_action_without_capture() {
+        let action = ExecuteAction::new("sh")
+            .with_args(vec!["-c".to_string(), "echo ignored".to_string()])
+            .with_capture_output(false);
+        let event = Event::new(EventKind::TimerTick, "test");
+
+        assert!(matches!(
+            action.execute(&event),
+            Ok(ActionResult::Success { message: None })
+        ));
+    }
+
+    #[test]
+    fn test_event_env_vars_sanitizes_keys() {
+        let event = Event::new(EventKind::TimerTick, "timer_1")
+            .with_metadata("process_name", "notepad.exe")
+            .with_metadata("file.path-full", "C:/a.txt");
+
+        let vars = event_env_vars(&event);
+        assert_eq!(vars["WEE_EVENT_SOURCE"], "timer_1");
+        assert_eq!(vars["WEE_EVENT_KIND"], "TimerTick");
+        assert_eq!(vars["WEE_META_PROCESS_NAME"], "notepad.exe");
+        assert_eq!(vars["WEE_META_FILE_PATH_FULL"], "C:/a.txt");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_execute_action_injects_env() {
+        let mut env = HashMap::new();
+        env.insert("CUSTOM_VAR".to_string(), "custom".to_string());
+        let action = ExecuteAction::new("sh")
+            .with_args(vec![
+                "-c".to_string(),
+                "echo \"$WEE_EVENT_SOURCE|$WEE_META_PROCESS_NAME|$CUSTOM_VAR\"".to_string(),
+            ])
+            .with_env(env);
+        let event = Event::new(EventKind::TimerTick, "timer_1")
+            .with_metadata("process_name", "notepad.exe");
+
+        match action.execute(&event) {
+            Ok(ActionResult::Success { message: Some(msg) }) => {
+                assert_eq!(msg.trim(), "timer_1|notepad.exe|custom");
+            }
+            other => panic!("expected output, got {:?}", other),
+        }
+    }
+
+    /// Records the source of every event it actually ran for
+    #[derive(Debug, Clone, Default)]
+    struct RecordingAction {
+        runs: Arc<Mutex<Vec<String>>>,
+    }
+
+    impl RecordingAction {
+        fn runs(&self) -> Vec<String> {
+            self.runs.lock().unwrap().clone()
+        }
+
+        /// Wait (up to a few seconds) for the action to have run `count` times
+        fn wait_for_runs(&self, count: usize) -> Vec<String> {
+            let deadline = Instant::now() + Duration::from_secs(5);
+            while self.runs.lock().unwrap().len() < count && Instant::now() < deadline {
+                std::thread::sleep(Duration::from_millis(10));
+            }
+            self.runs()
+        }
+    }
+
+    impl Action for RecordingAction {
+        fn execute(&self, event: &Event) -> Result<ActionResult, ActionError> {
+            self.runs.lock().unwrap().push(event.source.clone());
+            Ok(ActionResult::Success { message: None })
+        }
+
+        fn description(&self) -> String {
+            "Recording".to_string()
+        }
+
+        fn clone_box(&self) -> Box<dyn Action> {
+            Box::new(self.clone())
+        }
+    }
+
+    #[test]
+    fn test_debounce_action_within_window() {
+        let inner = RecordingAction::default();
+        let action = DebounceAction::new(Box::new(inner.clone()), Duration::from_millis(200));
+
+        for source in ["first", "second", "third"] {
+            match action.execute(&Event::new(EventKind::TimerTick, source)) {
+                Ok(ActionResult::Skipped { reason }) => assert_eq!(reason, "debounced"),
+                other => panic!("expected skip, got {:?}", other),
+            }
+        }
+        // Clones share the window
+        assert!(matches!(
+            action
+                .clone_box()
+                .execute(&Event::new(EventKind::TimerTick, "last")),
+            Ok(ActionResult::Skipped { .. })
+        ));
+        assert!(inner.runs().is_empty());
+
+        // Runs once the burst has gone quiet, with the last event only
+        assert_eq!(inner.wait_for_runs(1), vec!["last"]);
+        std::thread::sleep(Duration::from_millis(300));
+        assert_eq!(inner.runs(), vec!["last"]);
+    }
+
+    #[test]
+    fn test_debounce_action_outside_window() {
+        let inner = RecordingAction::default();
+        let action = DebounceAction::new(Box::new(inner.clone()), Duration::from_millis(50));
+
+        action
+            .execute(&Event::new(EventKind::TimerTick, "first"))
+            .unwrap();
+        assert_eq!(inner.wait_for_runs(1), vec!["first"]);
+
+        action
+            .execute(&Event::new(EventKind::TimerTick, "second"))
+            .unwrap();
+        assert_eq!(inner.wait_for_runs(2), vec!["first", "second"]);
+    }
+
+    /// Fails while `failing` is set and panics while `panicking` is set,
+    /// counting every run
+    #[derive(Debug, Clone, Default)]
+    struct FlakyAction {
+        failing: Arc<std::sync::atomic::AtomicBool>,
+        panicking: Arc<std::sync::atomic::AtomicBool>,
+        runs: Arc<std::sync::atomic::AtomicUsize>,
+    }
+
+    impl Action for FlakyAction {
+        fn execute(&self, _event: &Event) -> Result<ActionResult, ActionError> {
+            self.runs.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+            if self.panicking.load(std::sync::atomic::Ordering::SeqCst) {
+                panic!("action panicked");
+            }
+            if self.failing.load(std::sync::atomic::Ordering::SeqCst) {
+                Err(ActionError::Execution("endpoint down".to_string()))
+            } else {
+                Ok(ActionResult::Success { message: None })
+            }
+        }
+
+        fn description(&self) -> String {
+            "Flaky".to_string()
+        }
+
+        fn clone_box(&self) -> Box<dyn Action> {
+            Box::new(self.clone())
+        }
+    }
+
+    #[test]
+    fn test_circuit_breaker_transitions() {
+        let inner = FlakyAction::default();
+        inner
+            .failing
+            .store(true, std::sync::atomic::Ordering::SeqCst);
+        let action = CircuitBreakerAction::new(Box::new(inner.clone()))
+            .with_failure_threshold(3)
+            .with_cooldown(Duration::from_millis(200));
+        let event = Event::new(EventKind::TimerTick, "test");
+        let runs = || inner.runs.load(std::sync::atomic::Ordering::SeqCst);
+
+        // Closed: failures pass through until the threshold
+        for _ in 0..3 {
+            assert_eq!(action.state(), CircuitState::Closed);
+            assert!(action.execute(&event).is_err());
+        }
+
+        // Open: the inner action is not called
+        assert_eq!(action.state(), CircuitState::Open);
+        for _ in 0..5 {
+            match action.execute(&event) {
+                Ok(ActionResult::Skipped { reason }) => assert_eq!(reason, "circuit open"),
+                other => panic!("expected skip, got {:?}", other),
+            }
+        }
+        assert_eq!(runs(), 3);
+
+        // Half-open: a successful trial closes the circuit
+        std::thread::sleep(Duration::from_millis(300));
+        assert_eq!(action.state(), CircuitState::HalfOpen);
+        inner
+            .failing
+            .store(false, std::sync::atomic::Ordering::SeqCst);
+        assert!(matches!(
+            action.execute(&event),
+            Ok(ActionResult::Success { .. })
+        ));
+        assert_eq!(action.state(), CircuitState::Closed);
+        assert_eq!(runs(), 4);
+
+        // Failures are counted afresh after closing
+        inner
+            .failing
+            .store(true, std::sync::atomic::Ordering::SeqCst);
+        assert!(action.execute(&event).is_err());
+        assert_eq!(action.clone_box().description(), "Flaky (circuit breaker)");
+        assert_eq!(action.state(), CircuitState::Closed);
+    }
+
+    #[test]
+    fn test_circuit_breaker_backs_off_after_failed_trial() {
+        let inner = FlakyAction::default();
+        inner
+            .failing
+            .store(true, std::sync::atomic::Ordering::SeqCst);
+        let action = CircuitBreakerAction::new(Box::new(inner.clone()))
+            .with_failure_threshold(1)
+            .with_cooldown(Duration::from_millis(200))
+            .with_max_cooldown(Duration::from_secs(10));
+        let event = Event::new(EventKind::TimerTick, "test");
+
+        assert!(action.execute(&event).is_err());
+        std::thread::sleep(Duration::from_millis(300));
+        assert_eq!(action.state(), CircuitState::HalfOpen);
+
+        // The trial fails, so the circuit reopens for twice the cooldown
+        assert!(action.execute(&event).is_err());
+        std::thread::sleep(Duration::from_millis(200));
+        assert_eq!(action.state(), CircuitState::Open);
+        std::thread::sleep(Duration::from_millis(400));
+        assert_eq!(action.state(), CircuitState::HalfOpen);
+        assert_eq!(inner.runs.load(std::sync::atomic::Ordering::SeqCst), 2);
+    }
+
+    #[test]
+    fn test_circuit_breakers_inside_composite() {
+        let flaky = FlakyAction::default();
+        flaky
+            .failing
+            .store(true, std::sync::atomic::Ordering::SeqCst);
+        let steady = FlakyAction::default();
+        let breaker = |inner: &FlakyAction| -> Box<dyn Action> {
+            Box::new(
+                CircuitBreakerAction::new(Box::new(inner.clone()))
+                    .with_failure_threshold(2)
+                    .with_cooldown(Duration::from_secs(60)),
+            )
+        };
+        let action = CompositeAction::new(vec![breaker(&flaky), breaker(&steady)]);
+        let event = Event::new(EventKind::TimerTick, "test");
+
+        // The composite continues past the failures, but the breaker still trips
+        for _ in 0..4 {
+            assert!(action.execute(&event).is_ok());
+        }
+        assert_eq!(flaky.runs.load(std::sync::atomic::Ordering::SeqCst), 2);
+        assert_eq!(steady.runs.load(std::sync::atomic::Ordering::SeqCst), 4);
+    }
+
+    #[test]
+    fn test_circuit_breaker_survives_panicking_trial() {
+        let inner = FlakyAction::default();
+        inner
+            .failing
+            .store(true, std::sync::atomic::Ordering::SeqCst);
+        let action = CircuitBreakerAction::new(Box::new(inner.clone()))
+            .with_failure_threshold(1)
+            .with_cooldown(Duration::from_millis(100));
+        let event = Event::new(EventKind::TimerTick, "test");
+
+        assert!(action.execute(&event).is_err());
+        std::thread::sleep(Duration::from_millis(150));
+
+        // The trial panics, which reopens the circuit like a failure
+        inner
+            .panicking
+            .store(true, std::sync::atomic::Ordering::SeqCst);
+        let trial =
+            std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| action.execute(&event)));
+        assert!(trial.is_err());
+        assert_eq!(action.state(), CircuitState::Open);
+
+        // Once the cooldown has passed again, another trial is allowed
+        inner
+            .panicking
+            .store(false, std::sync::atomic::Ordering::SeqCst);
+        inner
+            .failing
+            .store(false, std::sync::atomic::Ordering::SeqCst);
+        std::thread::sleep(Duration::from_millis(150));
+        assert!(matches!(
+            action.execute(&event),
+            Ok(ActionResult::Success { .. })
+        ));
+        assert_eq!(action.state(), CircuitState::Closed);
+        assert_eq!(inner.runs.load(std::sync::atomic::Ordering::SeqCst), 3);
+    }
+
+    #[test]
+    fn test_file_append_action() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("logs").join("events.log");
+        let action = FileAppendAction::new(&path).with_format("{source} {kind} {metadata.user}");
+
+        let first = Event::new(EventKind::TimerTick, "timer_1").with_metadata("user", "alice");
+        let second = Event::new(EventKind::TimerTick, "timer_2");
+        assert!(action.execute(&first).is_ok());
+        assert!(action.execute(&second).is_ok());
+
+        let contents = std::fs::read_to_string(&path).unwrap();
+        assert_eq!(contents, "timer_1 TimerTick alice\ntimer_2 TimerTick \n");
+    }
+
+    #[test]
+    fn test_file_append_action_rotates_by_size() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("events.log");
+        // Room for two 8-byte lines per file
+        let action = FileAppendAction::new(&path)
+            .with_format("{source}")
+            .with_max_bytes(16)
+            .with_max_files(2);
+
+        for i in 1..=7 {
+            let event = Event::new(EventKind::TimerTick, format!("line_{:02}", i));
+            action.execute(&event).unwrap();
+        }
+
+        let read = |index: usize| {
+            std::fs::read_to_string(log_rotation::rotated_path(&path, index)).unwrap()
+        };
+        // The oldest file rotated away, then 2 + 2 + 1
+        assert_eq!(read(2), "line_03\nline_04\n");
+        assert_eq!(read(1), "line_05\nline_06\n");
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "line_07\n");
+        assert!(!log_rotation::rotated_path(&path, 3).exists());
+    }
+
+    #[test]
+    fn test_file_append_action_unwritable_path() {
+        let dir = tempfile::tempdir().unwrap();
+        // The target is a directory, so it can't be opened for appending
+        let action = FileAppendAction::new(dir.path());
+        let event = Event::new(EventKind::TimerTick, "test");
+
+        assert!(matches!(
+            action.execute(&event),
+            Err(ActionError::Execution(_))
+        ));
+    }
+
+    /// Tracks how many copies of itself are running at once
+    #[derive(Debug, Clone, Default)]
+    struct OverlapAction {
+        active: Arc<std::sync::atomic::AtomicUsize>,
+        max_active: Arc<std::sync::atomic::AtomicUsize>,
+        runs: Arc<std::sync::atomic::AtomicUsize>,
+    }
+
+    impl Action for OverlapAction {
+        fn execute(&self, _event: &Event) -> Result<ActionResult, ActionError> {
+            use std::sync::atomic::Ordering;
+
+            let now_active = self.active.fetch_add(1, Ordering::SeqCst) + 1;
+            self.max_active.fetch_max(now_active, Ordering::SeqCst);
+            std::thread::sleep(Duration::from_millis(20));
+            self.active.fetch_sub(1, Ordering::SeqCst);
+            self.runs.fetch_add(1, Ordering::SeqCst);
+            Ok(ActionResult::Success { message: None })
+        }
+
+        fn description(&self) -> String {
+            "Overlap".to_string()
+        }
+
+        fn clone_box(&self) -> Box<dyn Action> {
+            Box::new(self.clone())
+        }
+    }
+
+    fn run_on_threads(action: &dyn Action, threads: usize) -> Vec<ActionResult> {
+        std::thread::scope(|scope| {
+            let handles: Vec<_> = (0..threads)
+                .map(|_| {
+                    let action = action.clone_box();
+                    scope.spawn(move || {
+                        let event = Event::new(EventKind::TimerTick, "test");
+                        action.execute(&event).unwrap()
+                    })
+                })
+                .collect();
+            handles.into_iter().map(|h| h.join().unwrap()).collect()
+        })
+    }
+
+    #[test]
+    fn test_serialize_action_queues() {
+        use std::sync::atomic::Ordering;
+
+        let inner = OverlapAction::default();
+        let action = SerializeAction::new(Box::new(inner.clone()));
+
+        let results = run_on_threads(&action, 8);
+        assert!(
+            results
+                .iter()
+                .all(|r| matches!(r, ActionResult::Success { .. }))
+        );
+        assert_eq!(inner.runs.load(Ordering::SeqCst), 8);
+        assert_eq!(inner.max_active.load(Ordering::SeqCst), 1);
+    }
+
+    #[test]
+    fn test_serialize_action_skips_when_busy() {
+        use std::sync::atomic::Ordering;
+
+        let inner = OverlapAction::default();
+        let action =
+            SerializeAction::new(Box::new(inner.clone())).with_busy_behavior(BusyBehavior::Skip);
+
+        let results = run_on_threads(&action, 8);
+        let ran = results
+            .iter()
+            .filter(|r| matches!(r, ActionResult::Success { .. }))
+            .count();
+        let skipped = results
+            .iter()
+            .filter(|r| matches!(r, ActionResult::Skipped { .. }))
+            .count();
+        assert!(ran >= 1);
+        assert_eq!(ran + skipped, 8);
+        assert_eq!(inner.runs.load(Ordering::SeqCst), ran);
+        assert_eq!(inner.max_active.load(Ordering::SeqCst), 1);
+    }
+
     #[test]
     fn test_action_executor() {
         let mut executor = ActionExecutor::new();
@@ -411,4 +1645,25 @@ mod tests {
         let result = executor.execute("nonexistent", &event);
         assert!(result.is_err());
     }
+
+    #[test]
+    fn test_action_executor_management() {
+        let mut executor = ActionExecutor::new();
+        executor.register("b_log", Box::new(LogAction::new("B")));
+        executor.register("a_log", Box::new(LogAction::new("A")));
+
+        assert_eq!(executor.list(), vec!["a_log", "b_log"]);
+        assert!(executor.contains("a_log"));
+
+        assert!(executor.unregister("a_log"));
+        assert!(!executor.unregister("a_log"));
+        assert!(!executor.contains("a_log"));
+        assert_eq!(executor.list(), vec!["b_log"]);
+
+        let event = Event::new(EventKind::TimerTick, "test");
+        match executor.execute("a_log", &event) {
+            Err(ActionError::Configuration(msg)) => assert!(msg.contains("not found")),
+            other => panic!("expected not-found error, got {:?}", other),
+        }
+    }
 }
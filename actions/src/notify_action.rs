@@ -0,0 +1,159 @@
+use crate::{Action, ActionError, ActionResult, render_event_template};
+use engine_core::event::Event;
+
+/// AppUserModelID that toasts are shown under by default. Windows silently drops
+/// toasts from IDs that no Start menu shortcut registers, so this borrows the
+/// one Windows PowerShell registers for itself.
+pub const DEFAULT_APP_ID: &str =
+    r"{1AC14E77-02E7-4E5D-B744-2EB1AE5198B7}\WindowsPowerShell\v1.0\powershell.exe";
+
+/// Shows a desktop toast notification.
+///
+/// `title` and `message` accept the same placeholders as
+/// [`render_event_template`](crate::render_event_template).
+#[derive(Debug, Clone)]
+pub struct NotifyAction {
+    pub title: String,
+    pub message: String,
+    pub app_id: String,
+}
+
+impl NotifyAction {
+    pub fn new(title: impl Into<String>, message: impl Into<String>) -> Self {
+        Self {
+            title: title.into(),
+            message: message.into(),
+            app_id: DEFAULT_APP_ID.to_string(),
+        }
+    }
+
+    /// Show toasts under another AppUserModelID, which must be registered
+    pub fn with_app_id(mut self, app_id: impl Into<String>) -> Self {
+        self.app_id = app_id.into();
+        self
+    }
+
+    fn render(&self, event: &Event) -> (String, String) {
+        (
+            render_event_template(&self.title, event),
+            render_event_template(&self.message, event),
+        )
+    }
+}
+
+/// Builds the toast through the WinRT notification API. Text is passed in via
+/// environment variables so it never has to be escaped into the script.
+///
+/// `Show` doesn't fail when the toast is going to be dropped, so the notifier's
+/// setting is checked first; reading it throws for an unregistered app ID.
+#[cfg(windows)]
+const TOAST_SCRIPT: &str = r#"
+$ErrorActionPreference = 'Stop'
+[Windows.UI.Notifications.ToastNotificationManager, Windows.UI.Notifications, ContentType = WindowsRuntime] | Out-Null
+$template = [Windows.UI.Notifications.ToastNotificationManager]::GetTemplateContent([Windows.UI.Notifications.ToastTemplateType]::ToastText02)
+$text = $template.GetElementsByTagName('text')
+$text.Item(0).AppendChild($template.CreateTextNode($env:WEE_TOAST_TITLE)) | Out-Null
+$text.Item(1).AppendChild($template.CreateTextNode($env:WEE_TOAST_MESSAGE)) | Out-Null
+$toast = [Windows.UI.Notifications.ToastNotification]::new($template)
+$notifier = [Windows.UI.Notifications.ToastNotificationManager]::CreateToastNotifier($env:WEE_TOAST_APP_ID)
+if ($notifier.Setting -ne 'Enabled') {
+    throw "Notifications are $($notifier.Setting) for $env:WEE_TOAST_APP_ID"
+}
+$notifier.Show($toast)
+"#;
+
+impl Action for NotifyAction {
+    #[cfg(windows)]
+    fn execute(&self, event: &Event) -> Result<ActionResult, ActionError> {
+        use std::process::Stdio;
+
+        let (title, message) = self.render(event);
+
+        let output = std::process::Command::new("powershell.exe")
+            .args(["-NoProfile", "-NonInteractive", "-Command", TOAST_SCRIPT])
+            .env("WEE_TOAST_TITLE", &title)
+            .env("WEE_TOAST_MESSAGE", &message)
+            .env("WEE_TOAST_APP_ID", &self.app_id)
+            .stdout(Stdio::null())
+            .stderr(Stdio::piped())
+            .output()
+            .map_err(|e| {
+                ActionError::Execution(format!("Notification subsystem unavailable: {}", e))
+            })?;
+
+        if output.status.success() {
+            tracing::info!("Displayed notification: {}", title);
+            Ok(ActionResult::Success { message: None })
+        } else {
+            Err(ActionError::Execution(format!(
+                "Failed to display notification: {}",
+                String::from_utf8_lossy(&output.stderr).trim()
+            )))
+        }
+    }
+
+    #[cfg(not(windows))]
+    fn execute(&self, event: &Event) -> Result<ActionResult, ActionError> {
+        let (title, _) = self.render(event);
+        Ok(ActionResult::Skipped {
+            reason: format!(
+                "Notifications are only supported on Windows (title: {})",
+                title
+            ),
+        })
+    }
+
+    fn description(&self) -> String {
+        format!("Notify: {}", self.title)
+    }
+
+    fn clone_box(&self) -> Box<dyn Action> {
+        Box::new(self.clone())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use engine_core::event::EventKind;
+    use std::path::PathBuf;
+
+    #[test]
+    fn test_notify_action_substitutes_event_fields() {
+        let action = NotifyAction::new("New file from {source}", "{metadata.name} was created");
+        let event = Event::new(
+            EventKind::FileCreated {
+                path: PathBuf::from("report.pdf"),
+            },
+            "downloads",
+        )
+        .with_metadata("name", "report.pdf");
+
+        let (title, message) = action.render(&event);
+        assert_eq!(title, "New file from downloads");
+        assert_eq!(message, "report.pdf was created");
+        assert_eq!(action.description(), "Notify: New file from {source}");
+    }
+
+    #[test]
+    fn test_notify_action_defaults_to_registered_app_id() {
+        let action = NotifyAction::new("Title", "Message");
+        assert_eq!(action.app_id, DEFAULT_APP_ID);
+        assert!(DEFAULT_APP_ID.ends_with(r"\powershell.exe"));
+
+        let action = action.with_app_id("Contoso.Automation");
+        assert_eq!(action.app_id, "Contoso.Automation");
+    }
+
+    #[cfg(not(windows))]
+    #[test]
+    fn test_notify_action_skipped_off_windows() {
+        let action = NotifyAction::new("Title", "Message");
+        let event = Event::new(EventKind::TimerTick, "test");
+
+        assert!(matches!(
+            action.execute(&event),
+            Ok(ActionResult::Skipped { .. })
+        ));
+    }
+}
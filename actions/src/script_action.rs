@@ -1,12 +1,18 @@
 use std::fs;
 use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
 use std::time::{Duration, Instant};
 
 use crate::{Action, ActionError, ActionResult};
-use engine_core::event::Event;
-use mlua::{Lua, Table, Value};
+use dashmap::DashMap;
+use engine_core::event::{Event, EventKind};
+use mlua::{HookTriggers, Lua, Table, Value};
 use tracing::{debug, error, info, warn};
 
+/// How many Lua VM instructions run between timeout checks
+const TIMEOUT_CHECK_INSTRUCTIONS: u32 = 10_000;
+
 /// Configuration for script error handling
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum ScriptErrorBehavior {
@@ -47,10 +53,29 @@ pub struct ScriptAction {
     pub timeout_ms: u64,
     /// Error handling behavior
     pub on_error: ScriptErrorBehavior,
+    /// Directories the Lua `fs` API may touch
+    pub allowed_roots: Vec<PathBuf>,
     /// Cached script content
     script_content: String,
     /// Last modified time for hot-reload
     last_modified: std::time::SystemTime,
+    /// Sandboxed Lua state with the script loaded, reused across executions
+    state: Mutex<Option<LoadedScript>>,
+    /// Backing map for the Lua `store` API; survives script reloads
+    store: Arc<DashMap<String, serde_json::Value>>,
+}
+
+/// Deadline of the execution currently running in a Lua state, stored as app
+/// data so host functions like `sleep` can respect the action's timeout
+struct ExecutionBudget {
+    deadline: Instant,
+    timed_out: Arc<AtomicBool>,
+}
+
+/// A Lua state with the script already executed, and the file version it came from
+struct LoadedScript {
+    lua: Lua,
+    loaded_from: std::time::SystemTime,
 }
 
 impl ScriptAction {
@@ -63,10 +88,13 @@ impl ScriptAction {
         let script_content = fs::read_to_string(&script_path)
             .map_err(|e| ActionError::Execution(format!("Cannot read script: {}", e)))?;
 
+        let store = Arc::new(DashMap::new());
+
         // Validate script syntax by loading it in a temporary Lua state
         {
             let lua = Lua::new();
-            Self::setup_sandbox(&lua)?;
+            Self::setup_sandbox(&lua, &[])?;
+            Self::setup_store(&lua, &store)?;
 
             lua.load(&script_content)
                 .set_name(script_path.to_string_lossy().as_ref())
@@ -99,8 +127,11 @@ impl ScriptAction {
             function_name,
             timeout_ms: 30000, // Default 30 seconds
             on_error: ScriptErrorBehavior::default(),
+            allowed_roots: default_allowed_roots(),
             script_content,
             last_modified: metadata.modified().unwrap_or(std::time::SystemTime::now()),
+            state: Mutex::new(None),
+            store,
         })
     }
 
@@ -116,16 +147,77 @@ impl ScriptAction {
         self
     }
 
-    /// Check if script needs reload
-    fn needs_reload(&self) -> bool {
+    /// Replace the directories the Lua `fs` API may touch
+    pub fn with_allowed_roots(mut self, roots: Vec<PathBuf>) -> Self {
+        self.allowed_roots = roots;
+        self
+    }
+
+    /// Check if the script file changed after `loaded_from`
+    fn needs_reload(&self, loaded_from: std::time::SystemTime) -> bool {
         if let Ok(metadata) = fs::metadata(&self.script_path) {
             if let Ok(modified) = metadata.modified() {
-                return modified > self.last_modified;
+                return modified > loaded_from;
             }
         }
         false
     }
 
+    /// Create a sandboxed Lua state and run the script's top-level code in it
+    fn load_state(
+        &self,
+        content: &str,
+        timed_out: &Arc<AtomicBool>,
+        start: Instant,
+        timeout: Duration,
+    ) -> Result<Lua, ActionError> {
+        let lua = Lua::new();
+        Self::setup_sandbox(&lua, &self.allowed_roots)?;
+        Self::setup_store(&lua, &self.store)?;
+        Self::install_timeout_hook(&lua, timed_out, start, timeout);
+
+        lua.load(content)
+            .set_name(self.script_path.to_string_lossy().as_ref())
+            .exec()
+            .map_err(|e| {
+                if timed_out.load(Ordering::Relaxed) {
+                    ActionError::Timeout
+                } else {
+                    ActionError::Execution(format!("Failed to load script: {}", e))
+                }
+            })?;
+
+        Ok(lua)
+    }
+
+    /// Abort the script from inside the VM once it overruns `timeout`, setting
+    /// `timed_out`. This can't interrupt a blocking host call such as exec() or http.get().
+    fn install_timeout_hook(
+        lua: &Lua,
+        timed_out: &Arc<AtomicBool>,
+        start: Instant,
+        timeout: Duration,
+    ) {
+        lua.set_app_data(ExecutionBudget {
+            deadline: start + timeout,
+            timed_out: timed_out.clone(),
+        });
+
+        let timed_out = timed_out.clone();
+        lua.set_hook(
+            HookTriggers::new().every_nth_instruction(TIMEOUT_CHECK_INSTRUCTIONS),
+            move |_, _| {
+                if start.elapsed() > timeout {
+                    timed_out.store(true, Ordering::Relaxed);
+                    return Err(mlua::Error::RuntimeError(
+                        "script execution timed out".to_string(),
+                    ));
+                }
+                Ok(())
+            },
+        );
+    }
+
     /// Reload the script
     #[allow(dead_code)]
     fn reload(&mut self) -> Result<(), ActionError> {
@@ -137,7 +229,8 @@ impl ScriptAction {
         // Validate script syntax
         {
             let lua = Lua::new();
-            Self::setup_sandbox(&lua)?;
+            Self::setup_sandbox(&lua, &self.allowed_roots)?;
+            Self::setup_store(&lua, &self.store)?;
 
             lua.load(&script_content)
                 .set_name(self.script_path.to_string_lossy().as_ref())
@@ -146,6 +239,7 @@ impl ScriptAction {
         }
 
         self.script_content = script_content;
+        *self.state.get_mut().unwrap_or_else(|e| e.into_inner()) = None;
 
         // Update last modified time
         if let Ok(metadata) = fs::metadata(&self.script_path) {
@@ -159,8 +253,14 @@ impl ScriptAction {
     }
 
     /// Set up sandboxed Lua environment
-    fn setup_sandbox(lua: &Lua) -> Result<(), ActionError> {
+    fn setup_sandbox(lua: &Lua, allowed_roots: &[PathBuf]) -> Result<(), ActionError> {
         let globals = lua.globals();
+        let allowed_roots: Arc<Vec<PathBuf>> = Arc::new(
+            allowed_roots
+                .iter()
+                .filter_map(|r| resolve_path(r))
+                .collect(),
+        );
 
         // Remove dangerous functions
         globals.raw_set("dofile", Value::Nil)?;
@@ -265,98 +365,26 @@ impl ScriptAction {
         // HTTP API
         let http_table = lua.create_table()?;
 
+        http_table.raw_set(
+            "request",
+            lua.create_function(
+                |lua, (method, url, options): (String, String, Option<Table>)| {
+                    Self::http_request(lua, &method, &url, options)
+                },
+            )?,
+        )?;
+
         http_table.raw_set(
             "get",
             lua.create_function(|lua, (url, options): (String, Option<Table>)| {
-                let client = reqwest::blocking::Client::builder()
-                    .timeout(Duration::from_secs(10))
-                    .build()
-                    .map_err(|e| {
-                        mlua::Error::RuntimeError(format!("Failed to create HTTP client: {}", e))
-                    })?;
-
-                let mut request = client.get(&url);
-
-                // Add headers if provided
-                if let Some(opts) = options {
-                    if let Ok(headers_table) = opts.get::<_, Table>("headers") {
-                        for pair in headers_table.pairs::<String, String>() {
-                            let (key, value) = pair?;
-                            request = request.header(&key, &value);
-                        }
-                    }
-                }
-
-                debug!("[LUA] HTTP GET: {}", url);
-
-                match request.send() {
-                    Ok(response) => {
-                        let status = response.status().as_u16() as i32;
-                        let body = response.text().unwrap_or_default();
-
-                        let result = lua.create_table()?;
-                        result.raw_set("status", status)?;
-                        result.raw_set("body", body)?;
-                        Ok(result)
-                    }
-                    Err(e) => {
-                        error!("[LUA] HTTP GET failed: {}", e);
-                        let result = lua.create_table()?;
-                        result.raw_set("status", 0)?;
-                        result.raw_set("body", e.to_string())?;
-                        Ok(result)
-                    }
-                }
+                Self::http_request(lua, "GET", &url, options)
             })?,
         )?;
 
         http_table.raw_set(
             "post",
             lua.create_function(|lua, (url, options): (String, Option<Table>)| {
-                let client = reqwest::blocking::Client::builder()
-                    .timeout(Duration::from_secs(10))
-                    .build()
-                    .map_err(|e| {
-                        mlua::Error::RuntimeError(format!("Failed to create HTTP client: {}", e))
-                    })?;
-
-                let mut request = client.post(&url);
-
-                if let Some(opts) = options {
-                    // Add body if provided
-                    if let Ok(body) = opts.get::<_, String>("body") {
-                        request = request.body(body);
-                    }
-
-                    // Add headers if provided
-                    if let Ok(headers_table) = opts.get::<_, Table>("headers") {
-                        for pair in headers_table.pairs::<String, String>() {
-                            let (key, value) = pair?;
-                            request = request.header(&key, &value);
-                        }
-                    }
-                }
-
-                debug!("[LUA] HTTP POST: {}", url);
-
-                match request.send() {
-                    Ok(response) => {
-                        let status = response.status().as_u16() as i32;
-                        let body = response.text().unwrap_or_default();
-
-                        let result = lua.create_table()?;
-                        result.raw_set("status", status)?;
-                        result.raw_set("body", body)?;
-                        Ok(result)
-                    }
-                    Err(e) => {
-                        error!("[LUA] HTTP POST failed: {}", e);
-                        let result = lua.create_table()?;
-                        result.raw_set("status", 0)?;
-                        result.raw_set("body", e.to_string())?;
-                        Ok(result)
-                    }
-                }
+                Self::http_request(lua, "POST", &url, options)
             })?,
         )?;
 
@@ -400,11 +428,19 @@ impl ScriptAction {
         // FILE SYSTEM API (restricted)
         let fs_table = lua.create_table()?;
 
+        let roots = allowed_roots.clone();
         fs_table.raw_set(
             "file_size",
-            lua.create_function(|_, path: String| match fs::metadata(&path) {
-                Ok(metadata) => Ok(metadata.len() as i64),
-                Err(_) => Ok(-1),
+            lua.create_function(move |_, path: String| {
+                if !is_path_allowed(&path, &roots) {
+                    error!("[LUA] fs.file_size rejected: path outside allowed directories");
+                    return Ok(-1);
+                }
+
+                match fs::metadata(&path) {
+                    Ok(metadata) => Ok(metadata.len() as i64),
+                    Err(_) => Ok(-1),
+                }
             })?,
         )?;
 
@@ -425,10 +461,11 @@ impl ScriptAction {
         )?;
 
         // Restricted move operation
+        let roots = allowed_roots.clone();
         fs_table.raw_set(
             "move",
-            lua.create_function(|_, (source, dest): (String, String)| {
-                if !is_path_allowed(&source) || !is_path_allowed(&dest) {
+            lua.create_function(move |_, (source, dest): (String, String)| {
+                if !is_path_allowed(&source, &roots) || !is_path_allowed(&dest, &roots) {
                     error!("[LUA] fs.move rejected: path outside allowed directories");
                     return Ok(false);
                 }
@@ -447,10 +484,11 @@ impl ScriptAction {
         )?;
 
         // Restricted delete operation
+        let roots = allowed_roots;
         fs_table.raw_set(
             "delete",
-            lua.create_function(|_, path: String| {
-                if !is_path_allowed(&path) {
+            lua.create_function(move |_, path: String| {
+                if !is_path_allowed(&path, &roots) {
                     error!("[LUA] fs.delete rejected: path outside allowed directories");
                     return Ok(false);
                 }
@@ -488,6 +526,31 @@ impl ScriptAction {
 
         api.raw_set("os", os_table)?;
 
+        // Blocks the action thread, but never past the action's timeout
+        api.raw_set(
+            "sleep",
+            lua.create_function(|lua, ms: u64| {
+                let requested = Duration::from_millis(ms);
+                let budget = lua
+                    .app_data_ref::<ExecutionBudget>()
+                    .map(|b| (b.deadline, b.timed_out.clone()));
+
+                if let Some((deadline, timed_out)) = budget {
+                    let remaining = deadline.saturating_duration_since(Instant::now());
+                    if requested > remaining {
+                        std::thread::sleep(remaining);
+                        timed_out.store(true, Ordering::Relaxed);
+                        return Err(mlua::Error::RuntimeError(
+                            "sleep exceeded the script timeout".to_string(),
+                        ));
+                    }
+                }
+
+                std::thread::sleep(requested);
+                Ok(())
+            })?,
+        )?;
+
         // Register API in globals
         globals.raw_set("log", api.get::<_, Table>("log")?)?;
         globals.raw_set("exec", api.get::<_, Value>("exec")?)?;
@@ -495,7 +558,123 @@ impl ScriptAction {
         globals.raw_set("json", api.get::<_, Table>("json")?)?;
         globals.raw_set("fs", api.get::<_, Table>("fs")?)?;
         globals.raw_set("os", api.get::<_, Table>("os")?)?;
+        globals.raw_set("sleep", api.get::<_, Value>("sleep")?)?;
+
+        Ok(())
+    }
+
+    /// Backs `http.request`. `options` may carry `headers`, `body` and `timeout_ms`.
+    /// Transport failures are reported as status 0 with the error in `body`.
+    fn http_request<'a>(
+        lua: &'a Lua,
+        method: &str,
+        url: &str,
+        options: Option<Table>,
+    ) -> Result<Table<'a>, mlua::Error> {
+        let method = reqwest::Method::from_bytes(method.to_uppercase().as_bytes())
+            .map_err(|_| mlua::Error::RuntimeError(format!("Invalid HTTP method: {}", method)))?;
+
+        let timeout = match &options {
+            Some(opts) => opts.get::<_, Option<u64>>("timeout_ms")?,
+            None => None,
+        }
+        .map(Duration::from_millis)
+        .unwrap_or(Duration::from_secs(10));
+
+        let client = reqwest::blocking::Client::builder()
+            .timeout(timeout)
+            .build()
+            .map_err(|e| {
+                mlua::Error::RuntimeError(format!("Failed to create HTTP client: {}", e))
+            })?;
+
+        let mut request = client.request(method.clone(), url);
+
+        if let Some(opts) = options {
+            // Add body if provided
+            if let Ok(body) = opts.get::<_, String>("body") {
+                request = request.body(body);
+            }
+
+            // Add headers if provided
+            if let Ok(headers_table) = opts.get::<_, Table>("headers") {
+                for pair in headers_table.pairs::<String, String>() {
+                    let (key, value) = pair?;
+                    request = request.header(&key, &value);
+                }
+            }
+        }
+
+        debug!("[LUA] HTTP {}: {}", method, url);
+
+        let result = lua.create_table()?;
+        match request.send() {
+            Ok(response) => {
+                let headers = lua.create_table()?;
+                for (name, value) in response.headers() {
+                    headers.raw_set(name.as_str(), String::from_utf8_lossy(value.as_bytes()))?;
+                }
+
+                result.raw_set("status", response.status().as_u16() as i32)?;
+                result.raw_set("headers", headers)?;
+                result.raw_set("body", response.text().unwrap_or_default())?;
+            }
+            Err(e) => {
+                error!("[LUA] HTTP {} failed: {}", method, e);
+                result.raw_set("status", 0)?;
+                result.raw_set("headers", lua.create_table()?)?;
+                result.raw_set("body", e.to_string())?;
+            }
+        }
+        Ok(result)
+    }
+
+    /// Expose `store` as a key-value API over the action's shared map
+    fn setup_store(
+        lua: &Lua,
+        store: &Arc<DashMap<String, serde_json::Value>>,
+    ) -> Result<(), ActionError> {
+        let store_table = lua.create_table()?;
+
+        let map = store.clone();
+        store_table.raw_set(
+            "get",
+            lua.create_function(move |lua, key: String| match map.get(&key) {
+                Some(value) => Self::json_value_to_lua(lua, value.clone()),
+                None => Ok(Value::Nil),
+            })?,
+        )?;
+
+        let map = store.clone();
+        store_table.raw_set(
+            "set",
+            lua.create_function(move |_, (key, value): (String, Value)| {
+                if value.is_nil() {
+                    map.remove(&key);
+                } else {
+                    map.insert(key, Self::lua_value_to_json(value)?);
+                }
+                Ok(())
+            })?,
+        )?;
+
+        let map = store.clone();
+        store_table.raw_set(
+            "incr",
+            lua.create_function(move |_, (key, n): (String, Option<i64>)| {
+                let mut entry = map.entry(key.clone()).or_insert(serde_json::Value::from(0));
+                let current = entry.as_i64().ok_or_else(|| {
+                    mlua::Error::RuntimeError(format!("store value '{}' is not an integer", key))
+                })?;
+                let updated = current.checked_add(n.unwrap_or(1)).ok_or_else(|| {
+                    mlua::Error::RuntimeError(format!("store value '{}' would overflow", key))
+                })?;
+                *entry = serde_json::Value::from(updated);
+                Ok(updated)
+            })?,
+        )?;
 
+        lua.globals().raw_set("store", store_table)?;
         Ok(())
     }
 
@@ -583,14 +762,267 @@ impl ScriptAction {
         }
     }
 
+    /// Apply `on_error` to a script that overran its timeout
+    fn handle_timeout(&self, timeout: Duration) -> Result<ActionResult, ActionError> {
+        error!(
+            "Lua script {} exceeded timeout of {:?}",
+            self.script_path.display(),
+            timeout
+        );
+        match self.on_error {
+            ScriptErrorBehavior::Fail => Err(ActionError::Timeout),
+            ScriptErrorBehavior::Continue | ScriptErrorBehavior::Log => Ok(ActionResult::Success {
+                message: Some(format!("Timed out after {:?} but continuing", timeout)),
+            }),
+        }
+    }
+
+    /// Split an event kind into its variant name and a table of its fields
+    fn kind_to_lua<'a>(
+        lua: &'a Lua,
+        kind: &EventKind,
+    ) -> Result<(&'static str, Table<'a>), mlua::Error> {
+        let fields = lua.create_table()?;
+        let path_str = |p: &Path| p.to_string_lossy().to_string();
+
+        let kind_type = match kind {
+            EventKind::FileCreated { path } => {
+                fields.raw_set("path", path_str(path))?;
+                "FileCreated"
+            }
+            EventKind::FileModified { path } => {
+                fields.raw_set("path", path_str(path))?;
+                "FileModified"
+            }
+            EventKind::FileDeleted { path } => {
+                fields.raw_set("path", path_str(path))?;
+                "FileDeleted"
+            }
+            EventKind::FileRenamed { old_path, new_path } => {
+                fields.raw_set("old_path", path_str(old_path))?;
+                fields.raw_set("new_path", path_str(new_path))?;
+                "FileRenamed"
+            }
+            EventKind::WindowCreated {
+                hwnd,
+                title,
+                process_id,
+            } => {
+                fields.raw_set("hwnd", *hwnd as i64)?;
+                fields.raw_set("title", title.as_str())?;
+                fields.raw_set("process_id", *process_id)?;
+                "WindowCreated"
+            }
+            EventKind::WindowDestroyed { hwnd } => {
+                fields.raw_set("hwnd", *hwnd as i64)?;
+                "WindowDestroyed"
+            }
+            EventKind::WindowFocused { hwnd, title } => {
+                fields.raw_set("hwnd", *hwnd as i64)?;
+                fields.raw_set("title", title.as_str())?;
+                "WindowFocused"
+            }
+            EventKind::WindowUnfocused { hwnd, title } => {
+                fields.raw_set("hwnd", *hwnd as i64)?;
+                fields.raw_set("title", title.as_str())?;
+                "WindowUnfocused"
+            }
+            EventKind::WindowMinimized { hwnd, title } => {
+                fields.raw_set("hwnd", *hwnd as i64)?;
+                fields.raw_set("title", title.as_str())?;
+                "WindowMinimized"
+            }
+            EventKind::WindowRestored { hwnd, title } => {
+                fields.raw_set("hwnd", *hwnd as i64)?;
+                fields.raw_set("title", title.as_str())?;
+                "WindowRestored"
+            }
+            EventKind::WindowMoved {
+                hwnd,
+                x,
+                y,
+                width,
+                height,
+            } => {
+                fields.raw_set("hwnd", *hwnd as i64)?;
+                fields.raw_set("x", *x)?;
+                fields.raw_set("y", *y)?;
+                fields.raw_set("width", *width)?;
+                fields.raw_set("height", *height)?;
+                "WindowMoved"
+            }
+            EventKind::ProcessStarted {
+                pid,
+                parent_pid,
+                name,
+                path,
+                command_line,
+                session_id,
+                user,
+            } => {
+                fields.raw_set("pid", *pid)?;
+                fields.raw_set("parent_pid", *parent_pid)?;
+                fields.raw_set("name", name.as_str())?;
+                fields.raw_set("path", path.as_str())?;
+                fields.raw_set("command_line", command_line.as_str())?;
+                fields.raw_set("session_id", *session_id)?;
+                fields.raw_set("user", user.as_str())?;
+                "ProcessStarted"
+            }
+            EventKind::ProcessStopped {
+                pid,
+                name,
+                exit_code,
+            } => {
+                fields.raw_set("pid", *pid)?;
+                fields.raw_set("name", name.as_str())?;
+                fields.raw_set("exit_code", *exit_code)?;
+                "ProcessStopped"
+            }
+            EventKind::ThreadCreated {
+                pid,
+                tid,
+                start_address,
+                user_stack,
+            } => {
+                fields.raw_set("pid", *pid)?;
+                fields.raw_set("tid", *tid)?;
+                fields.raw_set("start_address", *start_address)?;
+                fields.raw_set("user_stack", user_stack.as_deref())?;
+                "ThreadCreated"
+            }
+            EventKind::ThreadDestroyed { pid, tid } => {
+                fields.raw_set("pid", *pid)?;
+                fields.raw_set("tid", *tid)?;
+                "ThreadDestroyed"
+            }
+            EventKind::FileAccessed {
+                pid,
+                path,
+                access_mask,
+            } => {
+                fields.raw_set("pid", *pid)?;
+                fields.raw_set("path", path_str(path))?;
+                fields.raw_set("access_mask", *access_mask)?;
+                "FileAccessed"
+            }
+            EventKind::FileIoRead {
+                pid,
+                path,
+                bytes_read,
+            } => {
+                fields.raw_set("pid", *pid)?;
+                fields.raw_set("path", path_str(path))?;
+                fields.raw_set("bytes_read", *bytes_read)?;
+                "FileIoRead"
+            }
+            EventKind::FileIoWrite {
+                pid,
+                path,
+                bytes_written,
+            } => {
+                fields.raw_set("pid", *pid)?;
+                fields.raw_set("path", path_str(path))?;
+                fields.raw_set("bytes_written", *bytes_written)?;
+                "FileIoWrite"
+            }
+            EventKind::FileIoDelete { pid, path } => {
+                fields.raw_set("pid", *pid)?;
+                fields.raw_set("path", path_str(path))?;
+                "FileIoDelete"
+            }
+            EventKind::NetworkConnectionCreated {
+                pid,
+                local_addr,
+                local_port,
+                remote_addr,
+                remote_port,
+                protocol,
+            } => {
+                fields.raw_set("pid", *pid)?;
+                fields.raw_set("local_addr", local_addr.as_str())?;
+                fields.raw_set("local_port", *local_port)?;
+                fields.raw_set("remote_addr", remote_addr.as_str())?;
+                fields.raw_set("remote_port", *remote_port)?;
+                fields.raw_set("protocol", format!("{:?}", protocol))?;
+                "NetworkConnectionCreated"
+            }
+            EventKind::NetworkConnectionClosed {
+                pid,
+                local_addr,
+                local_port,
+                remote_addr,
+                remote_port,
+            } => {
+                fields.raw_set("pid", *pid)?;
+                fields.raw_set("local_addr", local_addr.as_str())?;
+                fields.raw_set("local_port", *local_port)?;
+                fields.raw_set("remote_addr", remote_addr.as_str())?;
+                fields.raw_set("remote_port", *remote_port)?;
+                "NetworkConnectionClosed"
+            }
+            EventKind::RegistryChanged {
+                root,
+                key,
+                value_name,
+                change_type,
+            } => {
+                fields.raw_set("root", root.as_str())?;
+                fields.raw_set("key", key.as_str())?;
+                fields.raw_set("value_name", value_name.as_deref())?;
+                fields.raw_set("change_type", format!("{:?}", change_type))?;
+                "RegistryChanged"
+            }
+            EventKind::UserIdle { idle_seconds } => {
+                fields.raw_set("idle_seconds", *idle_seconds)?;
+                "UserIdle"
+            }
+            EventKind::UserActive => "UserActive",
+            EventKind::ClipboardChanged {
+                formats,
+                text_preview,
+            } => {
+                fields.raw_set(
+                    "formats",
+                    lua.create_sequence_from(formats.iter().cloned())?,
+                )?;
+                fields.raw_set("text_preview", text_preview.as_deref())?;
+                "ClipboardChanged"
+            }
+            EventKind::DeviceArrived {
+                device_id,
+                description,
+            } => {
+                fields.raw_set("device_id", device_id.as_str())?;
+                fields.raw_set("description", description.as_deref())?;
+                "DeviceArrived"
+            }
+            EventKind::DeviceRemoved {
+                device_id,
+                description,
+            } => {
+                fields.raw_set("device_id", device_id.as_str())?;
+                fields.raw_set("description", description.as_deref())?;
+                "DeviceRemoved"
+            }
+            EventKind::TimerTick => "TimerTick",
+        };
+
+        Ok((kind_type, fields))
+    }
+
     /// Convert Event to Lua table
     fn event_to_lua<'a>(lua: &'a Lua, event: &Event) -> Result<Table<'a>, mlua::Error> {
         let table = lua.create_table()?;
 
+        let (kind_type, fields) = Self::kind_to_lua(lua, &event.kind)?;
         table.raw_set("kind", format!("{:?}", event.kind))?;
+        table.raw_set("kind_type", kind_type)?;
+        table.raw_set("fields", fields)?;
         table.raw_set("source", event.source.clone())?;
         table.raw_set("timestamp", chrono::Local::now().to_rfc3339())?;
         table.raw_set("id", event.id.to_string())?;
+        table.raw_set("seq", event.seq)?;
 
         // Add metadata
         let metadata = lua.create_table()?;
@@ -605,31 +1037,53 @@ impl ScriptAction {
 
 impl Action for ScriptAction {
     fn execute(&self, event: &Event) -> Result<ActionResult, ActionError> {
-        // Check for hot-reload
-        if self.needs_reload() {
-            // Note: In a real implementation, we'd need mutable self
-            // For now, we'll skip reload on each execution and rely on config reload
-            debug!(
-                "Script {} changed, will reload on next config reload",
-                self.script_path.display()
-            );
-        }
-
         let start = Instant::now();
         let timeout = Duration::from_millis(self.timeout_ms);
-
-        // Create a fresh Lua state for this execution
-        let lua = Lua::new();
-        Self::setup_sandbox(&lua)?;
-
-        // Load the script
-        lua.load(&self.script_content)
-            .set_name(self.script_path.to_string_lossy().as_ref())
-            .exec()
-            .map_err(|e| ActionError::Execution(format!("Failed to load script: {}", e)))?;
+        let timed_out = Arc::new(AtomicBool::new(false));
+
+        // Reuse the loaded Lua state; only build a new one on first use or
+        // after the script file changes on disk
+        let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        match state.as_mut() {
+            None => {
+                let lua = match self.load_state(&self.script_content, &timed_out, start, timeout) {
+                    Ok(lua) => lua,
+                    Err(ActionError::Timeout) => return self.handle_timeout(timeout),
+                    Err(e) => return Err(e),
+                };
+                *state = Some(LoadedScript {
+                    lua,
+                    loaded_from: self.last_modified,
+                });
+            }
+            Some(loaded) if self.needs_reload(loaded.loaded_from) => {
+                info!("Reloading changed script: {}", self.script_path.display());
+                let modified = fs::metadata(&self.script_path)
+                    .and_then(|m| m.modified())
+                    .unwrap_or_else(|_| std::time::SystemTime::now());
+                let reloaded = fs::read_to_string(&self.script_path)
+                    .map_err(|e| ActionError::Execution(format!("Cannot read script: {}", e)))
+                    .and_then(|content| self.load_state(&content, &timed_out, start, timeout));
+
+                match reloaded {
+                    Ok(lua) => loaded.lua = lua,
+                    Err(e) => warn!(
+                        "Failed to reload {}, keeping previous version: {}",
+                        self.script_path.display(),
+                        e
+                    ),
+                }
+                // Don't retry a broken version on every event; wait for the next change
+                loaded.loaded_from = modified;
+                timed_out.store(false, Ordering::Relaxed);
+            }
+            Some(_) => {}
+        }
+        let lua = &state.as_ref().expect("script state is loaded above").lua;
+        Self::install_timeout_hook(lua, &timed_out, start, timeout);
 
         // Convert event to Lua table
-        let event_table = Self::event_to_lua(&lua, event)?;
+        let event_table = Self::event_to_lua(lua, event)?;
 
         // Get the function
         let globals = lua.globals();
@@ -647,6 +1101,10 @@ impl Action for ScriptAction {
 
         let elapsed = start.elapsed();
 
+        if timed_out.load(Ordering::Relaxed) {
+            return self.handle_timeout(timeout);
+        }
+
         match result {
             Ok(value) => {
                 debug!("Lua script executed successfully in {:?}", elapsed);
@@ -654,6 +1112,7 @@ impl Action for ScriptAction {
                 // Parse result
                 let mut success = true;
                 let mut message = String::new();
+                let mut next_action = None;
 
                 if let Value::Table(table) = value {
                     if let Ok(s) = table.get::<_, bool>("success") {
@@ -662,10 +1121,18 @@ impl Action for ScriptAction {
                     if let Ok(m) = table.get::<_, String>("message") {
                         message = m;
                     }
+                    if let Ok(n) = table.get::<_, String>("next_action") {
+                        next_action = Some(n);
+                    }
                 }
 
                 if success {
-                    if message.is_empty() {
+                    if let Some(next_action) = next_action {
+                        Ok(ActionResult::Chain {
+                            message: (!message.is_empty()).then_some(message),
+                            next_action,
+                        })
+                    } else if message.is_empty() {
                         Ok(ActionResult::Success { message: None })
                     } else {
                         Ok(ActionResult::Success {
@@ -718,6 +1185,8 @@ impl Action for ScriptAction {
             Ok(mut action) => {
                 action.timeout_ms = self.timeout_ms;
                 action.on_error = self.on_error;
+                action.allowed_roots = self.allowed_roots.clone();
+                action.store = self.store.clone();
                 Box::new(action)
             }
             Err(e) => {
@@ -727,29 +1196,58 @@ impl Action for ScriptAction {
     }
 }
 
-/// Check if a path is within allowed directories
-fn is_path_allowed(path: &str) -> bool {
-    let path = Path::new(path);
-
-    // Get allowed directories
-    let allowed_dirs = vec![
+/// Directories the Lua `fs` API may touch when none are configured:
+/// the working directory, the temp directory and `~/Documents`
+pub fn default_allowed_roots() -> Vec<PathBuf> {
+    [
         std::env::current_dir().ok(),
-        std::env::temp_dir().into(),
+        Some(std::env::temp_dir()),
         dirs::home_dir().map(|h| h.join("Documents")),
-    ];
+    ]
+    .into_iter()
+    .flatten()
+    .collect()
+}
 
-    for allowed in allowed_dirs.iter().flatten() {
-        if path.starts_with(allowed) {
-            return true;
+/// Resolve `path` the way the OS would: relative paths are taken from the
+/// working directory, and symlinks and `..` are resolved for the part of the
+/// path that exists. The non-existent remainder (e.g. a move destination) is
+/// normalized lexically.
+fn resolve_path(path: &Path) -> Option<PathBuf> {
+    use std::path::Component;
+
+    let absolute = if path.is_relative() {
+        std::env::current_dir().ok()?.join(path)
+    } else {
+        path.to_path_buf()
+    };
+
+    let mut resolved = PathBuf::new();
+    for component in absolute.components() {
+        match component {
+            Component::Prefix(_) | Component::RootDir => resolved.push(component),
+            Component::CurDir => {}
+            Component::ParentDir => {
+                resolved.pop();
+            }
+            Component::Normal(part) => {
+                resolved.push(part);
+                // Keep the existing prefix canonical so a later `..` can't walk out through a symlink
+                if let Ok(canonical) = resolved.canonicalize() {
+                    resolved = canonical;
+                }
+            }
         }
     }
+    Some(resolved)
+}
 
-    // Also allow relative paths
-    if path.is_relative() {
-        return true;
+/// Check if a path is within one of the (already resolved) allowed roots
+fn is_path_allowed(path: &str, allowed_roots: &[PathBuf]) -> bool {
+    match resolve_path(Path::new(path)) {
+        Some(resolved) => allowed_roots.iter().any(|root| resolved.starts_with(root)),
+        None => false,
     }
-
-    false
 }
 
 #[cfg(test)]
@@ -801,7 +1299,7 @@ end
     #[test]
     fn test_json_encode_decode() {
         let lua = Lua::new();
-        ScriptAction::setup_sandbox(&lua).unwrap();
+        ScriptAction::setup_sandbox(&lua, &[]).unwrap();
 
         // Test encoding
         let table = lua.create_table().unwrap();
@@ -824,4 +1322,458 @@ end
         assert_eq!(result.get::<_, String>("key").unwrap(), "value");
         assert_eq!(result.get::<_, i64>("num").unwrap(), 42);
     }
+
+    #[test]
+    fn test_script_reads_structured_fields() {
+        let mut file = NamedTempFile::new().unwrap();
+        write!(
+            file,
+            r#"
+function on_event(event)
+    if event.kind_type ~= "FileCreated" then
+        return {{success = false, message = "unexpected kind " .. event.kind_type}}
+    end
+    return {{success = true, message = event.fields.path}}
+end
+"#
+        )
+        .unwrap();
+
+        let action = ScriptAction::new(file.path().to_path_buf(), "on_event".to_string()).unwrap();
+        let event = Event::new(
+            EventKind::FileCreated {
+                path: PathBuf::from("/tmp/report.pdf"),
+            },
+            "test",
+        );
+
+        match action.execute(&event) {
+            Ok(ActionResult::Success { message }) => {
+                assert_eq!(message.as_deref(), Some("/tmp/report.pdf"));
+            }
+            other => panic!("expected success, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_event_to_lua_process_fields() {
+        let lua = Lua::new();
+        let event = Event::new(
+            EventKind::ProcessStopped {
+                pid: 4242,
+                name: "notepad.exe".to_string(),
+                exit_code: None,
+            },
+            "test",
+        );
+        let event = Event { seq: 7, ..event };
+
+        let table = ScriptAction::event_to_lua(&lua, &event).unwrap();
+        assert_eq!(table.get::<_, u64>("seq").unwrap(), 7);
+        assert_eq!(
+            table.get::<_, String>("kind_type").unwrap(),
+            "ProcessStopped"
+        );
+        let fields: Table = table.get("fields").unwrap();
+        assert_eq!(fields.get::<_, u32>("pid").unwrap(), 4242);
+        assert_eq!(fields.get::<_, String>("name").unwrap(), "notepad.exe");
+        assert_eq!(fields.get::<_, Value>("exit_code").unwrap(), Value::Nil);
+    }
+
+    fn lua_fs_delete(allowed_roots: &[PathBuf], path: &Path) -> bool {
+        let lua = Lua::new();
+        ScriptAction::setup_sandbox(&lua, allowed_roots).unwrap();
+        let fs_table: Table = lua.globals().get("fs").unwrap();
+        let delete: mlua::Function = fs_table.get("delete").unwrap();
+        delete.call(path.to_string_lossy().to_string()).unwrap()
+    }
+
+    #[test]
+    fn test_fs_allows_path_inside_root() {
+        let root = tempfile::tempdir().unwrap();
+        let file = root.path().join("scratch.txt");
+        fs::write(&file, "x").unwrap();
+
+        assert!(lua_fs_delete(&[root.path().to_path_buf()], &file));
+        assert!(!file.exists());
+    }
+
+    #[test]
+    fn test_fs_denies_absolute_path_outside_roots() {
+        let root = tempfile::tempdir().unwrap();
+        let outside = tempfile::tempdir().unwrap();
+        let file = outside.path().join("keep.txt");
+        fs::write(&file, "x").unwrap();
+
+        assert!(!lua_fs_delete(&[root.path().to_path_buf()], &file));
+        assert!(file.exists());
+    }
+
+    #[test]
+    fn test_fs_denies_traversal_out_of_root() {
+        let parent = tempfile::tempdir().unwrap();
+        let root = parent.path().join("allowed");
+        fs::create_dir(&root).unwrap();
+        let file = parent.path().join("secret.txt");
+        fs::write(&file, "x").unwrap();
+
+        let traversal = root.join("..").join("secret.txt");
+        assert!(!lua_fs_delete(std::slice::from_ref(&root), &traversal));
+        assert!(file.exists());
+
+        // `..` that stays inside the root is fine, even for paths that don't exist yet
+        let roots = [resolve_path(&root).unwrap()];
+        let inside = root.join("sub").join("..").join("new.txt");
+        assert!(is_path_allowed(&inside.to_string_lossy(), &roots));
+    }
+
+    fn busy_loop_script() -> NamedTempFile {
+        let mut file = NamedTempFile::new().unwrap();
+        write!(
+            file,
+            r#"
+function on_event(event)
+    while true do end
+end
+"#
+        )
+        .unwrap();
+        file
+    }
+
+    #[test]
+    fn test_script_timeout_aborts_busy_loop() {
+        let file = busy_loop_script();
+        let action = ScriptAction::new(file.path().to_path_buf(), "on_event".to_string())
+            .unwrap()
+            .with_timeout(100);
+        let event = Event::new(EventKind::TimerTick, "test");
+
+        let start = Instant::now();
+        let result = action.execute(&event);
+        assert!(matches!(result, Err(ActionError::Timeout)));
+        assert!(start.elapsed() < Duration::from_secs(5));
+    }
+
+    #[test]
+    fn test_script_timeout_respects_on_error() {
+        let file = busy_loop_script();
+        let action = ScriptAction::new(file.path().to_path_buf(), "on_event".to_string())
+            .unwrap()
+            .with_timeout(100)
+            .with_error_behavior(ScriptErrorBehavior::Continue);
+        let event = Event::new(EventKind::TimerTick, "test");
+
+        assert!(matches!(
+            action.execute(&event),
+            Ok(ActionResult::Success { .. })
+        ));
+    }
+
+    #[test]
+    fn test_script_next_action_is_surfaced() {
+        let mut file = NamedTempFile::new().unwrap();
+        write!(
+            file,
+            r#"
+function on_event(event)
+    return {{success = true, message = "stopped", next_action = "restart_service"}}
+end
+"#
+        )
+        .unwrap();
+
+        let action = ScriptAction::new(file.path().to_path_buf(), "on_event".to_string()).unwrap();
+        let event = Event::new(EventKind::TimerTick, "test");
+
+        match action.execute(&event) {
+            Ok(ActionResult::Chain {
+                message,
+                next_action,
+            }) => {
+                assert_eq!(message.as_deref(), Some("stopped"));
+                assert_eq!(next_action, "restart_service");
+            }
+            other => panic!("expected chained result, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_store_incr_accumulates_across_executions() {
+        let mut file = NamedTempFile::new().unwrap();
+        write!(
+            file,
+            r#"
+function on_event(event)
+    local count = store.incr("opened", 2)
+    store.set("last_source", event.source)
+    return {{success = true, message = tostring(count)}}
+end
+"#
+        )
+        .unwrap();
+
+        let action = ScriptAction::new(file.path().to_path_buf(), "on_event".to_string()).unwrap();
+        let event = Event::new(EventKind::TimerTick, "test");
+
+        assert_eq!(message_of(action.execute(&event)), "2");
+        // Drop the cached state so only the store can carry the count over
+        *action.state.lock().unwrap() = None;
+        assert_eq!(message_of(action.execute(&event)), "4");
+        assert_eq!(
+            action.store.get("last_source").unwrap().as_str(),
+            Some("test")
+        );
+
+        let clone = action.clone_box();
+        assert_eq!(message_of(clone.execute(&event)), "6");
+    }
+
+    #[test]
+    fn test_store_incr_reports_overflow() {
+        let mut file = NamedTempFile::new().unwrap();
+        write!(
+            file,
+            r#"
+function on_event(event)
+    store.set("count", math.maxinteger)
+    store.incr("count")
+    return {{success = true}}
+end
+"#
+        )
+        .unwrap();
+
+        let action = ScriptAction::new(file.path().to_path_buf(), "on_event".to_string()).unwrap();
+        let event = Event::new(EventKind::TimerTick, "test");
+
+        let error = action.execute(&event).unwrap_err();
+        assert!(error.to_string().contains("would overflow"), "{}", error);
+        // The stored value is left as it was
+        assert_eq!(action.store.get("count").unwrap().as_i64(), Some(i64::MAX));
+    }
+
+    fn sleep_script(ms: u64) -> NamedTempFile {
+        let mut file = NamedTempFile::new().unwrap();
+        write!(
+            file,
+            r#"
+function on_event(event)
+    sleep({ms})
+    return {{success = true}}
+end
+"#
+        )
+        .unwrap();
+        file
+    }
+
+    #[test]
+    fn test_sleep_blocks_for_requested_time() {
+        let file = sleep_script(100);
+        let action = ScriptAction::new(file.path().to_path_buf(), "on_event".to_string()).unwrap();
+        let event = Event::new(EventKind::TimerTick, "test");
+
+        let start = Instant::now();
+        assert!(action.execute(&event).is_ok());
+        assert!(start.elapsed() >= Duration::from_millis(100));
+    }
+
+    #[test]
+    fn test_sleep_is_bounded_by_timeout() {
+        let file = sleep_script(10_000);
+        let action = ScriptAction::new(file.path().to_path_buf(), "on_event".to_string())
+            .unwrap()
+            .with_timeout(100);
+        let event = Event::new(EventKind::TimerTick, "test");
+
+        let start = Instant::now();
+        assert!(matches!(action.execute(&event), Err(ActionError::Timeout)));
+        assert!(start.elapsed() < Duration::from_secs(5));
+    }
+
+    fn message_of(result: Result<ActionResult, ActionError>) -> String {
+        match result {
+            Ok(ActionResult::Success { message: Some(m) }) => m,
+            other => panic!("expected success with message, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_script_state_persists_between_events() {
+        let mut file = NamedTempFile::new().unwrap();
+        write!(
+            file,
+            r#"
+function on_event(event)
+    count = (count or 0) + 1
+    return {{success = true, message = tostring(count)}}
+end
+"#
+        )
+        .unwrap();
+
+        let action = ScriptAction::new(file.path().to_path_buf(), "on_event".to_string()).unwrap();
+        let event = Event::new(EventKind::TimerTick, "test");
+
+        assert_eq!(message_of(action.execute(&event)), "1");
+        assert_eq!(message_of(action.execute(&event)), "2");
+    }
+
+    #[test]
+    fn test_script_reloads_when_file_changes() {
+        let file = NamedTempFile::new().unwrap();
+        let script = |message: &str| {
+            format!(
+                "function on_event(event) return {{success = true, message = \"{}\"}} end",
+                message
+            )
+        };
+        fs::write(file.path(), script("v1")).unwrap();
+
+        let action = ScriptAction::new(file.path().to_path_buf(), "on_event".to_string()).unwrap();
+        let event = Event::new(EventKind::TimerTick, "test");
+        assert_eq!(message_of(action.execute(&event)), "v1");
+
+        fs::write(file.path(), script("v2")).unwrap();
+        let later = std::time::SystemTime::now() + Duration::from_secs(5);
+        fs::File::options()
+            .write(true)
+            .open(file.path())
+            .unwrap()
+            .set_modified(later)
+            .unwrap();
+        assert_eq!(message_of(action.execute(&event)), "v2");
+
+        // A broken edit keeps the last good version running
+        fs::write(file.path(), "function on_event(").unwrap();
+        fs::File::options()
+            .write(true)
+            .open(file.path())
+            .unwrap()
+            .set_modified(later + Duration::from_secs(5))
+            .unwrap();
+        assert_eq!(message_of(action.execute(&event)), "v2");
+    }
+
+    #[test]
+    fn test_cached_state_loads_script_once() {
+        let mut file = NamedTempFile::new().unwrap();
+        write!(
+            file,
+            r#"
+-- Top-level code runs each time the script is loaded into a fresh state
+store.incr("loads")
+
+function on_event(event)
+    return {{success = true}}
+end
+"#
+        )
+        .unwrap();
+
+        let action = ScriptAction::new(file.path().to_path_buf(), "on_event".to_string()).unwrap();
+        let event = Event::new(EventKind::TimerTick, "test");
+        let loads = || action.store.get("loads").and_then(|v| v.as_i64());
+
+        action.execute(&event).unwrap();
+        let loaded = loads().unwrap();
+        for _ in 0..5 {
+            action.execute(&event).unwrap();
+        }
+        assert_eq!(loads(), Some(loaded));
+
+        // Old behaviour: a fresh sandbox, and so a fresh load, for every event
+        for _ in 0..5 {
+            *action.state.lock().unwrap() = None;
+            action.execute(&event).unwrap();
+        }
+        assert_eq!(loads(), Some(loaded + 5));
+    }
+
+    /// Per-event latency with and without the cached state. Timing depends on
+    /// the machine, so run it on demand with `cargo test -- --ignored`.
+    #[test]
+    #[ignore]
+    fn bench_cached_state_latency() {
+        let mut file = NamedTempFile::new().unwrap();
+        write!(
+            file,
+            r#"
+function on_event(event)
+    return {{success = true}}
+end
+"#
+        )
+        .unwrap();
+
+        let action = ScriptAction::new(file.path().to_path_buf(), "on_event".to_string()).unwrap();
+        let event = Event::new(EventKind::TimerTick, "test");
+        const RUNS: u32 = 200;
+
+        // Old behaviour: a fresh sandbox for every event
+        let fresh_start = Instant::now();
+        for _ in 0..RUNS {
+            *action.state.lock().unwrap() = None;
+            action.execute(&event).unwrap();
+        }
+        let fresh = fresh_start.elapsed() / RUNS;
+
+        action.execute(&event).unwrap();
+        let cached_start = Instant::now();
+        for _ in 0..RUNS {
+            action.execute(&event).unwrap();
+        }
+        let cached = cached_start.elapsed() / RUNS;
+
+        println!("per-event latency: fresh state {fresh:?}, cached state {cached:?}");
+        assert!(cached < fresh);
+    }
+
+    #[test]
+    fn test_http_request_put_with_custom_header() {
+        use std::io::Read;
+        use std::net::TcpListener;
+
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/items/42", listener.local_addr().unwrap());
+        let server = std::thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut request = Vec::new();
+            let mut buf = [0u8; 1024];
+            while !String::from_utf8_lossy(&request).ends_with("payload") {
+                let n = stream.read(&mut buf).unwrap();
+                if n == 0 {
+                    break;
+                }
+                request.extend_from_slice(&buf[..n]);
+            }
+            stream
+                .write_all(b"HTTP/1.1 201 Created\r\nX-Reply: yes\r\nContent-Length: 2\r\n\r\nok")
+                .unwrap();
+            String::from_utf8_lossy(&request).to_string()
+        });
+
+        let lua = Lua::new();
+        ScriptAction::setup_sandbox(&lua, &[]).unwrap();
+        let response: Table = lua
+            .load(format!(
+                r#"return http.request("put", "{url}", {{
+                    headers = {{ ["X-Token"] = "abc" }},
+                    body = "payload",
+                    timeout_ms = 2000
+                }})"#
+            ))
+            .eval()
+            .unwrap();
+
+        assert_eq!(response.get::<_, i32>("status").unwrap(), 201);
+        assert_eq!(response.get::<_, String>("body").unwrap(), "ok");
+        let headers: Table = response.get("headers").unwrap();
+        assert_eq!(headers.get::<_, String>("x-reply").unwrap(), "yes");
+
+        let request = server.join().unwrap();
+        assert!(request.starts_with("PUT /items/42"));
+        assert!(request.to_lowercase().contains("x-token: abc"));
+    }
 }
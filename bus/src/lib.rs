@@ -1,24 +1,181 @@
-pub fn add(left: u64, right: u64) -> u64 {
-    left + right
+use engine_core::event::Event;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use tokio::sync::mpsc;
+use tokio::sync::mpsc::error::TrySendError;
+
+pub type EventSender = mpsc::Sender<Event>;
+pub type EventReceiver = mpsc::Receiver<Event>;
+
+pub fn create_event_bus(buffer: usize) -> (EventSender, EventReceiver) {
+    mpsc::channel(buffer)
+}
+
+struct Subscriber {
+    name: String,
+    sender: EventSender,
+    lagged: Arc<AtomicU64>,
+}
+
+/// Fan-out bus: every published event is delivered to every subscriber.
+///
+/// Each subscriber has its own bounded buffer, so a slow subscriber only loses
+/// its own events (counted in [`Subscription::lagged`]) and never holds up the
+/// others. Subscribers whose receiving end has been dropped are removed on the
+/// next publish.
+#[derive(Clone, Default)]
+pub struct EventBus {
+    subscribers: Arc<Mutex<Vec<Subscriber>>>,
+}
+
+impl EventBus {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a subscriber that buffers up to `buffer` events it has not yet received
+    pub fn subscribe(&self, name: impl Into<String>, buffer: usize) -> Subscription {
+        let name = name.into();
+        let (sender, receiver) = mpsc::channel(buffer);
+        let lagged = Arc::new(AtomicU64::new(0));
+
+        self.subscribers
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .push(Subscriber {
+                name: name.clone(),
+                sender,
+                lagged: lagged.clone(),
+            });
+
+        Subscription {
+            name,
+            receiver,
+            lagged,
+        }
+    }
+
+    /// Deliver `event` to every subscriber without waiting, returning how many
+    /// received it
+    pub fn publish(&self, event: &Event) -> usize {
+        let mut subscribers = self.subscribers.lock().unwrap_or_else(|e| e.into_inner());
+        let mut delivered = 0;
+
+        subscribers.retain(
+            |subscriber| match subscriber.sender.try_send(event.clone()) {
+                Ok(()) => {
+                    delivered += 1;
+                    true
+                }
+                Err(TrySendError::Full(_)) => {
+                    subscriber.lagged.fetch_add(1, Ordering::Relaxed);
+                    true
+                }
+                Err(TrySendError::Closed(_)) => false,
+            },
+        );
+
+        delivered
+    }
+
+    pub fn subscriber_count(&self) -> usize {
+        self.subscribers
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .len()
+    }
+
+    pub fn subscriber_names(&self) -> Vec<String> {
+        self.subscribers
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .iter()
+            .map(|subscriber| subscriber.name.clone())
+            .collect()
+    }
+}
+
+/// Receiving end of an [`EventBus`] subscription
+pub struct Subscription {
+    name: String,
+    receiver: EventReceiver,
+    lagged: Arc<AtomicU64>,
+}
+
+impl Subscription {
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Wait for the next event; `None` once the bus and all its clones are gone
+    pub async fn recv(&mut self) -> Option<Event> {
+        self.receiver.recv().await
+    }
+
+    pub fn try_recv(&mut self) -> Option<Event> {
+        self.receiver.try_recv().ok()
+    }
+
+    /// Events this subscriber missed because its buffer was full
+    pub fn lagged(&self) -> u64 {
+        self.lagged.load(Ordering::Relaxed)
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use engine_core::event::EventKind;
+
+    #[tokio::test]
+    async fn test_every_subscriber_receives_each_event() {
+        let bus = EventBus::new();
+        let mut rules = bus.subscribe("rules", 8);
+        let mut logger = bus.subscribe("logger", 8);
+
+        let event = Event::new(EventKind::TimerTick, "test");
+        assert_eq!(bus.publish(&event), 2);
+
+        assert_eq!(rules.recv().await.unwrap().id, event.id);
+        assert_eq!(logger.recv().await.unwrap().id, event.id);
+    }
 
     #[test]
-    fn it_works() {
-        let result = add(2, 2);
-        assert_eq!(result, 4);
+    fn test_slow_subscriber_lags_alone() {
+        let bus = EventBus::new();
+        let mut fast = bus.subscribe("fast", 8);
+        let mut slow = bus.subscribe("slow", 1);
+
+        for _ in 0..3 {
+            bus.publish(&Event::new(EventKind::TimerTick, "test"));
+            assert!(fast.try_recv().is_some());
+        }
+
+        assert_eq!(fast.lagged(), 0);
+        assert_eq!(slow.lagged(), 2);
+        assert!(slow.try_recv().is_some());
+        assert!(slow.try_recv().is_none());
     }
-}
 
-use engine_core::event::Event;
-use tokio::sync::mpsc;
+    #[test]
+    fn test_dropped_subscriptions_are_removed() {
+        let bus = EventBus::new();
+        let _kept = bus.subscribe("kept", 4);
+        drop(bus.subscribe("dropped", 4));
+        assert_eq!(bus.subscriber_count(), 2);
 
-pub type EventSender = mpsc::Sender<Event>;
-pub type EventReceiver = mpsc::Receiver<Event>;
+        assert_eq!(bus.publish(&Event::new(EventKind::TimerTick, "test")), 1);
+        assert_eq!(bus.subscriber_names(), vec!["kept"]);
+    }
 
-pub fn create_event_bus(buffer: usize) -> (EventSender, EventReceiver) {
-    mpsc::channel(buffer)
+    #[tokio::test]
+    async fn test_subscription_ends_with_bus() {
+        let bus = EventBus::new();
+        let mut subscription = bus.subscribe("rules", 4);
+        bus.publish(&Event::new(EventKind::TimerTick, "test"));
+        drop(bus);
+
+        assert!(subscription.recv().await.is_some());
+        assert!(subscription.recv().await.is_none());
+    }
 }
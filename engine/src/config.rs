@@ -1,33 +1,95 @@
-use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
-use std::path::PathBuf;
+use engine_core::event::EventKind;
+use engine_core::log_rotation;
+use serde::{Deserialize, Deserializer, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use tracing::warn;
+
+/// Config schema version written by, and fully understood by, this build
+pub const CONFIG_VERSION: u32 = 1;
 
 #[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct Config {
+    /// Schema version; files without one are treated as the current version
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub version: Option<u32>,
+    /// Other config files, relative to this one, whose sources, rules and
+    /// sinks are loaded ahead of this file's own
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub include: Vec<PathBuf>,
     #[serde(default)]
     pub engine: EngineConfig,
     #[serde(default)]
     pub sources: Vec<SourceConfig>,
     #[serde(default)]
     pub rules: Vec<RuleConfig>,
+    #[serde(default)]
+    pub sinks: Vec<SinkConfig>,
 }
 
 impl Default for Config {
     fn default() -> Self {
         Self {
+            version: Some(CONFIG_VERSION),
+            include: Vec::new(),
             engine: EngineConfig::default(),
             sources: Vec::new(),
             rules: Vec::new(),
+            sinks: Vec::new(),
         }
     }
 }
 
-#[derive(Debug, Clone, Deserialize, Serialize)]
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
 pub struct EngineConfig {
     #[serde(default = "default_event_buffer_size")]
     pub event_buffer_size: usize,
     #[serde(default)]
     pub log_level: String,
+    /// Whether console logs are written as plain text or JSON lines; the
+    /// `--log-format` flag takes precedence
+    #[serde(default)]
+    pub log_format: LogFormat,
+    /// Maximum number of actions allowed to run at the same time
+    #[serde(default = "default_max_concurrent_actions")]
+    pub max_concurrent_actions: usize,
+    /// How long the engine waits for any single action before reporting it
+    /// as timed out; 0 waits indefinitely
+    #[serde(default = "default_action_timeout_seconds")]
+    pub action_timeout_seconds: u64,
+    /// Only evaluate each event against rules whose trigger can match its kind
+    #[serde(default = "default_true")]
+    pub index_rules: bool,
+    /// Port for the metrics server and dashboard
+    #[serde(default = "default_metrics_port")]
+    pub metrics_port: u16,
+    /// How often sources are checked for having died; 0 turns the check off
+    #[serde(default = "default_plugin_check_interval")]
+    pub plugin_check_interval_seconds: u64,
+    /// Restart a source automatically when the check finds it has died
+    #[serde(default)]
+    pub restart_failed_plugins: bool,
+    /// Port for the local control API; it is not started when unset
+    #[serde(default)]
+    pub control_port: Option<u16>,
+    /// Failed actions kept in memory for the control API; 0 keeps none
+    #[serde(default = "default_dead_letter_capacity")]
+    pub dead_letter_capacity: usize,
+    /// File every failed action is also appended to, as JSON lines
+    #[serde(default)]
+    pub dead_letter_file: Option<PathBuf>,
+    /// Size at which the dead letter file is rotated
+    #[serde(default = "default_dead_letter_max_bytes")]
+    pub dead_letter_max_bytes: u64,
+    /// Rotated dead letter files kept alongside the current one
+    #[serde(default = "default_dead_letter_max_files")]
+    pub dead_letter_max_files: usize,
+    /// Size at which the Windows service log is rolled over
+    #[serde(default = "default_service_log_max_bytes")]
+    pub service_log_max_bytes: u64,
+    /// Rolled-over service logs kept alongside the current one
+    #[serde(default = "default_service_log_max_files")]
+    pub service_log_max_files: usize,
 }
 
 impl Default for EngineConfig {
@@ -35,15 +97,73 @@ impl Default for EngineConfig {
         Self {
             event_buffer_size: default_event_buffer_size(),
             log_level: "info".to_string(),
+            log_format: LogFormat::default(),
+            max_concurrent_actions: default_max_concurrent_actions(),
+            action_timeout_seconds: default_action_timeout_seconds(),
+            index_rules: true,
+            metrics_port: default_metrics_port(),
+            plugin_check_interval_seconds: default_plugin_check_interval(),
+            restart_failed_plugins: false,
+            control_port: None,
+            dead_letter_capacity: default_dead_letter_capacity(),
+            dead_letter_file: None,
+            dead_letter_max_bytes: default_dead_letter_max_bytes(),
+            dead_letter_max_files: default_dead_letter_max_files(),
+            service_log_max_bytes: default_service_log_max_bytes(),
+            service_log_max_files: default_service_log_max_files(),
         }
     }
 }
 
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize, clap::ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum LogFormat {
+    #[default]
+    Text,
+    Json,
+}
+
 fn default_event_buffer_size() -> usize {
     1000
 }
 
-#[derive(Debug, Clone, Deserialize, Serialize)]
+fn default_max_concurrent_actions() -> usize {
+    8
+}
+
+fn default_action_timeout_seconds() -> u64 {
+    60
+}
+
+fn default_metrics_port() -> u16 {
+    9090
+}
+
+fn default_plugin_check_interval() -> u64 {
+    30
+}
+
+fn default_dead_letter_capacity() -> usize {
+    100
+}
+
+fn default_dead_letter_max_bytes() -> u64 {
+    log_rotation::DEFAULT_MAX_BYTES
+}
+
+fn default_dead_letter_max_files() -> usize {
+    log_rotation::DEFAULT_MAX_FILES
+}
+
+fn default_service_log_max_bytes() -> u64 {
+    log_rotation::DEFAULT_MAX_BYTES
+}
+
+fn default_service_log_max_files() -> usize {
+    log_rotation::DEFAULT_MAX_FILES
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
 pub struct SourceConfig {
     pub name: String,
     #[serde(flatten)]
@@ -52,54 +172,229 @@ pub struct SourceConfig {
     pub enabled: bool,
 }
 
-#[derive(Debug, Clone, Deserialize, Serialize)]
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
 #[serde(tag = "type", rename_all = "snake_case")]
 pub enum SourceType {
     FileWatcher {
         paths: Vec<PathBuf>,
+        /// File name globs; `pattern = "*.txt"` is accepted as a single entry
+        #[serde(default, alias = "pattern", deserialize_with = "one_or_many")]
+        patterns: Vec<String>,
+        /// Globs matched against the full path; matching paths are never reported
         #[serde(default)]
-        pattern: Option<String>,
+        exclude_patterns: Vec<String>,
         #[serde(default = "default_true")]
         recursive: bool,
+        #[serde(default)]
+        follow_symlinks: bool,
+        #[serde(default = "default_file_debounce_ms")]
+        debounce_ms: u64,
     },
     WindowWatcher {
         #[serde(default)]
         title_pattern: Option<String>,
         #[serde(default)]
         process_pattern: Option<String>,
+        #[serde(default = "default_true")]
+        emit_unfocus: bool,
+        #[serde(default = "default_true")]
+        top_level_only: bool,
+        #[serde(default)]
+        track_location: bool,
+        #[serde(default = "default_move_debounce_ms")]
+        move_debounce_ms: u64,
     },
     ProcessMonitor {
         #[serde(default)]
         process_name: Option<String>,
         #[serde(default)]
+        command_line_pattern: Option<String>,
+        #[serde(default)]
         monitor_threads: bool,
         #[serde(default)]
         monitor_files: bool,
         #[serde(default)]
         monitor_network: bool,
+        #[serde(default = "default_poll_interval_seconds")]
+        poll_interval_seconds: u64,
+        #[serde(default)]
+        prefer_polling: bool,
+        #[serde(default)]
+        io_coalesce_ms: Option<u64>,
     },
     RegistryMonitor {
         root: String,
         key: String,
         #[serde(default)]
         recursive: bool,
+        #[serde(default)]
+        value_name: Option<String>,
+    },
+    IdleMonitor {
+        #[serde(default = "default_idle_threshold_seconds")]
+        threshold_seconds: u64,
+        #[serde(default = "default_idle_poll_interval_seconds")]
+        poll_interval_seconds: u64,
+    },
+    ClipboardMonitor {
+        /// Include the start of copied text in events; off so clipboard
+        /// contents aren't recorded unless asked for
+        #[serde(default)]
+        capture_text: bool,
+        #[serde(default = "default_clipboard_preview_chars")]
+        preview_chars: usize,
+    },
+    DeviceMonitor {
+        /// Only report devices whose instance id contains this, ignoring case
+        #[serde(default)]
+        device_id_filter: Option<String>,
+    },
+    Timer {
+        #[serde(default = "default_timer_interval")]
+        interval_seconds: u64,
+    },
+}
+
+/// Accepts either a single value or a list of values
+fn one_or_many<'de, D, T>(deserializer: D) -> Result<Vec<T>, D::Error>
+where
+    D: Deserializer<'de>,
+    T: Deserialize<'de>,
+{
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum OneOrMany<T> {
+        One(T),
+        Many(Vec<T>),
+    }
+
+    Ok(match OneOrMany::deserialize(deserializer)? {
+        OneOrMany::One(value) => vec![value],
+        OneOrMany::Many(values) => values,
+    })
+}
+
+/// Receives every event the engine sees, whether or not a rule matches it
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct SinkConfig {
+    pub name: String,
+    #[serde(flatten)]
+    pub sink_type: SinkType,
+    #[serde(default)]
+    pub enabled: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum SinkType {
+    /// One JSON object per line, rotated once the file reaches `max_bytes`
+    JsonlFile {
+        path: PathBuf,
+        #[serde(default = "default_sink_max_bytes")]
+        max_bytes: u64,
+        /// Rotated files kept alongside the current one
+        #[serde(default = "default_sink_max_files")]
+        max_files: usize,
     },
 }
 
+fn default_sink_max_bytes() -> u64 {
+    log_rotation::DEFAULT_MAX_BYTES
+}
+
+fn default_sink_max_files() -> usize {
+    log_rotation::DEFAULT_MAX_FILES
+}
+
 fn default_true() -> bool {
     true
 }
 
+fn default_file_debounce_ms() -> u64 {
+    300
+}
+
+fn default_move_debounce_ms() -> u64 {
+    250
+}
+
+fn default_poll_interval_seconds() -> u64 {
+    2
+}
+
+fn default_idle_threshold_seconds() -> u64 {
+    300
+}
+
+fn default_idle_poll_interval_seconds() -> u64 {
+    5
+}
+
+fn default_clipboard_preview_chars() -> usize {
+    100
+}
+
 #[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct RuleConfig {
     pub name: String,
     pub description: Option<String>,
     pub trigger: TriggerConfig,
-    pub action: ActionConfig,
+    /// Run in order on a match; `action = { ... }` is accepted as a single entry
+    #[serde(alias = "action", deserialize_with = "one_or_many")]
+    pub actions: Vec<ActionConfig>,
+    /// What a multi-action rule does when one of its actions fails:
+    /// `continue`, `stop` or `skip_remaining`
+    #[serde(default = "default_rule_on_error")]
+    pub on_error: String,
+    /// Stop running the rule's actions for a while after repeated failures
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub circuit_breaker: Option<CircuitBreakerConfig>,
+    /// Don't fire again for an event with the same signature within a window
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub dedup_window: Option<DedupConfig>,
     #[serde(default)]
     pub enabled: bool,
 }
 
+fn default_rule_on_error() -> String {
+    "continue".to_string()
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct CircuitBreakerConfig {
+    /// Consecutive failures that open the circuit
+    #[serde(default = "default_failure_threshold")]
+    pub failure_threshold: u32,
+    /// How long the circuit stays open before a trial run; doubled after each
+    /// failed trial
+    #[serde(default = "default_cooldown_seconds")]
+    pub cooldown_seconds: u64,
+    #[serde(default = "default_max_cooldown_seconds")]
+    pub max_cooldown_seconds: u64,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct DedupConfig {
+    pub seconds: u64,
+    /// Parts of the event that make up its signature: `source`, `kind`,
+    /// `metadata.<key>` or a field of the event kind such as `title`; empty
+    /// means only identical events are duplicates
+    #[serde(default)]
+    pub key: Vec<String>,
+}
+
+fn default_failure_threshold() -> u32 {
+    5
+}
+
+fn default_cooldown_seconds() -> u64 {
+    30
+}
+
+fn default_max_cooldown_seconds() -> u64 {
+    600
+}
+
 #[derive(Debug, Clone, Deserialize, Serialize)]
 #[serde(tag = "type", rename_all = "snake_case")]
 pub enum TriggerConfig {
@@ -115,15 +410,52 @@ pub enum TriggerConfig {
         #[serde(default)]
         pattern: Option<String>,
     },
+    FileRenamed {
+        #[serde(default)]
+        pattern: Option<String>,
+    },
     WindowFocused {
         #[serde(default)]
         title_contains: Option<String>,
+        /// Regex the title must match, on top of `title_contains`
+        #[serde(default)]
+        title_regex: Option<String>,
         #[serde(default)]
         process_name: Option<String>,
     },
     WindowUnfocused {
         #[serde(default)]
         title_contains: Option<String>,
+        /// Regex the title must match, on top of `title_contains`
+        #[serde(default)]
+        title_regex: Option<String>,
+        #[serde(default)]
+        process_name: Option<String>,
+    },
+    WindowMinimized {
+        #[serde(default)]
+        title_contains: Option<String>,
+        /// Regex the title must match, on top of `title_contains`
+        #[serde(default)]
+        title_regex: Option<String>,
+        #[serde(default)]
+        process_name: Option<String>,
+    },
+    WindowRestored {
+        #[serde(default)]
+        title_contains: Option<String>,
+        /// Regex the title must match, on top of `title_contains`
+        #[serde(default)]
+        title_regex: Option<String>,
+        #[serde(default)]
+        process_name: Option<String>,
+    },
+    WindowMoved {
+        #[serde(default)]
+        title_contains: Option<String>,
+        /// Regex the title must match, on top of `title_contains`
+        #[serde(default)]
+        title_regex: Option<String>,
         #[serde(default)]
         process_name: Option<String>,
     },
@@ -135,15 +467,31 @@ pub enum TriggerConfig {
     ProcessStopped {
         #[serde(default)]
         process_name: Option<String>,
+        /// Only match processes that exited with a non-zero code
+        #[serde(default)]
+        exit_code_nonzero: bool,
     },
     RegistryChanged {
         #[serde(default)]
         value_name: Option<String>,
     },
+    UserIdle,
+    UserActive,
+    ClipboardChanged,
+    DeviceArrived,
+    DeviceRemoved,
     Timer {
         #[serde(default = "default_timer_interval")]
         interval_seconds: u64,
     },
+    /// Matches when every nested trigger matches
+    All {
+        triggers: Vec<TriggerConfig>,
+    },
+    /// Matches when at least one nested trigger matches
+    Any {
+        triggers: Vec<TriggerConfig>,
+    },
 }
 
 fn default_timer_interval() -> u64 {
@@ -182,6 +530,19 @@ pub enum ActionConfig {
         headers: HashMap<String, String>,
         #[serde(default)]
         body: Option<String>,
+        #[serde(default)]
+        timeout_seconds: Option<u64>,
+    },
+    FileAppend {
+        path: PathBuf,
+        #[serde(default = "default_file_append_format")]
+        format: String,
+        /// Size at which the file is rotated
+        #[serde(default = "default_file_append_max_bytes")]
+        max_bytes: u64,
+        /// Rotated files kept alongside the current one
+        #[serde(default = "default_file_append_max_files")]
+        max_files: usize,
     },
     Media {
         command: String,
@@ -194,6 +555,10 @@ pub enum ActionConfig {
         timeout_ms: Option<u64>,
         #[serde(default = "default_script_on_error")]
         on_error: String,
+        /// Directories the script's `fs` API may touch; defaults to the
+        /// working directory, the temp directory and `~/Documents`
+        #[serde(default)]
+        allowed_paths: Option<Vec<PathBuf>>,
     },
 }
 
@@ -213,17 +578,96 @@ fn default_http_method() -> String {
     "POST".to_string()
 }
 
+fn default_file_append_format() -> String {
+    actions::DEFAULT_FILE_APPEND_FORMAT.to_string()
+}
+
+fn default_file_append_max_bytes() -> u64 {
+    log_rotation::DEFAULT_MAX_BYTES
+}
+
+fn default_file_append_max_files() -> usize {
+    log_rotation::DEFAULT_MAX_FILES
+}
+
 impl Config {
     pub fn load_from_file(path: &PathBuf) -> Result<Self, ConfigError> {
+        Self::load_with_includes(path, &mut Vec::new())?.expand_env()
+    }
+
+    /// Load `path` and, depth first, every file it includes. `chain` holds
+    /// the files currently being loaded, outermost first, so an include that
+    /// leads back to one of them is reported instead of recursing forever.
+    fn load_with_includes(path: &PathBuf, chain: &mut Vec<PathBuf>) -> Result<Self, ConfigError> {
+        let canonical = path
+            .canonicalize()
+            .map_err(|e| ConfigError::FileRead(path.clone(), e.to_string()))?;
+        if let Some(start) = chain.iter().position(|loading| *loading == canonical) {
+            let mut cycle = chain[start..].to_vec();
+            cycle.push(canonical);
+            return Err(ConfigError::IncludeCycle(cycle));
+        }
+
         let contents = std::fs::read_to_string(path)
             .map_err(|e| ConfigError::FileRead(path.clone(), e.to_string()))?;
 
-        let config: Config =
+        let mut config: Config =
             toml::from_str(&contents).map_err(|e| ConfigError::Parse(e.to_string()))?;
+        config = config.migrate()?;
 
+        if config.include.is_empty() {
+            return Ok(config);
+        }
+
+        let base = path.parent().unwrap_or(Path::new(""));
+        let mut sources = Vec::new();
+        let mut rules = Vec::new();
+        let mut sinks = Vec::new();
+
+        chain.push(canonical);
+        for include in std::mem::take(&mut config.include) {
+            let included = Self::load_with_includes(&base.join(include), chain)?;
+            sources.extend(included.sources);
+            rules.extend(included.rules);
+            sinks.extend(included.sinks);
+        }
+        chain.pop();
+
+        sources.append(&mut config.sources);
+        rules.append(&mut config.rules);
+        sinks.append(&mut config.sinks);
+        config.sources = sources;
+        config.rules = rules;
+        config.sinks = sinks;
         Ok(config)
     }
 
+    /// Bring a config parsed from an older schema version up to
+    /// [`CONFIG_VERSION`]. Versions this build doesn't know, including newer
+    /// ones, are rejected rather than loaded with options silently ignored.
+    pub fn migrate(mut self) -> Result<Self, ConfigError> {
+        let version = match self.version {
+            Some(version) => version,
+            None => {
+                warn!(
+                    "Config has no `version`, assuming version {}; add `version = {}` to silence this",
+                    CONFIG_VERSION, CONFIG_VERSION
+                );
+                CONFIG_VERSION
+            }
+        };
+
+        // Each supported older version gets an arm that upgrades it by one
+        // step and falls through to the next
+        match version {
+            CONFIG_VERSION => {}
+            other => return Err(ConfigError::UnsupportedVersion(other)),
+        }
+
+        self.version = Some(CONFIG_VERSION);
+        Ok(self)
+    }
+
     pub fn load_from_dir(dir: &PathBuf) -> Result<Self, ConfigError> {
         let mut config = Config::default();
 
@@ -241,107 +685,724 @@ impl Config {
                 let file_config = Self::load_from_file(&path)?;
                 config.sources.extend(file_config.sources);
                 config.rules.extend(file_config.rules);
+                config.sinks.extend(file_config.sinks);
             }
         }
 
         Ok(config)
     }
 
-    pub fn validate(&self) -> Result<(), ConfigError> {
-        // Validate that all rules reference valid sources
-        for rule in &self.rules {
+    /// Check every source and rule, reporting all problems found rather than
+    /// stopping at the first. Globs and regexes are compiled here so a typo
+    /// fails at load time instead of when the first event arrives.
+    pub fn validate(&self) -> Result<(), Vec<ConfigError>> {
+        let mut errors = Vec::new();
+
+        // No permits would leave every action waiting forever
+        check_nonzero(
+            &mut errors,
+            "engine",
+            "max_concurrent_actions",
+            self.engine.max_concurrent_actions as u64,
+        );
+        check_nonzero(
+            &mut errors,
+            "engine",
+            "dead_letter_max_bytes",
+            self.engine.dead_letter_max_bytes,
+        );
+
+        let mut source_names = HashSet::new();
+        for (idx, source) in self.sources.iter().enumerate() {
+            let owner = owner_label("source", &source.name, idx);
+
+            if source.name.is_empty() {
+                errors.push(ConfigError::invalid(&owner, "name", "must not be empty"));
+            } else if !source_names.insert(&source.name) {
+                errors.push(ConfigError::invalid(
+                    &owner,
+                    "name",
+                    "duplicate source name",
+                ));
+            }
+
+            source.source_type.validate(&owner, &mut errors);
+        }
+
+        // Actions are looked up by rule name, so rule names must be unique
+        let mut rule_names = HashSet::new();
+        for (idx, rule) in self.rules.iter().enumerate() {
+            let owner = owner_label("rule", &rule.name, idx);
+
             if rule.name.is_empty() {
-                return Err(ConfigError::Validation(format!("Rule must have a name")));
+                errors.push(ConfigError::invalid(&owner, "name", "must not be empty"));
+            } else if !rule_names.insert(&rule.name) {
+                errors.push(ConfigError::invalid(&owner, "name", "duplicate rule name"));
+            }
+
+            if rule.actions.is_empty() {
+                errors.push(ConfigError::invalid(
+                    &owner,
+                    "actions",
+                    "must have at least one action",
+                ));
+            }
+
+            if let Err(e) = rule.on_error.parse::<actions::ErrorBehavior>() {
+                errors.push(ConfigError::invalid(&owner, "on_error", e));
+            }
+
+            rule.trigger.validate(&owner, "trigger", &mut errors);
+
+            if let Some(breaker) = &rule.circuit_breaker {
+                check_nonzero(
+                    &mut errors,
+                    &owner,
+                    "circuit_breaker.failure_threshold",
+                    breaker.failure_threshold.into(),
+                );
+                if breaker.max_cooldown_seconds < breaker.cooldown_seconds {
+                    errors.push(ConfigError::invalid(
+                        &owner,
+                        "circuit_breaker.max_cooldown_seconds",
+                        "must not be less than cooldown_seconds",
+                    ));
+                }
+            }
+
+            if let Some(dedup) = &rule.dedup_window {
+                check_nonzero(&mut errors, &owner, "dedup_window.seconds", dedup.seconds);
+                for part in &dedup.key {
+                    if !is_dedup_key_part(part) {
+                        errors.push(ConfigError::invalid(
+                            &owner,
+                            "dedup_window.key",
+                            format!(
+                                "unknown part '{}': expected source, kind, metadata.<key> or an \
+                                 event field",
+                                part
+                            ),
+                        ));
+                    }
+                }
+            }
+
+            for (action_idx, action) in rule.actions.iter().enumerate() {
+                action.validate(&owner, action_idx, &mut errors);
             }
         }
 
-        // Validate sources have unique names
-        let mut names = std::collections::HashSet::new();
-        for source in &self.sources {
-            if !names.insert(&source.name) {
-                return Err(ConfigError::Validation(format!(
-                    "Duplicate source name: {}",
-                    source.name
-                )));
+        let mut sink_names = HashSet::new();
+        for (idx, sink) in self.sinks.iter().enumerate() {
+            let owner = owner_label("sink", &sink.name, idx);
+
+            if sink.name.is_empty() {
+                errors.push(ConfigError::invalid(&owner, "name", "must not be empty"));
+            } else if !sink_names.insert(&sink.name) {
+                errors.push(ConfigError::invalid(&owner, "name", "duplicate sink name"));
+            }
+
+            match &sink.sink_type {
+                SinkType::JsonlFile { max_bytes, .. } => {
+                    check_nonzero(&mut errors, &owner, "max_bytes", *max_bytes);
+                }
             }
         }
 
-        Ok(())
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors)
+        }
+    }
+
+    /// Replace `${VAR}` and `${VAR:-default}` with environment variables in
+    /// the fields that name commands, paths and endpoints, so per-machine
+    /// values and secrets needn't be written into the file. `$$` stands for a
+    /// literal `$`. Patterns, messages and PowerShell scripts are left as
+    /// written, since `$` means something else there.
+    pub fn expand_env(self) -> Result<Self, ConfigError> {
+        self.expand_env_with(&|name| std::env::var(name).ok())
+    }
+
+    fn expand_env_with(
+        mut self,
+        lookup: &dyn Fn(&str) -> Option<String>,
+    ) -> Result<Self, ConfigError> {
+        let env = EnvExpander { lookup };
+
+        if let Some(path) = &mut self.engine.dead_letter_file {
+            env.path("engine", "dead_letter_file", path)?;
+        }
+
+        for (idx, source) in self.sources.iter_mut().enumerate() {
+            let owner = owner_label("source", &source.name, idx);
+            if let SourceType::FileWatcher { paths, .. } = &mut source.source_type {
+                for path in paths {
+                    env.path(&owner, "paths", path)?;
+                }
+            }
+        }
+
+        for (idx, rule) in self.rules.iter_mut().enumerate() {
+            let owner = owner_label("rule", &rule.name, idx);
+            for (action_idx, action) in rule.actions.iter_mut().enumerate() {
+                action.expand_env(&owner, action_idx, &env)?;
+            }
+        }
+
+        for (idx, sink) in self.sinks.iter_mut().enumerate() {
+            let owner = owner_label("sink", &sink.name, idx);
+            match &mut sink.sink_type {
+                SinkType::JsonlFile { path, .. } => env.path(&owner, "path", path)?,
+            }
+        }
+
+        Ok(self)
     }
 }
 
-#[derive(Debug, Clone)]
-pub enum ConfigError {
-    FileRead(PathBuf, String),
-    Parse(String),
-    Validation(String),
+/// Applies [`expand_vars`] to config fields, naming the field in any error
+struct EnvExpander<'a> {
+    lookup: &'a dyn Fn(&str) -> Option<String>,
 }
 
-impl std::fmt::Display for ConfigError {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        match self {
-            ConfigError::FileRead(path, msg) => {
-                write!(f, "Failed to read config file {:?}: {}", path, msg)
-            }
-            ConfigError::Parse(msg) => write!(f, "Failed to parse config: {}", msg),
-            ConfigError::Validation(msg) => write!(f, "Config validation error: {}", msg),
+impl EnvExpander<'_> {
+    fn string(&self, owner: &str, field: &str, value: &mut String) -> Result<(), ConfigError> {
+        *value = expand_vars(value, self.lookup)
+            .map_err(|reason| ConfigError::invalid(owner, field, reason))?;
+        Ok(())
+    }
+
+    /// Paths that aren't valid UTF-8 can't contain `${...}` and are kept as is
+    fn path(&self, owner: &str, field: &str, path: &mut PathBuf) -> Result<(), ConfigError> {
+        if let Some(value) = path.to_str() {
+            let mut value = value.to_string();
+            self.string(owner, field, &mut value)?;
+            *path = PathBuf::from(value);
         }
+        Ok(())
     }
 }
 
-impl std::error::Error for ConfigError {}
+/// Expand `${VAR}` and `${VAR:-default}` in a single pass; the default is
+/// used when `VAR` is unset or empty. `$$` becomes `$`, and a `$` followed by
+/// anything else is kept as is. Substituted values are not expanded again.
+fn expand_vars(input: &str, lookup: &dyn Fn(&str) -> Option<String>) -> Result<String, String> {
+    let mut output = String::with_capacity(input.len());
+    let mut rest = input;
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use std::io::Write;
-    use tempfile::NamedTempFile;
+    while let Some(pos) = rest.find('$') {
+        output.push_str(&rest[..pos]);
+        rest = &rest[pos + 1..];
 
-    #[test]
-    fn test_parse_simple_config() {
-        let toml_str = r#"
-[engine]
-event_buffer_size = 500
-log_level = "debug"
+        if let Some(after) = rest.strip_prefix('$') {
+            output.push('$');
+            rest = after;
+        } else if let Some(after) = rest.strip_prefix('{') {
+            let end = after
+                .find('}')
+                .ok_or_else(|| format!("unterminated '${{' in '{}'", input))?;
+            let expression = &after[..end];
+            let (name, default) = match expression.split_once(":-") {
+                Some((name, default)) => (name, Some(default)),
+                None => (expression, None),
+            };
+            if name.is_empty() {
+                return Err(format!("empty variable name in '{}'", input));
+            }
 
-[[sources]]
-name = "downloads_watcher"
-type = "file_watcher"
-paths = ["C:/Users/Downloads"]
-pattern = "*.exe"
-recursive = false
-enabled = true
+            let value = lookup(name).filter(|value| !value.is_empty() || default.is_none());
+            match (value, default) {
+                (Some(value), _) => output.push_str(&value),
+                (None, Some(default)) => output.push_str(default),
+                (None, None) => {
+                    return Err(format!("environment variable '{}' is not set", name));
+                }
+            }
+            rest = &after[end + 1..];
+        } else {
+            output.push('$');
+        }
+    }
 
-[[rules]]
-name = "test_rule"
-description = "Test file watcher"
-trigger = { type = "file_created", pattern = "*.txt" }
-action = { type = "log", message = "File created!" }
-enabled = true
-"#;
+    output.push_str(rest);
+    Ok(output)
+}
 
-        let config: Config = toml::from_str(toml_str).expect("Failed to parse config");
+/// Registry roots accepted by the registry monitor
+const REGISTRY_ROOTS: &[&str] = &["HKLM", "HKCU", "HKU", "HKCC"];
 
-        assert_eq!(config.engine.event_buffer_size, 500);
-        assert_eq!(config.engine.log_level, "debug");
-        assert_eq!(config.sources.len(), 1);
-        assert_eq!(config.rules.len(), 1);
+/// How a source or rule is named in validation errors; unnamed entries are
+/// identified by their 1-based position instead
+fn owner_label(kind: &str, name: &str, idx: usize) -> String {
+    if name.is_empty() {
+        format!("{} #{}", kind, idx + 1)
+    } else {
+        format!("{} '{}'", kind, name)
+    }
+}
 
-        let rule = &config.rules[0];
-        assert_eq!(rule.name, "test_rule");
-        assert!(rule.enabled);
+fn check_glob(errors: &mut Vec<ConfigError>, owner: &str, field: &str, pattern: &str) {
+    if let Err(e) = glob::Pattern::new(pattern) {
+        errors.push(ConfigError::invalid(
+            owner,
+            field,
+            format!("invalid file pattern '{}': {}", pattern, e.msg),
+        ));
     }
+}
 
-    #[test]
-    fn test_load_from_file() {
-        let mut temp_file = NamedTempFile::new().unwrap();
-        writeln!(
-            temp_file,
-            r#"
-[[sources]]
-name = "test_source"
-type = "file_watcher"
-paths = ["C:/test"]
+fn check_regex(errors: &mut Vec<ConfigError>, owner: &str, field: &str, pattern: &str) {
+    if let Err(e) = regex::Regex::new(pattern) {
+        errors.push(ConfigError::invalid(
+            owner,
+            field,
+            format!("invalid regex '{}': {}", pattern, e),
+        ));
+    }
+}
+
+/// Whether `part` names something a dedup signature can be built from
+fn is_dedup_key_part(part: &str) -> bool {
+    match part {
+        "source" | "kind" => true,
+        _ => match part.strip_prefix("metadata.") {
+            Some(key) => !key.is_empty(),
+            None => EventKind::FIELD_NAMES.contains(&part),
+        },
+    }
+}
+
+fn check_nonzero(errors: &mut Vec<ConfigError>, owner: &str, field: &str, value: u64) {
+    if value == 0 {
+        errors.push(ConfigError::invalid(
+            owner,
+            field,
+            "must be greater than zero",
+        ));
+    }
+}
+
+impl SourceType {
+    fn validate(&self, owner: &str, errors: &mut Vec<ConfigError>) {
+        match self {
+            SourceType::FileWatcher {
+                paths,
+                patterns,
+                exclude_patterns,
+                ..
+            } => {
+                if paths.is_empty() {
+                    errors.push(ConfigError::invalid(
+                        owner,
+                        "paths",
+                        "must list at least one path",
+                    ));
+                }
+                for pattern in patterns {
+                    check_glob(errors, owner, "patterns", pattern);
+                }
+                for pattern in exclude_patterns {
+                    check_glob(errors, owner, "exclude_patterns", pattern);
+                }
+            }
+            SourceType::WindowWatcher {
+                title_pattern,
+                process_pattern,
+                ..
+            } => {
+                if let Some(pattern) = title_pattern {
+                    check_regex(errors, owner, "title_pattern", pattern);
+                }
+                if let Some(pattern) = process_pattern {
+                    check_regex(errors, owner, "process_pattern", pattern);
+                }
+            }
+            SourceType::ProcessMonitor {
+                process_name,
+                command_line_pattern,
+                poll_interval_seconds,
+                prefer_polling,
+                ..
+            } => {
+                if let Some(pattern) = process_name {
+                    check_glob(errors, owner, "process_name", pattern);
+                }
+                if let Some(pattern) = command_line_pattern {
+                    check_regex(errors, owner, "command_line_pattern", pattern);
+                    // Polled process starts carry no command line
+                    if *prefer_polling {
+                        errors.push(ConfigError::invalid(
+                            owner,
+                            "command_line_pattern",
+                            "can't be used with prefer_polling",
+                        ));
+                    }
+                }
+                check_nonzero(
+                    errors,
+                    owner,
+                    "poll_interval_seconds",
+                    *poll_interval_seconds,
+                );
+            }
+            SourceType::RegistryMonitor {
+                root, value_name, ..
+            } => {
+                if !REGISTRY_ROOTS.contains(&root.as_str()) {
+                    errors.push(ConfigError::invalid(
+                        owner,
+                        "root",
+                        format!(
+                            "unknown registry root '{}', expected one of {}",
+                            root,
+                            REGISTRY_ROOTS.join(", ")
+                        ),
+                    ));
+                }
+                if let Some(pattern) = value_name {
+                    check_glob(errors, owner, "value_name", pattern);
+                }
+            }
+            SourceType::IdleMonitor {
+                poll_interval_seconds,
+                ..
+            } => {
+                check_nonzero(
+                    errors,
+                    owner,
+                    "poll_interval_seconds",
+                    *poll_interval_seconds,
+                );
+            }
+            SourceType::ClipboardMonitor {
+                capture_text,
+                preview_chars,
+            } => {
+                if *capture_text {
+                    check_nonzero(errors, owner, "preview_chars", *preview_chars as u64);
+                }
+            }
+            SourceType::DeviceMonitor { .. } => {}
+            SourceType::Timer { interval_seconds } => {
+                check_nonzero(errors, owner, "interval_seconds", *interval_seconds);
+            }
+        }
+    }
+}
+
+impl TriggerConfig {
+    /// `field` is this trigger's path within the rule, e.g. `trigger.triggers[1]`
+    fn validate(&self, owner: &str, field: &str, errors: &mut Vec<ConfigError>) {
+        match self {
+            TriggerConfig::FileCreated { pattern }
+            | TriggerConfig::FileModified { pattern }
+            | TriggerConfig::FileDeleted { pattern }
+            | TriggerConfig::FileRenamed { pattern } => {
+                if let Some(pattern) = pattern {
+                    check_glob(errors, owner, &format!("{}.pattern", field), pattern);
+                }
+            }
+            TriggerConfig::WindowFocused {
+                title_regex: Some(pattern),
+                ..
+            }
+            | TriggerConfig::WindowUnfocused {
+                title_regex: Some(pattern),
+                ..
+            }
+            | TriggerConfig::WindowMinimized {
+                title_regex: Some(pattern),
+                ..
+            }
+            | TriggerConfig::WindowRestored {
+                title_regex: Some(pattern),
+                ..
+            }
+            | TriggerConfig::WindowMoved {
+                title_regex: Some(pattern),
+                ..
+            } => {
+                check_regex(errors, owner, &format!("{}.title_regex", field), pattern);
+            }
+            TriggerConfig::ProcessStarted {
+                process_name: Some(pattern),
+            }
+            | TriggerConfig::ProcessStopped {
+                process_name: Some(pattern),
+                ..
+            } => {
+                check_glob(errors, owner, &format!("{}.process_name", field), pattern);
+            }
+            TriggerConfig::All { triggers } | TriggerConfig::Any { triggers } => {
+                if triggers.is_empty() {
+                    errors.push(ConfigError::invalid(
+                        owner,
+                        &format!("{}.triggers", field),
+                        "must list at least one trigger",
+                    ));
+                }
+                for (idx, trigger) in triggers.iter().enumerate() {
+                    trigger.validate(owner, &format!("{}.triggers[{}]", field, idx), errors);
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Where a script action's `path` points; relative paths are looked up under
+/// `plugins/actions/`
+pub fn resolve_script_path(path: &Path) -> PathBuf {
+    if path.is_absolute() {
+        path.to_path_buf()
+    } else {
+        PathBuf::from("plugins/actions").join(path)
+    }
+}
+
+impl ActionConfig {
+    fn expand_env(
+        &mut self,
+        owner: &str,
+        idx: usize,
+        env: &EnvExpander,
+    ) -> Result<(), ConfigError> {
+        let field = |name: &str| format!("actions[{}].{}", idx, name);
+        match self {
+            ActionConfig::Execute {
+                command,
+                args,
+                working_dir,
+            } => {
+                env.string(owner, &field("command"), command)?;
+                for arg in args {
+                    env.string(owner, &field("args"), arg)?;
+                }
+                if let Some(dir) = working_dir {
+                    env.path(owner, &field("working_dir"), dir)?;
+                }
+            }
+            ActionConfig::PowerShell { working_dir, .. } => {
+                if let Some(dir) = working_dir {
+                    env.path(owner, &field("working_dir"), dir)?;
+                }
+            }
+            ActionConfig::HttpRequest { url, headers, .. } => {
+                env.string(owner, &field("url"), url)?;
+                for value in headers.values_mut() {
+                    env.string(owner, &field("headers"), value)?;
+                }
+            }
+            ActionConfig::FileAppend { path, .. } => {
+                env.path(owner, &field("path"), path)?;
+            }
+            ActionConfig::Script {
+                path,
+                allowed_paths,
+                ..
+            } => {
+                env.path(owner, &field("path"), path)?;
+                for allowed in allowed_paths.iter_mut().flatten() {
+                    env.path(owner, &field("allowed_paths"), allowed)?;
+                }
+            }
+            ActionConfig::Log { .. } | ActionConfig::Notify { .. } | ActionConfig::Media { .. } => {
+            }
+        }
+        Ok(())
+    }
+
+    fn validate(&self, owner: &str, idx: usize, errors: &mut Vec<ConfigError>) {
+        if let ActionConfig::FileAppend { max_bytes, .. } = self {
+            check_nonzero(
+                errors,
+                owner,
+                &format!("actions[{}].max_bytes", idx),
+                *max_bytes,
+            );
+        }
+
+        if let ActionConfig::Script {
+            path,
+            function,
+            on_error,
+            ..
+        } = self
+        {
+            if let Err(e) = on_error.parse::<actions::ScriptErrorBehavior>() {
+                errors.push(ConfigError::invalid(
+                    owner,
+                    &format!("actions[{}].on_error", idx),
+                    e,
+                ));
+            }
+
+            // Compiles the script and checks `function` exists
+            let script_path = resolve_script_path(path);
+            if let Err(e) = actions::ScriptAction::new(script_path.clone(), function.clone()) {
+                errors.push(ConfigError::invalid(
+                    owner,
+                    &format!("actions[{}].path", idx),
+                    format!("cannot load script {}: {}", script_path.display(), e),
+                ));
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum ConfigError {
+    FileRead(PathBuf, String),
+    Parse(String),
+    /// The config declares a schema version this build can't load
+    UnsupportedVersion(u32),
+    /// Following `include`s from the first file leads back to it
+    IncludeCycle(Vec<PathBuf>),
+    /// A field of a source or rule is invalid, e.g. a malformed glob
+    Validation {
+        owner: String,
+        field: String,
+        reason: String,
+    },
+}
+
+impl ConfigError {
+    fn invalid(owner: &str, field: &str, reason: impl Into<String>) -> Self {
+        ConfigError::Validation {
+            owner: owner.to_string(),
+            field: field.to_string(),
+            reason: reason.into(),
+        }
+    }
+}
+
+impl std::fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ConfigError::FileRead(path, msg) => {
+                write!(f, "Failed to read config file {:?}: {}", path, msg)
+            }
+            ConfigError::Parse(msg) => write!(f, "Failed to parse config: {}", msg),
+            ConfigError::UnsupportedVersion(version) if *version > CONFIG_VERSION => write!(
+                f,
+                "Config version {} is newer than this build supports (up to {}); upgrade the engine",
+                version, CONFIG_VERSION
+            ),
+            ConfigError::UnsupportedVersion(version) => write!(
+                f,
+                "Unknown config version {} (supported: {})",
+                version, CONFIG_VERSION
+            ),
+            ConfigError::IncludeCycle(chain) => {
+                let chain: Vec<String> = chain.iter().map(|p| p.display().to_string()).collect();
+                write!(f, "Config include cycle: {}", chain.join(" -> "))
+            }
+            ConfigError::Validation {
+                owner,
+                field,
+                reason,
+            } => write!(f, "{}: {}: {}", owner, field, reason),
+        }
+    }
+}
+
+impl std::error::Error for ConfigError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+    use tempfile::NamedTempFile;
+
+    #[test]
+    fn test_parse_simple_config() {
+        let toml_str = r#"
+[engine]
+event_buffer_size = 500
+log_level = "debug"
+
+[[sources]]
+name = "downloads_watcher"
+type = "file_watcher"
+paths = ["C:/Users/Downloads"]
+pattern = "*.exe"
+recursive = false
+enabled = true
+
+[[rules]]
+name = "test_rule"
+description = "Test file watcher"
+trigger = { type = "file_created", pattern = "*.txt" }
+action = { type = "log", message = "File created!" }
+enabled = true
+"#;
+
+        let config: Config = toml::from_str(toml_str).expect("Failed to parse config");
+
+        assert_eq!(config.engine.event_buffer_size, 500);
+        assert_eq!(config.engine.log_level, "debug");
+        assert_eq!(config.engine.log_format, LogFormat::Text);
+        assert_eq!(config.sources.len(), 1);
+        assert_eq!(config.rules.len(), 1);
+
+        let rule = &config.rules[0];
+        assert_eq!(rule.name, "test_rule");
+        assert!(rule.enabled);
+    }
+
+    #[test]
+    fn test_file_watcher_patterns() {
+        let toml_str = r#"
+[[sources]]
+name = "logs"
+type = "file_watcher"
+paths = ["C:/Logs"]
+patterns = ["*.txt", "*.log"]
+
+[[sources]]
+name = "downloads"
+type = "file_watcher"
+paths = ["C:/Users/Downloads"]
+pattern = "*.exe"
+
+[[sources]]
+name = "everything"
+type = "file_watcher"
+paths = ["C:/Data"]
+"#;
+
+        let config: Config = toml::from_str(toml_str).expect("Failed to parse config");
+        let patterns: Vec<Vec<String>> = config
+            .sources
+            .iter()
+            .map(|source| match &source.source_type {
+                SourceType::FileWatcher { patterns, .. } => patterns.clone(),
+                other => panic!("expected file watcher, got {:?}", other),
+            })
+            .collect();
+
+        assert_eq!(
+            patterns,
+            vec![
+                vec!["*.txt".to_string(), "*.log".to_string()],
+                vec!["*.exe".to_string()],
+                Vec::new(),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_load_from_file() {
+        let mut temp_file = NamedTempFile::new().unwrap();
+        writeln!(
+            temp_file,
+            r#"
+[[sources]]
+name = "test_source"
+type = "file_watcher"
+paths = ["C:/test"]
 enabled = true
 
 [[rules]]
@@ -368,8 +1429,11 @@ enabled = true
                     name: "test".to_string(),
                     source_type: SourceType::FileWatcher {
                         paths: vec![PathBuf::from("/test")],
-                        pattern: None,
+                        patterns: Vec::new(),
+                        exclude_patterns: Vec::new(),
                         recursive: false,
+                        follow_symlinks: false,
+                        debounce_ms: 300,
                     },
                     enabled: true,
                 },
@@ -377,8 +1441,11 @@ enabled = true
                     name: "test".to_string(),
                     source_type: SourceType::FileWatcher {
                         paths: vec![PathBuf::from("/test2")],
-                        pattern: None,
+                        patterns: Vec::new(),
+                        exclude_patterns: Vec::new(),
                         recursive: false,
+                        follow_symlinks: false,
+                        debounce_ms: 300,
                     },
                     enabled: true,
                 },
@@ -388,4 +1455,852 @@ enabled = true
 
         assert!(config.validate().is_err());
     }
+
+    #[test]
+    fn test_validate_duplicate_rule_names() {
+        let rule = RuleConfig {
+            name: "test".to_string(),
+            description: None,
+            trigger: TriggerConfig::UserIdle,
+            actions: vec![ActionConfig::Log {
+                message: "idle".to_string(),
+                level: "info".to_string(),
+            }],
+            on_error: "continue".to_string(),
+            circuit_breaker: None,
+            dedup_window: None,
+            enabled: true,
+        };
+        let config = Config {
+            rules: vec![rule.clone(), rule],
+            ..Default::default()
+        };
+
+        assert!(config.validate().is_err());
+    }
+
+    #[test]
+    fn test_rule_with_multiple_actions() {
+        let toml_str = r#"
+[[rules]]
+name = "log_and_record"
+trigger = { type = "file_created" }
+on_error = "stop"
+enabled = true
+
+[[rules.action]]
+type = "log"
+message = "Step 1"
+
+[[rules.action]]
+type = "file_append"
+path = "created.log"
+"#;
+
+        let config: Config = toml::from_str(toml_str).expect("Failed to parse config");
+        let rule = &config.rules[0];
+        assert_eq!(rule.actions.len(), 2);
+        assert!(matches!(rule.actions[0], ActionConfig::Log { .. }));
+        assert!(matches!(rule.actions[1], ActionConfig::FileAppend { .. }));
+        assert_eq!(rule.on_error, "stop");
+        assert!(config.validate().is_ok());
+
+        // The single-action form still parses, with the default error behavior
+        let single: Config = toml::from_str(
+            r#"
+[[rules]]
+name = "single"
+trigger = { type = "user_idle" }
+action = { type = "log", message = "idle" }
+"#,
+        )
+        .expect("Failed to parse config");
+        assert_eq!(single.rules[0].actions.len(), 1);
+        assert_eq!(single.rules[0].on_error, "continue");
+    }
+
+    fn validation_errors(toml_str: &str) -> Vec<String> {
+        let config: Config = toml::from_str(toml_str).expect("Failed to parse config");
+        match config.validate() {
+            Ok(()) => Vec::new(),
+            Err(errors) => errors.iter().map(|e| e.to_string()).collect(),
+        }
+    }
+
+    #[test]
+    fn test_validate_reports_invalid_patterns() {
+        let errors = validation_errors(
+            r#"
+[[sources]]
+name = "downloads"
+type = "file_watcher"
+paths = ["C:/Users/Downloads"]
+patterns = ["*.txt", "["]
+exclude_patterns = ["**/[tmp"]
+
+[[sources]]
+name = "windows"
+type = "window_watcher"
+title_pattern = "(unclosed"
+
+[[rules]]
+name = "x"
+trigger = { type = "file_created", pattern = "[" }
+action = { type = "log", message = "created" }
+"#,
+        );
+
+        assert_eq!(errors.len(), 4, "{:?}", errors);
+        assert!(errors[0].starts_with("source 'downloads': patterns: invalid file pattern '['"));
+        assert!(errors[1].starts_with("source 'downloads': exclude_patterns:"));
+        assert!(
+            errors[2].starts_with("source 'windows': title_pattern: invalid regex '(unclosed'")
+        );
+        assert_eq!(
+            errors[3],
+            "rule 'x': trigger.pattern: invalid file pattern '[': invalid range pattern"
+        );
+    }
+
+    #[test]
+    fn test_validate_reports_invalid_fields() {
+        let errors = validation_errors(
+            r#"
+[[sources]]
+name = "run_keys"
+type = "registry_monitor"
+root = "HKEY_NOWHERE"
+key = "Software"
+
+[[sources]]
+name = "ticker"
+type = "timer"
+interval_seconds = 0
+
+[[rules]]
+name = ""
+trigger = { type = "user_idle" }
+action = { type = "log", message = "idle" }
+on_error = "explode"
+
+[[rules]]
+name = "script"
+trigger = { type = "user_active" }
+actions = [
+    { type = "log", message = "active" },
+    { type = "script", path = "a.lua", on_error = "sometimes" },
+]
+"#,
+        );
+
+        assert_eq!(
+            errors[..5],
+            [
+                "source 'run_keys': root: unknown registry root 'HKEY_NOWHERE', expected one of HKLM, HKCU, HKU, HKCC",
+                "source 'ticker': interval_seconds: must be greater than zero",
+                "rule #1: name: must not be empty",
+                "rule #1: on_error: Invalid error behavior: explode",
+                "rule 'script': actions[1].on_error: Invalid error behavior: sometimes",
+            ]
+        );
+        assert_eq!(errors.len(), 6);
+        assert!(
+            errors[5].starts_with("rule 'script': actions[1].path: cannot load script"),
+            "{}",
+            errors[5]
+        );
+    }
+
+    #[test]
+    fn test_validate_rejects_command_line_pattern_when_polling() {
+        let errors = validation_errors(
+            r#"
+[[sources]]
+name = "encoded"
+type = "process_monitor"
+command_line_pattern = '(?i)\s-enc?\s'
+prefer_polling = true
+"#,
+        );
+
+        assert_eq!(
+            errors,
+            ["source 'encoded': command_line_pattern: can't be used with prefer_polling"]
+        );
+    }
+
+    #[test]
+    fn test_validate_rejects_zero_max_concurrent_actions() {
+        let errors = validation_errors(
+            r#"
+[engine]
+max_concurrent_actions = 0
+"#,
+        );
+
+        assert_eq!(
+            errors,
+            ["engine: max_concurrent_actions: must be greater than zero"]
+        );
+    }
+
+    #[test]
+    fn test_validate_accepts_valid_config() {
+        let errors = validation_errors(
+            r#"
+[[sources]]
+name = "downloads"
+type = "file_watcher"
+paths = ["C:/Users/Downloads"]
+patterns = ["*.exe", "*.msi"]
+
+[[sources]]
+name = "run_keys"
+type = "registry_monitor"
+root = "HKCU"
+key = "Software"
+value_name = "One*"
+
+[[rules]]
+name = "installer"
+trigger = { type = "file_created", pattern = "*.msi" }
+action = { type = "log", message = "installer downloaded" }
+"#,
+        );
+
+        assert!(errors.is_empty(), "{:?}", errors);
+    }
+
+    #[test]
+    fn test_parse_circuit_breaker() {
+        let config: Config = toml::from_str(
+            r#"
+[[rules]]
+name = "webhook"
+trigger = { type = "user_active" }
+action = { type = "log", message = "active" }
+circuit_breaker = { failure_threshold = 3 }
+"#,
+        )
+        .expect("Failed to parse config");
+
+        let breaker = config.rules[0].circuit_breaker.as_ref().unwrap();
+        assert_eq!(breaker.failure_threshold, 3);
+        assert_eq!(breaker.cooldown_seconds, 30);
+        assert_eq!(breaker.max_cooldown_seconds, 600);
+
+        let errors = validation_errors(
+            r#"
+[[rules]]
+name = "webhook"
+trigger = { type = "user_active" }
+action = { type = "log", message = "active" }
+circuit_breaker = { failure_threshold = 0, cooldown_seconds = 60, max_cooldown_seconds = 10 }
+"#,
+        );
+        assert_eq!(
+            errors,
+            [
+                "rule 'webhook': circuit_breaker.failure_threshold: must be greater than zero",
+                "rule 'webhook': circuit_breaker.max_cooldown_seconds: must not be less than cooldown_seconds",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_dedup_window() {
+        let config: Config = toml::from_str(
+            r#"
+[[rules]]
+name = "focus"
+trigger = { type = "window_focused" }
+action = { type = "log", message = "focused" }
+dedup_window = { seconds = 30, key = ["source", "kind", "title"] }
+"#,
+        )
+        .expect("Failed to parse config");
+
+        let dedup = config.rules[0].dedup_window.as_ref().unwrap();
+        assert_eq!(dedup.seconds, 30);
+        assert_eq!(dedup.key, ["source", "kind", "title"]);
+
+        let errors = validation_errors(
+            r#"
+[[rules]]
+name = "focus"
+trigger = { type = "window_focused" }
+action = { type = "log", message = "focused" }
+dedup_window = { seconds = 0 }
+"#,
+        );
+        assert_eq!(
+            errors,
+            ["rule 'focus': dedup_window.seconds: must be greater than zero"]
+        );
+    }
+
+    #[test]
+    fn test_validate_file_append_max_bytes() {
+        let errors = validation_errors(
+            r#"
+[[rules]]
+name = "audit"
+trigger = { type = "timer" }
+action = { type = "file_append", path = "audit.log", max_bytes = 0 }
+"#,
+        );
+        assert_eq!(
+            errors,
+            ["rule 'audit': actions[0].max_bytes: must be greater than zero"]
+        );
+    }
+
+    #[test]
+    fn test_validate_rejects_unknown_dedup_key_part() {
+        let errors = validation_errors(
+            r#"
+[[rules]]
+name = "focus"
+trigger = { type = "window_focused" }
+action = { type = "log", message = "focused" }
+dedup_window = { seconds = 30, key = ["source", "titel", "metadata.user", "metadata."] }
+"#,
+        );
+        assert_eq!(
+            errors,
+            [
+                "rule 'focus': dedup_window.key: unknown part 'titel': expected source, kind, \
+                 metadata.<key> or an event field",
+                "rule 'focus': dedup_window.key: unknown part 'metadata.': expected source, \
+                 kind, metadata.<key> or an event field",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_process_stopped_exit_code_nonzero() {
+        let config: Config = toml::from_str(
+            r#"
+[[rules]]
+name = "crash"
+trigger = { type = "process_stopped", exit_code_nonzero = true }
+action = { type = "log", message = "crashed" }
+
+[[rules]]
+name = "exit"
+trigger = { type = "process_stopped", process_name = "backup.exe" }
+action = { type = "log", message = "exited" }
+"#,
+        )
+        .expect("Failed to parse config");
+
+        assert!(matches!(
+            config.rules[0].trigger,
+            TriggerConfig::ProcessStopped {
+                exit_code_nonzero: true,
+                ..
+            }
+        ));
+        assert!(matches!(
+            config.rules[1].trigger,
+            TriggerConfig::ProcessStopped {
+                exit_code_nonzero: false,
+                ..
+            }
+        ));
+    }
+
+    #[test]
+    fn test_window_trigger_title_regex() {
+        let config: Config = toml::from_str(
+            r#"
+[[rules]]
+name = "editor"
+trigger = { type = "window_focused", title_regex = "^(Visual Studio|nvim)" }
+action = { type = "log", message = "editing" }
+"#,
+        )
+        .expect("Failed to parse config");
+        match &config.rules[0].trigger {
+            TriggerConfig::WindowFocused { title_regex, .. } => {
+                assert_eq!(title_regex.as_deref(), Some("^(Visual Studio|nvim)"));
+            }
+            other => panic!("expected a window_focused trigger, got {:?}", other),
+        }
+
+        let errors = validation_errors(
+            r#"
+[[rules]]
+name = "editor"
+trigger = { type = "window_moved", title_regex = "(unclosed" }
+action = { type = "log", message = "moved" }
+"#,
+        );
+        assert_eq!(errors.len(), 1);
+        assert!(
+            errors[0].starts_with("rule 'editor': trigger.title_regex: invalid regex '(unclosed'"),
+            "{}",
+            errors[0]
+        );
+    }
+
+    #[test]
+    fn test_validate_process_name_patterns() {
+        let errors = validation_errors(
+            r#"
+[[rules]]
+name = "started"
+trigger = { type = "process_started", process_name = "[chrome" }
+action = { type = "log", message = "started" }
+
+[[rules]]
+name = "stopped"
+trigger = { type = "process_stopped", process_name = "[chrome" }
+action = { type = "log", message = "stopped" }
+"#,
+        );
+        assert_eq!(errors.len(), 2);
+        for (error, rule) in errors.iter().zip(["started", "stopped"]) {
+            let expected = format!("rule '{}': trigger.process_name: invalid", rule);
+            assert!(error.starts_with(&expected), "{}", error);
+        }
+    }
+
+    fn write_config(dir: &Path, name: &str, contents: &str) -> PathBuf {
+        let path = dir.join(name);
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(&path, contents).unwrap();
+        path
+    }
+
+    #[test]
+    fn test_include_merges_before_own_entries() {
+        let dir = tempfile::TempDir::new().unwrap();
+        write_config(
+            dir.path(),
+            "rules/common.toml",
+            r#"
+include = ["timers.toml"]
+
+[engine]
+event_buffer_size = 1
+
+[[rules]]
+name = "common"
+trigger = { type = "user_idle" }
+action = { type = "log", message = "idle" }
+"#,
+        );
+        write_config(
+            dir.path(),
+            "rules/timers.toml",
+            r#"
+[[rules]]
+name = "timer"
+trigger = { type = "timer" }
+action = { type = "log", message = "tick" }
+"#,
+        );
+        let main = write_config(
+            dir.path(),
+            "main.toml",
+            r#"
+include = ["rules/common.toml"]
+
+[engine]
+event_buffer_size = 500
+
+[[rules]]
+name = "own"
+trigger = { type = "user_active" }
+action = { type = "log", message = "active" }
+"#,
+        );
+
+        let config = Config::load_from_file(&main).expect("Failed to load config");
+        let names: Vec<&str> = config.rules.iter().map(|r| r.name.as_str()).collect();
+        assert_eq!(names, vec!["timer", "common", "own"]);
+        // Only the including file's engine settings apply
+        assert_eq!(config.engine.event_buffer_size, 500);
+        assert!(config.include.is_empty());
+    }
+
+    #[test]
+    fn test_include_cycle_is_reported() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let first = write_config(dir.path(), "first.toml", r#"include = ["second.toml"]"#);
+        write_config(dir.path(), "second.toml", r#"include = ["./first.toml"]"#);
+
+        let error = Config::load_from_file(&first).unwrap_err();
+        match &error {
+            ConfigError::IncludeCycle(chain) => {
+                let names: Vec<_> = chain.iter().map(|p| p.file_name().unwrap()).collect();
+                assert_eq!(names, vec!["first.toml", "second.toml", "first.toml"]);
+            }
+            other => panic!("Expected an include cycle, got {:?}", other),
+        }
+        let message = error.to_string();
+        assert!(message.starts_with("Config include cycle: "), "{}", message);
+        assert!(message.contains("second.toml -> "), "{}", message);
+
+        let own = write_config(dir.path(), "own.toml", r#"include = ["own.toml"]"#);
+        assert!(matches!(
+            Config::load_from_file(&own),
+            Err(ConfigError::IncludeCycle(chain)) if chain.len() == 2
+        ));
+    }
+
+    #[test]
+    fn test_missing_include_is_reported() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let main = write_config(dir.path(), "main.toml", r#"include = ["missing.toml"]"#);
+
+        assert!(matches!(
+            Config::load_from_file(&main),
+            Err(ConfigError::FileRead(path, _)) if path.ends_with("missing.toml")
+        ));
+    }
+
+    fn lookup(name: &str) -> Option<String> {
+        match name {
+            "TOOLS" => Some("C:/Tools".to_string()),
+            "API_TOKEN" => Some("s3cret".to_string()),
+            "EMPTY" => Some(String::new()),
+            "NESTED" => Some("${TOOLS}".to_string()),
+            _ => None,
+        }
+    }
+
+    #[test]
+    fn test_expand_vars() {
+        let expand = |input: &str| expand_vars(input, &lookup);
+
+        assert_eq!(expand("${TOOLS}/run.exe").unwrap(), "C:/Tools/run.exe");
+        assert_eq!(
+            expand("${MISSING:-D:/Fallback}/run.exe").unwrap(),
+            "D:/Fallback/run.exe"
+        );
+        assert_eq!(expand("${EMPTY:-default}").unwrap(), "default");
+        assert_eq!(expand("${TOOLS:-unused}").unwrap(), "C:/Tools");
+        assert_eq!(expand("${EMPTY}").unwrap(), "");
+
+        // `$$` is a literal `$`, and it isn't the start of a variable
+        assert_eq!(expand("cost: $$5").unwrap(), "cost: $5");
+        assert_eq!(expand("$${TOOLS}").unwrap(), "${TOOLS}");
+        assert_eq!(expand("a$b$").unwrap(), "a$b$");
+
+        // Substituted values are not expanded again
+        assert_eq!(expand("${NESTED}").unwrap(), "${TOOLS}");
+
+        assert_eq!(
+            expand("${MISSING}").unwrap_err(),
+            "environment variable 'MISSING' is not set"
+        );
+        assert!(expand("${TOOLS").is_err());
+        assert!(expand("${}").is_err());
+    }
+
+    #[test]
+    fn test_expand_env_fields() {
+        let config: Config = toml::from_str(
+            r#"
+[engine]
+dead_letter_file = "${TOOLS}/dead_letters.jsonl"
+
+[[sources]]
+name = "downloads"
+type = "file_watcher"
+paths = ["${DOWNLOADS:-C:/Users/Downloads}"]
+pattern = "*.exe$"
+
+[[rules]]
+name = "installers"
+trigger = { type = "file_created" }
+
+[[rules.actions]]
+type = "execute"
+command = "${TOOLS}/scan.exe"
+args = ["--token", "${API_TOKEN}", "$$HOME"]
+working_dir = "${TOOLS}"
+
+[[rules.actions]]
+type = "http_request"
+url = "https://${API_HOST:-api.example.com}/scan"
+headers = { Authorization = "Bearer ${API_TOKEN}" }
+
+[[rules.actions]]
+type = "power_shell"
+script = "Write-Host ${env:USERNAME}"
+
+[[rules.actions]]
+type = "log"
+message = "Scanned ${TOOLS}"
+
+[[sinks]]
+name = "audit"
+type = "jsonl_file"
+path = "${TOOLS}/events.jsonl"
+"#,
+        )
+        .expect("Failed to parse config");
+        let config = config
+            .expand_env_with(&lookup)
+            .expect("Expansion should succeed");
+
+        assert_eq!(
+            config.engine.dead_letter_file,
+            Some(PathBuf::from("C:/Tools/dead_letters.jsonl"))
+        );
+        match &config.sources[0].source_type {
+            SourceType::FileWatcher {
+                paths, patterns, ..
+            } => {
+                assert_eq!(paths, &[PathBuf::from("C:/Users/Downloads")]);
+                assert_eq!(patterns, &["*.exe$"]);
+            }
+            other => panic!("Unexpected source type: {:?}", other),
+        }
+
+        let actions = &config.rules[0].actions;
+        match &actions[0] {
+            ActionConfig::Execute {
+                command,
+                args,
+                working_dir,
+            } => {
+                assert_eq!(command, "C:/Tools/scan.exe");
+                assert_eq!(args, &["--token", "s3cret", "$HOME"]);
+                assert_eq!(working_dir, &Some(PathBuf::from("C:/Tools")));
+            }
+            other => panic!("Unexpected action: {:?}", other),
+        }
+        match &actions[1] {
+            ActionConfig::HttpRequest { url, headers, .. } => {
+                assert_eq!(url, "https://api.example.com/scan");
+                assert_eq!(headers["Authorization"], "Bearer s3cret");
+            }
+            other => panic!("Unexpected action: {:?}", other),
+        }
+        // `$` means something else in scripts and messages, so they're left alone
+        assert!(
+            matches!(&actions[2], ActionConfig::PowerShell { script, .. } if script == "Write-Host ${env:USERNAME}")
+        );
+        assert!(
+            matches!(&actions[3], ActionConfig::Log { message, .. } if message == "Scanned ${TOOLS}")
+        );
+
+        match &config.sinks[0].sink_type {
+            SinkType::JsonlFile { path, .. } => {
+                assert_eq!(path, &PathBuf::from("C:/Tools/events.jsonl"));
+            }
+        }
+    }
+
+    #[test]
+    fn test_expand_env_reports_missing_variable() {
+        let config: Config = toml::from_str(
+            r#"
+[[rules]]
+name = "scan"
+trigger = { type = "file_created" }
+action = { type = "execute", command = "${SCANNER}" }
+"#,
+        )
+        .expect("Failed to parse config");
+
+        let error = config.expand_env_with(&lookup).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "rule 'scan': actions[0].command: environment variable 'SCANNER' is not set"
+        );
+    }
+
+    #[test]
+    fn test_parse_log_format() {
+        let config: Config = toml::from_str(
+            r#"
+[engine]
+log_format = "json"
+"#,
+        )
+        .expect("Failed to parse config");
+        assert_eq!(config.engine.log_format, LogFormat::Json);
+
+        assert!(toml::from_str::<Config>("[engine]\nlog_format = \"xml\"").is_err());
+    }
+
+    #[test]
+    fn test_parse_sinks() {
+        let config: Config = toml::from_str(
+            r#"
+[[sinks]]
+name = "audit"
+type = "jsonl_file"
+path = "C:/logs/events.jsonl"
+enabled = true
+"#,
+        )
+        .expect("Failed to parse config");
+
+        assert_eq!(config.sinks.len(), 1);
+        assert!(config.sinks[0].enabled);
+        match &config.sinks[0].sink_type {
+            SinkType::JsonlFile {
+                path,
+                max_bytes,
+                max_files,
+            } => {
+                assert_eq!(path, &PathBuf::from("C:/logs/events.jsonl"));
+                assert_eq!(*max_bytes, 10 * 1024 * 1024);
+                assert_eq!(*max_files, 5);
+            }
+        }
+
+        let errors = validation_errors(
+            r#"
+[[sinks]]
+name = "audit"
+type = "jsonl_file"
+path = "a.jsonl"
+max_bytes = 0
+
+[[sinks]]
+name = "audit"
+type = "jsonl_file"
+path = "b.jsonl"
+"#,
+        );
+        assert_eq!(
+            errors,
+            [
+                "sink 'audit': max_bytes: must be greater than zero",
+                "sink 'audit': name: duplicate sink name",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_config_version() {
+        let config: Config = toml::from_str("version = 1").expect("Failed to parse config");
+        let config = config.migrate().expect("Current version should load");
+        assert_eq!(config.version, Some(CONFIG_VERSION));
+
+        // A missing version is assumed to be current
+        let config: Config = toml::from_str("").expect("Failed to parse config");
+        assert_eq!(config.version, None);
+        assert_eq!(config.migrate().unwrap().version, Some(CONFIG_VERSION));
+    }
+
+    #[test]
+    fn test_config_version_rejects_unsupported() {
+        let future = CONFIG_VERSION + 1;
+        let mut temp_file = NamedTempFile::new().unwrap();
+        writeln!(temp_file, "version = {}", future).unwrap();
+
+        match Config::load_from_file(&temp_file.path().to_path_buf()) {
+            Err(e @ ConfigError::UnsupportedVersion(version)) => {
+                assert_eq!(version, future);
+                assert!(e.to_string().contains("newer than this build supports"));
+            }
+            other => panic!("expected unsupported version, got {:?}", other),
+        }
+
+        let config: Config = toml::from_str("version = 0").expect("Failed to parse config");
+        assert!(matches!(
+            config.migrate(),
+            Err(ConfigError::UnsupportedVersion(0))
+        ));
+    }
+
+    #[test]
+    fn test_validate_loads_scripts() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let script = dir.path().join("handler.lua");
+        std::fs::write(
+            &script,
+            "function on_event(event)\n    return {success = true}\nend\n",
+        )
+        .unwrap();
+        let broken = dir.path().join("broken.lua");
+        std::fs::write(&broken, "function on_event(event\n").unwrap();
+
+        let config_for = |path: &Path, function: &str| Config {
+            rules: vec![RuleConfig {
+                name: "script".to_string(),
+                description: None,
+                trigger: TriggerConfig::UserIdle,
+                actions: vec![ActionConfig::Script {
+                    path: path.to_path_buf(),
+                    function: function.to_string(),
+                    timeout_ms: None,
+                    on_error: "fail".to_string(),
+                    allowed_paths: None,
+                }],
+                on_error: "continue".to_string(),
+                circuit_breaker: None,
+                dedup_window: None,
+                enabled: true,
+            }],
+            ..Default::default()
+        };
+
+        assert!(config_for(&script, "on_event").validate().is_ok());
+
+        for (path, function) in [(&script, "missing"), (&broken, "on_event")] {
+            let errors = config_for(path, function).validate().unwrap_err();
+            assert_eq!(errors.len(), 1);
+            assert!(
+                errors[0]
+                    .to_string()
+                    .starts_with("rule 'script': actions[0].path: cannot load script")
+            );
+        }
+    }
+
+    #[test]
+    fn test_composite_trigger() {
+        let toml_str = r#"
+[[rules]]
+name = "reports"
+action = { type = "log", message = "report" }
+
+[rules.trigger]
+type = "all"
+triggers = [
+    { type = "file_created", pattern = "report*" },
+    { type = "any", triggers = [
+        { type = "file_created", pattern = "*.pdf" },
+        { type = "file_created", pattern = "*.docx" },
+    ] },
+]
+"#;
+
+        let config: Config = toml::from_str(toml_str).expect("Failed to parse config");
+        let TriggerConfig::All { triggers } = &config.rules[0].trigger else {
+            panic!("expected an all trigger");
+        };
+        assert_eq!(triggers.len(), 2);
+        assert!(matches!(&triggers[1], TriggerConfig::Any { triggers } if triggers.len() == 2));
+        assert!(config.validate().is_ok());
+
+        // Nested problems are reported with their position in the tree
+        let errors = validation_errors(
+            r#"
+[[rules]]
+name = "broken"
+action = { type = "log", message = "broken" }
+trigger = { type = "any", triggers = [
+    { type = "user_idle" },
+    { type = "all", triggers = [] },
+    { type = "file_deleted", pattern = "[" },
+] }
+"#,
+        );
+        assert_eq!(errors.len(), 2, "{:?}", errors);
+        assert_eq!(
+            errors[0],
+            "rule 'broken': trigger.triggers[1].triggers: must list at least one trigger"
+        );
+        assert!(errors[1].starts_with("rule 'broken': trigger.triggers[2].pattern:"));
+    }
 }
@@ -0,0 +1,414 @@
+use crate::dead_letter::DeadLetter;
+use crate::engine::{EngineError, EngineStatus};
+use axum::{
+    Json, Router,
+    extract::{Path, Request, State},
+    http::{StatusCode, header},
+    middleware::{self, Next},
+    response::{IntoResponse, Response},
+    routing::{get, post},
+};
+use serde_json::{Value, json};
+use std::net::SocketAddr;
+use tokio::net::TcpListener;
+use tokio::sync::{mpsc, oneshot};
+use tracing::info;
+
+/// A request from the control API, answered by the task that owns the engine
+/// through [`Engine::handle_control`](crate::engine::Engine::handle_control)
+pub enum ControlCommand {
+    Status {
+        reply: oneshot::Sender<EngineStatus>,
+    },
+    Reload {
+        reply: oneshot::Sender<Result<(), String>>,
+    },
+    SetRuleEnabled {
+        name: String,
+        enabled: bool,
+        reply: oneshot::Sender<bool>,
+    },
+    RestartPlugin {
+        name: String,
+        reply: oneshot::Sender<Result<(), EngineError>>,
+    },
+    DeadLetters {
+        reply: oneshot::Sender<Vec<DeadLetter>>,
+    },
+}
+
+/// Local HTTP API for inspecting and steering a running engine:
+///
+/// - `GET /status` - plugin and rule counts, and each rule's enabled state
+/// - `POST /reload` - re-read the config file and apply it
+/// - `POST /rules/{name}/enable` and `POST /rules/{name}/disable`
+/// - `POST /plugins/{name}/restart` - stop and re-create a single source
+/// - `GET /dead-letters` - recent action failures with their events
+///
+/// Only listens on 127.0.0.1, and refuses requests from web pages (see
+/// [`reject_cross_origin`]).
+pub struct ControlServer {
+    commands: mpsc::Sender<ControlCommand>,
+    port: u16,
+}
+
+impl ControlServer {
+    pub fn new(commands: mpsc::Sender<ControlCommand>, port: u16) -> Self {
+        Self { commands, port }
+    }
+
+    fn router(&self) -> Router {
+        Router::new()
+            .route("/status", get(status_handler))
+            .route("/reload", post(reload_handler))
+            .route("/rules/:name/enable", post(enable_rule_handler))
+            .route("/rules/:name/disable", post(disable_rule_handler))
+            .route("/plugins/:name/restart", post(restart_plugin_handler))
+            .route("/dead-letters", get(dead_letters_handler))
+            .layer(middleware::from_fn(reject_cross_origin))
+            .with_state(self.commands.clone())
+    }
+
+    pub async fn start(&self) -> Result<(), Box<dyn std::error::Error>> {
+        let addr = SocketAddr::from(([127, 0, 0, 1], self.port));
+        info!("Starting control API on http://{}", addr);
+
+        let listener = TcpListener::bind(addr).await?;
+        axum::serve(listener, self.router()).await?;
+
+        Ok(())
+    }
+}
+
+type ControlError = (StatusCode, Json<Value>);
+
+/// Any web page the user has open can POST to localhost, but browsers always
+/// attach an `Origin` header when they do. Local tools like curl don't send
+/// one, so requests that carry it are refused.
+async fn reject_cross_origin(request: Request, next: Next) -> Response {
+    if request.headers().contains_key(header::ORIGIN) {
+        return (
+            StatusCode::FORBIDDEN,
+            Json(json!({ "error": "requests from web pages are not allowed" })),
+        )
+            .into_response();
+    }
+    next.run(request).await
+}
+
+fn engine_unavailable() -> ControlError {
+    (
+        StatusCode::SERVICE_UNAVAILABLE,
+        Json(json!({ "error": "engine is not running" })),
+    )
+}
+
+/// Send a command to the engine and wait for its reply
+async fn request<T>(
+    commands: &mpsc::Sender<ControlCommand>,
+    command: impl FnOnce(oneshot::Sender<T>) -> ControlCommand,
+) -> Result<T, ControlError> {
+    let (reply, response) = oneshot::channel();
+    commands
+        .send(command(reply))
+        .await
+        .map_err(|_| engine_unavailable())?;
+    response.await.map_err(|_| engine_unavailable())
+}
+
+async fn status_handler(
+    State(commands): State<mpsc::Sender<ControlCommand>>,
+) -> Result<Json<EngineStatus>, ControlError> {
+    let status = request(&commands, |reply| ControlCommand::Status { reply }).await?;
+    Ok(Json(status))
+}
+
+async fn reload_handler(
+    State(commands): State<mpsc::Sender<ControlCommand>>,
+) -> Result<Json<Value>, ControlError> {
+    match request(&commands, |reply| ControlCommand::Reload { reply }).await? {
+        Ok(()) => Ok(Json(json!({ "reloaded": true }))),
+        Err(e) => Err((
+            StatusCode::UNPROCESSABLE_ENTITY,
+            Json(json!({ "error": e })),
+        )),
+    }
+}
+
+async fn set_rule_enabled(
+    commands: &mpsc::Sender<ControlCommand>,
+    name: String,
+    enabled: bool,
+) -> Result<Json<Value>, ControlError> {
+    let found = request(commands, |reply| ControlCommand::SetRuleEnabled {
+        name: name.clone(),
+        enabled,
+        reply,
+    })
+    .await?;
+
+    if found {
+        Ok(Json(json!({ "rule": name, "enabled": enabled })))
+    } else {
+        Err((
+            StatusCode::NOT_FOUND,
+            Json(json!({ "error": format!("no rule named '{}'", name) })),
+        ))
+    }
+}
+
+async fn enable_rule_handler(
+    State(commands): State<mpsc::Sender<ControlCommand>>,
+    Path(name): Path<String>,
+) -> Result<Json<Value>, ControlError> {
+    set_rule_enabled(&commands, name, true).await
+}
+
+async fn disable_rule_handler(
+    State(commands): State<mpsc::Sender<ControlCommand>>,
+    Path(name): Path<String>,
+) -> Result<Json<Value>, ControlError> {
+    set_rule_enabled(&commands, name, false).await
+}
+
+async fn restart_plugin_handler(
+    State(commands): State<mpsc::Sender<ControlCommand>>,
+    Path(name): Path<String>,
+) -> Result<Json<Value>, ControlError> {
+    let result = request(&commands, |reply| ControlCommand::RestartPlugin {
+        name: name.clone(),
+        reply,
+    })
+    .await?;
+
+    match result {
+        Ok(()) => Ok(Json(json!({ "plugin": name, "restarted": true }))),
+        Err(EngineError::Config(e)) => Err((StatusCode::NOT_FOUND, Json(json!({ "error": e })))),
+        Err(e) => Err((
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({ "error": e.to_string() })),
+        )),
+    }
+}
+
+async fn dead_letters_handler(
+    State(commands): State<mpsc::Sender<ControlCommand>>,
+) -> Result<Json<Vec<DeadLetter>>, ControlError> {
+    let letters = request(&commands, |reply| ControlCommand::DeadLetters { reply }).await?;
+    Ok(Json(letters))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{ActionConfig, Config, RuleConfig, TriggerConfig};
+    use crate::engine::Engine;
+    use engine_core::event::{Event, EventKind};
+    use std::collections::HashMap;
+    use std::io::Write;
+
+    fn tick_rule(name: &str) -> RuleConfig {
+        RuleConfig {
+            name: name.to_string(),
+            description: None,
+            trigger: TriggerConfig::Timer {
+                interval_seconds: 60,
+            },
+            actions: vec![ActionConfig::Log {
+                message: format!("{} fired", name),
+                level: "info".to_string(),
+            }],
+            on_error: "continue".to_string(),
+            circuit_breaker: None,
+            dedup_window: None,
+            enabled: true,
+        }
+    }
+
+    /// Start an engine from `config_file` and serve control commands for it
+    /// on a background task, the way `main` does
+    async fn control_engine(
+        config_file: &std::path::Path,
+    ) -> (
+        mpsc::Sender<ControlCommand>,
+        tokio::task::JoinHandle<Engine>,
+    ) {
+        let config = Config::load_from_file(&config_file.to_path_buf()).unwrap();
+        let mut engine = Engine::new(config, Some(config_file.to_path_buf()));
+        engine
+            .initialize()
+            .await
+            .expect("Failed to initialize engine");
+
+        let (commands, mut rx) = mpsc::channel(8);
+        let task = tokio::spawn(async move {
+            while let Some(command) = rx.recv().await {
+                engine.handle_control(command).await;
+            }
+            engine
+        });
+        (commands, task)
+    }
+
+    fn write_config(file: &mut tempfile::NamedTempFile, rules: &[&str]) {
+        let config = Config {
+            rules: rules.iter().map(|name| tick_rule(name)).collect(),
+            ..Default::default()
+        };
+        file.as_file().set_len(0).unwrap();
+        let mut handle = file.reopen().unwrap();
+        handle
+            .write_all(toml::to_string(&config).unwrap().as_bytes())
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_status_and_rule_toggles() {
+        let mut config_file = tempfile::NamedTempFile::new().unwrap();
+        write_config(&mut config_file, &["ticks", "other"]);
+        let (commands, task) = control_engine(config_file.path()).await;
+
+        let Json(status) = status_handler(State(commands.clone())).await.ok().unwrap();
+        assert_eq!(status.active_rules, 2);
+        assert!(status.rules.iter().all(|rule| rule.enabled));
+
+        let Json(body) = disable_rule_handler(State(commands.clone()), Path("ticks".into()))
+            .await
+            .ok()
+            .unwrap();
+        assert_eq!(body, json!({ "rule": "ticks", "enabled": false }));
+
+        let Json(status) = status_handler(State(commands.clone())).await.ok().unwrap();
+        let ticks = status
+            .rules
+            .iter()
+            .find(|rule| rule.name == "ticks")
+            .unwrap();
+        assert!(!ticks.enabled);
+
+        let missing = enable_rule_handler(State(commands.clone()), Path("nope".into())).await;
+        assert!(matches!(missing, Err((StatusCode::NOT_FOUND, _))));
+
+        drop(commands);
+        let mut engine = task.await.unwrap();
+
+        // A disabled rule is skipped by the running processing loop
+        let sender = engine.event_sender().unwrap();
+        sender
+            .send(Event::new(EventKind::TimerTick, "test"))
+            .await
+            .unwrap();
+        drop(sender);
+        engine.shutdown().await;
+
+        let matches = |rule: &str| {
+            let labels = HashMap::from([("rule".to_string(), rule.to_string())]);
+            engine.metrics().get_counter("rules_matched_total", &labels)
+        };
+        assert_eq!(matches("ticks"), None);
+        assert_eq!(matches("other"), Some(1));
+    }
+
+    #[tokio::test]
+    async fn test_reload_handler() {
+        let mut config_file = tempfile::NamedTempFile::new().unwrap();
+        write_config(&mut config_file, &["first"]);
+        let (commands, task) = control_engine(config_file.path()).await;
+
+        write_config(&mut config_file, &["first", "second"]);
+        let Json(body) = reload_handler(State(commands.clone())).await.ok().unwrap();
+        assert_eq!(body, json!({ "reloaded": true }));
+
+        let Json(status) = status_handler(State(commands.clone())).await.ok().unwrap();
+        assert_eq!(status.active_rules, 2);
+
+        // An invalid config is rejected and the running one kept
+        std::fs::write(config_file.path(), "[[rules]]\nname = \"broken\"\n").unwrap();
+        let rejected = reload_handler(State(commands.clone())).await;
+        assert!(matches!(
+            rejected,
+            Err((StatusCode::UNPROCESSABLE_ENTITY, _))
+        ));
+
+        let Json(status) = status_handler(State(commands.clone())).await.ok().unwrap();
+        assert_eq!(status.active_rules, 2);
+
+        drop(commands);
+        task.await.unwrap().shutdown().await;
+    }
+
+    #[tokio::test]
+    async fn test_restart_plugin_handler() {
+        let config_file = tempfile::NamedTempFile::new().unwrap();
+        std::fs::write(
+            config_file.path(),
+            "[[sources]]\nname = \"timer\"\ntype = \"timer\"\ninterval_seconds = 60\nenabled = true\n",
+        )
+        .unwrap();
+        let (commands, task) = control_engine(config_file.path()).await;
+
+        let Json(body) = restart_plugin_handler(State(commands.clone()), Path("timer".into()))
+            .await
+            .ok()
+            .unwrap();
+        assert_eq!(body, json!({ "plugin": "timer", "restarted": true }));
+
+        let missing = restart_plugin_handler(State(commands.clone()), Path("nope".into())).await;
+        assert!(matches!(missing, Err((StatusCode::NOT_FOUND, _))));
+
+        drop(commands);
+        task.await.unwrap().shutdown().await;
+    }
+
+    /// Send a raw HTTP request and return the response's status line
+    async fn status_line(addr: SocketAddr, request: &str) -> String {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        let mut stream = tokio::net::TcpStream::connect(addr).await.unwrap();
+        stream.write_all(request.as_bytes()).await.unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).await.unwrap();
+        response.lines().next().unwrap_or_default().to_string()
+    }
+
+    #[tokio::test]
+    async fn test_requests_from_web_pages_rejected() {
+        let mut config_file = tempfile::NamedTempFile::new().unwrap();
+        write_config(&mut config_file, &["ticks"]);
+        let (commands, task) = control_engine(config_file.path()).await;
+
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let router = ControlServer::new(commands, 0).router();
+        let server = tokio::spawn(async move { axum::serve(listener, router).await });
+
+        let from_page = status_line(
+            addr,
+            "POST /rules/ticks/disable HTTP/1.1\r\nHost: localhost\r\n\
+             Origin: https://example.com\r\nContent-Length: 0\r\nConnection: close\r\n\r\n",
+        )
+        .await;
+        assert!(from_page.contains("403"), "{}", from_page);
+
+        let from_tool = status_line(
+            addr,
+            "POST /rules/ticks/disable HTTP/1.1\r\nHost: localhost\r\n\
+             Content-Length: 0\r\nConnection: close\r\n\r\n",
+        )
+        .await;
+        assert!(from_tool.contains("200"), "{}", from_tool);
+
+        server.abort();
+        let _ = server.await;
+        task.await.unwrap().shutdown().await;
+    }
+
+    #[tokio::test]
+    async fn test_handlers_without_engine() {
+        let (commands, rx) = mpsc::channel(1);
+        drop(rx);
+
+        let result = status_handler(State(commands)).await;
+        assert!(matches!(result, Err((StatusCode::SERVICE_UNAVAILABLE, _))));
+    }
+}
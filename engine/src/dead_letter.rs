@@ -0,0 +1,222 @@
+use chrono::{DateTime, Utc};
+use engine_core::event::Event;
+use engine_core::log_rotation;
+use serde::Serialize;
+use std::collections::VecDeque;
+use std::fs::{File, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use tracing::warn;
+
+/// An action failure, kept so it can be inspected or replayed later
+#[derive(Debug, Clone, Serialize)]
+pub struct DeadLetter {
+    pub timestamp: DateTime<Utc>,
+    pub action: String,
+    pub error: String,
+    pub event: Event,
+}
+
+/// The most recent action failures, oldest dropped first once `capacity` is
+/// reached. With a file attached, every failure is also appended to it as a
+/// JSON line, regardless of capacity; the file is rotated by size.
+pub struct DeadLetterQueue {
+    capacity: usize,
+    entries: Mutex<VecDeque<DeadLetter>>,
+    file: Option<Mutex<DeadLetterFile>>,
+}
+
+/// The file failures are appended to, rolled to `path.1` (and older files up
+/// to `path.<max_files>`) when a line would take it past `max_bytes`
+struct DeadLetterFile {
+    path: PathBuf,
+    max_bytes: u64,
+    max_files: usize,
+    file: Option<File>,
+    size: u64,
+}
+
+impl DeadLetterFile {
+    fn open(&mut self) -> io::Result<&mut File> {
+        let file = match self.file.take() {
+            Some(file) => file,
+            None => {
+                let file = OpenOptions::new()
+                    .create(true)
+                    .append(true)
+                    .open(&self.path)?;
+                self.size = file.metadata()?.len();
+                file
+            }
+        };
+        Ok(self.file.insert(file))
+    }
+
+    fn append(&mut self, line: &[u8]) -> io::Result<()> {
+        self.open()?;
+        if log_rotation::should_rotate(self.size, line.len(), self.max_bytes) {
+            // Windows won't rename a file that is still open
+            self.file = None;
+            log_rotation::rotate_files(&self.path, self.max_files)?;
+        }
+        self.open()?.write_all(line)?;
+        self.size += line.len() as u64;
+        Ok(())
+    }
+}
+
+impl DeadLetterQueue {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            entries: Mutex::new(VecDeque::with_capacity(capacity)),
+            file: None,
+        }
+    }
+
+    /// Also append every failure to `path`, rotating it once a line would take
+    /// it past `max_bytes` and keeping `max_files` rotated files
+    pub fn with_file(mut self, path: &Path, max_bytes: u64, max_files: usize) -> io::Result<Self> {
+        let mut file = DeadLetterFile {
+            path: path.to_path_buf(),
+            max_bytes,
+            max_files,
+            file: None,
+            size: 0,
+        };
+        file.open()?;
+        self.file = Some(Mutex::new(file));
+        Ok(self)
+    }
+
+    /// Start with the most recent entries of `previous` that fit, so failures
+    /// recorded before a reload stay visible
+    pub fn with_entries_from(self, previous: &DeadLetterQueue) -> Self {
+        {
+            let mut entries = self.entries.lock().unwrap_or_else(|e| e.into_inner());
+            let previous = previous.entries();
+            let skip = previous.len().saturating_sub(self.capacity);
+            entries.extend(previous.into_iter().skip(skip));
+        }
+        self
+    }
+
+    pub fn record(&self, action: &str, error: impl std::fmt::Display, event: Event) {
+        let letter = DeadLetter {
+            timestamp: Utc::now(),
+            action: action.to_string(),
+            error: error.to_string(),
+            event,
+        };
+
+        if let Some(file) = &self.file {
+            let written = serde_json::to_vec(&letter)
+                .map_err(|e| e.to_string())
+                .and_then(|mut line| {
+                    line.push(b'\n');
+                    let mut file = file.lock().unwrap_or_else(|e| e.into_inner());
+                    file.append(&line).map_err(|e| e.to_string())
+                });
+            if let Err(e) = written {
+                warn!("Failed to write dead letter for action '{}': {}", action, e);
+            }
+        }
+
+        if self.capacity == 0 {
+            return;
+        }
+        let mut entries = self.entries.lock().unwrap_or_else(|e| e.into_inner());
+        if entries.len() == self.capacity {
+            entries.pop_front();
+        }
+        entries.push_back(letter);
+    }
+
+    /// Recorded failures, oldest first
+    pub fn entries(&self) -> Vec<DeadLetter> {
+        self.entries
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .iter()
+            .cloned()
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use engine_core::event::EventKind;
+
+    #[test]
+    fn test_oldest_dropped_at_capacity() {
+        let queue = DeadLetterQueue::new(2);
+        for action in ["first", "second", "third"] {
+            queue.record(action, "boom", Event::new(EventKind::TimerTick, "test"));
+        }
+
+        let actions: Vec<String> = queue
+            .entries()
+            .into_iter()
+            .map(|letter| letter.action)
+            .collect();
+        assert_eq!(actions, vec!["second", "third"]);
+    }
+
+    #[test]
+    fn test_failures_appended_to_file() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let path = dir.path().join("dead_letters.jsonl");
+        let queue = DeadLetterQueue::new(0)
+            .with_file(&path, log_rotation::DEFAULT_MAX_BYTES, 1)
+            .unwrap();
+
+        let event = Event::new(EventKind::TimerTick, "timer");
+        queue.record("webhook", "connection refused", event.clone());
+        assert!(queue.entries().is_empty());
+
+        let contents = std::fs::read_to_string(&path).unwrap();
+        let letter: serde_json::Value = serde_json::from_str(contents.trim()).unwrap();
+        assert_eq!(letter["action"], "webhook");
+        assert_eq!(letter["error"], "connection refused");
+        assert_eq!(letter["event"]["id"], event.id.to_string());
+    }
+
+    #[test]
+    fn test_file_rotated_by_size() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let path = dir.path().join("dead_letters.jsonl");
+        // Every line is far longer than this, so each lands in a file of its own
+        let queue = DeadLetterQueue::new(0).with_file(&path, 10, 1).unwrap();
+
+        for action in ["first", "second", "third"] {
+            queue.record(action, "boom", Event::new(EventKind::TimerTick, "timer"));
+        }
+
+        let action = |path: &Path| {
+            let contents = std::fs::read_to_string(path).unwrap();
+            let letter: serde_json::Value = serde_json::from_str(contents.trim()).unwrap();
+            letter["action"].as_str().unwrap().to_string()
+        };
+        assert_eq!(action(&path), "third");
+        assert_eq!(action(&log_rotation::rotated_path(&path, 1)), "second");
+        assert!(!log_rotation::rotated_path(&path, 2).exists());
+    }
+
+    #[test]
+    fn test_entries_carried_over() {
+        let previous = DeadLetterQueue::new(3);
+        for action in ["first", "second", "third"] {
+            previous.record(action, "boom", Event::new(EventKind::TimerTick, "test"));
+        }
+
+        let queue = DeadLetterQueue::new(2).with_entries_from(&previous);
+        let actions: Vec<String> = queue
+            .entries()
+            .into_iter()
+            .map(|letter| letter.action)
+            .collect();
+        assert_eq!(actions, vec!["second", "third"]);
+    }
+}
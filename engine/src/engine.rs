@@ -1,51 +1,126 @@
-use crate::config::{ActionConfig, Config, RuleConfig, SourceConfig, SourceType, TriggerConfig};
+use crate::config::{
+    ActionConfig, CircuitBreakerConfig, Config, RuleConfig, SinkConfig, SinkType, SourceConfig,
+    SourceType, TriggerConfig, resolve_script_path,
+};
+use crate::control::ControlCommand;
+use crate::dead_letter::{DeadLetter, DeadLetterQueue};
+use crate::plugins::clipboard_monitor::ClipboardMonitorPlugin;
+use crate::plugins::device_monitor::DeviceMonitorPlugin;
 use crate::plugins::file_watcher::FileWatcherPlugin;
+use crate::plugins::idle_monitor::IdleMonitorPlugin;
 use crate::plugins::process_monitor::ProcessMonitorPlugin;
 use crate::plugins::registry_monitor::{RegistryMonitorPlugin, RegistryRoot};
+use crate::plugins::timer::TimerPlugin;
 use crate::plugins::window_watcher::WindowEventPlugin;
-use actions::{Action, ActionExecutor, ExecuteAction, LogAction, LogLevel, PowerShellAction};
-use bus::create_event_bus;
+use crate::sinks::jsonl_file::JsonlFileSink;
+use actions::{
+    Action, ActionError, ActionExecutor, ActionResult, CircuitBreakerAction, CompositeAction,
+    ErrorBehavior, ExecuteAction, FileAppendAction, HttpAction, LogAction, LogLevel, NotifyAction,
+    PowerShellAction,
+};
+use bus::{EventBus, Subscription, create_event_bus};
 use engine_core::event::EventKind;
-use engine_core::plugin::EventSourcePlugin;
+use engine_core::plugin::{EventEmitter, EventSequence, EventSourcePlugin, PluginHealth};
+use engine_core::sink::EventSink;
+use metrics::server::{RuleInfo, RuleRegistry};
 use metrics::{
-    record_event_processing_duration, record_rule_match_duration, MetricsCollector,
+    ActionStatus, MetricsCollector, record_action_timeout, record_event_processing_duration,
+    record_plugin_error, record_rule_match_duration,
 };
-use rules::{EventKindMatcher, FilePatternMatcher, Rule, RuleMatcher, WindowMatcher, WindowEventType};
-use std::path::PathBuf;
-use std::sync::Arc;
-use tokio::sync::mpsc;
+use rules::{
+    CompositeMatcher, DedupMatcher, EventKindMatcher, FilePatternMatcher, MatchOperator,
+    ProcessMatcher, Rule, RuleMatcher, RuleSet, WindowEventType, WindowMatcher,
+};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::io::{BufRead, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, RwLock};
+use tokio::sync::{Semaphore, mpsc};
+use tokio::task::JoinHandle;
 use tokio::time::{Duration, timeout, Instant};
 use tracing::{error, info, warn};
 
+/// How long shutdown and reload wait for events already on the bus to be processed
+pub const SHUTDOWN_DRAIN_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Quiet period after the last config file change before a reload is signalled
+pub const CONFIG_RELOAD_DEBOUNCE: Duration = Duration::from_millis(500);
+
 pub struct Engine {
     config: Config,
     config_path: Option<PathBuf>,
-    plugins: Vec<Box<dyn EventSourcePlugin>>,
-    rules: Vec<Rule>,
-    action_executor: ActionExecutor,
+    /// Running sources, keyed by source name
+    plugins: HashMap<String, Box<dyn EventSourcePlugin>>,
+    /// Shared with the processing loop so enabling or disabling a rule takes
+    /// effect on the next event
+    rules: Arc<RwLock<RuleSet>>,
+    /// Also shared with the processing loop, and swapped as a whole when only
+    /// the rules are reloaded
+    action_executor: Arc<RwLock<Arc<ActionExecutor>>>,
     event_sender: Option<mpsc::Sender<engine_core::event::Event>>,
+    event_loop: Option<JoinHandle<()>>,
+    /// Actions dispatched by the processing loop that may still be running
+    running_actions: Arc<Mutex<Vec<JoinHandle<()>>>>,
+    /// One task per sink, each fed from the processing loop; they finish once
+    /// the loop exits and they have written everything it handed them
+    sink_tasks: Vec<JoinHandle<()>>,
+    events_processed: Arc<AtomicU64>,
+    /// Numbers events as they enter the bus; kept across reloads, and seeded
+    /// from the clock so numbers don't repeat across restarts either
+    event_seq: Arc<EventSequence>,
+    dry_run: bool,
     shutdown_flag: Arc<std::sync::atomic::AtomicBool>,
+    paused: Arc<std::sync::atomic::AtomicBool>,
     config_reload_rx: Option<mpsc::Receiver<()>>,
     metrics: Arc<MetricsCollector>,
+    dead_letters: Arc<DeadLetterQueue>,
+    /// When set, events are printed here instead of being matched against rules
+    event_dump: Option<Arc<Mutex<dyn Write + Send>>>,
 }
 
 impl Engine {
     pub fn new(config: Config, config_path: Option<PathBuf>) -> Self {
         let metrics = Arc::new(MetricsCollector::new());
+        let rules = Arc::new(RwLock::new(empty_rule_set(&config)));
+        let dead_letters = Arc::new(dead_letter_queue(&config));
         
         Self {
             config,
             config_path,
-            plugins: Vec::new(),
-            rules: Vec::new(),
-            action_executor: ActionExecutor::new(),
+            plugins: HashMap::new(),
+            rules,
+            action_executor: Arc::new(RwLock::new(Arc::new(ActionExecutor::new()))),
             event_sender: None,
+            event_loop: None,
+            running_actions: Arc::new(Mutex::new(Vec::new())),
+            sink_tasks: Vec::new(),
+            events_processed: Arc::new(AtomicU64::new(0)),
+            event_seq: Arc::new(EventSequence::starting_now()),
+            dry_run: false,
             shutdown_flag: Arc::new(std::sync::atomic::AtomicBool::new(false)),
+            paused: Arc::new(std::sync::atomic::AtomicBool::new(false)),
             config_reload_rx: None,
             metrics,
+            dead_letters,
+            event_dump: None,
         }
     }
     
+    /// Match rules as usual but log matched actions instead of running them
+    pub fn with_dry_run(mut self, dry_run: bool) -> Self {
+        self.dry_run = dry_run;
+        self
+    }
+
+    /// Load no rules and write every event the plugins emit to `out` instead,
+    /// to see what a matcher would be given
+    pub fn with_event_dump(mut self, out: impl Write + Send + 'static) -> Self {
+        self.event_dump = Some(Arc::new(Mutex::new(out)));
+        self
+    }
+
     /// Get a reference to the metrics collector
     pub fn metrics(&self) -> Arc<MetricsCollector> {
         self.metrics.clone()
@@ -58,25 +133,53 @@ impl Engine {
     pub async fn initialize(&mut self) -> Result<(), EngineError> {
         info!("Initializing Windows Event Automation Engine");
 
-        // Create event bus
-        let (sender, mut receiver) = create_event_bus(self.config.engine.event_buffer_size);
-        self.event_sender = Some(sender.clone());
-
-        // Initialize plugins from configuration
-        self.initialize_plugins(sender.clone()).await?;
-
         // Initialize rules from configuration
         self.initialize_rules();
 
         // Initialize actions from configuration
         self.initialize_actions();
 
-        // Start event processing loop
+        // Create event bus and start the processing loop
+        let sender = self.start_event_loop();
+
+        // Initialize plugins from configuration
+        self.initialize_plugins(sender).await?;
+        self.publish_status();
+
+        info!("Engine initialized successfully");
+        Ok(())
+    }
+
+    /// Create a fresh event bus and spawn a processing loop over the current
+    /// rules, returning the sender plugins should emit into
+    fn start_event_loop(&mut self) -> mpsc::Sender<engine_core::event::Event> {
+        let (sender, mut receiver) = create_event_bus(self.config.engine.event_buffer_size);
+        self.event_sender = Some(sender.clone());
+
+        if let Some(out) = self.event_dump.clone() {
+            self.event_loop = Some(spawn_event_dump(
+                receiver,
+                out,
+                self.events_processed.clone(),
+            ));
+            return sender;
+        }
+
         let rules = self.rules.clone();
         let action_executor = self.action_executor.clone();
+        let action_limiter = Arc::new(Semaphore::new(self.config.engine.max_concurrent_actions));
         let metrics = self.metrics.clone();
-
-        tokio::spawn(async move {
+        let events_processed = self.events_processed.clone();
+        let dead_letters = self.dead_letters.clone();
+        let dry_run = self.dry_run;
+        let action_timeout = Some(self.config.engine.action_timeout_seconds)
+            .filter(|&seconds| seconds > 0)
+            .map(Duration::from_secs);
+        let running_actions = self.running_actions.clone();
+        let paused = self.paused.clone();
+        let sink_bus = self.start_sinks();
+
+        self.event_loop = Some(tokio::spawn(async move {
             info!("Event processing loop started");
 
             while let Some(event) = receiver.recv().await {
@@ -86,10 +189,18 @@ impl Engine {
 
                 // Record event received with broadcast
                 metrics.record_event_with_broadcast(&event_source, &event_type);
+                sink_bus.publish(&event);
 
                 tracing::debug!("Processing event: {:?} from {}", event.kind, event.source);
 
-                for (idx, rule) in rules.iter().enumerate() {
+                // Taken under the rules lock, so a rules-only reload can't pair
+                // new rules with old actions or the other way round
+                let rules = rules.read().unwrap_or_else(|e| e.into_inner());
+                let action_executor = action_executor
+                    .read()
+                    .unwrap_or_else(|e| e.into_inner())
+                    .clone();
+                for rule in rules.candidates(&event.kind) {
                     if !rule.enabled {
                         continue;
                     }
@@ -106,39 +217,121 @@ impl Engine {
                         metrics.record_rule_match_with_broadcast(&rule.name);
                         info!("Rule '{}' matched event from {}", rule.name, event.source);
 
-                        let action_name = format!("rule_{}_action", idx);
-                        let action_start = Instant::now();
-
-                        match action_executor.execute(&action_name, &event) {
-                            Ok(result) => {
-                                metrics.record_action_execution_with_broadcast(
-                                    &action_name,
-                                    true,
-                                    action_start.elapsed()
-                                );
-                                info!("Action executed successfully: {:?}", result);
-                            }
-                            Err(e) => {
-                                metrics.record_action_execution_with_broadcast(
-                                    &action_name,
-                                    false,
-                                    action_start.elapsed()
-                                );
-                                error!("Action execution failed: {}", e);
-                            }
+                        if paused.load(Ordering::Relaxed) {
+                            tracing::debug!(
+                                "Engine paused, not running action for rule '{}'",
+                                rule.name
+                            );
+                            continue;
                         }
+
+                        let action = dispatch_action(
+                            action_executor.clone(),
+                            action_limiter.clone(),
+                            metrics.clone(),
+                            dead_letters.clone(),
+                            rule.name.clone(),
+                            event.clone(),
+                            action_timeout,
+                            dry_run,
+                        );
+                        let mut running = running_actions.lock().unwrap_or_else(|e| e.into_inner());
+                        running.retain(|action| !action.is_finished());
+                        running.push(action);
                     }
                 }
 
                 // Record total event processing duration
                 record_event_processing_duration(&metrics, start_time.elapsed());
+                events_processed.fetch_add(1, Ordering::Relaxed);
             }
 
             info!("Event processing loop stopped");
-        });
+        }));
 
-        info!("Engine initialized successfully");
-        Ok(())
+        sender
+    }
+
+    /// Open every enabled sink and spawn a task feeding it from a fresh
+    /// [`EventBus`], which the processing loop publishes each event to
+    fn start_sinks(&mut self) -> EventBus {
+        let bus = EventBus::new();
+
+        for sink_config in &self.config.sinks {
+            if !sink_config.enabled {
+                info!("Skipping disabled sink: {}", sink_config.name);
+                continue;
+            }
+
+            match Self::create_sink(sink_config) {
+                Ok(sink) => {
+                    info!("Initialized sink: {}", sink_config.name);
+                    let subscription =
+                        bus.subscribe(&sink_config.name, self.config.engine.event_buffer_size);
+                    self.sink_tasks
+                        .push(spawn_sink(sink, subscription, self.metrics.clone()));
+                }
+                Err(e) => {
+                    error!("Failed to initialize sink {}: {}", sink_config.name, e);
+                }
+            }
+        }
+
+        bus
+    }
+
+    fn create_sink(config: &SinkConfig) -> Result<Box<dyn EventSink>, EngineError> {
+        match &config.sink_type {
+            SinkType::JsonlFile {
+                path,
+                max_bytes,
+                max_files,
+            } => {
+                let sink = JsonlFileSink::new(&config.name, path)
+                    .map_err(|e| EngineError::SinkInit(config.name.clone(), e.to_string()))?
+                    .with_max_bytes(*max_bytes)
+                    .with_max_files(*max_files);
+                Ok(Box::new(sink))
+            }
+        }
+    }
+
+    /// Close the bus and wait (up to [`SHUTDOWN_DRAIN_TIMEOUT`]) for the
+    /// processing loop and sinks to work through whatever is still queued.
+    /// Plugins must already be stopped, or the bus never closes.
+    async fn stop_event_loop(&mut self) {
+        let processed_before = self.events_processed.load(Ordering::Relaxed);
+        let deadline = Instant::now() + SHUTDOWN_DRAIN_TIMEOUT;
+
+        // Once the last sender is gone the bus closes, and the loop exits after
+        // processing everything already buffered
+        self.event_sender = None;
+
+        if let Some(mut event_loop) = self.event_loop.take() {
+            let finished = timeout(SHUTDOWN_DRAIN_TIMEOUT, &mut event_loop)
+                .await
+                .is_ok();
+            let drained = self.events_processed.load(Ordering::Relaxed) - processed_before;
+
+            if finished {
+                info!("Drained {} buffered events", drained);
+            } else {
+                event_loop.abort();
+                warn!(
+                    "Event drain timed out after {:?}; processed {} buffered events, abandoning the rest",
+                    SHUTDOWN_DRAIN_TIMEOUT, drained
+                );
+            }
+        }
+
+        // The loop owned the sinks' bus, so they are now finishing their backlog
+        for mut sink_task in self.sink_tasks.drain(..) {
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            if timeout(remaining, &mut sink_task).await.is_err() {
+                sink_task.abort();
+                warn!("Event sink drain timed out, abandoning unwritten events");
+            }
+        }
     }
 
     async fn initialize_plugins(
@@ -154,7 +347,7 @@ impl Engine {
             match self.create_plugin(source_config, sender.clone()).await {
                 Ok(plugin) => {
                     info!("Initialized plugin: {}", source_config.name);
-                    self.plugins.push(plugin);
+                    self.plugins.insert(source_config.name.clone(), plugin);
                 }
                 Err(e) => {
                     error!("Failed to initialize plugin {}: {}", source_config.name, e);
@@ -165,24 +358,49 @@ impl Engine {
         Ok(())
     }
 
+    /// Wrap the bus sender for a plugin, numbering its events and counting
+    /// those it has to drop
+    pub(crate) fn plugin_emitter(
+        &self,
+        sender: mpsc::Sender<engine_core::event::Event>,
+    ) -> EventEmitter {
+        let metrics = self.metrics.clone();
+        let emitter = EventEmitter::new(sender).with_sequence(self.event_seq.clone());
+        emitter.with_drop_hook(Arc::new(move |event| {
+            let reason = format!("Event buffer full (source: {})", event.source);
+            metrics.record_event_dropped_with_broadcast(&reason);
+        }))
+    }
+
     async fn create_plugin(
         &self,
         config: &SourceConfig,
         sender: mpsc::Sender<engine_core::event::Event>,
     ) -> Result<Box<dyn EventSourcePlugin>, EngineError> {
+        let sender = self.plugin_emitter(sender);
+
         match &config.source_type {
             SourceType::FileWatcher {
                 paths,
-                pattern,
+                patterns,
+                exclude_patterns,
                 recursive,
+                follow_symlinks,
+                debounce_ms,
             } => {
-                let mut plugin =
-                    FileWatcherPlugin::new(&config.name, paths.clone()).with_recursive(*recursive);
+                let mut plugin = FileWatcherPlugin::new(&config.name, paths.clone())
+                    .with_recursive(*recursive)
+                    .with_follow_symlinks(*follow_symlinks)
+                    .with_debounce(Duration::from_millis(*debounce_ms));
 
-                if let Some(pattern) = pattern {
+                for pattern in patterns {
                     plugin = plugin.with_pattern(pattern);
                 }
 
+                for pattern in exclude_patterns {
+                    plugin = plugin.with_exclude_pattern(pattern);
+                }
+
                 plugin
                     .start(sender)
                     .await
@@ -193,8 +411,16 @@ impl Engine {
             SourceType::WindowWatcher {
                 title_pattern,
                 process_pattern,
+                emit_unfocus,
+                top_level_only,
+                track_location,
+                move_debounce_ms,
             } => {
-                let mut plugin = WindowEventPlugin::new(&config.name);
+                let mut plugin = WindowEventPlugin::new(&config.name)
+                    .with_emit_unfocus(*emit_unfocus)
+                    .with_top_level_only(*top_level_only)
+                    .with_location_tracking(*track_location)
+                    .with_move_debounce(Duration::from_millis(*move_debounce_ms));
 
                 if let Some(title) = title_pattern {
                     plugin = plugin.with_title_filter(title);
@@ -213,19 +439,33 @@ impl Engine {
             }
             SourceType::ProcessMonitor {
                 process_name,
+                command_line_pattern,
                 monitor_threads,
                 monitor_files,
                 monitor_network,
+                poll_interval_seconds,
+                prefer_polling,
+                io_coalesce_ms,
             } => {
                 let mut plugin = ProcessMonitorPlugin::new(&config.name)
                     .with_thread_monitoring(*monitor_threads)
                     .with_file_monitoring(*monitor_files)
-                    .with_network_monitoring(*monitor_network);
+                    .with_network_monitoring(*monitor_network)
+                    .with_poll_interval(Duration::from_secs(*poll_interval_seconds))
+                    .with_prefer_polling(*prefer_polling);
 
                 if let Some(name) = process_name {
                     plugin = plugin.with_name_filter(name);
                 }
 
+                if let Some(pattern) = command_line_pattern {
+                    plugin = plugin.with_command_line_filter(pattern);
+                }
+
+                if let Some(window) = io_coalesce_ms {
+                    plugin = plugin.with_io_coalesce(Duration::from_millis(*window));
+                }
+
                 plugin
                     .start(sender)
                     .await
@@ -237,6 +477,7 @@ impl Engine {
                 root,
                 key,
                 recursive,
+                value_name,
             } => {
                 let root_enum = match root.as_str() {
                     "HKLM" => RegistryRoot::HKEY_LOCAL_MACHINE,
@@ -257,6 +498,62 @@ impl Engine {
                     RegistryMonitorPlugin::new(&config.name).watch_key(root_enum, key)
                 };
 
+                if let Some(value_name) = value_name {
+                    plugin = plugin.with_value_filter(value_name);
+                }
+
+                plugin
+                    .start(sender)
+                    .await
+                    .map_err(|e| EngineError::PluginInit(config.name.clone(), e.to_string()))?;
+
+                Ok(Box::new(plugin))
+            }
+            SourceType::IdleMonitor {
+                threshold_seconds,
+                poll_interval_seconds,
+            } => {
+                let mut plugin = IdleMonitorPlugin::new(&config.name)
+                    .with_threshold(Duration::from_secs(*threshold_seconds))
+                    .with_poll_interval(Duration::from_secs(*poll_interval_seconds));
+
+                plugin
+                    .start(sender)
+                    .await
+                    .map_err(|e| EngineError::PluginInit(config.name.clone(), e.to_string()))?;
+
+                Ok(Box::new(plugin))
+            }
+            SourceType::ClipboardMonitor {
+                capture_text,
+                preview_chars,
+            } => {
+                let mut plugin = ClipboardMonitorPlugin::new(&config.name)
+                    .with_text_capture(*capture_text)
+                    .with_preview_chars(*preview_chars);
+
+                plugin
+                    .start(sender)
+                    .await
+                    .map_err(|e| EngineError::PluginInit(config.name.clone(), e.to_string()))?;
+
+                Ok(Box::new(plugin))
+            }
+            SourceType::DeviceMonitor { device_id_filter } => {
+                let mut plugin = DeviceMonitorPlugin::new(&config.name)
+                    .with_device_id_filter(device_id_filter.clone());
+
+                plugin
+                    .start(sender)
+                    .await
+                    .map_err(|e| EngineError::PluginInit(config.name.clone(), e.to_string()))?;
+
+                Ok(Box::new(plugin))
+            }
+            SourceType::Timer { interval_seconds } => {
+                let mut plugin =
+                    TimerPlugin::new(&config.name, Duration::from_secs(*interval_seconds));
+
                 plugin
                     .start(sender)
                     .await
@@ -268,6 +565,16 @@ impl Engine {
     }
 
     fn initialize_rules(&mut self) {
+        let rules = self.build_rules();
+        *self.rules.write().unwrap_or_else(|e| e.into_inner()) = rules;
+    }
+
+    fn build_rules(&self) -> RuleSet {
+        let mut rules = empty_rule_set(&self.config);
+        if self.event_dump.is_some() {
+            return rules;
+        }
+
         for rule_config in &self.config.rules {
             if !rule_config.enabled {
                 continue;
@@ -276,17 +583,38 @@ impl Engine {
             match self.create_rule(rule_config) {
                 Ok(rule) => {
                     info!("Loaded rule: {}", rule.name);
-                    self.rules.push(rule);
+                    rules.push(rule);
                 }
                 Err(e) => {
                     error!("Failed to create rule {}: {}", rule_config.name, e);
                 }
             }
         }
+
+        rules
     }
 
     fn create_rule(&self, config: &RuleConfig) -> Result<Rule, EngineError> {
-        let matcher: Box<dyn RuleMatcher> = match &config.trigger {
+        let mut matcher = Self::create_matcher(&config.trigger)?;
+        if let Some(dedup) = &config.dedup_window {
+            matcher = Box::new(DedupMatcher::new(
+                matcher,
+                Duration::from_secs(dedup.seconds),
+                dedup.key.clone(),
+            ));
+        }
+
+        let mut rule = Rule::new(&config.name, matcher);
+
+        if let Some(desc) = &config.description {
+            rule = rule.with_description(desc);
+        }
+
+        Ok(rule.with_enabled(config.enabled))
+    }
+
+    fn create_matcher(trigger: &TriggerConfig) -> Result<Box<dyn RuleMatcher>, EngineError> {
+        let matcher: Box<dyn RuleMatcher> = match trigger {
             TriggerConfig::FileCreated { pattern } => {
                 let mut matcher = FilePatternMatcher::created();
                 if let Some(pat) = pattern {
@@ -314,22 +642,65 @@ impl Engine {
                 }
                         Box::new(matcher)
             }
+            TriggerConfig::FileRenamed { pattern } => {
+                let mut matcher = FilePatternMatcher::renamed();
+                if let Some(pat) = pattern {
+                    matcher = matcher
+                        .with_file_pattern(pat)
+                        .map_err(|e| EngineError::Config(format!("Invalid pattern: {}", e)))?;
+                }
+                Box::new(matcher)
+            }
             TriggerConfig::WindowFocused {
                 title_contains,
+                title_regex,
                 process_name,
-            } => Box::new(WindowMatcher {
-                event_type: WindowEventType::Focused,
-                title_contains: title_contains.clone(),
-                process_name: process_name.clone(),
-            }),
+            } => Box::new(window_matcher(
+                WindowEventType::Focused,
+                title_contains,
+                title_regex,
+                process_name,
+            )?),
             TriggerConfig::WindowUnfocused {
                 title_contains,
+                title_regex,
                 process_name,
-            } => Box::new(WindowMatcher {
-                event_type: WindowEventType::Unfocused,
-                title_contains: title_contains.clone(),
-                process_name: process_name.clone(),
-            }),
+            } => Box::new(window_matcher(
+                WindowEventType::Unfocused,
+                title_contains,
+                title_regex,
+                process_name,
+            )?),
+            TriggerConfig::WindowMinimized {
+                title_contains,
+                title_regex,
+                process_name,
+            } => Box::new(window_matcher(
+                WindowEventType::Minimized,
+                title_contains,
+                title_regex,
+                process_name,
+            )?),
+            TriggerConfig::WindowRestored {
+                title_contains,
+                title_regex,
+                process_name,
+            } => Box::new(window_matcher(
+                WindowEventType::Restored,
+                title_contains,
+                title_regex,
+                process_name,
+            )?),
+            TriggerConfig::WindowMoved {
+                title_contains,
+                title_regex,
+                process_name,
+            } => Box::new(window_matcher(
+                WindowEventType::Moved,
+                title_contains,
+                title_regex,
+                process_name,
+            )?),
             TriggerConfig::WindowCreated => Box::new(EventKindMatcher {
                 kind: EventKind::WindowCreated {
                     hwnd: 0,
@@ -337,24 +708,16 @@ impl Engine {
                     process_id: 0,
                 },
             }),
-            TriggerConfig::ProcessStarted { process_name: _ } => Box::new(EventKindMatcher {
-                kind: EventKind::ProcessStarted {
-                    pid: 0,
-                    parent_pid: 0,
-                    name: String::new(),
-                    path: String::new(),
-                    command_line: String::new(),
-                    session_id: 0,
-                    user: String::new(),
-                },
-            }),
-            TriggerConfig::ProcessStopped { process_name: _ } => Box::new(EventKindMatcher {
-                kind: EventKind::ProcessStopped {
-                    pid: 0,
-                    name: String::new(),
-                    exit_code: None,
-                },
-            }),
+            TriggerConfig::ProcessStarted { process_name } => {
+                Box::new(with_process_name(ProcessMatcher::started(), process_name)?)
+            }
+            TriggerConfig::ProcessStopped {
+                process_name,
+                exit_code_nonzero,
+            } => Box::new(with_process_name(
+                ProcessMatcher::stopped().with_exit_code_nonzero(*exit_code_nonzero),
+                process_name,
+            )?),
             TriggerConfig::RegistryChanged { value_name: _ } => Box::new(EventKindMatcher {
                 kind: EventKind::RegistryChanged {
                     root: String::new(),
@@ -363,70 +726,180 @@ impl Engine {
                     change_type: engine_core::event::RegistryChangeType::Modified,
                 },
             }),
+            TriggerConfig::UserIdle => Box::new(EventKindMatcher {
+                kind: EventKind::UserIdle { idle_seconds: 0 },
+            }),
+            TriggerConfig::UserActive => Box::new(EventKindMatcher {
+                kind: EventKind::UserActive,
+            }),
+            TriggerConfig::ClipboardChanged => Box::new(EventKindMatcher {
+                kind: EventKind::ClipboardChanged {
+                    formats: Vec::new(),
+                    text_preview: None,
+                },
+            }),
+            TriggerConfig::DeviceArrived => Box::new(EventKindMatcher {
+                kind: EventKind::DeviceArrived {
+                    device_id: String::new(),
+                    description: None,
+                },
+            }),
+            TriggerConfig::DeviceRemoved => Box::new(EventKindMatcher {
+                kind: EventKind::DeviceRemoved {
+                    device_id: String::new(),
+                    description: None,
+                },
+            }),
             TriggerConfig::Timer {
                 interval_seconds: _,
             } => Box::new(EventKindMatcher {
                 kind: EventKind::TimerTick,
             }),
+            TriggerConfig::All { triggers } => Box::new(CompositeMatcher {
+                matchers: triggers
+                    .iter()
+                    .map(Self::create_matcher)
+                    .collect::<Result<_, _>>()?,
+                operator: MatchOperator::And,
+            }),
+            TriggerConfig::Any { triggers } => Box::new(CompositeMatcher {
+                matchers: triggers
+                    .iter()
+                    .map(Self::create_matcher)
+                    .collect::<Result<_, _>>()?,
+                operator: MatchOperator::Or,
+            }),
         };
 
-        let mut rule = Rule::new(&config.name, matcher);
+        Ok(matcher)
+    }
 
-        if let Some(desc) = &config.description {
-            rule = rule.with_description(desc);
+    fn initialize_actions(&mut self) {
+        let executor = self.build_actions();
+        *self
+            .action_executor
+            .write()
+            .unwrap_or_else(|e| e.into_inner()) = Arc::new(executor);
+    }
+
+    fn build_actions(&self) -> ActionExecutor {
+        let mut executor = ActionExecutor::new();
+        if self.event_dump.is_some() {
+            return executor;
         }
 
-        Ok(rule.with_enabled(config.enabled))
+        // Register each rule's actions under the rule's name, so reordering
+        // rules in the config never changes which action a rule runs
+        for rule_config in &self.config.rules {
+            // Each action gets its own breaker: a multi-action rule that
+            // continues on errors reports success, hiding its failures
+            let mut actions: Vec<Box<dyn Action>> = rule_config
+                .actions
+                .iter()
+                .map(|action_config| {
+                    let action = Self::create_action(action_config);
+                    match &rule_config.circuit_breaker {
+                        Some(breaker) => Self::with_circuit_breaker(action, breaker),
+                        None => action,
+                    }
+                })
+                .collect();
+
+            let action = if actions.len() == 1 {
+                actions.remove(0)
+            } else {
+                let on_error = rule_config.on_error.parse().unwrap_or_else(|e| {
+                    warn!("Rule {}: {}, continuing on errors", rule_config.name, e);
+                    ErrorBehavior::Continue
+                });
+                Box::new(CompositeAction::new(actions).with_error_behavior(on_error))
+            };
+
+            executor.register(rule_config.name.clone(), action);
+        }
+
+        executor
     }
 
-    fn initialize_actions(&mut self) {
-        // Register actions from rule configurations
-        for (idx, rule_config) in self.config.rules.iter().enumerate() {
-            let action_name = format!("rule_{}_action", idx);
-            let action: Box<dyn Action> = match &rule_config.action {
-                ActionConfig::Execute {
-                    command,
-                    args,
-                    working_dir,
-                } => {
-                    let mut exec = ExecuteAction::new(command).with_args(args.clone());
-                    if let Some(dir) = working_dir {
-                        exec = exec.with_working_dir(dir.clone());
-                    }
-                    Box::new(exec)
-                }
-                ActionConfig::PowerShell {
-                    script,
-                    working_dir,
-                } => {
-                    let mut ps = PowerShellAction::new(script);
-                    if let Some(dir) = working_dir {
-                        ps = ps.with_working_dir(dir.clone());
-                    }
-                    Box::new(ps)
+    fn with_circuit_breaker(
+        action: Box<dyn Action>,
+        breaker: &CircuitBreakerConfig,
+    ) -> Box<dyn Action> {
+        Box::new(
+            CircuitBreakerAction::new(action)
+                .with_failure_threshold(breaker.failure_threshold)
+                .with_cooldown(Duration::from_secs(breaker.cooldown_seconds))
+                .with_max_cooldown(Duration::from_secs(breaker.max_cooldown_seconds)),
+        )
+    }
+
+    fn create_action(action_config: &ActionConfig) -> Box<dyn Action> {
+        match action_config {
+            ActionConfig::Execute {
+                command,
+                args,
+                working_dir,
+            } => {
+                let mut exec = ExecuteAction::new(command).with_args(args.clone());
+                if let Some(dir) = working_dir {
+                    exec = exec.with_working_dir(dir.clone());
                 }
-                ActionConfig::Log { message, level } => {
-                    let log_level = match level.as_str() {
-                        "debug" => LogLevel::Debug,
-                        "info" => LogLevel::Info,
-                        "warn" => LogLevel::Warn,
-                        "error" => LogLevel::Error,
-                        _ => LogLevel::Info,
-                    };
-                    Box::new(LogAction::new(message).with_level(log_level))
+                Box::new(exec)
+            }
+            ActionConfig::PowerShell {
+                script,
+                working_dir,
+            } => {
+                let mut ps = PowerShellAction::new(script);
+                if let Some(dir) = working_dir {
+                    ps = ps.with_working_dir(dir.clone());
                 }
-                ActionConfig::Notify { title, message } => {
-                    // For now, use log action as a placeholder for notifications
-                    Box::new(LogAction::new(format!("{}: {}", title, message)))
+                Box::new(ps)
+            }
+            ActionConfig::Log { message, level } => {
+                let log_level = match level.as_str() {
+                    "debug" => LogLevel::Debug,
+                    "info" => LogLevel::Info,
+                    "warn" => LogLevel::Warn,
+                    "error" => LogLevel::Error,
+                    _ => LogLevel::Info,
+                };
+                Box::new(LogAction::new(message).with_level(log_level))
+            }
+            ActionConfig::Notify { title, message } => Box::new(NotifyAction::new(title, message)),
+            ActionConfig::HttpRequest {
+                url,
+                method,
+                headers,
+                body,
+                timeout_seconds,
+            } => {
+                let mut http = HttpAction::new(url)
+                    .with_method(method)
+                    .with_headers(headers.clone());
+                if let Some(body) = body {
+                    http = http.with_body(body);
                 }
-                ActionConfig::HttpRequest { url, .. } => {
-                    // HTTP requests would need additional implementation
-                    Box::new(LogAction::new(format!("HTTP request to: {}", url)))
+                if let Some(seconds) = timeout_seconds {
+                    http = http.with_timeout(Duration::from_secs(*seconds));
                 }
-                ActionConfig::Media { command } => {
-                    let script = match command.as_str() {
-                        "play" => {
-                            r#"
+                Box::new(http)
+            }
+            ActionConfig::FileAppend {
+                path,
+                format,
+                max_bytes,
+                max_files,
+            } => Box::new(
+                FileAppendAction::new(path)
+                    .with_format(format)
+                    .with_max_bytes(*max_bytes)
+                    .with_max_files(*max_files),
+            ),
+            ActionConfig::Media { command } => {
+                let script = match command.as_str() {
+                    "play" => {
+                        r#"
 Add-Type @"
 using System;
 using System.Runtime.InteropServices;
@@ -442,9 +915,9 @@ public class MediaKeys {
 "@
 [MediaKeys]::PlayPause()
 "#
-                        }
-                        "pause" => {
-                            r#"
+                    }
+                    "pause" => {
+                        r#"
 Add-Type @"
 using System;
 using System.Runtime.InteropServices;
@@ -460,9 +933,9 @@ public class MediaKeys {
 "@
 [MediaKeys]::PlayPause()
 "#
-                        }
-                        "toggle" => {
-                            r#"
+                    }
+                    "toggle" => {
+                        r#"
 Add-Type @"
 using System;
 using System.Runtime.InteropServices;
@@ -478,9 +951,9 @@ public class MediaKeys {
 "@
 [MediaKeys]::PlayPause()
 "#
-                        }
-                        _ => {
-                            r#"
+                    }
+                    _ => {
+                        r#"
 Add-Type @"
 using System;
 using System.Runtime.InteropServices;
@@ -496,175 +969,404 @@ public class MediaKeys {
 "@
 [MediaKeys]::PlayPause()
 "#
+                    }
+                };
+                Box::new(PowerShellAction::new(script))
+            }
+            ActionConfig::Script {
+                path,
+                function,
+                timeout_ms,
+                on_error,
+                allowed_paths,
+            } => {
+                use actions::{ScriptAction, ScriptErrorBehavior};
+
+                let script_path = resolve_script_path(path);
+
+                match ScriptAction::new(script_path, function.clone()) {
+                    Ok(mut script_action) => {
+                        // Set timeout if specified
+                        if let Some(timeout) = timeout_ms {
+                            script_action = script_action.with_timeout(*timeout);
                         }
-                    };
-                    Box::new(PowerShellAction::new(script))
-                }
-                ActionConfig::Script {
-                    path,
-                    function,
-                    timeout_ms,
-                    on_error,
-                } => {
-                    use actions::{ScriptAction, ScriptErrorBehavior};
-                    
-                    // Resolve path relative to plugins/actions/ if not absolute
-                    let script_path = if path.is_absolute() {
-                        path.clone()
-                    } else {
-                        PathBuf::from("plugins/actions").join(path)
-                    };
-                    
-                    match ScriptAction::new(script_path, function.clone()) {
-                        Ok(mut script_action) => {
-                            // Set timeout if specified
-                            if let Some(timeout) = timeout_ms {
-                                script_action = script_action.with_timeout(*timeout);
-                            }
-                            
-                            // Set error behavior
-                            if let Ok(behavior) = on_error.parse::<ScriptErrorBehavior>() {
-                                script_action = script_action.with_error_behavior(behavior);
-                            }
-                            
-                            Box::new(script_action)
+
+                        // Set error behavior
+                        if let Ok(behavior) = on_error.parse::<ScriptErrorBehavior>() {
+                            script_action = script_action.with_error_behavior(behavior);
                         }
-                        Err(e) => {
-                            error!("Failed to create script action: {}", e);
-                            // Fallback to log action showing the error
-                            Box::new(LogAction::new(format!(
-                                "Script action failed to load: {}",
-                                e
-                            )))
+
+                        if let Some(paths) = allowed_paths {
+                            script_action = script_action.with_allowed_roots(paths.clone());
                         }
+
+                        Box::new(script_action)
+                    }
+                    Err(e) => {
+                        error!("Failed to create script action: {}", e);
+                        // Fallback to log action showing the error
+                        Box::new(LogAction::new(format!(
+                            "Script action failed to load: {}",
+                            e
+                        )))
                     }
                 }
-            };
+            }
+        }
+    }
+
+    /// Stop and re-create a single source from its config, leaving the other
+    /// sources running. The new instance emits into the existing event bus.
+    pub async fn restart_plugin(&mut self, name: &str) -> Result<(), EngineError> {
+        let Some(source_config) = self
+            .config
+            .sources
+            .iter()
+            .find(|source| source.name == name && source.enabled)
+            .cloned()
+        else {
+            return Err(EngineError::Config(format!(
+                "No enabled source named '{}'",
+                name
+            )));
+        };
+
+        let Some(sender) = self.event_sender.clone() else {
+            return Err(EngineError::PluginInit(
+                name.to_string(),
+                "engine is not running".to_string(),
+            ));
+        };
+
+        if let Some(mut plugin) = self.plugins.remove(name) {
+            info!("Stopping plugin for restart: {}", name);
+            if let Err(e) = plugin.stop().await {
+                error!("Error stopping plugin {}: {}", name, e);
+            }
+        }
+
+        let plugin = self.create_plugin(&source_config, sender).await?;
+        self.plugins.insert(source_config.name, plugin);
+        self.publish_status();
+
+        info!("Restarted plugin: {}", name);
+        Ok(())
+    }
+
+    /// Ticks every `plugin_check_interval_seconds`, first one period from now,
+    /// to drive [`check_plugin_health`](Self::check_plugin_health); `None`
+    /// when the check is turned off. Wait on it with [`next_plugin_check`].
+    pub fn plugin_check_timer(&self) -> Option<tokio::time::Interval> {
+        let seconds = self.config.engine.plugin_check_interval_seconds;
+        (seconds > 0).then(|| {
+            let period = Duration::from_secs(seconds);
+            tokio::time::interval_at(Instant::now() + period, period)
+        })
+    }
+
+    /// Look for sources that have died without being stopped, logging each one
+    /// and restarting it if `restart_failed_plugins` is set. Returns the names
+    /// of the sources found dead.
+    pub async fn check_plugin_health(&mut self) -> Vec<String> {
+        let mut failed = Vec::new();
+        for (name, plugin) in &self.plugins {
+            if let PluginHealth::Failed(reason) = plugin.health() {
+                warn!("Plugin '{}' has died: {}", name, reason);
+                record_plugin_error(&self.metrics, name, "died");
+                failed.push(name.clone());
+            }
+        }
+        failed.sort();
+
+        if self.config.engine.restart_failed_plugins {
+            for name in &failed {
+                if let Err(e) = self.restart_plugin(name).await {
+                    error!("Failed to restart plugin {}: {}", name, e);
+                }
+            }
+        }
+
+        failed
+    }
+
+    /// Feed the events recorded in `path`, one JSON object per line as
+    /// written by a `jsonl_file` sink, through the rules in place of live
+    /// ones. `rate` is in events per second; `None` replays as fast as the
+    /// processing loop keeps up. Returns how many events were replayed.
+    pub async fn replay(&mut self, path: &Path, rate: Option<f64>) -> Result<usize, EngineError> {
+        let Some(sender) = self.event_sender.clone() else {
+            return Err(EngineError::Replay("engine is not running".to_string()));
+        };
+        let file = std::fs::File::open(path)
+            .map_err(|e| EngineError::Replay(format!("{}: {}", path.display(), e)))?;
+        let interval = rate.map(|rate| Duration::from_secs_f64(1.0 / rate));
+
+        let mut replayed = 0;
+        for (idx, line) in std::io::BufReader::new(file).lines().enumerate() {
+            let line =
+                line.map_err(|e| EngineError::Replay(format!("{}: {}", path.display(), e)))?;
+            if line.trim().is_empty() {
+                continue;
+            }
+
+            let mut event: engine_core::event::Event =
+                serde_json::from_str(&line).map_err(|e| {
+                    EngineError::Replay(format!("{} line {}: {}", path.display(), idx + 1, e))
+                })?;
+            event.seq = self.event_seq.next();
+
+            if sender.send(event).await.is_err() {
+                return Err(EngineError::Replay("processing loop stopped".to_string()));
+            }
+            replayed += 1;
+
+            if let Some(interval) = interval {
+                tokio::time::sleep(interval).await;
+            }
+        }
 
-            self.action_executor.register(action_name, action);
+        info!("Replayed {} events from {}", replayed, path.display());
+        Ok(replayed)
+    }
+
+    /// Wait for every action dispatched so far to finish
+    pub async fn wait_for_actions(&self) {
+        loop {
+            let running = std::mem::take(
+                &mut *self
+                    .running_actions
+                    .lock()
+                    .unwrap_or_else(|e| e.into_inner()),
+            );
+            if running.is_empty() {
+                return;
+            }
+            for action in running {
+                let _ = action.await;
+            }
         }
     }
 
+    /// Stop all sources, then let the processing loop work through whatever is
+    /// still queued on the bus before returning
     pub async fn shutdown(&mut self) {
         info!("Shutting down engine");
 
-        for plugin in &mut self.plugins {
+        for plugin in self.plugins.values_mut() {
             if let Err(e) = plugin.stop().await {
                 error!("Error stopping plugin: {}", e);
             }
         }
+        self.plugins.clear();
+
+        self.stop_event_loop().await;
 
         info!("Engine shutdown complete");
     }
 
+    #[cfg(test)]
+    pub(crate) fn event_sender(&self) -> Option<mpsc::Sender<engine_core::event::Event>> {
+        self.event_sender.clone()
+    }
+
+    #[cfg(test)]
+    pub(crate) fn insert_plugin(&mut self, plugin: Box<dyn EventSourcePlugin>) {
+        self.plugins.insert(plugin.name().to_string(), plugin);
+    }
+
+    #[cfg(test)]
+    pub(crate) fn plugin_mut(&mut self, name: &str) -> Option<&mut Box<dyn EventSourcePlugin>> {
+        self.plugins.get_mut(name)
+    }
+
+    /// Recent action failures, oldest first
+    pub fn get_dead_letters(&self) -> Vec<DeadLetter> {
+        self.dead_letters.entries()
+    }
+
     pub fn get_status(&self) -> EngineStatus {
+        let rules = self.rules.read().unwrap_or_else(|e| e.into_inner());
         EngineStatus {
             active_plugins: self.plugins.len(),
-            active_rules: self.rules.len(),
+            active_rules: rules.len(),
+            paused: self.is_paused(),
+            rules: rules
+                .iter()
+                .map(|rule| RuleStatus {
+                    name: rule.name.clone(),
+                    enabled: rule.enabled,
+                })
+                .collect(),
         }
     }
 
-    pub async fn reload(&mut self, new_config: Config) -> Result<(), EngineError> {
-        info!("Starting full config reload");
+    /// Feed the current plugin and rule counts to the dashboard's health updates
+    fn publish_status(&self) -> EngineStatus {
+        let status = self.get_status();
+        self.metrics
+            .set_system_status(status.active_plugins, status.active_rules);
+        status
+    }
 
-        if let Err(e) = new_config.validate() {
-            warn!(
-                "New configuration validation failed: {}, keeping current config",
-                e
-            );
-            self.metrics.record_config_reload_with_broadcast(false);
-            return Err(EngineError::Config(e.to_string()));
+    /// Enable or disable a loaded rule by name, returning whether it exists.
+    /// The change lasts until the next reload.
+    pub fn set_rule_enabled(&self, name: &str, enabled: bool) -> bool {
+        SharedRules(self.rules.clone())
+            .set_rule_enabled(name, enabled)
+            .is_some()
+    }
+
+    /// The loaded rules, for the metrics server's `/api/rules`. The handle
+    /// stays valid across reloads.
+    pub fn rule_registry(&self) -> Arc<dyn RuleRegistry> {
+        Arc::new(SharedRules(self.rules.clone()))
+    }
+
+    /// Re-read the config from the path the engine was started with and apply it
+    pub async fn reload_from_disk(&mut self) -> Result<(), EngineError> {
+        let Some(path) = self.config_path.clone() else {
+            return Err(EngineError::Config(
+                "Engine was not started from a config file".to_string(),
+            ));
+        };
+
+        let new_config = if path.is_dir() {
+            Config::load_from_dir(&path)
+        } else {
+            Config::load_from_file(&path)
         }
+        .map_err(|e| EngineError::Config(e.to_string()))?;
+
+        self.reload_rules_only(new_config).await
+    }
+
+    /// Answer a request from the control API
+    pub async fn handle_control(&mut self, command: ControlCommand) {
+        match command {
+            ControlCommand::Status { reply } => {
+                let _ = reply.send(self.get_status());
+            }
+            ControlCommand::Reload { reply } => {
+                let result = self.reload_from_disk().await.map_err(|e| e.to_string());
+                let _ = reply.send(result);
+            }
+            ControlCommand::SetRuleEnabled {
+                name,
+                enabled,
+                reply,
+            } => {
+                let _ = reply.send(self.set_rule_enabled(&name, enabled));
+            }
+            ControlCommand::RestartPlugin { name, reply } => {
+                let _ = reply.send(self.restart_plugin(&name).await);
+            }
+            ControlCommand::DeadLetters { reply } => {
+                let _ = reply.send(self.get_dead_letters());
+            }
+        }
+    }
+
+    /// Apply `new_config`, stopping and recreating every plugin
+    pub async fn reload(&mut self, new_config: Config) -> Result<(), EngineError> {
+        info!("Starting full config reload");
+        self.check_reload_config(&new_config)?;
 
         info!("Stopping all plugins for reload");
-        for plugin in &mut self.plugins {
+        for plugin in self.plugins.values_mut() {
             if let Err(e) = plugin.stop().await {
                 error!("Error stopping plugin during reload: {}", e);
             }
         }
         self.plugins.clear();
-        self.rules.clear();
 
-        self.config = new_config;
+        // Events already queued were emitted under the old config, so let the
+        // old loop finish them before it goes away
+        self.stop_event_loop().await;
 
-        if let Some(sender) = &self.event_sender {
-            self.initialize_plugins(sender.clone()).await?;
-        }
+        self.config = new_config;
+        // The old queue's file closes once the old loop's actions finish
+        self.dead_letters =
+            Arc::new(dead_letter_queue(&self.config).with_entries_from(&self.dead_letters));
+        // Replaced in place, as handles from `rule_registry` share it
+        *self.rules.write().unwrap_or_else(|e| e.into_inner()) = empty_rule_set(&self.config);
 
         self.initialize_rules();
         self.initialize_actions();
 
-        if let Some(_sender) = &self.event_sender {
-            let rules = self.rules.clone();
-            let action_executor = self.action_executor.clone();
-            let metrics = self.metrics.clone();
-            let mut receiver = bus::create_event_bus(self.config.engine.event_buffer_size).1;
+        let sender = self.start_event_loop();
+        self.initialize_plugins(sender).await?;
+        let status = self.publish_status();
 
-            tokio::spawn(async move {
-                while let Some(event) = receiver.recv().await {
-                    let start_time = Instant::now();
-                    let event_source = event.source.clone();
-                    let event_type = format!("{:?}", event.kind);
-                    
-                    metrics.record_event_with_broadcast(&event_source, &event_type);
+        self.metrics.record_config_reload_with_broadcast(true);
 
-                    tracing::debug!("Processing event: {:?} from {}", event.kind, event.source);
+        info!(
+            "Config reload complete: {} plugins, {} rules",
+            status.active_plugins, status.active_rules
+        );
 
-                    for (idx, rule) in rules.iter().enumerate() {
-                        if !rule.enabled {
-                            continue;
-                        }
+        Ok(())
+    }
 
-                        metrics.record_rule_evaluation_with_broadcast(&rule.name);
-
-                        let match_start = Instant::now();
-                        let matched = rule.matches(&event);
-                        record_rule_match_duration(&metrics, &rule.name, match_start.elapsed());
-
-                        if matched {
-                            metrics.record_rule_match_with_broadcast(&rule.name);
-                            info!("Rule '{}' matched event from {}", rule.name, event.source);
-
-                            let action_name = format!("rule_{}_action", idx);
-                            let action_start = Instant::now();
-
-                            match action_executor.execute(&action_name, &event) {
-                                Ok(result) => {
-                                    metrics.record_action_execution_with_broadcast(
-                                        &action_name,
-                                        true,
-                                        action_start.elapsed()
-                                    );
-                                    info!("Action executed successfully: {:?}", result);
-                                }
-                                Err(e) => {
-                                    metrics.record_action_execution_with_broadcast(
-                                        &action_name,
-                                        false,
-                                        action_start.elapsed()
-                                    );
-                                    error!("Action execution failed: {}", e);
-                                }
-                            }
-                        }
-                    }
+    /// Apply `new_config` without touching running plugins when its sources,
+    /// sinks and `[engine]` settings are the same as the current ones, so ETW
+    /// sessions and window hooks survive a rule edit. Only the rules and their
+    /// actions are rebuilt; everything else falls back to a full
+    /// [`reload`](Self::reload), which restarts the processing loop with the
+    /// new settings.
+    pub async fn reload_rules_only(&mut self, new_config: Config) -> Result<(), EngineError> {
+        if new_config.sources != self.config.sources
+            || new_config.sinks != self.config.sinks
+            || new_config.engine != self.config.engine
+        {
+            info!("Sources, sinks or engine settings changed, restarting plugins");
+            return self.reload(new_config).await;
+        }
 
-                    record_event_processing_duration(&metrics, start_time.elapsed());
-                }
-            });
+        info!("Starting rules-only config reload");
+        self.check_reload_config(&new_config)?;
+
+        self.config = new_config;
+        let rules = self.build_rules();
+        let executor = Arc::new(self.build_actions());
+        {
+            // The processing loop reads both under the rules lock
+            let mut current_rules = self.rules.write().unwrap_or_else(|e| e.into_inner());
+            *self
+                .action_executor
+                .write()
+                .unwrap_or_else(|e| e.into_inner()) = executor;
+            *current_rules = rules;
         }
-        
+        let status = self.publish_status();
+
         self.metrics.record_config_reload_with_broadcast(true);
 
-        let status = self.get_status();
         info!(
-            "Config reload complete: {} plugins, {} rules",
-            status.active_plugins, status.active_rules
+            "Rules reloaded: {} rules, {} plugins left running",
+            status.active_rules, status.active_plugins
         );
 
         Ok(())
     }
 
+    /// Reject a config that fails validation, keeping the current one
+    fn check_reload_config(&self, new_config: &Config) -> Result<(), EngineError> {
+        let Err(errors) = new_config.validate() else {
+            return Ok(());
+        };
+
+        for e in &errors {
+            warn!("Invalid configuration: {}", e);
+        }
+        warn!(
+            "New configuration has {} validation errors, keeping current config",
+            errors.len()
+        );
+        self.metrics.record_config_reload_with_broadcast(false);
+        let messages: Vec<String> = errors.iter().map(|e| e.to_string()).collect();
+        Err(EngineError::Config(messages.join("; ")))
+    }
+
     pub async fn watch_config(&mut self) {
         let config_path = match &self.config_path {
             Some(p) => p.clone(),
@@ -682,7 +1384,7 @@ public class MediaKeys {
         tokio::spawn(async move {
             use notify::{Config as NotifyConfig, RecommendedWatcher, RecursiveMode, Watcher};
 
-            let (notify_tx, mut notify_rx) = mpsc::channel(100);
+            let (notify_tx, notify_rx) = mpsc::channel(100);
 
             let mut watcher: RecommendedWatcher = match Watcher::new(
                 move |res: Result<notify::Event, notify::Error>| {
@@ -711,39 +1413,7 @@ public class MediaKeys {
             }
 
             info!("Config watcher started for: {:?}", watch_path);
-            let mut last_reload = std::time::Instant::now();
-            let debounce_duration = Duration::from_millis(500);
-
-            while !shutdown_flag.load(std::sync::atomic::Ordering::Relaxed) {
-                match timeout(Duration::from_millis(250), notify_rx.recv()).await {
-                    Ok(Some(event)) => {
-                        if let notify::EventKind::Modify(_) | notify::EventKind::Create(_) =
-                            event.kind
-                        {
-                            if last_reload.elapsed() < debounce_duration {
-                                continue;
-                            }
-
-                            let paths: Vec<_> = event
-                                .paths
-                                .iter()
-                                .filter(|p| p.extension().map(|e| e == "toml").unwrap_or(false))
-                                .collect();
-
-                            if paths.is_empty() {
-                                continue;
-                            }
-
-                            info!("Config change detected, signaling reload...");
-                            let _ = tx.send(()).await;
-                            last_reload = std::time::Instant::now();
-                        }
-                    }
-                    Ok(None) => break,
-                    Err(_) => continue,
-                }
-            }
-
+            debounce_config_changes(notify_rx, tx, CONFIG_RELOAD_DEBOUNCE, shutdown_flag).await;
             info!("Config watcher stopped");
         });
     }
@@ -751,18 +1421,358 @@ public class MediaKeys {
     pub fn shutdown_flag(&self) -> Arc<std::sync::atomic::AtomicBool> {
         self.shutdown_flag.clone()
     }
+
+    /// While set, events are still received, counted and matched against
+    /// rules, but no actions are run
+    pub fn pause_flag(&self) -> Arc<std::sync::atomic::AtomicBool> {
+        self.paused.clone()
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.paused.load(Ordering::Relaxed)
+    }
 }
 
-#[derive(Debug, Clone)]
+/// Print each event to `out` as it arrives, in place of the processing loop
+fn spawn_event_dump(
+    mut receiver: mpsc::Receiver<engine_core::event::Event>,
+    out: Arc<Mutex<dyn Write + Send>>,
+    events_processed: Arc<AtomicU64>,
+) -> JoinHandle<()> {
+    tokio::spawn(async move {
+        while let Some(event) = receiver.recv().await {
+            let text = format_event_dump(&event);
+            let mut out = out.lock().unwrap_or_else(|e| e.into_inner());
+            if let Err(e) = out.write_all(text.as_bytes()).and_then(|_| out.flush()) {
+                warn!("Failed to write event dump: {}", e);
+            }
+            events_processed.fetch_add(1, Ordering::Relaxed);
+        }
+    })
+}
+
+/// Sequence number, source and kind (with all of its fields) on one line,
+/// followed by the metadata sorted by key
+fn format_event_dump(event: &engine_core::event::Event) -> String {
+    let mut text = format!("#{} [{}] {:?}\n", event.seq, event.source, event.kind);
+
+    let mut metadata: Vec<_> = event.metadata.iter().collect();
+    metadata.sort();
+    for (key, value) in metadata {
+        text.push_str(&format!("    {} = {}\n", key, value));
+    }
+
+    text
+}
+
+/// Send one reload signal per burst of config file changes: every change
+/// (re)arms a `quiet` timer, and the signal goes out once it expires, so an
+/// editor's save-as-several-writes only reloads once, after the last write.
+/// Returns when `changes` closes, `reload_tx` is dropped or `shutdown_flag`
+/// is set.
+pub(crate) async fn debounce_config_changes(
+    mut changes: mpsc::Receiver<notify::Event>,
+    reload_tx: mpsc::Sender<()>,
+    quiet: Duration,
+    shutdown_flag: Arc<std::sync::atomic::AtomicBool>,
+) {
+    let mut deadline: Option<Instant> = None;
+
+    while !shutdown_flag.load(Ordering::Relaxed) {
+        let settled = async {
+            match deadline {
+                Some(deadline) => tokio::time::sleep_until(deadline).await,
+                None => std::future::pending().await,
+            }
+        };
+
+        tokio::select! {
+            change = changes.recv() => match change {
+                Some(event) if is_config_change(&event) => {
+                    deadline = Some(Instant::now() + quiet);
+                }
+                Some(_) => {}
+                None => break,
+            },
+            _ = settled => {
+                deadline = None;
+                info!("Config change detected, signaling reload...");
+                if reload_tx.send(()).await.is_err() {
+                    break;
+                }
+            }
+            // Wake up now and then to notice the shutdown flag
+            _ = tokio::time::sleep(Duration::from_millis(250)) => {}
+        }
+    }
+}
+
+/// A `.toml` file was created or written
+fn is_config_change(event: &notify::Event) -> bool {
+    matches!(
+        event.kind,
+        notify::EventKind::Modify(_) | notify::EventKind::Create(_)
+    ) && event
+        .paths
+        .iter()
+        .any(|p| p.extension().map(|e| e == "toml").unwrap_or(false))
+}
+
+/// Run a matched rule's action on the blocking pool so a slow action doesn't
+/// stall processing of later events, with at most `action_limiter`'s permits
+/// worth of actions in flight. Each action is a single blocking call, so a
+/// `CompositeAction` still runs its steps in order and honours `ErrorBehavior::Stop`.
+///
+/// An action still running after `action_timeout` is reported as failed with
+/// `ActionError::Timeout` and gives up its permit; the blocking call itself
+/// can't be cancelled and is left to finish in the background.
+///
+/// With `dry_run` set the action is only logged and reported as skipped.
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn dispatch_action(
+    action_executor: Arc<ActionExecutor>,
+    action_limiter: Arc<Semaphore>,
+    metrics: Arc<MetricsCollector>,
+    dead_letters: Arc<DeadLetterQueue>,
+    action_name: String,
+    event: engine_core::event::Event,
+    action_timeout: Option<Duration>,
+    dry_run: bool,
+) -> tokio::task::JoinHandle<()> {
+    tokio::spawn(async move {
+        let Ok(permit) = action_limiter.acquire_owned().await else {
+            return;
+        };
+        let action_start = Instant::now();
+
+        let result = if dry_run {
+            let description = action_executor
+                .description(&action_name)
+                .unwrap_or_else(|| action_name.clone());
+            info!(
+                "Dry run: would execute {} for '{}'",
+                description, action_name
+            );
+            Ok(Ok(ActionResult::Skipped {
+                reason: "dry run".to_string(),
+            }))
+        } else {
+            let name = action_name.clone();
+            let event = event.clone();
+            // The permit goes with the blocking thread, so an action that times
+            // out still counts against the limit until it actually returns
+            let task = tokio::task::spawn_blocking(move || {
+                let _permit = permit;
+                action_executor.execute(&name, &event)
+            });
+            match action_timeout {
+                Some(limit) => timeout(limit, task).await.unwrap_or_else(|_| {
+                    error!("Action '{}' did not finish within {:?}", action_name, limit);
+                    record_action_timeout(&metrics, &action_name);
+                    Ok(Err(ActionError::Timeout))
+                }),
+                None => task.await,
+            }
+        };
+
+        match result {
+            Ok(Ok(ActionResult::Skipped { reason })) => {
+                metrics.record_action_execution_with_broadcast(
+                    &action_name,
+                    ActionStatus::Skipped,
+                    action_start.elapsed(),
+                );
+                info!("Action '{}' skipped: {}", action_name, reason);
+            }
+            Ok(Ok(result)) => {
+                metrics.record_action_execution_with_broadcast(
+                    &action_name,
+                    ActionStatus::Success,
+                    action_start.elapsed(),
+                );
+                info!("Action executed successfully: {:?}", result);
+                if let ActionResult::Chain { next_action, .. } = &result {
+                    info!(
+                        "Action '{}' requested follow-up action '{}'",
+                        action_name, next_action
+                    );
+                }
+            }
+            Ok(Err(e)) => {
+                metrics.record_action_execution_with_broadcast(
+                    &action_name,
+                    ActionStatus::Error,
+                    action_start.elapsed(),
+                );
+                error!("Action execution failed: {}", e);
+                dead_letters.record(&action_name, e, event);
+            }
+            Err(e) => {
+                metrics.record_action_execution_with_broadcast(
+                    &action_name,
+                    ActionStatus::Error,
+                    action_start.elapsed(),
+                );
+                error!("Action '{}' panicked: {}", action_name, e);
+                dead_letters.record(&action_name, format!("panicked: {}", e), event);
+            }
+        }
+    })
+}
+
+/// Feed a sink from its subscription, flushing whenever it catches up
+fn spawn_sink(
+    mut sink: Box<dyn EventSink>,
+    mut subscription: Subscription,
+    metrics: Arc<MetricsCollector>,
+) -> JoinHandle<()> {
+    tokio::spawn(async move {
+        let mut reported_lag = 0;
+
+        while let Some(event) = subscription.recv().await {
+            let mut next = Some(event);
+            while let Some(event) = next {
+                if let Err(e) = sink.write(&event).await {
+                    warn!("Sink '{}' failed to write event: {}", sink.name(), e);
+                }
+                next = subscription.try_recv();
+            }
+
+            if let Err(e) = sink.flush().await {
+                warn!("Sink '{}' failed to flush: {}", sink.name(), e);
+            }
+
+            let lagged = subscription.lagged();
+            for _ in reported_lag..lagged {
+                let reason = format!("Sink buffer full (sink: {})", sink.name());
+                metrics.record_event_dropped_with_broadcast(&reason);
+            }
+            reported_lag = lagged;
+        }
+
+        info!("Sink '{}' stopped", sink.name());
+    })
+}
+
+fn window_matcher(
+    event_type: WindowEventType,
+    title_contains: &Option<String>,
+    title_regex: &Option<String>,
+    process_name: &Option<String>,
+) -> Result<WindowMatcher, EngineError> {
+    let title_regex = title_regex
+        .as_deref()
+        .map(regex::Regex::new)
+        .transpose()
+        .map_err(|e| EngineError::Config(format!("Invalid regex: {}", e)))?;
+    Ok(WindowMatcher {
+        event_type,
+        title_contains: title_contains.clone(),
+        title_regex,
+        process_name: process_name.clone(),
+    })
+}
+
+/// Wait for the next tick of a [`Engine::plugin_check_timer`], forever if the
+/// check is turned off
+pub async fn next_plugin_check(timer: &mut Option<tokio::time::Interval>) {
+    match timer {
+        Some(interval) => {
+            interval.tick().await;
+        }
+        None => std::future::pending().await,
+    }
+}
+
+/// Narrow `matcher` to processes whose name matches the `process_name` glob
+fn with_process_name(
+    matcher: ProcessMatcher,
+    process_name: &Option<String>,
+) -> Result<ProcessMatcher, EngineError> {
+    match process_name {
+        Some(name) => matcher
+            .with_name_pattern(name)
+            .map_err(|e| EngineError::Config(format!("Invalid pattern: {}", e))),
+        None => Ok(matcher),
+    }
+}
+
+fn dead_letter_queue(config: &Config) -> DeadLetterQueue {
+    let engine = &config.engine;
+    let capacity = engine.dead_letter_capacity;
+    match &engine.dead_letter_file {
+        Some(path) => DeadLetterQueue::new(capacity)
+            .with_file(
+                path,
+                engine.dead_letter_max_bytes,
+                engine.dead_letter_max_files,
+            )
+            .unwrap_or_else(|e| {
+                error!("Failed to open dead letter file {:?}: {}", path, e);
+                DeadLetterQueue::new(capacity)
+            }),
+        None => DeadLetterQueue::new(capacity),
+    }
+}
+
+fn empty_rule_set(config: &Config) -> RuleSet {
+    if config.engine.index_rules {
+        RuleSet::new()
+    } else {
+        RuleSet::unindexed()
+    }
+}
+
+/// The engine's rule set as seen by the metrics server
+struct SharedRules(Arc<RwLock<RuleSet>>);
+
+impl RuleRegistry for SharedRules {
+    fn rules(&self) -> Vec<RuleInfo> {
+        let rules = self.0.read().unwrap_or_else(|e| e.into_inner());
+        rules.iter().map(rule_info).collect()
+    }
+
+    fn set_rule_enabled(&self, name: &str, enabled: bool) -> Option<RuleInfo> {
+        let mut rules = self.0.write().unwrap_or_else(|e| e.into_inner());
+        let rule = rules.iter_mut().find(|rule| rule.name == name)?;
+        rule.enabled = enabled;
+        info!(
+            "Rule '{}' {}",
+            name,
+            if enabled { "enabled" } else { "disabled" }
+        );
+        Some(rule_info(rule))
+    }
+}
+
+fn rule_info(rule: &Rule) -> RuleInfo {
+    RuleInfo {
+        name: rule.name.clone(),
+        description: rule.description.clone(),
+        enabled: rule.enabled,
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
 pub struct EngineStatus {
     pub active_plugins: usize,
     pub active_rules: usize,
+    pub paused: bool,
+    pub rules: Vec<RuleStatus>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct RuleStatus {
+    pub name: String,
+    pub enabled: bool,
 }
 
 #[derive(Debug, Clone)]
 pub enum EngineError {
     Config(String),
     PluginInit(String, String),
+    SinkInit(String, String),
+    Replay(String),
 }
 
 impl std::fmt::Display for EngineError {
@@ -772,6 +1782,10 @@ impl std::fmt::Display for EngineError {
             EngineError::PluginInit(name, msg) => {
                 write!(f, "Plugin '{}' initialization error: {}", name, msg)
             }
+            EngineError::SinkInit(name, msg) => {
+                write!(f, "Sink '{}' initialization error: {}", name, msg)
+            }
+            EngineError::Replay(msg) => write!(f, "Replay error: {}", msg),
         }
     }
 }
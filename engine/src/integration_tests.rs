@@ -1,12 +1,48 @@
 #[cfg(test)]
 mod integration_tests {
+    use crate::config::{
+        ActionConfig, Config, DedupConfig, RuleConfig, SinkConfig, SinkType, SourceConfig,
+        SourceType, TriggerConfig,
+    };
+    use crate::dead_letter::DeadLetterQueue;
+    use crate::engine::{Engine, EngineError, debounce_config_changes, dispatch_action};
     use crate::plugins::file_watcher::FileWatcherPlugin;
-    use actions::{ActionExecutor, LogAction, LogLevel};
+    use actions::{Action, ActionError, ActionExecutor, ActionResult, LogAction};
     use bus::create_event_bus;
-    use engine_core::plugin::EventSourcePlugin;
+    use engine_core::event::{Event, EventKind};
+    use engine_core::log_rotation;
+    use engine_core::plugin::{EventEmitter, EventSourcePlugin, PluginError, PluginHealth};
+    use metrics::{MetricUpdate, MetricsCollector};
     use rules::{FilePatternMatcher, Rule};
+    use std::collections::HashMap;
+    use std::sync::{Arc, Mutex};
+    use tokio::sync::Semaphore;
     use tokio::time::{Duration, sleep};
 
+    /// Blocks for `delay`, then records its label as completed
+    #[derive(Debug, Clone)]
+    struct SleepAction {
+        label: &'static str,
+        delay: std::time::Duration,
+        completed: Arc<Mutex<Vec<&'static str>>>,
+    }
+
+    impl Action for SleepAction {
+        fn execute(&self, _event: &Event) -> Result<ActionResult, ActionError> {
+            std::thread::sleep(self.delay);
+            self.completed.lock().unwrap().push(self.label);
+            Ok(ActionResult::Success { message: None })
+        }
+
+        fn description(&self) -> String {
+            format!("Sleep {:?}", self.delay)
+        }
+
+        fn clone_box(&self) -> Box<dyn Action> {
+            Box::new(self.clone())
+        }
+    }
+
     #[tokio::test]
     async fn test_full_event_flow() {
         // Setup
@@ -23,7 +59,7 @@ mod integration_tests {
         let mut watcher =
             FileWatcherPlugin::new("test", vec![temp_dir.clone()]).with_pattern("*.txt");
         watcher
-            .start(sender)
+            .start(sender.into())
             .await
             .expect("Failed to start watcher");
 
@@ -68,4 +104,1540 @@ mod integration_tests {
 
         println!("✓ Integration test passed!");
     }
+
+    #[tokio::test]
+    async fn test_slow_action_does_not_block_fast_action() {
+        let completed = Arc::new(Mutex::new(Vec::new()));
+        let mut executor = ActionExecutor::new();
+        executor.register(
+            "slow",
+            Box::new(SleepAction {
+                label: "slow",
+                delay: Duration::from_millis(500),
+                completed: completed.clone(),
+            }),
+        );
+        executor.register(
+            "fast",
+            Box::new(SleepAction {
+                label: "fast",
+                delay: Duration::ZERO,
+                completed: completed.clone(),
+            }),
+        );
+
+        let executor = Arc::new(executor);
+        let limiter = Arc::new(Semaphore::new(4));
+        let metrics = Arc::new(MetricsCollector::new());
+        let dead_letters = Arc::new(DeadLetterQueue::new(0));
+        let event = Event::new(EventKind::TimerTick, "test");
+
+        let slow = dispatch_action(
+            executor.clone(),
+            limiter.clone(),
+            metrics.clone(),
+            dead_letters.clone(),
+            "slow".to_string(),
+            event.clone(),
+            None,
+            false,
+        );
+        let fast = dispatch_action(
+            executor,
+            limiter,
+            metrics,
+            dead_letters,
+            "fast".to_string(),
+            event,
+            None,
+            false,
+        );
+
+        fast.await.unwrap();
+        assert_eq!(*completed.lock().unwrap(), vec!["fast"]);
+
+        slow.await.unwrap();
+        assert_eq!(*completed.lock().unwrap(), vec!["fast", "slow"]);
+    }
+
+    #[tokio::test]
+    async fn test_hung_action_times_out_but_keeps_its_slot() {
+        let completed = Arc::new(Mutex::new(Vec::new()));
+        let mut executor = ActionExecutor::new();
+        executor.register(
+            "hung",
+            Box::new(SleepAction {
+                label: "hung",
+                delay: Duration::from_millis(500),
+                completed: completed.clone(),
+            }),
+        );
+        executor.register(
+            "fast",
+            Box::new(SleepAction {
+                label: "fast",
+                delay: Duration::ZERO,
+                completed: completed.clone(),
+            }),
+        );
+
+        // A single slot, which "hung" holds until its thread really returns
+        let executor = Arc::new(executor);
+        let limiter = Arc::new(Semaphore::new(1));
+        let metrics = Arc::new(MetricsCollector::new());
+        let dead_letters = Arc::new(DeadLetterQueue::new(10));
+        let event = Event::new(EventKind::TimerTick, "test");
+        let action_timeout = Some(Duration::from_millis(100));
+
+        let hung = dispatch_action(
+            executor.clone(),
+            limiter.clone(),
+            metrics.clone(),
+            dead_letters.clone(),
+            "hung".to_string(),
+            event.clone(),
+            action_timeout,
+            false,
+        );
+        let fast = dispatch_action(
+            executor,
+            limiter.clone(),
+            metrics.clone(),
+            dead_letters.clone(),
+            "fast".to_string(),
+            event,
+            action_timeout,
+            false,
+        );
+
+        // The timeout is reported as soon as it expires...
+        tokio::time::timeout(Duration::from_millis(400), hung)
+            .await
+            .expect("The timeout should be reported before the action returns")
+            .unwrap();
+        let letters = dead_letters.entries();
+        assert_eq!(letters.len(), 1);
+        assert_eq!(letters[0].action, "hung");
+        assert_eq!(letters[0].error, "Action timed out");
+        let labels = HashMap::from([("action".to_string(), "hung".to_string())]);
+        assert_eq!(
+            metrics.get_counter("actions_timed_out_total", &labels),
+            Some(1)
+        );
+
+        // ...but the next action only gets the slot once the hung one is done
+        assert_eq!(limiter.available_permits(), 0);
+        fast.await.unwrap();
+        assert_eq!(*completed.lock().unwrap(), vec!["hung", "fast"]);
+    }
+
+    #[tokio::test]
+    async fn test_shutdown_drains_buffered_events() {
+        let mut config = Config::default();
+        config.engine.event_buffer_size = 64;
+        let mut engine = Engine::new(config, None);
+        engine
+            .initialize()
+            .await
+            .expect("Failed to initialize engine");
+
+        // Queue a full buffer without yielding, so none of it is processed yet
+        let sender = engine.event_sender().expect("Engine should own a sender");
+        for _ in 0..64 {
+            sender
+                .try_send(Event::new(EventKind::TimerTick, "drain_test"))
+                .expect("Buffer should have room");
+        }
+        drop(sender);
+
+        engine.shutdown().await;
+
+        let labels = HashMap::from([
+            ("plugin".to_string(), "drain_test".to_string()),
+            ("type".to_string(), "TimerTick".to_string()),
+        ]);
+        assert_eq!(
+            engine.metrics().get_counter("events_total", &labels),
+            Some(64)
+        );
+    }
+
+    fn tick_rule(name: &str) -> RuleConfig {
+        RuleConfig {
+            name: name.to_string(),
+            description: None,
+            trigger: TriggerConfig::Timer {
+                interval_seconds: 60,
+            },
+            actions: vec![ActionConfig::Log {
+                message: format!("{} fired", name),
+                level: "info".to_string(),
+            }],
+            on_error: "continue".to_string(),
+            circuit_breaker: None,
+            dedup_window: None,
+            enabled: true,
+        }
+    }
+
+    fn rule_matches(engine: &Engine, rule: &str) -> u64 {
+        let labels = HashMap::from([("rule".to_string(), rule.to_string())]);
+        engine
+            .metrics()
+            .get_counter("rules_matched_total", &labels)
+            .unwrap_or(0)
+    }
+
+    #[tokio::test]
+    async fn test_plugin_emitter_counts_dropped_events() {
+        let engine = Engine::new(Config::default(), None);
+        let mut updates = engine.metrics().subscribe();
+        let (tx, mut rx) = tokio::sync::mpsc::channel(2);
+        let emitter = engine.plugin_emitter(tx);
+
+        for _ in 0..5 {
+            emitter.emit(Event::new(EventKind::TimerTick, "flood"));
+        }
+
+        assert_eq!(
+            engine
+                .metrics()
+                .get_counter("events_dropped_total", &HashMap::new()),
+            Some(3)
+        );
+
+        let mut dropped = Vec::new();
+        while let Ok(update) = updates.try_recv() {
+            if let MetricUpdate::EventDropped { reason, .. } = update {
+                dropped.push(reason);
+            }
+        }
+        assert_eq!(dropped, vec!["Event buffer full (source: flood)"; 3]);
+
+        // Dropped events still used up a sequence number
+        let first = rx.recv().await.unwrap().seq;
+        assert_eq!(rx.recv().await.unwrap().seq, first + 1);
+        emitter.emit(Event::new(EventKind::TimerTick, "flood"));
+        assert_eq!(rx.recv().await.unwrap().seq, first + 5);
+    }
+
+    #[tokio::test]
+    async fn test_processing_records_metrics() {
+        let mut idle = tick_rule("idle");
+        idle.trigger = TriggerConfig::UserIdle;
+        let config = Config {
+            rules: vec![tick_rule("ticks"), idle],
+            ..Default::default()
+        };
+        let mut engine = Engine::new(config, None);
+        engine
+            .initialize()
+            .await
+            .expect("Failed to initialize engine");
+        let mut updates = engine.metrics().subscribe();
+
+        let sender = engine.event_sender().expect("Engine should own a sender");
+        sender
+            .send(Event::new(EventKind::TimerTick, "metrics_test"))
+            .await
+            .unwrap();
+        drop(sender);
+        engine.shutdown().await;
+
+        let metrics = engine.metrics();
+        let counter = |name: &str, labels: &[(&str, &str)]| {
+            let labels = labels
+                .iter()
+                .map(|(k, v)| (k.to_string(), v.to_string()))
+                .collect();
+            metrics.get_counter(name, &labels)
+        };
+
+        // Actions run in the background, so wait for the result to be recorded
+        let action = [("action", "ticks"), ("status", "success")];
+        let deadline = std::time::Instant::now() + std::time::Duration::from_secs(5);
+        while counter("actions_executed_total", &action).is_none()
+            && std::time::Instant::now() < deadline
+        {
+            sleep(Duration::from_millis(10)).await;
+        }
+
+        assert_eq!(
+            counter(
+                "events_total",
+                &[("plugin", "metrics_test"), ("type", "TimerTick")]
+            ),
+            Some(1)
+        );
+        assert_eq!(
+            counter("rules_evaluated_total", &[("rule", "ticks")]),
+            Some(1)
+        );
+        // The idle rule can never match a tick, so the index skips it
+        assert_eq!(counter("rules_evaluated_total", &[("rule", "idle")]), None);
+        assert_eq!(rule_matches(&engine, "ticks"), 1);
+        assert_eq!(rule_matches(&engine, "idle"), 0);
+        assert_eq!(counter("actions_executed_total", &action), Some(1));
+
+        // The dashboard stream sees the same activity
+        assert!(matches!(
+            updates.try_recv(),
+            Ok(MetricUpdate::EventReceived { source, .. }) if source == "metrics_test"
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_rule_index_reduces_evaluations() {
+        async fn evaluations(index_rules: bool) -> (u64, u64) {
+            let mut idle = tick_rule("idle");
+            idle.trigger = TriggerConfig::UserIdle;
+            let mut active = tick_rule("active");
+            active.trigger = TriggerConfig::UserActive;
+            let mut config = Config {
+                rules: vec![tick_rule("ticks"), idle, active],
+                ..Default::default()
+            };
+            config.engine.index_rules = index_rules;
+
+            let mut engine = Engine::new(config, None);
+            engine
+                .initialize()
+                .await
+                .expect("Failed to initialize engine");
+            let sender = engine.event_sender().expect("Engine should own a sender");
+            for _ in 0..4 {
+                sender
+                    .send(Event::new(EventKind::TimerTick, "index_test"))
+                    .await
+                    .unwrap();
+            }
+            sender
+                .send(Event::new(EventKind::UserActive, "index_test"))
+                .await
+                .unwrap();
+            drop(sender);
+            engine.shutdown().await;
+
+            let metrics = engine.metrics();
+            let total = ["ticks", "idle", "active"]
+                .iter()
+                .filter_map(|rule| {
+                    let labels = HashMap::from([("rule".to_string(), rule.to_string())]);
+                    metrics.get_counter("rules_evaluated_total", &labels)
+                })
+                .sum();
+            (
+                total,
+                rule_matches(&engine, "ticks") + rule_matches(&engine, "active"),
+            )
+        }
+
+        let (unindexed, unindexed_matches) = evaluations(false).await;
+        let (indexed, indexed_matches) = evaluations(true).await;
+
+        // Without the index every event is checked against all three rules
+        assert_eq!(unindexed, 15);
+        assert_eq!(indexed, 5);
+        assert_eq!(indexed_matches, unindexed_matches);
+        assert_eq!(indexed_matches, 5);
+    }
+
+    #[tokio::test]
+    async fn test_sink_receives_every_event() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let path = dir.path().join("events.jsonl");
+        let config = Config {
+            // One rule that only matches ticks; the sink should see everything
+            rules: vec![tick_rule("ticks")],
+            sinks: vec![SinkConfig {
+                name: "audit".to_string(),
+                sink_type: SinkType::JsonlFile {
+                    path: path.clone(),
+                    max_bytes: 1024 * 1024,
+                    max_files: 1,
+                },
+                enabled: true,
+            }],
+            ..Default::default()
+        };
+        let mut engine = Engine::new(config, None);
+        engine
+            .initialize()
+            .await
+            .expect("Failed to initialize engine");
+
+        let sender = engine.event_sender().expect("Engine should own a sender");
+        sender
+            .send(Event::new(EventKind::TimerTick, "sink_test"))
+            .await
+            .unwrap();
+        sender
+            .send(Event::new(EventKind::UserActive, "sink_test"))
+            .await
+            .unwrap();
+        drop(sender);
+        engine.shutdown().await;
+
+        let kinds: Vec<String> = std::fs::read_to_string(&path)
+            .unwrap()
+            .lines()
+            .map(|line| {
+                let record: serde_json::Value = serde_json::from_str(line).unwrap();
+                assert_eq!(record["source"], "sink_test");
+                record["kind"]["type"].as_str().unwrap().to_string()
+            })
+            .collect();
+        assert_eq!(kinds, vec!["timer_tick", "user_active"]);
+        assert_eq!(rule_matches(&engine, "ticks"), 1);
+    }
+
+    #[tokio::test]
+    async fn test_sequence_numbers_increase_across_plugins() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let path = dir.path().join("events.jsonl");
+        let config = Config {
+            sinks: vec![SinkConfig {
+                name: "audit".to_string(),
+                sink_type: SinkType::JsonlFile {
+                    path: path.clone(),
+                    max_bytes: 1024 * 1024,
+                    max_files: 1,
+                },
+                enabled: true,
+            }],
+            ..Default::default()
+        };
+        let mut engine = Engine::new(config, None);
+        engine
+            .initialize()
+            .await
+            .expect("Failed to initialize engine");
+
+        let sender = engine.event_sender().expect("Engine should own a sender");
+        let timer = engine.plugin_emitter(sender.clone());
+        let watcher = engine.plugin_emitter(sender);
+        for _ in 0..5 {
+            timer.emit(Event::new(EventKind::TimerTick, "timer"));
+            watcher.emit(Event::new(
+                EventKind::FileCreated {
+                    path: "C:/Data/a.txt".into(),
+                },
+                "watcher",
+            ));
+        }
+        drop((timer, watcher));
+        engine.shutdown().await;
+
+        let records: Vec<(u64, String)> = std::fs::read_to_string(&path)
+            .unwrap()
+            .lines()
+            .map(|line| {
+                let record: serde_json::Value = serde_json::from_str(line).unwrap();
+                (
+                    record["seq"].as_u64().unwrap(),
+                    record["source"].as_str().unwrap().to_string(),
+                )
+            })
+            .collect();
+
+        assert_eq!(records.len(), 10);
+        assert!(records.windows(2).all(|pair| pair[0].0 < pair[1].0));
+        assert!(records.iter().all(|(seq, _)| *seq > 0));
+        assert!(records.iter().any(|(_, source)| source == "timer"));
+        assert!(records.iter().any(|(_, source)| source == "watcher"));
+    }
+
+    #[tokio::test]
+    async fn test_failed_action_recorded_as_dead_letter() {
+        let mut failing = tick_rule("failing");
+        failing.actions = vec![ActionConfig::Execute {
+            command: "win-event-engine-no-such-command".to_string(),
+            args: Vec::new(),
+            working_dir: None,
+        }];
+        let config = Config {
+            rules: vec![tick_rule("ticks"), failing],
+            ..Default::default()
+        };
+        let mut engine = Engine::new(config, None);
+        engine
+            .initialize()
+            .await
+            .expect("Failed to initialize engine");
+
+        let event = Event::new(EventKind::TimerTick, "dead_letter_test");
+        let sender = engine.event_sender().expect("Engine should own a sender");
+        sender.send(event.clone()).await.unwrap();
+        drop(sender);
+        engine.shutdown().await;
+
+        // Actions run in the background, so wait for the failure to be recorded
+        let deadline = std::time::Instant::now() + std::time::Duration::from_secs(5);
+        while engine.get_dead_letters().is_empty() && std::time::Instant::now() < deadline {
+            sleep(Duration::from_millis(10)).await;
+        }
+
+        let letters = engine.get_dead_letters();
+        assert_eq!(letters.len(), 1);
+        assert_eq!(letters[0].action, "failing");
+        assert_eq!(letters[0].event.id, event.id);
+        assert!(!letters[0].error.is_empty());
+    }
+
+    /// Actions run in the background, so wait for their failures to be recorded
+    async fn wait_for_dead_letters(engine: &Engine, count: usize) {
+        let deadline = std::time::Instant::now() + std::time::Duration::from_secs(5);
+        while engine.get_dead_letters().len() < count && std::time::Instant::now() < deadline {
+            sleep(Duration::from_millis(10)).await;
+        }
+    }
+
+    #[tokio::test]
+    async fn test_reload_rebuilds_dead_letter_queue() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let path = dir.path().join("dead_letters.jsonl");
+        let mut failing = tick_rule("failing");
+        failing.actions = vec![ActionConfig::Execute {
+            command: "win-event-engine-no-such-command".to_string(),
+            args: Vec::new(),
+            working_dir: None,
+        }];
+        let config = Config {
+            rules: vec![failing],
+            ..Default::default()
+        };
+        let mut engine = Engine::new(config.clone(), None);
+        engine
+            .initialize()
+            .await
+            .expect("Failed to initialize engine");
+
+        let sender = engine.event_sender().expect("Engine should own a sender");
+        sender
+            .send(Event::new(EventKind::TimerTick, "before"))
+            .await
+            .unwrap();
+        drop(sender);
+        wait_for_dead_letters(&engine, 1).await;
+
+        let mut new_config = config;
+        new_config.engine.dead_letter_file = Some(path.clone());
+        engine
+            .reload(new_config)
+            .await
+            .expect("Reload should succeed");
+
+        let sender = engine.event_sender().expect("Engine should own a sender");
+        sender
+            .send(Event::new(EventKind::TimerTick, "after"))
+            .await
+            .unwrap();
+        drop(sender);
+        wait_for_dead_letters(&engine, 2).await;
+        engine.shutdown().await;
+
+        // The failure from before the reload is kept, and only the new one
+        // goes to the file configured by it
+        let sources: Vec<String> = engine
+            .get_dead_letters()
+            .into_iter()
+            .map(|letter| letter.event.source)
+            .collect();
+        assert_eq!(sources, ["before", "after"]);
+        let contents = std::fs::read_to_string(&path).unwrap();
+        assert_eq!(contents.lines().count(), 1);
+        assert!(contents.contains("\"after\""));
+    }
+
+    /// A writer whose output can be read back after it has been handed off
+    #[derive(Clone, Default)]
+    struct SharedBuffer(Arc<Mutex<Vec<u8>>>);
+
+    impl std::io::Write for SharedBuffer {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.lock().unwrap().extend_from_slice(buf);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_event_dump_prints_events_instead_of_matching() {
+        let output = SharedBuffer::default();
+        let config = Config {
+            rules: vec![tick_rule("ticks")],
+            ..Default::default()
+        };
+        let mut engine = Engine::new(config, None).with_event_dump(output.clone());
+        engine
+            .initialize()
+            .await
+            .expect("Failed to initialize engine");
+        assert_eq!(engine.get_status().active_rules, 0);
+
+        let sender = engine.event_sender().expect("Engine should own a sender");
+        sender
+            .send(
+                Event::new(
+                    EventKind::FileCreated {
+                        path: "C:/Downloads/setup.exe".into(),
+                    },
+                    "downloads",
+                )
+                .with_metadata("size", "1024")
+                .with_metadata("extension", "exe"),
+            )
+            .await
+            .unwrap();
+        sender
+            .send(Event::new(EventKind::TimerTick, "timer"))
+            .await
+            .unwrap();
+        drop(sender);
+        engine.shutdown().await;
+
+        let printed = String::from_utf8(output.0.lock().unwrap().clone()).unwrap();
+        assert_eq!(
+            printed,
+            "#1 [downloads] FileCreated { path: \"C:/Downloads/setup.exe\" }\n\
+             \x20   extension = exe\n\
+             \x20   size = 1024\n\
+             #2 [timer] TimerTick\n"
+        );
+        assert_eq!(rule_matches(&engine, "ticks"), 0);
+    }
+
+    #[tokio::test]
+    async fn test_paused_engine_matches_without_running_actions() {
+        let mut failing = tick_rule("failing");
+        failing.actions = vec![ActionConfig::Execute {
+            command: "win-event-engine-no-such-command".to_string(),
+            args: Vec::new(),
+            working_dir: None,
+        }];
+        let config = Config {
+            rules: vec![failing],
+            ..Default::default()
+        };
+        let mut engine = Engine::new(config, None);
+        engine
+            .initialize()
+            .await
+            .expect("Failed to initialize engine");
+        engine
+            .pause_flag()
+            .store(true, std::sync::atomic::Ordering::Relaxed);
+        assert!(engine.get_status().paused);
+
+        let sender = engine.event_sender().expect("Engine should own a sender");
+        sender
+            .send(Event::new(EventKind::TimerTick, "pause_test"))
+            .await
+            .unwrap();
+        drop(sender);
+        engine.shutdown().await;
+
+        // The event was still matched, but no action was dispatched to fail
+        assert_eq!(rule_matches(&engine, "failing"), 1);
+        assert!(engine.get_dead_letters().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_reload_routes_events_through_new_rules() {
+        let config = Config {
+            rules: vec![tick_rule("old_rule")],
+            ..Default::default()
+        };
+        let mut engine = Engine::new(config, None);
+        engine
+            .initialize()
+            .await
+            .expect("Failed to initialize engine");
+
+        let sender = engine.event_sender().expect("Engine should own a sender");
+        sender
+            .send(Event::new(EventKind::TimerTick, "test"))
+            .await
+            .unwrap();
+        drop(sender);
+
+        let new_config = Config {
+            rules: vec![tick_rule("new_rule")],
+            ..Default::default()
+        };
+        engine
+            .reload(new_config)
+            .await
+            .expect("Reload should succeed");
+
+        // The event queued before the reload was handled by the old rules
+        assert_eq!(rule_matches(&engine, "old_rule"), 1);
+        assert_eq!(rule_matches(&engine, "new_rule"), 0);
+
+        let sender = engine
+            .event_sender()
+            .expect("Reload should create a new sender");
+        for _ in 0..3 {
+            sender
+                .send(Event::new(EventKind::TimerTick, "test"))
+                .await
+                .unwrap();
+        }
+        drop(sender);
+
+        engine.shutdown().await;
+
+        assert_eq!(rule_matches(&engine, "old_rule"), 1);
+        assert_eq!(rule_matches(&engine, "new_rule"), 3);
+    }
+
+    fn timer_source(name: &str) -> SourceConfig {
+        SourceConfig {
+            name: name.to_string(),
+            source_type: SourceType::Timer {
+                interval_seconds: 60,
+            },
+            enabled: true,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_restart_single_plugin() {
+        let config = Config {
+            sources: vec![timer_source("first"), timer_source("second")],
+            ..Default::default()
+        };
+        let mut engine = Engine::new(config, None);
+        engine
+            .initialize()
+            .await
+            .expect("Failed to initialize engine");
+
+        // Simulate both sources dying
+        for name in ["first", "second"] {
+            let plugin = engine.plugin_mut(name).expect("Plugin should be loaded");
+            plugin.stop().await.unwrap();
+        }
+
+        engine
+            .restart_plugin("first")
+            .await
+            .expect("Restart should succeed");
+
+        assert!(engine.plugin_mut("first").unwrap().is_running());
+        assert!(!engine.plugin_mut("second").unwrap().is_running());
+        assert_eq!(engine.get_status().active_plugins, 2);
+
+        assert!(matches!(
+            engine.restart_plugin("missing").await,
+            Err(EngineError::Config(_))
+        ));
+
+        engine.shutdown().await;
+    }
+
+    fn config_write(path: &str) -> notify::Event {
+        notify::Event::new(notify::EventKind::Modify(notify::event::ModifyKind::Any))
+            .add_path(std::path::PathBuf::from(path))
+    }
+
+    #[tokio::test]
+    async fn test_config_change_burst_signals_one_reload() {
+        let (change_tx, change_rx) = tokio::sync::mpsc::channel(100);
+        let (reload_tx, mut reload_rx) = tokio::sync::mpsc::channel(10);
+        let quiet = Duration::from_millis(100);
+        let watcher = tokio::spawn(debounce_config_changes(
+            change_rx,
+            reload_tx,
+            quiet,
+            Arc::new(std::sync::atomic::AtomicBool::new(false)),
+        ));
+
+        // An editor saving in several writes, each well inside the quiet period
+        let mut last_change = std::time::Instant::now();
+        for _ in 0..5 {
+            sleep(Duration::from_millis(20)).await;
+            change_tx
+                .send(config_write("config/rules.toml"))
+                .await
+                .unwrap();
+            last_change = std::time::Instant::now();
+        }
+        // Not a config file, so it doesn't extend the quiet period
+        change_tx
+            .send(config_write("config/notes.txt"))
+            .await
+            .unwrap();
+
+        tokio::time::timeout(Duration::from_secs(5), reload_rx.recv())
+            .await
+            .expect("Burst should signal a reload")
+            .unwrap();
+        assert!(last_change.elapsed() >= quiet);
+
+        sleep(quiet * 3).await;
+        assert!(
+            reload_rx.try_recv().is_err(),
+            "Burst should signal only once"
+        );
+
+        drop(change_tx);
+        watcher.await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_rules_only_reload_keeps_plugins_running() {
+        let config = Config {
+            sources: vec![timer_source("first"), timer_source("second")],
+            rules: vec![tick_rule("old_rule")],
+            ..Default::default()
+        };
+        let mut engine = Engine::new(config.clone(), None);
+        engine
+            .initialize()
+            .await
+            .expect("Failed to initialize engine");
+
+        // A full reload would recreate this plugin and start it again
+        engine.plugin_mut("second").unwrap().stop().await.unwrap();
+
+        let new_config = Config {
+            rules: vec![tick_rule("new_rule")],
+            ..config.clone()
+        };
+        engine
+            .reload_rules_only(new_config)
+            .await
+            .expect("Reload should succeed");
+
+        assert!(engine.plugin_mut("first").unwrap().is_running());
+        assert!(!engine.plugin_mut("second").unwrap().is_running());
+
+        // Plugins keep emitting into the same loop, which now has the new rules
+        let sender = engine.event_sender().expect("Engine should own a sender");
+        sender
+            .send(Event::new(EventKind::TimerTick, "test"))
+            .await
+            .unwrap();
+        drop(sender);
+        engine.shutdown().await;
+
+        assert_eq!(rule_matches(&engine, "old_rule"), 0);
+        assert_eq!(rule_matches(&engine, "new_rule"), 1);
+    }
+
+    #[tokio::test]
+    async fn test_rules_only_reload_restarts_plugins_when_sources_change() {
+        let config = Config {
+            sources: vec![timer_source("first")],
+            ..Default::default()
+        };
+        let mut engine = Engine::new(config, None);
+        engine
+            .initialize()
+            .await
+            .expect("Failed to initialize engine");
+        engine.plugin_mut("first").unwrap().stop().await.unwrap();
+
+        let new_config = Config {
+            sources: vec![timer_source("first"), timer_source("second")],
+            ..Default::default()
+        };
+        engine
+            .reload_rules_only(new_config)
+            .await
+            .expect("Reload should succeed");
+
+        assert!(engine.plugin_mut("first").unwrap().is_running());
+        assert!(engine.plugin_mut("second").unwrap().is_running());
+
+        engine.shutdown().await;
+    }
+
+    #[tokio::test]
+    async fn test_rules_only_reload_restarts_plugins_when_engine_settings_change() {
+        let config = Config {
+            sources: vec![timer_source("first")],
+            ..Default::default()
+        };
+        let mut engine = Engine::new(config.clone(), None);
+        engine
+            .initialize()
+            .await
+            .expect("Failed to initialize engine");
+        engine.plugin_mut("first").unwrap().stop().await.unwrap();
+
+        // The action limit is fixed when the processing loop starts, so it
+        // only takes effect through a full reload
+        let mut new_config = config;
+        new_config.engine.max_concurrent_actions += 1;
+        engine
+            .reload_rules_only(new_config)
+            .await
+            .expect("Reload should succeed");
+
+        assert!(engine.plugin_mut("first").unwrap().is_running());
+
+        engine.shutdown().await;
+    }
+
+    /// Still claims to be running after its worker thread has exited, like an
+    /// ETW source whose session was stopped underneath it
+    struct DyingPlugin {
+        name: String,
+        is_running: bool,
+        worker: Option<std::thread::JoinHandle<()>>,
+    }
+
+    impl DyingPlugin {
+        fn new(name: &str) -> Self {
+            Self {
+                name: name.to_string(),
+                is_running: false,
+                worker: None,
+            }
+        }
+    }
+
+    #[async_trait::async_trait]
+    impl EventSourcePlugin for DyingPlugin {
+        fn name(&self) -> &str {
+            &self.name
+        }
+
+        async fn start(&mut self, _emitter: EventEmitter) -> Result<(), PluginError> {
+            self.worker = Some(std::thread::spawn(|| {}));
+            self.is_running = true;
+            Ok(())
+        }
+
+        fn is_running(&self) -> bool {
+            self.is_running
+        }
+
+        fn health(&self) -> PluginHealth {
+            match &self.worker {
+                Some(worker) if self.is_running && worker.is_finished() => {
+                    PluginHealth::Failed("worker thread exited".to_string())
+                }
+                _ if self.is_running => PluginHealth::Healthy,
+                _ => PluginHealth::Stopped,
+            }
+        }
+    }
+
+    async fn dying_plugin(name: &str) -> Box<dyn EventSourcePlugin> {
+        let (tx, _rx) = tokio::sync::mpsc::channel(1);
+        let mut plugin = DyingPlugin::new(name);
+        plugin.start(tx.into()).await.unwrap();
+        while plugin.health() == PluginHealth::Healthy {
+            sleep(Duration::from_millis(10)).await;
+        }
+        Box::new(plugin)
+    }
+
+    #[tokio::test]
+    async fn test_watchdog_detects_dead_plugin() {
+        let config = Config {
+            sources: vec![timer_source("ticker")],
+            ..Default::default()
+        };
+        let mut engine = Engine::new(config, None);
+        engine
+            .initialize()
+            .await
+            .expect("Failed to initialize engine");
+        engine.insert_plugin(dying_plugin("dying").await);
+
+        assert_eq!(engine.check_plugin_health().await, vec!["dying"]);
+        let labels = HashMap::from([
+            ("plugin".to_string(), "dying".to_string()),
+            ("error_type".to_string(), "died".to_string()),
+        ]);
+        assert_eq!(
+            engine
+                .metrics()
+                .get_counter("plugins_errors_total", &labels),
+            Some(1)
+        );
+
+        // Without restart_failed_plugins the dead plugin is only reported
+        assert_eq!(engine.check_plugin_health().await, vec!["dying"]);
+        assert_eq!(
+            engine.plugin_mut("ticker").unwrap().health(),
+            PluginHealth::Healthy
+        );
+
+        engine.shutdown().await;
+    }
+
+    #[tokio::test]
+    async fn test_watchdog_restarts_dead_plugin() {
+        let mut config = Config {
+            sources: vec![timer_source("ticker")],
+            ..Default::default()
+        };
+        config.engine.restart_failed_plugins = true;
+        let mut engine = Engine::new(config, None);
+        engine
+            .initialize()
+            .await
+            .expect("Failed to initialize engine");
+
+        // Swap the running source for one whose thread has died
+        engine.plugin_mut("ticker").unwrap().stop().await.unwrap();
+        engine.insert_plugin(dying_plugin("ticker").await);
+
+        assert_eq!(engine.check_plugin_health().await, vec!["ticker"]);
+        assert_eq!(
+            engine.plugin_mut("ticker").unwrap().health(),
+            PluginHealth::Healthy
+        );
+        assert!(engine.check_plugin_health().await.is_empty());
+
+        engine.shutdown().await;
+    }
+
+    #[tokio::test]
+    async fn test_reload_reports_system_status() {
+        let config = Config {
+            rules: vec![tick_rule("first")],
+            ..Default::default()
+        };
+        let mut engine = Engine::new(config, None);
+        engine
+            .initialize()
+            .await
+            .expect("Failed to initialize engine");
+        let mut updates = engine.metrics().subscribe();
+
+        let new_config = Config {
+            rules: vec![tick_rule("first"), tick_rule("second")],
+            ..Default::default()
+        };
+        engine
+            .reload(new_config)
+            .await
+            .expect("Reload should succeed");
+        engine.shutdown().await;
+
+        match updates.try_recv() {
+            Ok(MetricUpdate::Health {
+                active_plugins,
+                active_rules,
+                ..
+            }) => {
+                assert_eq!(active_plugins, 0);
+                assert_eq!(active_rules, 2);
+            }
+            other => panic!("Expected Health, got {:?}", other),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_rule_registry_toggles_rules_and_survives_reload() {
+        let mut described = tick_rule("described");
+        described.description = Some("Logs every tick".to_string());
+        let config = Config {
+            rules: vec![described, tick_rule("other")],
+            ..Default::default()
+        };
+        let mut engine = Engine::new(config, None);
+        engine
+            .initialize()
+            .await
+            .expect("Failed to initialize engine");
+        let registry = engine.rule_registry();
+
+        let rules = registry.rules();
+        assert_eq!(rules.len(), 2);
+        assert_eq!(rules[0].description.as_deref(), Some("Logs every tick"));
+        assert!(rules.iter().all(|rule| rule.enabled));
+
+        // Rules are toggled by name
+        let updated = registry.set_rule_enabled("other", false).unwrap();
+        assert!(!updated.enabled);
+        assert!(registry.set_rule_enabled("missing", false).is_none());
+
+        let new_config = Config {
+            rules: vec![tick_rule("reloaded")],
+            ..Default::default()
+        };
+        engine
+            .reload(new_config)
+            .await
+            .expect("Reload should succeed");
+
+        // The handle taken before the reload sees the new rules
+        let names: Vec<String> = registry.rules().into_iter().map(|rule| rule.name).collect();
+        assert_eq!(names, ["reloaded"]);
+
+        registry.set_rule_enabled("reloaded", false).unwrap();
+        let sender = engine
+            .event_sender()
+            .expect("Reload should create a new sender");
+        sender
+            .send(Event::new(EventKind::TimerTick, "test"))
+            .await
+            .unwrap();
+        drop(sender);
+        engine.shutdown().await;
+
+        assert_eq!(rule_matches(&engine, "reloaded"), 0);
+    }
+
+    fn append_rule(name: &str, trigger: TriggerConfig, path: &std::path::Path) -> RuleConfig {
+        RuleConfig {
+            name: name.to_string(),
+            description: None,
+            trigger,
+            actions: vec![ActionConfig::FileAppend {
+                path: path.to_path_buf(),
+                format: name.to_string(),
+                max_bytes: log_rotation::DEFAULT_MAX_BYTES,
+                max_files: log_rotation::DEFAULT_MAX_FILES,
+            }],
+            on_error: "continue".to_string(),
+            circuit_breaker: None,
+            dedup_window: None,
+            enabled: true,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_replay_runs_recorded_events_through_rules() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let installers = temp_dir.path().join("installers.log");
+        let ticks = temp_dir.path().join("ticks.log");
+        let recording = temp_dir.path().join("events.jsonl");
+
+        // As a jsonl_file sink writes them, with its timestamp alongside each event
+        let lines: Vec<String> = [
+            Event::new(
+                EventKind::FileCreated {
+                    path: "C:/Downloads/setup.exe".into(),
+                },
+                "downloads",
+            ),
+            Event::new(
+                EventKind::FileCreated {
+                    path: "C:/Downloads/notes.txt".into(),
+                },
+                "downloads",
+            ),
+            Event::new(EventKind::TimerTick, "timer"),
+            Event::new(
+                EventKind::FileCreated {
+                    path: "C:/Downloads/tool.exe".into(),
+                },
+                "downloads",
+            ),
+        ]
+        .iter()
+        .map(|event| {
+            let mut record = serde_json::to_value(event).unwrap();
+            record["timestamp"] = "2026-01-01T00:00:00.000Z".into();
+            record.to_string()
+        })
+        .collect();
+        std::fs::write(&recording, lines.join("\n") + "\n\n").unwrap();
+
+        let config = Config {
+            rules: vec![
+                append_rule(
+                    "installer",
+                    TriggerConfig::FileCreated {
+                        pattern: Some("*.exe".to_string()),
+                    },
+                    &installers,
+                ),
+                append_rule(
+                    "tick",
+                    TriggerConfig::Timer {
+                        interval_seconds: 60,
+                    },
+                    &ticks,
+                ),
+            ],
+            ..Default::default()
+        };
+        let mut engine = Engine::new(config, None);
+        engine
+            .initialize()
+            .await
+            .expect("Failed to initialize engine");
+
+        let replayed = engine
+            .replay(&recording, None)
+            .await
+            .expect("Replay should succeed");
+        engine.shutdown().await;
+        engine.wait_for_actions().await;
+
+        assert_eq!(replayed, 4);
+        assert_eq!(rule_matches(&engine, "installer"), 2);
+        assert_eq!(rule_matches(&engine, "tick"), 1);
+        assert_eq!(
+            std::fs::read_to_string(&installers)
+                .unwrap()
+                .lines()
+                .count(),
+            2
+        );
+        assert_eq!(std::fs::read_to_string(&ticks).unwrap().lines().count(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_dedup_window_suppresses_duplicate_events() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let focus_log = temp_dir.path().join("focus.log");
+
+        let mut focus = append_rule(
+            "focus",
+            TriggerConfig::WindowFocused {
+                title_contains: None,
+                title_regex: None,
+                process_name: None,
+            },
+            &focus_log,
+        );
+        focus.dedup_window = Some(DedupConfig {
+            seconds: 1,
+            key: vec!["source".into(), "kind".into(), "title".into()],
+        });
+        let config = Config {
+            rules: vec![focus],
+            ..Default::default()
+        };
+        let mut engine = Engine::new(config, None);
+        engine
+            .initialize()
+            .await
+            .expect("Failed to initialize engine");
+
+        let focused = |title: &str| {
+            Event::new(
+                EventKind::WindowFocused {
+                    hwnd: 1,
+                    title: title.to_string(),
+                },
+                "windows",
+            )
+        };
+        let sender = engine.event_sender().expect("Engine should own a sender");
+        for title in ["Editor", "Editor", "Terminal", "Editor"] {
+            sender.send(focused(title)).await.unwrap();
+        }
+        // Past the window the same signature fires again
+        sleep(Duration::from_millis(1200)).await;
+        sender.send(focused("Editor")).await.unwrap();
+        drop(sender);
+        engine.shutdown().await;
+        engine.wait_for_actions().await;
+
+        assert_eq!(rule_matches(&engine, "focus"), 3);
+        assert_eq!(
+            std::fs::read_to_string(&focus_log).unwrap().lines().count(),
+            3
+        );
+    }
+
+    #[tokio::test]
+    async fn test_replay_reports_bad_line() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let recording = temp_dir.path().join("events.jsonl");
+        std::fs::write(&recording, "{\"not\": \"an event\"}\n").unwrap();
+
+        let mut engine = Engine::new(Config::default(), None);
+        engine
+            .initialize()
+            .await
+            .expect("Failed to initialize engine");
+
+        match engine.replay(&recording, None).await {
+            Err(EngineError::Replay(msg)) => assert!(msg.contains("line 1"), "{}", msg),
+            other => panic!("Expected a replay error, got {:?}", other),
+        }
+
+        engine.shutdown().await;
+    }
+
+    #[tokio::test]
+    async fn test_rule_order_does_not_change_actions() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let tick_log = temp_dir.path().join("tick.log");
+        let idle_log = temp_dir.path().join("idle.log");
+
+        let on_tick = append_rule(
+            "on_tick",
+            TriggerConfig::Timer {
+                interval_seconds: 60,
+            },
+            &tick_log,
+        );
+        let on_idle = append_rule("on_idle", TriggerConfig::UserIdle, &idle_log);
+        let mut disabled = tick_rule("disabled");
+        disabled.enabled = false;
+
+        for rules in [
+            vec![on_tick.clone(), on_idle.clone()],
+            vec![disabled.clone(), on_idle.clone(), on_tick.clone()],
+        ] {
+            let config = Config {
+                rules,
+                ..Default::default()
+            };
+            let mut engine = Engine::new(config, None);
+            engine
+                .initialize()
+                .await
+                .expect("Failed to initialize engine");
+
+            let sender = engine.event_sender().expect("Engine should own a sender");
+            sender
+                .send(Event::new(EventKind::TimerTick, "test"))
+                .await
+                .unwrap();
+            drop(sender);
+            engine.shutdown().await;
+
+            // Actions run in the background, so wait for the write to land
+            let deadline = std::time::Instant::now() + std::time::Duration::from_secs(5);
+            while std::fs::read_to_string(&tick_log)
+                .unwrap_or_default()
+                .is_empty()
+                && std::time::Instant::now() < deadline
+            {
+                sleep(Duration::from_millis(10)).await;
+            }
+
+            assert_eq!(
+                std::fs::read_to_string(&tick_log).unwrap().trim(),
+                "on_tick"
+            );
+            assert!(!idle_log.exists());
+            std::fs::remove_file(&tick_log).unwrap();
+        }
+    }
+
+    #[tokio::test]
+    async fn test_rule_actions_run_in_order() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let log = temp_dir.path().join("steps.log");
+
+        let mut rule = append_rule(
+            "two_steps",
+            TriggerConfig::Timer {
+                interval_seconds: 60,
+            },
+            &log,
+        );
+        rule.actions = ["first", "second"]
+            .into_iter()
+            .map(|step| ActionConfig::FileAppend {
+                path: log.clone(),
+                format: step.to_string(),
+                max_bytes: log_rotation::DEFAULT_MAX_BYTES,
+                max_files: log_rotation::DEFAULT_MAX_FILES,
+            })
+            .collect();
+
+        let config = Config {
+            rules: vec![rule],
+            ..Default::default()
+        };
+        let mut engine = Engine::new(config, None);
+        engine
+            .initialize()
+            .await
+            .expect("Failed to initialize engine");
+
+        let sender = engine.event_sender().expect("Engine should own a sender");
+        sender
+            .send(Event::new(EventKind::TimerTick, "test"))
+            .await
+            .unwrap();
+        drop(sender);
+        engine.shutdown().await;
+
+        let deadline = std::time::Instant::now() + std::time::Duration::from_secs(5);
+        while std::fs::read_to_string(&log)
+            .unwrap_or_default()
+            .lines()
+            .count()
+            < 2
+            && std::time::Instant::now() < deadline
+        {
+            sleep(Duration::from_millis(10)).await;
+        }
+
+        assert_eq!(std::fs::read_to_string(&log).unwrap(), "first\nsecond\n");
+    }
+
+    #[tokio::test]
+    async fn test_dry_run_skips_actions() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let log = temp_dir.path().join("dry_run.log");
+
+        let config = Config {
+            rules: vec![append_rule(
+                "append",
+                TriggerConfig::Timer {
+                    interval_seconds: 60,
+                },
+                &log,
+            )],
+            ..Default::default()
+        };
+        let mut engine = Engine::new(config, None).with_dry_run(true);
+        engine
+            .initialize()
+            .await
+            .expect("Failed to initialize engine");
+
+        let sender = engine.event_sender().expect("Engine should own a sender");
+        sender
+            .send(Event::new(EventKind::TimerTick, "test"))
+            .await
+            .unwrap();
+        drop(sender);
+        engine.shutdown().await;
+
+        // Wait until the dispatch has reported back before checking for side effects
+        let labels = HashMap::from([
+            ("action".to_string(), "append".to_string()),
+            ("status".to_string(), "skipped".to_string()),
+        ]);
+        let deadline = std::time::Instant::now() + std::time::Duration::from_secs(5);
+        while engine
+            .metrics()
+            .get_counter("actions_executed_total", &labels)
+            .is_none()
+            && std::time::Instant::now() < deadline
+        {
+            sleep(Duration::from_millis(10)).await;
+        }
+
+        assert_eq!(rule_matches(&engine, "append"), 1);
+        assert_eq!(
+            engine
+                .metrics()
+                .get_counter("actions_executed_total", &labels),
+            Some(1)
+        );
+        assert!(!log.exists(), "dry run must not run the action");
+    }
+
+    #[tokio::test]
+    async fn test_script_action_from_config() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let script = temp_dir.path().join("mark_seen.lua");
+        std::fs::write(
+            &script,
+            r#"
+function on_event(event)
+    local path = event.fields.path
+    if not fs.move(path, path .. ".seen") then
+        error("could not move " .. path)
+    end
+    return {success = true}
+end
+"#,
+        )
+        .unwrap();
+        let created = temp_dir.path().join("report.txt");
+        std::fs::write(&created, "data").unwrap();
+
+        let config = Config {
+            rules: vec![RuleConfig {
+                name: "mark_seen".to_string(),
+                description: None,
+                trigger: TriggerConfig::FileCreated {
+                    pattern: Some("*.txt".to_string()),
+                },
+                actions: vec![ActionConfig::Script {
+                    path: script,
+                    function: "on_event".to_string(),
+                    timeout_ms: None,
+                    on_error: "fail".to_string(),
+                    allowed_paths: Some(vec![temp_dir.path().to_path_buf()]),
+                }],
+                on_error: "continue".to_string(),
+                circuit_breaker: None,
+                dedup_window: None,
+                enabled: true,
+            }],
+            ..Default::default()
+        };
+        assert!(config.validate().is_ok());
+
+        let mut engine = Engine::new(config, None);
+        engine
+            .initialize()
+            .await
+            .expect("Failed to initialize engine");
+
+        let sender = engine.event_sender().expect("Engine should own a sender");
+        sender
+            .send(Event::new(
+                EventKind::FileCreated {
+                    path: created.clone(),
+                },
+                "test",
+            ))
+            .await
+            .unwrap();
+        drop(sender);
+        engine.shutdown().await;
+
+        let seen = temp_dir.path().join("report.txt.seen");
+        let deadline = std::time::Instant::now() + std::time::Duration::from_secs(5);
+        while !seen.exists() && std::time::Instant::now() < deadline {
+            sleep(Duration::from_millis(10)).await;
+        }
+
+        assert!(seen.exists(), "script should have moved the created file");
+        assert!(!created.exists());
+    }
+
+    #[tokio::test]
+    async fn test_composite_trigger_matching() {
+        let created = |pattern: &str| TriggerConfig::FileCreated {
+            pattern: Some(pattern.to_string()),
+        };
+        let mut rule = tick_rule("reports");
+        rule.trigger = TriggerConfig::All {
+            triggers: vec![
+                created("report*"),
+                TriggerConfig::Any {
+                    triggers: vec![created("*.pdf"), created("*.docx")],
+                },
+            ],
+        };
+
+        let config = Config {
+            rules: vec![rule],
+            ..Default::default()
+        };
+        let mut engine = Engine::new(config, None);
+        engine
+            .initialize()
+            .await
+            .expect("Failed to initialize engine");
+
+        let sender = engine.event_sender().expect("Engine should own a sender");
+        for name in ["report.pdf", "report.txt", "notes.pdf", "report.docx"] {
+            let kind = EventKind::FileCreated {
+                path: std::path::PathBuf::from(name),
+            };
+            sender.send(Event::new(kind, "test")).await.unwrap();
+        }
+        drop(sender);
+        engine.shutdown().await;
+
+        assert_eq!(rule_matches(&engine, "reports"), 2);
+    }
 }
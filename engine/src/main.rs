@@ -1,21 +1,33 @@
 mod config;
+mod control;
+mod dead_letter;
 mod engine;
 mod plugins;
 mod service;
+mod sinks;
 
 #[cfg(test)]
 mod integration_tests;
 
 use clap::Parser;
 use metrics::server::MetricsServer;
+use service::ServiceCommand;
+use std::io::Write;
 use std::path::PathBuf;
-use tracing::{Level, debug, error, info, warn};
-use tracing_subscriber;
+use std::sync::{Arc, Mutex};
+use tracing::field::Field;
+use tracing::{Dispatch, Event, Level, Subscriber, debug, error, info, trace, warn};
+use tracing_subscriber::EnvFilter;
+use tracing_subscriber::filter::ParseError;
+use tracing_subscriber::fmt::MakeWriter;
+use tracing_subscriber::layer::{Context, Layer, SubscriberExt};
+use tracing_subscriber::util::SubscriberInitExt;
 
 #[derive(Parser, Debug)]
 #[command(name = "Windows Event Automation Engine")]
 #[command(about = "A universal event automation system for Windows")]
 #[command(version)]
+#[command(group(clap::ArgGroup::new("service").multiple(false)))]
 struct Cli {
     /// Path to configuration file
     #[arg(short, long, value_name = "FILE")]
@@ -29,91 +41,239 @@ struct Cli {
     #[arg(long)]
     dry_run: bool,
 
-    /// Log level (debug, info, warn, error)
-    #[arg(short, long, default_value = "info")]
-    log_level: String,
+    /// Start the configured sources but print every event they emit to
+    /// stdout instead of evaluating rules
+    #[arg(long)]
+    dump_events: bool,
+
+    /// Feed the events recorded in a JSON-lines file (as written by a
+    /// `jsonl_file` sink) through the rules instead of starting sources
+    #[arg(long, value_name = "FILE", conflicts_with_all = ["dump_events", "service"])]
+    replay: Option<PathBuf>,
+
+    /// Events per second to replay at; as fast as possible when not given
+    #[arg(long, value_name = "EVENTS_PER_SEC", requires = "replay", value_parser = parse_replay_rate)]
+    replay_rate: Option<f64>,
+
+    /// Log level (debug, info, warn, error), or filter directives such as
+    /// `info,rules=debug`; defaults to `RUST_LOG`, then `info`
+    #[arg(short, long, value_name = "LEVEL|DIRECTIVES")]
+    log_level: Option<String>,
+
+    /// Log output format; overrides `engine.log_format` in the config [default: text]
+    #[arg(long, value_enum)]
+    log_format: Option<config::LogFormat>,
 
     /// Show engine status and exit
     #[arg(long)]
     status: bool,
 
+    /// Load and validate the configuration, report any problems and exit
+    /// (non-zero if there were any) without starting the engine
+    #[arg(long, conflicts_with = "service")]
+    validate: bool,
+
     /// Disable hot-reloading of configuration
     #[arg(long)]
     no_watch: bool,
 
     /// Install as Windows Service (requires admin)
-    #[arg(long)]
-    install: bool,
+    #[arg(long, alias = "install", group = "service")]
+    install_service: bool,
 
-    /// Uninstall Windows Service (requires admin)
-    #[arg(long)]
-    uninstall: bool,
+    /// Stop and uninstall the Windows Service (requires admin)
+    #[arg(long, alias = "uninstall", group = "service")]
+    uninstall_service: bool,
+
+    /// Start the installed Windows Service
+    #[arg(long, group = "service")]
+    start_service: bool,
+
+    /// Stop the running Windows Service
+    #[arg(long, group = "service")]
+    stop_service: bool,
+
+    /// Show whether the Windows Service is running
+    #[arg(long, group = "service")]
+    service_status: bool,
 
     /// Run as Windows Service (internal - called by SCM)
-    #[arg(long)]
+    #[arg(long, group = "service")]
     run_service: bool,
 }
 
+fn parse_replay_rate(value: &str) -> Result<f64, String> {
+    match value.parse::<f64>() {
+        Ok(rate) if rate > 0.0 && rate.is_finite() => Ok(rate),
+        Ok(_) => Err("must be greater than zero".to_string()),
+        Err(e) => Err(e.to_string()),
+    }
+}
+
+impl Cli {
+    /// The service manager operation requested, if any
+    fn service_command(&self) -> Option<ServiceCommand> {
+        if self.install_service {
+            Some(ServiceCommand::Install)
+        } else if self.uninstall_service {
+            Some(ServiceCommand::Uninstall)
+        } else if self.start_service {
+            Some(ServiceCommand::Start)
+        } else if self.stop_service {
+            Some(ServiceCommand::Stop)
+        } else if self.service_status {
+            Some(ServiceCommand::Status)
+        } else {
+            None
+        }
+    }
+}
+
+/// Parse `RUST_LOG`-style directives, e.g. `info,rules=debug`. A bare level
+/// such as `debug` applies to everything.
+fn log_filter(directives: &str) -> Result<EnvFilter, ParseError> {
+    EnvFilter::builder().parse(directives)
+}
+
+/// Build the logging subscriber, writing to `writer` in the given format
+fn log_dispatch<W>(filter: EnvFilter, format: config::LogFormat, writer: W) -> Dispatch
+where
+    W: for<'a> MakeWriter<'a> + Send + Sync + 'static,
+{
+    let builder = tracing_subscriber::fmt()
+        .with_env_filter(filter)
+        .with_target(false)
+        .with_writer(writer);
+
+    match format {
+        config::LogFormat::Text => Dispatch::new(builder.finish()),
+        config::LogFormat::Json => Dispatch::new(builder.json().finish()),
+    }
+}
+
+/// Log events raised before the subscriber is installed, such as config load
+/// warnings, held so they can be logged once it is
+#[derive(Clone, Default)]
+struct DeferredLogs(Arc<Mutex<Vec<(Level, String)>>>);
+
+impl DeferredLogs {
+    /// Run `f`, holding back anything it logs
+    fn capture<T>(&self, f: impl FnOnce() -> T) -> T {
+        let dispatch = Dispatch::new(tracing_subscriber::registry().with(self.clone()));
+        tracing::dispatcher::with_default(&dispatch, f)
+    }
+
+    /// Log everything held back through the current subscriber
+    fn replay(&self) {
+        for (level, message) in self.0.lock().unwrap().drain(..) {
+            match level {
+                Level::ERROR => error!("{}", message),
+                Level::WARN => warn!("{}", message),
+                Level::INFO => info!("{}", message),
+                Level::DEBUG => debug!("{}", message),
+                _ => trace!("{}", message),
+            }
+        }
+    }
+}
+
+impl<S: Subscriber> Layer<S> for DeferredLogs {
+    fn on_event(&self, event: &Event<'_>, _ctx: Context<'_, S>) {
+        let mut message = String::new();
+        event.record(&mut |field: &Field, value: &dyn std::fmt::Debug| {
+            if !message.is_empty() {
+                message.push(' ');
+            }
+            if field.name() == "message" {
+                message.push_str(&format!("{:?}", value));
+            } else {
+                message.push_str(&format!("{}={:?}", field.name(), value));
+            }
+        });
+        self.0
+            .lock()
+            .unwrap()
+            .push((*event.metadata().level(), message));
+    }
+}
+
 #[tokio::main]
 async fn main() {
     let cli = Cli::parse();
 
-    // Initialize logging
-    let log_level = match cli.log_level.as_str() {
-        "debug" => Level::DEBUG,
-        "info" => Level::INFO,
-        "warn" => Level::WARN,
-        "error" => Level::ERROR,
-        _ => Level::INFO,
+    // Service commands never load a configuration
+    let config_path = if cli.service_command().is_some() || cli.run_service {
+        None
+    } else {
+        resolve_config_path(&cli)
     };
 
-    tracing_subscriber::fmt()
-        .with_max_level(log_level)
-        .with_target(false)
-        .init();
+    if cli.validate {
+        let code = match &config_path {
+            Some(path) => validate_config(path, &mut std::io::stdout()),
+            None => {
+                eprintln!("No configuration found to validate; pass --config or --config-dir");
+                1
+            }
+        };
+        std::process::exit(code);
+    }
+
+    // Loaded before logging starts so the config can choose the log format;
+    // any error or warning is reported once logging is up
+    let load_logs = DeferredLogs::default();
+    let loaded_config = load_logs.capture(|| config_path.as_ref().map(load_config));
+
+    // Initialize logging
+    let log_directives = cli
+        .log_level
+        .clone()
+        .or_else(|| std::env::var(EnvFilter::DEFAULT_ENV).ok())
+        .unwrap_or_else(|| "info".to_string());
+    let (log_filter, filter_error) = match log_filter(&log_directives) {
+        Ok(filter) => (filter, None),
+        Err(e) => (EnvFilter::new("info"), Some(e)),
+    };
+    let log_format = cli
+        .log_format
+        .or(match &loaded_config {
+            Some(Ok(cfg)) => Some(cfg.engine.log_format),
+            _ => None,
+        })
+        .unwrap_or_default();
+
+    log_dispatch(log_filter, log_format, std::io::stdout).init();
+    load_logs.replay();
+
+    if let Some(e) = filter_error {
+        warn!("Invalid log level '{}' ({}), using info", log_directives, e);
+    }
 
     info!(
         "Windows Event Automation Engine v{}",
         env!("CARGO_PKG_VERSION")
     );
 
-    // Handle service install/uninstall commands
-    if cli.install || cli.uninstall {
-        match service::ServiceManagerHandle::new() {
-            Ok(manager) => {
-                if cli.install {
-                    let exe_path = std::env::current_exe()
-                        .map_err(|e| format!("Failed to get executable path: {}", e))
-                        .unwrap_or_else(|e| {
-                            error!("{}", e);
-                            std::process::exit(1);
-                        });
-                    match manager.install(exe_path.to_str().unwrap_or("")) {
-                        Ok(_) => {
-                            println!("Service installed successfully.");
-                            println!(
-                                "Use 'sc start WinEventEngine' or the Services control panel to start."
-                            );
-                        }
-                        Err(e) => {
-                            error!("Failed to install service: {}", e);
-                            std::process::exit(1);
-                        }
-                    }
-                } else if cli.uninstall {
-                    match manager.uninstall() {
-                        Ok(_) => {
-                            println!("Service uninstalled successfully.");
-                        }
-                        Err(e) => {
-                            error!("Failed to uninstall service: {}", e);
-                            std::process::exit(1);
-                        }
-                    }
-                }
-            }
+    // Handle service manager commands
+    if let Some(command) = cli.service_command() {
+        let manager = if command == ServiceCommand::Install {
+            service::ServiceManagerHandle::new()
+        } else {
+            service::ServiceManagerHandle::connect()
+        };
+        let manager = manager.unwrap_or_else(|e| {
+            error!("Failed to connect to Service Control Manager: {}", e);
+            std::process::exit(1);
+        });
+        let exe_path = std::env::current_exe().unwrap_or_else(|e| {
+            error!("Failed to get executable path: {}", e);
+            std::process::exit(1);
+        });
+
+        match service::run_service_command(&manager, command, exe_path.to_str().unwrap_or("")) {
+            Ok(message) => println!("{}", message),
             Err(e) => {
-                error!("Failed to connect to Service Control Manager: {}", e);
+                error!("{}", e);
                 std::process::exit(1);
             }
         }
@@ -126,68 +286,19 @@ async fn main() {
         return;
     }
 
-    // Determine config path for hot-reloading
-    let config_path = if let Some(ref path) = cli.config {
-        Some(path.clone())
-    } else if let Some(ref dir) = cli.config_dir {
-        Some(dir.clone())
-    } else {
-        let default_config = PathBuf::from("config.toml");
-        let default_config_dir = PathBuf::from("config");
-        if default_config.exists() {
-            Some(default_config)
-        } else if default_config_dir.exists() {
-            Some(default_config_dir)
-        } else {
-            None
-        }
-    };
-
     // Load configuration
-    let config = if let Some(config_path) = config_path.clone() {
-        if config_path.is_dir() {
-            info!("Loading configuration from directory: {:?}", config_path);
-            match config::Config::load_from_dir(&config_path) {
-                Ok(cfg) => cfg,
-                Err(e) => {
-                    error!("Failed to load configuration: {}", e);
-                    std::process::exit(1);
-                }
-            }
-        } else {
-            info!("Loading configuration from: {:?}", config_path);
-            match config::Config::load_from_file(&config_path) {
-                Ok(cfg) => cfg,
-                Err(e) => {
-                    error!("Failed to load configuration: {}", e);
-                    std::process::exit(1);
-                }
+    let config = match loaded_config {
+        Some(Ok(cfg)) => {
+            if let Some(ref path) = config_path {
+                info!("Loaded configuration from: {:?}", path);
             }
+            cfg
         }
-    } else {
-        // Try default locations
-        let default_config = PathBuf::from("config.toml");
-        let default_config_dir = PathBuf::from("config");
-
-        if default_config.exists() {
-            info!("Loading default configuration: config.toml");
-            match config::Config::load_from_file(&default_config) {
-                Ok(cfg) => cfg,
-                Err(e) => {
-                    error!("Failed to load configuration: {}", e);
-                    std::process::exit(1);
-                }
-            }
-        } else if default_config_dir.exists() {
-            info!("Loading configuration from config/ directory");
-            match config::Config::load_from_dir(&default_config_dir) {
-                Ok(cfg) => cfg,
-                Err(e) => {
-                    error!("Failed to load configuration: {}", e);
-                    std::process::exit(1);
-                }
-            }
-        } else {
+        Some(Err(e)) => {
+            error!("Failed to load configuration: {}", e);
+            std::process::exit(1);
+        }
+        None => {
             // Use default/demo configuration
             info!("No configuration found, using default demo setup");
             create_demo_config()
@@ -195,8 +306,14 @@ async fn main() {
     };
 
     // Validate configuration
-    if let Err(e) = config.validate() {
-        error!("Configuration validation failed: {}", e);
+    if let Err(errors) = config.validate() {
+        for e in &errors {
+            error!("Invalid configuration: {}", e);
+        }
+        error!(
+            "Configuration validation failed with {} errors",
+            errors.len()
+        );
         std::process::exit(1);
     }
 
@@ -209,6 +326,15 @@ async fn main() {
         info!("Running in dry-run mode (actions will not be executed)");
     }
 
+    if cli.dump_events {
+        info!("Dumping events to stdout (rules will not be evaluated)");
+    }
+
+    if let Some(ref replay_path) = cli.replay {
+        run_replay(config, replay_path, cli.replay_rate, cli.dry_run).await;
+        return;
+    }
+
     // Ensure plugins directory exists
     if let Err(e) = std::fs::create_dir_all("plugins/actions") {
         warn!("Failed to create plugins/actions directory: {}", e);
@@ -216,8 +342,15 @@ async fn main() {
         debug!("Plugins directory ready");
     }
 
+    let metrics_port = config.engine.metrics_port;
+    let control_port = config.engine.control_port;
+
     // Create and initialize engine
-    let mut engine_instance = engine::Engine::new(config, config_path.clone());
+    let mut engine_instance =
+        engine::Engine::new(config, config_path.clone()).with_dry_run(cli.dry_run);
+    if cli.dump_events {
+        engine_instance = engine_instance.with_event_dump(std::io::stdout());
+    }
 
     if let Err(e) = engine_instance.initialize().await {
         error!("Failed to initialize engine: {}", e);
@@ -227,13 +360,31 @@ async fn main() {
     // Start metrics server and cleanup task
     let metrics = engine_instance.metrics();
     metrics.start_cleanup_task().await;
-    let metrics_server = MetricsServer::new(metrics, 9090);
+    let metrics_server =
+        MetricsServer::new(metrics, metrics_port).with_rules(engine_instance.rule_registry());
     tokio::spawn(async move {
         if let Err(e) = metrics_server.start().await {
             error!("Metrics server error: {}", e);
         }
     });
-    info!("Metrics server available at http://127.0.0.1:9090");
+    info!(
+        "Metrics server available at http://127.0.0.1:{}",
+        metrics_port
+    );
+
+    // Start the control API if a port is configured
+    let (control_tx, mut control_rx) = tokio::sync::mpsc::channel(16);
+    if let Some(port) = control_port {
+        let control_server = control::ControlServer::new(control_tx, port);
+        tokio::spawn(async move {
+            if let Err(e) = control_server.start().await {
+                error!("Control API error: {}", e);
+            }
+        });
+        info!("Control API available at http://127.0.0.1:{}", port);
+    } else {
+        drop(control_tx);
+    }
 
     let status = engine_instance.get_status();
     info!(
@@ -252,6 +403,9 @@ async fn main() {
         None
     };
 
+    // Periodically look for sources that died without being stopped
+    let mut plugin_check = engine_instance.plugin_check_timer();
+
     let shutdown_flag = engine_instance.shutdown_flag();
 
     // Setup graceful shutdown
@@ -268,6 +422,12 @@ async fn main() {
                 info!("Received shutdown command");
                 break;
             }
+            Some(command) = control_rx.recv() => {
+                engine_for_shutdown.handle_control(command).await;
+            }
+            _ = engine::next_plugin_check(&mut plugin_check) => {
+                engine_for_shutdown.check_plugin_health().await;
+            }
             _ = async {
                 match &mut config_reload_rx {
                     Some(rx) => rx.recv().await,
@@ -278,13 +438,9 @@ async fn main() {
                 shutdown_flag.store(true, std::sync::atomic::Ordering::Relaxed);
 
                 if let Some(ref path) = config_path {
-                    match if path.is_dir() {
-                        config::Config::load_from_dir(path)
-                    } else {
-                        config::Config::load_from_file(path)
-                    } {
+                    match load_config(path) {
                         Ok(new_config) => {
-                            if let Err(e) = engine_for_shutdown.reload(new_config).await {
+                            if let Err(e) = engine_for_shutdown.reload_rules_only(new_config).await {
                                 error!("Failed to reload config: {}", e);
                             }
                         }
@@ -314,6 +470,104 @@ async fn main() {
     info!("Engine stopped");
 }
 
+/// Push recorded events through the configured rules instead of starting
+/// sources, then wait for the actions they trigger to finish
+async fn run_replay(
+    mut config: config::Config,
+    path: &std::path::Path,
+    rate: Option<f64>,
+    dry_run: bool,
+) {
+    strip_for_replay(&mut config);
+    let mut engine_instance = engine::Engine::new(config, None).with_dry_run(dry_run);
+
+    if let Err(e) = engine_instance.initialize().await {
+        error!("Failed to initialize engine: {}", e);
+        std::process::exit(1);
+    }
+
+    let result = engine_instance.replay(path, rate).await;
+    engine_instance.shutdown().await;
+    engine_instance.wait_for_actions().await;
+
+    match result {
+        Ok(count) => info!("Replay finished: {} events", count),
+        Err(e) => {
+            error!("{}", e);
+            std::process::exit(1);
+        }
+    }
+}
+
+/// Drop the sources, as the replayed file stands in for them, and the sinks, so
+/// a replay of a sink's own recording doesn't append to the file being read
+fn strip_for_replay(config: &mut config::Config) {
+    config.sources.clear();
+    config.sinks.clear();
+}
+
+/// The config file or directory to load (and watch for changes): the one
+/// given on the command line, else `config.toml` or `config/` if present
+fn resolve_config_path(cli: &Cli) -> Option<PathBuf> {
+    if let Some(ref path) = cli.config {
+        Some(path.clone())
+    } else if let Some(ref dir) = cli.config_dir {
+        Some(dir.clone())
+    } else {
+        let default_config = PathBuf::from("config.toml");
+        let default_config_dir = PathBuf::from("config");
+        if default_config.exists() {
+            Some(default_config)
+        } else if default_config_dir.exists() {
+            Some(default_config_dir)
+        } else {
+            None
+        }
+    }
+}
+
+fn load_config(path: &PathBuf) -> Result<config::Config, config::ConfigError> {
+    if path.is_dir() {
+        config::Config::load_from_dir(path)
+    } else {
+        config::Config::load_from_file(path)
+    }
+}
+
+/// Load and validate the configuration at `path`, writing a report of every
+/// problem found to `out`. Returns the process exit code: 0 if the
+/// configuration is usable, 1 otherwise.
+fn validate_config(path: &PathBuf, out: &mut dyn Write) -> i32 {
+    let config = match load_config(path) {
+        Ok(config) => config,
+        Err(e) => {
+            let _ = writeln!(out, "{}: {}", path.display(), e);
+            return 1;
+        }
+    };
+
+    match config.validate() {
+        Ok(()) => {
+            let _ = writeln!(
+                out,
+                "{}: OK ({} sources, {} rules, {} sinks)",
+                path.display(),
+                config.sources.len(),
+                config.rules.len(),
+                config.sinks.len()
+            );
+            0
+        }
+        Err(errors) => {
+            let _ = writeln!(out, "{}: {} problem(s) found", path.display(), errors.len());
+            for e in &errors {
+                let _ = writeln!(out, "  {}", e);
+            }
+            1
+        }
+    }
+}
+
 fn print_status(config: &config::Config) {
     println!("\n=== Engine Status ===\n");
     println!("Event Buffer Size: {}", config.engine.event_buffer_size);
@@ -339,7 +593,7 @@ fn print_status(config: &config::Config) {
         let status = if rule.enabled { "enabled" } else { "disabled" };
         println!(
             "  - {}: {:?} -> {:?} [{}]",
-            rule.name, rule.trigger, rule.action, status
+            rule.name, rule.trigger, rule.actions, status
         );
     }
     println!();
@@ -349,16 +603,35 @@ fn create_demo_config() -> config::Config {
     use config::*;
 
     Config {
+        version: Some(CONFIG_VERSION),
+        include: Vec::new(),
         engine: EngineConfig {
             event_buffer_size: 100,
             log_level: "info".to_string(),
+            log_format: config::LogFormat::Text,
+            max_concurrent_actions: 8,
+            action_timeout_seconds: 60,
+            index_rules: true,
+            metrics_port: 9090,
+            plugin_check_interval_seconds: 30,
+            restart_failed_plugins: false,
+            control_port: None,
+            dead_letter_capacity: 100,
+            dead_letter_file: None,
+            dead_letter_max_bytes: 10 * 1024 * 1024,
+            dead_letter_max_files: 5,
+            service_log_max_bytes: 10 * 1024 * 1024,
+            service_log_max_files: 5,
         },
         sources: vec![SourceConfig {
             name: "test_file_watcher".to_string(),
             source_type: SourceType::FileWatcher {
                 paths: vec![PathBuf::from(".")],
-                pattern: Some("*.txt".to_string()),
+                patterns: vec!["*.txt".to_string()],
+                exclude_patterns: Vec::new(),
                 recursive: false,
+                follow_symlinks: false,
+                debounce_ms: 300,
             },
             enabled: true,
         }],
@@ -368,11 +641,286 @@ fn create_demo_config() -> config::Config {
             trigger: TriggerConfig::FileCreated {
                 pattern: Some("*.txt".to_string()),
             },
-            action: ActionConfig::Log {
+            actions: vec![ActionConfig::Log {
                 message: "Text file created!".to_string(),
                 level: "info".to_string(),
-            },
+            }],
+            on_error: "continue".to_string(),
+            circuit_breaker: None,
+            dedup_window: None,
             enabled: true,
         }],
+        sinks: Vec::new(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(args: &[&str]) -> Result<Cli, clap::Error> {
+        Cli::try_parse_from(std::iter::once("engine").chain(args.iter().copied()))
+    }
+
+    #[test]
+    fn test_service_flags() {
+        let cases = [
+            ("--install-service", Some(ServiceCommand::Install)),
+            ("--install", Some(ServiceCommand::Install)),
+            ("--uninstall-service", Some(ServiceCommand::Uninstall)),
+            ("--uninstall", Some(ServiceCommand::Uninstall)),
+            ("--start-service", Some(ServiceCommand::Start)),
+            ("--stop-service", Some(ServiceCommand::Stop)),
+            ("--service-status", Some(ServiceCommand::Status)),
+            ("--run-service", None),
+        ];
+        for (flag, expected) in cases {
+            let cli = parse(&[flag]).unwrap();
+            assert_eq!(cli.service_command(), expected, "{}", flag);
+        }
+
+        assert!(parse(&["--run-service"]).unwrap().run_service);
+        assert_eq!(parse(&[]).unwrap().service_command(), None);
+    }
+
+    #[test]
+    fn test_service_flags_are_exclusive() {
+        assert!(parse(&["--start-service", "--stop-service"]).is_err());
+        assert!(parse(&["--install-service", "--run-service"]).is_err());
+    }
+
+    #[test]
+    fn test_log_format_flag() {
+        assert_eq!(parse(&[]).unwrap().log_format, None);
+        assert_eq!(
+            parse(&["--log-format", "json"]).unwrap().log_format,
+            Some(config::LogFormat::Json)
+        );
+        assert!(parse(&["--log-format", "xml"]).is_err());
+    }
+
+    /// Collects everything logged, so the output can be inspected
+    #[derive(Clone, Default)]
+    struct CapturedLogs(std::sync::Arc<std::sync::Mutex<Vec<u8>>>);
+
+    impl std::io::Write for CapturedLogs {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.lock().unwrap().extend_from_slice(buf);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    fn captured_output(format: config::LogFormat) -> String {
+        let logs = CapturedLogs::default();
+        let writer = logs.clone();
+        let dispatch = log_dispatch(log_filter("info").unwrap(), format, move || writer.clone());
+
+        tracing::dispatcher::with_default(&dispatch, || {
+            info!(rule = "downloads", "Rule matched");
+            warn!("Plugin restarted");
+            debug!("Filtered out by the level");
+        });
+
+        String::from_utf8(logs.0.lock().unwrap().clone()).unwrap()
+    }
+
+    #[test]
+    fn test_json_log_format_writes_json_lines() {
+        let output = captured_output(config::LogFormat::Json);
+        let lines: Vec<serde_json::Value> = output
+            .lines()
+            .map(|line| serde_json::from_str(line).expect("each line should be JSON"))
+            .collect();
+
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[0]["level"], "INFO");
+        assert_eq!(lines[0]["fields"]["message"], "Rule matched");
+        assert_eq!(lines[0]["fields"]["rule"], "downloads");
+        assert_eq!(lines[1]["level"], "WARN");
+    }
+
+    #[test]
+    fn test_text_log_format_is_not_json() {
+        let output = captured_output(config::LogFormat::Text);
+        assert_eq!(output.lines().count(), 2);
+        assert!(output.contains("Rule matched"));
+        assert!(serde_json::from_str::<serde_json::Value>(output.lines().next().unwrap()).is_err());
+    }
+
+    #[test]
+    fn test_log_filter_directives() {
+        let filter = log_filter("info,rules=debug,engine::plugins::process_monitor=warn").unwrap();
+        let directives = filter.to_string();
+        assert!(directives.contains("rules=debug"), "{}", directives);
+        assert!(
+            directives.contains("engine::plugins::process_monitor=warn"),
+            "{}",
+            directives
+        );
+
+        for level in ["debug", "info", "warn", "error"] {
+            assert!(log_filter(level).is_ok(), "{}", level);
+        }
+        assert!(log_filter("rules=loud").is_err());
+    }
+
+    #[test]
+    fn test_log_filter_applies_per_target() {
+        let logs = CapturedLogs::default();
+        let writer = logs.clone();
+        let filter = log_filter("warn,rules=debug").unwrap();
+        let dispatch = log_dispatch(filter, config::LogFormat::Text, move || writer.clone());
+
+        tracing::dispatcher::with_default(&dispatch, || {
+            debug!(target: "rules", "Rule evaluated");
+            info!(target: "engine::plugins::process_monitor", "Process started");
+            warn!(target: "engine::plugins::process_monitor", "Process monitor lagging");
+        });
+
+        let output = String::from_utf8(logs.0.lock().unwrap().clone()).unwrap();
+        assert!(output.contains("Rule evaluated"));
+        assert!(!output.contains("Process started"));
+        assert!(output.contains("Process monitor lagging"));
+    }
+
+    #[test]
+    fn test_config_load_warnings_are_logged_once_logging_starts() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let path = dir.path().join("config.toml");
+        std::fs::write(&path, "[engine]\n").unwrap();
+
+        let load_logs = DeferredLogs::default();
+        let loaded = load_logs.capture(|| load_config(&path));
+        assert!(loaded.is_ok());
+
+        let logs = CapturedLogs::default();
+        let writer = logs.clone();
+        let filter = log_filter("info").unwrap();
+        let dispatch = log_dispatch(filter, config::LogFormat::Text, move || writer.clone());
+        tracing::dispatcher::with_default(&dispatch, || load_logs.replay());
+
+        let output = String::from_utf8(logs.0.lock().unwrap().clone()).unwrap();
+        assert!(output.contains("WARN"), "{}", output);
+        assert!(output.contains("Config has no `version`"), "{}", output);
+    }
+
+    fn validate_file(contents: &str) -> (i32, String) {
+        let dir = tempfile::TempDir::new().unwrap();
+        let path = dir.path().join("config.toml");
+        std::fs::write(&path, contents).unwrap();
+
+        let mut out = Vec::new();
+        let code = validate_config(&path, &mut out);
+        (code, String::from_utf8(out).unwrap())
+    }
+
+    #[test]
+    fn test_validate_accepts_valid_config() {
+        let (code, report) = validate_file(
+            r#"
+version = 1
+
+[[sources]]
+name = "downloads"
+type = "file_watcher"
+paths = ["C:/Users/Downloads"]
+pattern = "*.exe"
+
+[[rules]]
+name = "installers"
+trigger = { type = "file_created", pattern = "*.msi" }
+action = { type = "log", message = "Installer downloaded" }
+"#,
+        );
+        assert_eq!(code, 0, "{}", report);
+        assert!(
+            report.contains("OK (1 sources, 1 rules, 0 sinks)"),
+            "{}",
+            report
+        );
+    }
+
+    #[test]
+    fn test_validate_reports_every_problem() {
+        let (code, report) = validate_file(
+            r#"
+version = 1
+
+[[sources]]
+name = "windows"
+type = "window_watcher"
+title_pattern = "(unclosed"
+
+[[rules]]
+name = "installers"
+trigger = { type = "file_created", pattern = "[*.msi" }
+action = { type = "log", message = "Installer downloaded" }
+"#,
+        );
+        assert_eq!(code, 1);
+        assert!(report.contains("2 problem(s) found"), "{}", report);
+        assert!(
+            report.contains("source 'windows': title_pattern"),
+            "{}",
+            report
+        );
+        assert!(
+            report.contains("rule 'installers': trigger.pattern"),
+            "{}",
+            report
+        );
+    }
+
+    #[test]
+    fn test_validate_fails_on_unreadable_config() {
+        let (code, report) = validate_file("[[rules]\nname = ");
+        assert_eq!(code, 1);
+        assert!(report.contains("Failed to parse config"), "{}", report);
+
+        let mut out = Vec::new();
+        assert_eq!(
+            validate_config(&PathBuf::from("no/such/config.toml"), &mut out),
+            1
+        );
+    }
+
+    #[test]
+    fn test_validate_conflicts_with_service_flags() {
+        assert!(parse(&["--validate"]).unwrap().validate);
+        assert!(parse(&["--validate", "--install-service"]).is_err());
+    }
+
+    #[test]
+    fn test_replay_flags() {
+        let cli = parse(&["--replay", "events.jsonl", "--replay-rate", "2.5"]).unwrap();
+        assert_eq!(cli.replay, Some(PathBuf::from("events.jsonl")));
+        assert_eq!(cli.replay_rate, Some(2.5));
+
+        assert!(parse(&["--replay-rate", "10"]).is_err());
+        assert!(parse(&["--replay", "events.jsonl", "--replay-rate", "0"]).is_err());
+        assert!(parse(&["--replay", "events.jsonl", "--dump-events"]).is_err());
+    }
+
+    #[test]
+    fn test_replay_runs_without_sources_or_sinks() {
+        let mut config = create_demo_config();
+        config.sinks.push(config::SinkConfig {
+            name: "recording".to_string(),
+            sink_type: config::SinkType::JsonlFile {
+                path: PathBuf::from("events.jsonl"),
+                max_bytes: 1024,
+                max_files: 1,
+            },
+            enabled: true,
+        });
+
+        strip_for_replay(&mut config);
+        assert!(config.sources.is_empty());
+        assert!(config.sinks.is_empty());
+        assert_eq!(config.rules.len(), 1);
     }
 }
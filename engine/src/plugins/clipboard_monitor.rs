@@ -0,0 +1,460 @@
+use async_trait::async_trait;
+use engine_core::event::{Event, EventKind};
+use engine_core::plugin::{
+    EmitOutcome, EventEmitter, EventSourcePlugin, PluginError, PluginHealth,
+};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, AtomicU32, Ordering};
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+use tracing::{error, info, warn};
+use windows::Win32::Foundation::{HGLOBAL, HINSTANCE, HWND, LPARAM, WPARAM};
+use windows::Win32::System::DataExchange::{
+    AddClipboardFormatListener, CloseClipboard, EnumClipboardFormats, GetClipboardData,
+    GetClipboardFormatNameW, GetClipboardSequenceNumber, OpenClipboard,
+    RemoveClipboardFormatListener,
+};
+use windows::Win32::System::Memory::{GlobalLock, GlobalSize, GlobalUnlock};
+use windows::Win32::System::Threading::GetCurrentThreadId;
+use windows::Win32::UI::WindowsAndMessaging::{
+    CreateWindowExW, DestroyWindow, GetMessageW, HMENU, HWND_MESSAGE, MSG, PostThreadMessageW,
+    WINDOW_EX_STYLE, WINDOW_STYLE, WM_CLIPBOARDUPDATE, WM_QUIT,
+};
+use windows::core::w;
+
+/// Characters of copied text included in events when text capture is on
+pub const DEFAULT_PREVIEW_CHARS: usize = 100;
+
+const CF_UNICODETEXT: u32 = 13;
+
+/// Another application may briefly hold the clipboard open right after
+/// changing it
+const OPEN_CLIPBOARD_ATTEMPTS: u32 = 5;
+const OPEN_CLIPBOARD_RETRY_DELAY: Duration = Duration::from_millis(20);
+
+/// Emits `ClipboardChanged` whenever the clipboard contents change, using a
+/// message-only window registered with `AddClipboardFormatListener`.
+///
+/// Events always list the formats the new contents are available in. The
+/// copied text itself is only read when text capture is turned on, and then
+/// only its first `preview_chars` characters end up in the event.
+pub struct ClipboardMonitorPlugin {
+    name: String,
+    capture_text: bool,
+    preview_chars: usize,
+    is_running: Arc<AtomicBool>,
+    listener_thread: Option<JoinHandle<()>>,
+    /// Win32 thread id of the message loop, so `stop()` can post `WM_QUIT` to it
+    listener_thread_id: Arc<AtomicU32>,
+}
+
+impl ClipboardMonitorPlugin {
+    pub fn new(name: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            capture_text: false,
+            preview_chars: DEFAULT_PREVIEW_CHARS,
+            is_running: Arc::new(AtomicBool::new(false)),
+            listener_thread: None,
+            listener_thread_id: Arc::new(AtomicU32::new(0)),
+        }
+    }
+
+    /// Include the start of copied text in events. Off by default, since the
+    /// clipboard often holds passwords and other private data.
+    pub fn with_text_capture(mut self, enabled: bool) -> Self {
+        self.capture_text = enabled;
+        self
+    }
+
+    pub fn with_preview_chars(mut self, chars: usize) -> Self {
+        self.preview_chars = chars;
+        self
+    }
+
+    /// The first `max_chars` characters of `text`, with `...` appended if
+    /// anything was cut off
+    fn truncate_preview(text: &str, max_chars: usize) -> String {
+        match text.char_indices().nth(max_chars) {
+            Some((end, _)) => format!("{}...", &text[..end]),
+            None => text.to_string(),
+        }
+    }
+
+    fn change_event(
+        plugin_name: &str,
+        formats: Vec<String>,
+        text: Option<&str>,
+        preview_chars: usize,
+    ) -> Event {
+        Event::new(
+            EventKind::ClipboardChanged {
+                formats,
+                text_preview: text.map(|text| Self::truncate_preview(text, preview_chars)),
+            },
+            plugin_name,
+        )
+    }
+
+    /// Names of the predefined `CF_*` formats, which `GetClipboardFormatNameW`
+    /// doesn't know about
+    fn standard_format_name(format: u32) -> Option<&'static str> {
+        let name = match format {
+            1 => "CF_TEXT",
+            2 => "CF_BITMAP",
+            3 => "CF_METAFILEPICT",
+            4 => "CF_SYLK",
+            5 => "CF_DIF",
+            6 => "CF_TIFF",
+            7 => "CF_OEMTEXT",
+            8 => "CF_DIB",
+            9 => "CF_PALETTE",
+            10 => "CF_PENDATA",
+            11 => "CF_RIFF",
+            12 => "CF_WAVE",
+            13 => "CF_UNICODETEXT",
+            14 => "CF_ENHMETAFILE",
+            15 => "CF_HDROP",
+            16 => "CF_LOCALE",
+            17 => "CF_DIBV5",
+            _ => return None,
+        };
+        Some(name)
+    }
+
+    fn format_name(format: u32) -> String {
+        if let Some(name) = Self::standard_format_name(format) {
+            return name.to_string();
+        }
+
+        let mut buffer = [0u16; 256];
+        let len = unsafe { GetClipboardFormatNameW(format, &mut buffer) };
+        if len > 0 {
+            String::from_utf16_lossy(&buffer[..len as usize])
+        } else {
+            format!("#{}", format)
+        }
+    }
+
+    /// The formats on the clipboard and, if `read_text` is set and there is
+    /// any, enough of its text for a `max_chars` preview
+    fn read_clipboard(
+        hwnd: HWND,
+        read_text: bool,
+        max_chars: usize,
+    ) -> Option<(Vec<String>, Option<String>)> {
+        let mut opened = false;
+        for _ in 0..OPEN_CLIPBOARD_ATTEMPTS {
+            if unsafe { OpenClipboard(hwnd) }.is_ok() {
+                opened = true;
+                break;
+            }
+            thread::sleep(OPEN_CLIPBOARD_RETRY_DELAY);
+        }
+        if !opened {
+            return None;
+        }
+
+        let mut formats = Vec::new();
+        let mut has_text = false;
+        let mut format = 0;
+        loop {
+            format = unsafe { EnumClipboardFormats(format) };
+            if format == 0 {
+                break;
+            }
+            has_text |= format == CF_UNICODETEXT;
+            formats.push(Self::format_name(format));
+        }
+
+        let text = if read_text && has_text {
+            // One extra character so truncation can still be detected, and
+            // room for every character to be a surrogate pair
+            Self::read_unicode_text((max_chars + 1) * 2)
+        } else {
+            None
+        };
+
+        let _ = unsafe { CloseClipboard() };
+        Some((formats, text))
+    }
+
+    /// Read `CF_UNICODETEXT` from the open clipboard, stopping after
+    /// `max_units` UTF-16 code units
+    fn read_unicode_text(max_units: usize) -> Option<String> {
+        unsafe {
+            let handle = GetClipboardData(CF_UNICODETEXT).ok()?;
+            let memory = HGLOBAL(handle.0 as *mut _);
+            let data = GlobalLock(memory) as *const u16;
+            if data.is_null() {
+                return None;
+            }
+
+            let available = GlobalSize(memory) / 2;
+            let units = std::slice::from_raw_parts(data, available.min(max_units));
+            let len = units
+                .iter()
+                .position(|&unit| unit == 0)
+                .unwrap_or(units.len());
+            let text = String::from_utf16_lossy(&units[..len]);
+            let _ = GlobalUnlock(memory);
+            Some(text)
+        }
+    }
+
+    fn run_listener(
+        plugin_name: String,
+        emitter: EventEmitter,
+        is_running: Arc<AtomicBool>,
+        thread_id: Arc<AtomicU32>,
+        capture_text: bool,
+        preview_chars: usize,
+    ) -> Result<(), String> {
+        thread_id.store(unsafe { GetCurrentThreadId() }, Ordering::SeqCst);
+
+        // A message-only window of a built-in class is enough: the listener
+        // notification is posted, so the loop below sees it directly
+        let hwnd = unsafe {
+            CreateWindowExW(
+                WINDOW_EX_STYLE::default(),
+                w!("STATIC"),
+                w!("win_event_engine clipboard listener"),
+                WINDOW_STYLE::default(),
+                0,
+                0,
+                0,
+                0,
+                HWND_MESSAGE,
+                HMENU::default(),
+                HINSTANCE::default(),
+                None,
+            )
+        };
+        if hwnd.0 == 0 {
+            return Err("Failed to create clipboard listener window".to_string());
+        }
+
+        if let Err(e) = unsafe { AddClipboardFormatListener(hwnd) } {
+            let _ = unsafe { DestroyWindow(hwnd) };
+            return Err(format!("AddClipboardFormatListener failed: {}", e));
+        }
+
+        info!("Clipboard listener installed, starting message loop");
+
+        let mut last_sequence = unsafe { GetClipboardSequenceNumber() };
+        let mut msg = MSG::default();
+
+        while is_running.load(Ordering::SeqCst) {
+            let result = unsafe { GetMessageW(&mut msg, None, 0, 0) };
+            if result.0 == -1 || result.0 == 0 {
+                break;
+            }
+            if msg.message != WM_CLIPBOARDUPDATE {
+                continue;
+            }
+
+            // The same change can be announced more than once
+            let sequence = unsafe { GetClipboardSequenceNumber() };
+            if sequence == last_sequence {
+                continue;
+            }
+            last_sequence = sequence;
+
+            let Some((formats, text)) = Self::read_clipboard(hwnd, capture_text, preview_chars)
+            else {
+                warn!("Clipboard changed but could not be opened");
+                continue;
+            };
+
+            let event = Self::change_event(&plugin_name, formats, text.as_deref(), preview_chars);
+            if emitter.emit(event) == EmitOutcome::Closed {
+                is_running.store(false, Ordering::SeqCst);
+                break;
+            }
+        }
+
+        unsafe {
+            let _ = RemoveClipboardFormatListener(hwnd);
+            let _ = DestroyWindow(hwnd);
+        }
+
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl EventSourcePlugin for ClipboardMonitorPlugin {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    async fn start(&mut self, emitter: EventEmitter) -> Result<(), PluginError> {
+        if self.is_running.load(Ordering::SeqCst) {
+            return Ok(());
+        }
+
+        info!(
+            "Starting clipboard monitor plugin: {} (text capture {})",
+            self.name,
+            if self.capture_text { "on" } else { "off" }
+        );
+
+        self.is_running.store(true, Ordering::SeqCst);
+
+        let plugin_name = self.name.clone();
+        let is_running = self.is_running.clone();
+        let thread_id = self.listener_thread_id.clone();
+        let capture_text = self.capture_text;
+        let preview_chars = self.preview_chars;
+
+        let listener_thread = thread::spawn(move || {
+            if let Err(e) = Self::run_listener(
+                plugin_name,
+                emitter,
+                is_running,
+                thread_id,
+                capture_text,
+                preview_chars,
+            ) {
+                error!("Clipboard listener thread failed: {}", e);
+            }
+        });
+
+        // Give the listener a moment to register
+        tokio::time::sleep(Duration::from_millis(50)).await;
+
+        if listener_thread.is_finished() {
+            self.is_running.store(false, Ordering::SeqCst);
+            let _ = listener_thread.join();
+            return Err(PluginError::Initialization(
+                "Failed to register a clipboard format listener".to_string(),
+            ));
+        }
+
+        self.listener_thread = Some(listener_thread);
+        Ok(())
+    }
+
+    async fn stop(&mut self) -> Result<(), PluginError> {
+        info!("Stopping clipboard monitor plugin: {}", self.name);
+        self.is_running.store(false, Ordering::SeqCst);
+
+        // GetMessageW blocks until the next message, so wake the loop up
+        let thread_id = self.listener_thread_id.swap(0, Ordering::SeqCst);
+        if thread_id != 0 {
+            let _ = unsafe { PostThreadMessageW(thread_id, WM_QUIT, WPARAM(0), LPARAM(0)) };
+        }
+
+        if let Some(thread) = self.listener_thread.take() {
+            let _ = thread.join();
+        }
+
+        Ok(())
+    }
+
+    fn is_running(&self) -> bool {
+        self.is_running.load(Ordering::SeqCst)
+    }
+
+    fn health(&self) -> PluginHealth {
+        if !self.is_running() {
+            return PluginHealth::Stopped;
+        }
+        match &self.listener_thread {
+            Some(thread) if thread.is_finished() => {
+                PluginHealth::Failed("Clipboard listener thread exited".to_string())
+            }
+            _ => PluginHealth::Healthy,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_truncate_preview() {
+        assert_eq!(
+            ClipboardMonitorPlugin::truncate_preview("short", 10),
+            "short"
+        );
+        assert_eq!(
+            ClipboardMonitorPlugin::truncate_preview("exactly", 7),
+            "exactly"
+        );
+        assert_eq!(
+            ClipboardMonitorPlugin::truncate_preview("{\"key\": \"value\"}", 6),
+            "{\"key\"..."
+        );
+        assert_eq!(
+            ClipboardMonitorPlugin::truncate_preview("anything", 0),
+            "..."
+        );
+
+        // Cut on character boundaries, not bytes
+        assert_eq!(
+            ClipboardMonitorPlugin::truncate_preview("héllo wörld", 4),
+            "héll..."
+        );
+        assert_eq!(
+            ClipboardMonitorPlugin::truncate_preview("日本語テキスト", 3),
+            "日本語..."
+        );
+    }
+
+    #[test]
+    fn test_change_event() {
+        let formats = vec!["CF_UNICODETEXT".to_string(), "CF_LOCALE".to_string()];
+
+        let event = ClipboardMonitorPlugin::change_event(
+            "clipboard",
+            formats.clone(),
+            Some("a long piece of copied text"),
+            6,
+        );
+        assert_eq!(event.source, "clipboard");
+        assert_eq!(
+            event.kind,
+            EventKind::ClipboardChanged {
+                formats: formats.clone(),
+                text_preview: Some("a long...".to_string()),
+            }
+        );
+
+        // Without text capture only the formats are reported
+        let event = ClipboardMonitorPlugin::change_event("clipboard", formats.clone(), None, 6);
+        assert_eq!(
+            event.kind,
+            EventKind::ClipboardChanged {
+                formats,
+                text_preview: None,
+            }
+        );
+    }
+
+    #[test]
+    fn test_standard_format_names() {
+        assert_eq!(
+            ClipboardMonitorPlugin::standard_format_name(CF_UNICODETEXT),
+            Some("CF_UNICODETEXT")
+        );
+        assert_eq!(
+            ClipboardMonitorPlugin::standard_format_name(15),
+            Some("CF_HDROP")
+        );
+        // Registered formats such as "HTML Format" have ids from 0xC000 up
+        assert_eq!(ClipboardMonitorPlugin::standard_format_name(0xC0F1), None);
+    }
+
+    #[test]
+    fn test_text_capture_is_opt_in() {
+        let plugin = ClipboardMonitorPlugin::new("clipboard");
+        assert!(!plugin.capture_text);
+        assert_eq!(plugin.preview_chars, DEFAULT_PREVIEW_CHARS);
+
+        let plugin = plugin.with_text_capture(true).with_preview_chars(20);
+        assert!(plugin.capture_text);
+        assert_eq!(plugin.preview_chars, 20);
+    }
+}
@@ -0,0 +1,510 @@
+use async_trait::async_trait;
+use engine_core::event::{Event, EventKind};
+use engine_core::plugin::{
+    EmitOutcome, EventEmitter, EventSourcePlugin, PluginError, PluginHealth,
+};
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, AtomicU32, Ordering};
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+use tracing::{debug, error, info};
+use windows::Win32::Devices::DeviceAndDriverInstallation::{
+    CM_DRP_DEVICEDESC, CM_DRP_FRIENDLYNAME, CM_Get_DevNode_Registry_PropertyW,
+    CM_LOCATE_DEVNODE_NORMAL, CM_Locate_DevNodeW, CR_SUCCESS,
+};
+use windows::Win32::Foundation::{HANDLE, HINSTANCE, HWND, LPARAM, LRESULT, WPARAM};
+use windows::Win32::System::Threading::GetCurrentThreadId;
+use windows::Win32::UI::WindowsAndMessaging::{
+    CreateWindowExW, DBT_DEVICEARRIVAL, DBT_DEVICEREMOVECOMPLETE, DBT_DEVTYP_DEVICEINTERFACE,
+    DEV_BROADCAST_DEVICEINTERFACE_W, DEV_BROADCAST_HDR, DEVICE_NOTIFY_WINDOW_HANDLE,
+    DefWindowProcW, DestroyWindow, DispatchMessageW, GetMessageW, HMENU, HWND_MESSAGE, MSG,
+    PostQuitMessage, PostThreadMessageW, RegisterClassW, RegisterDeviceNotificationW,
+    UnregisterDeviceNotification, WINDOW_EX_STYLE, WINDOW_STYLE, WM_DEVICECHANGE, WM_QUIT,
+    WNDCLASSW,
+};
+use windows::core::{GUID, PCWSTR, w};
+
+/// `GUID_DEVINTERFACE_USB_DEVICE`: every USB device, whatever its function
+const GUID_DEVINTERFACE_USB_DEVICE: GUID = GUID::from_u128(0xa5dcbf10_6530_11d2_901f_00c04fb951ed);
+
+const WINDOW_CLASS: PCWSTR = w!("win_event_engine_device_listener");
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum DeviceChange {
+    Arrived,
+    Removed,
+}
+
+/// What the window procedure needs to turn notifications into events. Device
+/// notifications are sent rather than posted, so they arrive in the window
+/// procedure instead of the message loop, and this lives in a thread local
+/// of the listener thread.
+struct ListenerState {
+    plugin_name: String,
+    emitter: EventEmitter,
+    is_running: Arc<AtomicBool>,
+    device_id_filter: Option<String>,
+    /// Descriptions of attached devices, since a removed device can no
+    /// longer be looked up
+    descriptions: HashMap<String, Option<String>>,
+}
+
+thread_local! {
+    static LISTENER: RefCell<Option<ListenerState>> = const { RefCell::new(None) };
+}
+
+/// Emits `DeviceArrived` and `DeviceRemoved` as USB devices are plugged in and
+/// removed, using a hidden message-only window registered for
+/// `WM_DEVICECHANGE` notifications.
+///
+/// Devices are identified by their instance id, as shown in Device Manager
+/// (e.g. `USB\VID_0781&PID_5581\4C530001231120115142`). With a filter set,
+/// only devices whose id contains it are reported.
+pub struct DeviceMonitorPlugin {
+    name: String,
+    device_id_filter: Option<String>,
+    is_running: Arc<AtomicBool>,
+    listener_thread: Option<JoinHandle<()>>,
+    /// Win32 thread id of the message loop, so `stop()` can post `WM_QUIT` to it
+    listener_thread_id: Arc<AtomicU32>,
+}
+
+impl DeviceMonitorPlugin {
+    pub fn new(name: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            device_id_filter: None,
+            is_running: Arc::new(AtomicBool::new(false)),
+            listener_thread: None,
+            listener_thread_id: Arc::new(AtomicU32::new(0)),
+        }
+    }
+
+    /// Only report devices whose instance id contains `filter`, ignoring case
+    pub fn with_device_id_filter(mut self, filter: Option<String>) -> Self {
+        self.device_id_filter = filter;
+        self
+    }
+
+    /// Whether a device passes the configured instance id filter
+    fn matches_device_filter(device_id: &str, filter: Option<&str>) -> bool {
+        match filter {
+            Some(filter) => device_id
+                .to_ascii_uppercase()
+                .contains(&filter.to_ascii_uppercase()),
+            None => true,
+        }
+    }
+
+    /// The device instance id behind an interface's symbolic link name, e.g.
+    /// `\\?\USB#VID_0781&PID_5581#4C530001231120115142#{a5dcbf10-...}` becomes
+    /// `USB\VID_0781&PID_5581\4C530001231120115142`
+    fn device_instance_id(symbolic_link: &str) -> String {
+        let path = symbolic_link.strip_prefix(r"\\?\").unwrap_or(symbolic_link);
+        let path = match path.rfind("#{") {
+            Some(end) => &path[..end],
+            None => path,
+        };
+        path.replace('#', "\\").to_ascii_uppercase()
+    }
+
+    fn device_event(
+        plugin_name: &str,
+        change: DeviceChange,
+        device_id: String,
+        description: Option<String>,
+    ) -> Event {
+        let kind = match change {
+            DeviceChange::Arrived => EventKind::DeviceArrived {
+                device_id,
+                description,
+            },
+            DeviceChange::Removed => EventKind::DeviceRemoved {
+                device_id,
+                description,
+            },
+        };
+        Event::new(kind, plugin_name)
+    }
+
+    /// The friendly name of an attached device, or its driver description if
+    /// it has none
+    fn lookup_description(device_id: &str) -> Option<String> {
+        let wide: Vec<u16> = device_id.encode_utf16().chain(Some(0)).collect();
+        let mut devinst = 0u32;
+        let located = unsafe {
+            CM_Locate_DevNodeW(
+                &mut devinst,
+                PCWSTR(wide.as_ptr()),
+                CM_LOCATE_DEVNODE_NORMAL,
+            )
+        };
+        if located != CR_SUCCESS {
+            return None;
+        }
+
+        [CM_DRP_FRIENDLYNAME, CM_DRP_DEVICEDESC]
+            .into_iter()
+            .find_map(|property| {
+                let mut buffer = [0u16; 256];
+                let mut len = std::mem::size_of_val(&buffer) as u32;
+                let result = unsafe {
+                    CM_Get_DevNode_Registry_PropertyW(
+                        devinst,
+                        property,
+                        None,
+                        Some(buffer.as_mut_ptr().cast()),
+                        &mut len,
+                        0,
+                    )
+                };
+                if result != CR_SUCCESS {
+                    return None;
+                }
+                let units = &buffer[..(len as usize / 2).min(buffer.len())];
+                let end = units
+                    .iter()
+                    .position(|&unit| unit == 0)
+                    .unwrap_or(units.len());
+                Some(String::from_utf16_lossy(&units[..end])).filter(|name| !name.is_empty())
+            })
+    }
+
+    /// The symbolic link name carried by a device interface notification
+    ///
+    /// # Safety
+    /// `lparam` must point at the `DEV_BROADCAST_HDR` of a `WM_DEVICECHANGE`
+    /// arrival or removal message
+    unsafe fn interface_name(lparam: LPARAM) -> Option<String> {
+        let header = lparam.0 as *const DEV_BROADCAST_HDR;
+        if header.is_null() || unsafe { (*header).dbch_devicetype } != DBT_DEVTYP_DEVICEINTERFACE {
+            return None;
+        }
+
+        // `dbcc_name` is declared as one character but runs to the end of the
+        // structure
+        let interface = header as *const DEV_BROADCAST_DEVICEINTERFACE_W;
+        let name_offset = std::mem::offset_of!(DEV_BROADCAST_DEVICEINTERFACE_W, dbcc_name);
+        let size = unsafe { (*interface).dbcc_size } as usize;
+        let max_units = size.saturating_sub(name_offset) / 2;
+        let name = unsafe {
+            std::slice::from_raw_parts(
+                std::ptr::addr_of!((*interface).dbcc_name).cast::<u16>(),
+                max_units,
+            )
+        };
+        let end = name
+            .iter()
+            .position(|&unit| unit == 0)
+            .unwrap_or(name.len());
+        Some(String::from_utf16_lossy(&name[..end]))
+    }
+
+    fn handle_device_change(state: &mut ListenerState, change: DeviceChange, symbolic_link: &str) {
+        let device_id = Self::device_instance_id(symbolic_link);
+        if !Self::matches_device_filter(&device_id, state.device_id_filter.as_deref()) {
+            debug!("Ignoring device {} (filtered out)", device_id);
+            return;
+        }
+
+        let description = match change {
+            DeviceChange::Arrived => {
+                let description = Self::lookup_description(&device_id);
+                state
+                    .descriptions
+                    .insert(device_id.clone(), description.clone());
+                description
+            }
+            DeviceChange::Removed => state.descriptions.remove(&device_id).flatten(),
+        };
+
+        let event = Self::device_event(&state.plugin_name, change, device_id, description);
+        if state.emitter.emit(event) == EmitOutcome::Closed {
+            state.is_running.store(false, Ordering::SeqCst);
+            unsafe { PostQuitMessage(0) };
+        }
+    }
+
+    unsafe extern "system" fn window_proc(
+        hwnd: HWND,
+        msg: u32,
+        wparam: WPARAM,
+        lparam: LPARAM,
+    ) -> LRESULT {
+        if msg == WM_DEVICECHANGE {
+            let change = match wparam.0 as u32 {
+                DBT_DEVICEARRIVAL => Some(DeviceChange::Arrived),
+                DBT_DEVICEREMOVECOMPLETE => Some(DeviceChange::Removed),
+                _ => None,
+            };
+            if let Some(change) = change {
+                if let Some(symbolic_link) = unsafe { Self::interface_name(lparam) } {
+                    LISTENER.with(|listener| {
+                        if let Some(state) = listener.borrow_mut().as_mut() {
+                            Self::handle_device_change(state, change, &symbolic_link);
+                        }
+                    });
+                }
+                return LRESULT(1);
+            }
+        }
+        unsafe { DefWindowProcW(hwnd, msg, wparam, lparam) }
+    }
+
+    fn run_listener(
+        plugin_name: String,
+        emitter: EventEmitter,
+        is_running: Arc<AtomicBool>,
+        thread_id: Arc<AtomicU32>,
+        device_id_filter: Option<String>,
+    ) -> Result<(), String> {
+        thread_id.store(unsafe { GetCurrentThreadId() }, Ordering::SeqCst);
+
+        LISTENER.with(|listener| {
+            *listener.borrow_mut() = Some(ListenerState {
+                plugin_name,
+                emitter,
+                is_running: is_running.clone(),
+                device_id_filter,
+                descriptions: HashMap::new(),
+            });
+        });
+
+        // A second monitor finds the class already registered, which is fine
+        let class = WNDCLASSW {
+            lpfnWndProc: Some(Self::window_proc),
+            lpszClassName: WINDOW_CLASS,
+            ..Default::default()
+        };
+        unsafe { RegisterClassW(&class) };
+
+        let hwnd = unsafe {
+            CreateWindowExW(
+                WINDOW_EX_STYLE::default(),
+                WINDOW_CLASS,
+                w!("win_event_engine device listener"),
+                WINDOW_STYLE::default(),
+                0,
+                0,
+                0,
+                0,
+                HWND_MESSAGE,
+                HMENU::default(),
+                HINSTANCE::default(),
+                None,
+            )
+        };
+        if hwnd.0 == 0 {
+            return Err("Failed to create device listener window".to_string());
+        }
+
+        let filter = DEV_BROADCAST_DEVICEINTERFACE_W {
+            dbcc_size: std::mem::size_of::<DEV_BROADCAST_DEVICEINTERFACE_W>() as u32,
+            dbcc_devicetype: DBT_DEVTYP_DEVICEINTERFACE.0,
+            dbcc_classguid: GUID_DEVINTERFACE_USB_DEVICE,
+            ..Default::default()
+        };
+        let notification = match unsafe {
+            RegisterDeviceNotificationW(
+                HANDLE(hwnd.0),
+                std::ptr::addr_of!(filter).cast(),
+                DEVICE_NOTIFY_WINDOW_HANDLE,
+            )
+        } {
+            Ok(notification) => notification,
+            Err(e) => {
+                let _ = unsafe { DestroyWindow(hwnd) };
+                return Err(format!("RegisterDeviceNotificationW failed: {}", e));
+            }
+        };
+
+        info!("Device notifications registered, starting message loop");
+
+        let mut msg = MSG::default();
+        while is_running.load(Ordering::SeqCst) {
+            let result = unsafe { GetMessageW(&mut msg, None, 0, 0) };
+            if result.0 == -1 || result.0 == 0 {
+                break;
+            }
+            unsafe { DispatchMessageW(&msg) };
+        }
+
+        unsafe {
+            let _ = UnregisterDeviceNotification(notification);
+            let _ = DestroyWindow(hwnd);
+        }
+        LISTENER.with(|listener| listener.borrow_mut().take());
+
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl EventSourcePlugin for DeviceMonitorPlugin {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    async fn start(&mut self, emitter: EventEmitter) -> Result<(), PluginError> {
+        if self.is_running.load(Ordering::SeqCst) {
+            return Ok(());
+        }
+
+        info!("Starting device monitor plugin: {}", self.name);
+
+        self.is_running.store(true, Ordering::SeqCst);
+
+        let plugin_name = self.name.clone();
+        let is_running = self.is_running.clone();
+        let thread_id = self.listener_thread_id.clone();
+        let device_id_filter = self.device_id_filter.clone();
+
+        let listener_thread = thread::spawn(move || {
+            if let Err(e) = Self::run_listener(
+                plugin_name,
+                emitter,
+                is_running,
+                thread_id,
+                device_id_filter,
+            ) {
+                error!("Device listener thread failed: {}", e);
+            }
+        });
+
+        // Give the listener a moment to register
+        tokio::time::sleep(Duration::from_millis(50)).await;
+
+        if listener_thread.is_finished() {
+            self.is_running.store(false, Ordering::SeqCst);
+            let _ = listener_thread.join();
+            return Err(PluginError::Initialization(
+                "Failed to register for device notifications".to_string(),
+            ));
+        }
+
+        self.listener_thread = Some(listener_thread);
+        Ok(())
+    }
+
+    async fn stop(&mut self) -> Result<(), PluginError> {
+        info!("Stopping device monitor plugin: {}", self.name);
+        self.is_running.store(false, Ordering::SeqCst);
+
+        // GetMessageW blocks until the next message, so wake the loop up
+        let thread_id = self.listener_thread_id.swap(0, Ordering::SeqCst);
+        if thread_id != 0 {
+            let _ = unsafe { PostThreadMessageW(thread_id, WM_QUIT, WPARAM(0), LPARAM(0)) };
+        }
+
+        if let Some(thread) = self.listener_thread.take() {
+            let _ = thread.join();
+        }
+
+        Ok(())
+    }
+
+    fn is_running(&self) -> bool {
+        self.is_running.load(Ordering::SeqCst)
+    }
+
+    fn health(&self) -> PluginHealth {
+        if !self.is_running() {
+            return PluginHealth::Stopped;
+        }
+        match &self.listener_thread {
+            Some(thread) if thread.is_finished() => {
+                PluginHealth::Failed("Device listener thread exited".to_string())
+            }
+            _ => PluginHealth::Healthy,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SANDISK: &str = r"USB\VID_0781&PID_5581\4C530001231120115142";
+
+    #[test]
+    fn test_matches_device_filter() {
+        // No filter reports every device
+        assert!(DeviceMonitorPlugin::matches_device_filter(SANDISK, None));
+
+        assert!(DeviceMonitorPlugin::matches_device_filter(
+            SANDISK,
+            Some("VID_0781&PID_5581")
+        ));
+        assert!(DeviceMonitorPlugin::matches_device_filter(
+            SANDISK,
+            Some("4C530001231120115142")
+        ));
+        assert!(!DeviceMonitorPlugin::matches_device_filter(
+            SANDISK,
+            Some("VID_046D")
+        ));
+    }
+
+    #[test]
+    fn test_device_filter_ignores_case() {
+        assert!(DeviceMonitorPlugin::matches_device_filter(
+            SANDISK,
+            Some("vid_0781&pid_5581")
+        ));
+        assert!(DeviceMonitorPlugin::matches_device_filter(
+            &SANDISK.to_lowercase(),
+            Some("VID_0781")
+        ));
+    }
+
+    #[test]
+    fn test_device_instance_id() {
+        assert_eq!(
+            DeviceMonitorPlugin::device_instance_id(
+                r"\\?\USB#VID_0781&PID_5581#4C530001231120115142#{a5dcbf10-6530-11d2-901f-00c04fb951ed}"
+            ),
+            SANDISK
+        );
+        // Ids generated by Windows for devices without a serial number
+        assert_eq!(
+            DeviceMonitorPlugin::device_instance_id(
+                r"\\?\usb#vid_046d&pid_c52b#5&1c2a7b3f&0&2#{a5dcbf10-6530-11d2-901f-00c04fb951ed}"
+            ),
+            r"USB\VID_046D&PID_C52B\5&1C2A7B3F&0&2"
+        );
+    }
+
+    #[test]
+    fn test_device_event() {
+        let event = DeviceMonitorPlugin::device_event(
+            "usb",
+            DeviceChange::Arrived,
+            SANDISK.to_string(),
+            Some("SanDisk Ultra".to_string()),
+        );
+        assert_eq!(event.source, "usb");
+        assert_eq!(
+            event.kind,
+            EventKind::DeviceArrived {
+                device_id: SANDISK.to_string(),
+                description: Some("SanDisk Ultra".to_string()),
+            }
+        );
+
+        let event = DeviceMonitorPlugin::device_event(
+            "usb",
+            DeviceChange::Removed,
+            SANDISK.to_string(),
+            None,
+        );
+        assert_eq!(
+            event.kind,
+            EventKind::DeviceRemoved {
+                device_id: SANDISK.to_string(),
+                description: None,
+            }
+        );
+    }
+}
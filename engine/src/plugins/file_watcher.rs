@@ -1,15 +1,357 @@
 use async_trait::async_trait;
 use engine_core::event::{Event, EventKind};
-use engine_core::plugin::{EventEmitter, EventSourcePlugin, PluginError};
+use engine_core::plugin::{EmitOutcome, EventEmitter, EventSourcePlugin, PluginError};
 use notify::{Config, Event as NotifyEvent, RecommendedWatcher, RecursiveMode, Watcher};
-use std::path::PathBuf;
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
 use tracing::{debug, error, info, warn};
 
+/// Default quiet period before a created/modified file is reported
+pub const DEFAULT_FILE_DEBOUNCE: Duration = Duration::from_millis(300);
+
+/// How long a rename-from notification waits for its rename-to half
+pub const RENAME_CORRELATION_WINDOW: Duration = Duration::from_millis(100);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum FileChange {
+    Created,
+    Modified,
+    Deleted,
+}
+
+/// A change as reported by notify, before debouncing and rename pairing
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum RawChange {
+    Changed(FileChange, PathBuf),
+    RenamedFrom(PathBuf),
+    RenamedTo(PathBuf),
+    Renamed { from: PathBuf, to: PathBuf },
+}
+
+impl RawChange {
+    fn from_notify(event: &NotifyEvent) -> Vec<RawChange> {
+        use notify::event::{ModifyKind, RenameMode};
+
+        match (&event.kind, event.paths.as_slice()) {
+            (notify::EventKind::Modify(ModifyKind::Name(RenameMode::Both)), [from, to]) => {
+                vec![RawChange::Renamed {
+                    from: from.clone(),
+                    to: to.clone(),
+                }]
+            }
+            (notify::EventKind::Modify(ModifyKind::Name(RenameMode::From)), paths) => {
+                paths.iter().cloned().map(RawChange::RenamedFrom).collect()
+            }
+            (notify::EventKind::Modify(ModifyKind::Name(RenameMode::To)), paths) => {
+                paths.iter().cloned().map(RawChange::RenamedTo).collect()
+            }
+            (kind, paths) => {
+                let change = match kind {
+                    notify::EventKind::Create(_) => FileChange::Created,
+                    notify::EventKind::Modify(_) => FileChange::Modified,
+                    notify::EventKind::Remove(_) => FileChange::Deleted,
+                    _ => return Vec::new(),
+                };
+                paths
+                    .iter()
+                    .map(|path| RawChange::Changed(change, path.clone()))
+                    .collect()
+            }
+        }
+    }
+}
+
+/// A settled change, ready to become an `Event`
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum FileNotice {
+    Changed(FileChange, PathBuf),
+    Renamed { from: PathBuf, to: PathBuf },
+}
+
+/// Per-path trailing-edge debounce. Editors often write a file several times
+/// per save, so created/modified changes are held until the path has been
+/// quiet for `window`; a modification of a pending creation stays a creation.
+/// Deletions are reported immediately, after whatever was pending for the path.
+///
+/// Renames are also paired up here: notify may report the old and new name
+/// separately, so a rename-from waits up to `rename_window` for its rename-to.
+/// An unpaired rename-from is reported as a deletion and an unpaired
+/// rename-to as a creation.
+struct ChangeDebouncer {
+    window: Duration,
+    rename_window: Duration,
+    pending: HashMap<PathBuf, (FileChange, Instant)>,
+    pending_rename: Option<(PathBuf, Instant)>,
+}
+
+impl ChangeDebouncer {
+    fn new(window: Duration) -> Self {
+        Self {
+            window,
+            rename_window: RENAME_CORRELATION_WINDOW,
+            pending: HashMap::new(),
+            pending_rename: None,
+        }
+    }
+
+    /// Record a change, returning any changes that are ready right away
+    fn add(&mut self, change: RawChange, now: Instant) -> Vec<FileNotice> {
+        let mut ready = self.expire_rename(now);
+
+        match change {
+            RawChange::Changed(FileChange::Deleted, path) => ready.extend(self.removed(path)),
+            RawChange::Changed(change, path) => self.debounce(change, path, now),
+            RawChange::RenamedFrom(path) => {
+                if let Some((unpaired, _)) = self.pending_rename.replace((path, now)) {
+                    ready.extend(self.removed(unpaired));
+                }
+            }
+            RawChange::RenamedTo(to) => match self.pending_rename.take() {
+                Some((from, _)) => ready.extend(self.renamed(from, to)),
+                None => self.debounce(FileChange::Created, to, now),
+            },
+            RawChange::Renamed { from, to } => ready.extend(self.renamed(from, to)),
+        }
+
+        ready
+    }
+
+    fn debounce(&mut self, change: FileChange, path: PathBuf, now: Instant) {
+        self.pending
+            .entry(path)
+            .and_modify(|(pending, last_seen)| {
+                if change == FileChange::Created {
+                    *pending = FileChange::Created;
+                }
+                *last_seen = now;
+            })
+            .or_insert((change, now));
+    }
+
+    /// Whatever was pending for `path`, then the deletion itself
+    fn removed(&mut self, path: PathBuf) -> Vec<FileNotice> {
+        let mut ready: Vec<_> = self.flush_path(&path).into_iter().collect();
+        ready.push(FileNotice::Changed(FileChange::Deleted, path));
+        ready
+    }
+
+    /// Whatever was pending for the old name, then the rename itself
+    fn renamed(&mut self, from: PathBuf, to: PathBuf) -> Vec<FileNotice> {
+        let mut ready: Vec<_> = self.flush_path(&from).into_iter().collect();
+        ready.push(FileNotice::Renamed { from, to });
+        ready
+    }
+
+    fn flush_path(&mut self, path: &Path) -> Option<FileNotice> {
+        self.pending
+            .remove(path)
+            .map(|(change, _)| FileNotice::Changed(change, path.to_path_buf()))
+    }
+
+    fn expire_rename(&mut self, now: Instant) -> Vec<FileNotice> {
+        match self.pending_rename.take() {
+            Some((from, seen)) if now.duration_since(seen) >= self.rename_window => {
+                self.removed(from)
+            }
+            still_pending => {
+                self.pending_rename = still_pending;
+                Vec::new()
+            }
+        }
+    }
+
+    /// When the earliest pending change settles, if any
+    fn next_flush(&self) -> Option<Instant> {
+        let rename_deadline = self
+            .pending_rename
+            .as_ref()
+            .map(|(_, seen)| *seen + self.rename_window);
+
+        self.pending
+            .values()
+            .map(|(_, last_seen)| *last_seen + self.window)
+            .chain(rename_deadline)
+            .min()
+    }
+
+    /// Remove and return the changes whose path has been quiet for the window
+    fn drain_expired(&mut self, now: Instant) -> Vec<FileNotice> {
+        let mut ready = self.expire_rename(now);
+        let window = self.window;
+        ready.extend(self.drain_where(|last_seen| now.duration_since(last_seen) >= window));
+        ready
+    }
+
+    /// Remove and return everything still pending, e.g. on shutdown
+    fn drain_all(&mut self) -> Vec<FileNotice> {
+        let mut ready = match self.pending_rename.take() {
+            Some((from, _)) => self.removed(from),
+            None => Vec::new(),
+        };
+        ready.extend(self.drain_where(|_| true));
+        ready
+    }
+
+    fn drain_where(&mut self, settled: impl Fn(Instant) -> bool) -> Vec<FileNotice> {
+        let paths: Vec<PathBuf> = self
+            .pending
+            .iter()
+            .filter(|(_, (_, last_seen))| settled(*last_seen))
+            .map(|(path, _)| path.clone())
+            .collect();
+
+        let mut ready: Vec<_> = paths
+            .into_iter()
+            .filter_map(|path| {
+                let (change, last_seen) = self.pending.remove(&path)?;
+                Some((last_seen, change, path))
+            })
+            .collect();
+        ready.sort_by_key(|(last_seen, _, _)| *last_seen);
+        ready
+            .into_iter()
+            .map(|(_, change, path)| FileNotice::Changed(change, path))
+            .collect()
+    }
+}
+
+/// Compiled include/exclude globs. Includes are matched against the file
+/// name, excludes against the full path, and excludes always win.
+struct PathFilter {
+    include: Vec<glob::Pattern>,
+    exclude: Vec<glob::Pattern>,
+}
+
+impl PathFilter {
+    fn new(include: &[String], exclude: &[String]) -> Self {
+        Self {
+            include: Self::compile(include),
+            exclude: Self::compile(exclude),
+        }
+    }
+
+    fn compile(patterns: &[String]) -> Vec<glob::Pattern> {
+        patterns
+            .iter()
+            .filter_map(|pattern| match glob::Pattern::new(pattern) {
+                Ok(glob) => Some(glob),
+                Err(e) => {
+                    warn!("Invalid file pattern '{}': {}", pattern, e);
+                    None
+                }
+            })
+            .collect()
+    }
+
+    fn excludes(&self, path: &Path) -> bool {
+        self.exclude
+            .iter()
+            .any(|pattern| pattern.matches_path(path))
+    }
+
+    /// No include patterns means every file name is included
+    fn allows(&self, path: &Path) -> bool {
+        if self.excludes(path) {
+            return false;
+        }
+
+        match path.file_name().and_then(|name| name.to_str()) {
+            Some(name) if !self.include.is_empty() => {
+                self.include.iter().any(|pattern| pattern.matches(name))
+            }
+            _ => true,
+        }
+    }
+
+    /// A rename passes if either path does, so files moved into or out of
+    /// the filtered set are seen
+    fn allows_notice(&self, notice: &FileNotice) -> bool {
+        match notice {
+            FileNotice::Changed(_, path) => self.allows(path),
+            FileNotice::Renamed { from, to } => self.allows(from) || self.allows(to),
+        }
+    }
+}
+
+/// Find the directories reachable through symlinks under `roots` that are not
+/// already inside a watched tree, following links inside those targets too.
+/// A link back into any watched (or already followed) tree is skipped, which
+/// also breaks symlink cycles, and excluded paths are neither walked nor
+/// followed.
+fn symlink_targets(roots: &[PathBuf], filter: &PathFilter) -> Vec<PathBuf> {
+    let mut watched: Vec<PathBuf> = roots
+        .iter()
+        .filter_map(|root| std::fs::canonicalize(root).ok())
+        .collect();
+    let mut targets = Vec::new();
+    let mut visited = HashSet::new();
+    let mut pending: Vec<PathBuf> = watched.clone();
+
+    while let Some(dir) = pending.pop() {
+        if !visited.insert(dir.clone()) {
+            continue;
+        }
+
+        let Ok(entries) = std::fs::read_dir(&dir) else {
+            continue;
+        };
+
+        for entry in entries.flatten() {
+            let Ok(file_type) = entry.file_type() else {
+                continue;
+            };
+            if filter.excludes(&entry.path()) {
+                continue;
+            }
+
+            if file_type.is_dir() {
+                pending.push(entry.path());
+                continue;
+            }
+
+            if !file_type.is_symlink() {
+                continue;
+            }
+
+            let Ok(target) = std::fs::canonicalize(entry.path()) else {
+                continue;
+            };
+            if !target.is_dir() {
+                continue;
+            }
+
+            let overlaps = watched
+                .iter()
+                .any(|root| target.starts_with(root) || root.starts_with(&target));
+            if overlaps {
+                debug!(
+                    "Not following {:?} -> {:?}: already watched",
+                    entry.path(),
+                    target
+                );
+                continue;
+            }
+
+            watched.push(target.clone());
+            targets.push(target.clone());
+            pending.push(target);
+        }
+    }
+
+    targets
+}
+
 pub struct FileWatcherPlugin {
     name: String,
     paths: Vec<PathBuf>,
-    pattern: Option<String>,
+    patterns: Vec<String>,
+    exclude_patterns: Vec<String>,
     recursive: bool,
+    follow_symlinks: bool,
+    /// Symlink targets watched in addition to `paths`
+    linked_paths: Vec<PathBuf>,
+    debounce: Duration,
     watcher: Option<RecommendedWatcher>,
     is_running: bool,
 }
@@ -19,37 +361,80 @@ impl FileWatcherPlugin {
         Self {
             name: name.into(),
             paths,
-            pattern: None,
+            patterns: Vec::new(),
+            exclude_patterns: Vec::new(),
             recursive: true,
+            follow_symlinks: false,
+            linked_paths: Vec::new(),
+            debounce: DEFAULT_FILE_DEBOUNCE,
             watcher: None,
             is_running: false,
         }
     }
 
+    /// Only report files whose name matches this glob. May be called more
+    /// than once; a file is reported if any pattern matches.
     pub fn with_pattern(mut self, pattern: impl Into<String>) -> Self {
-        self.pattern = Some(pattern.into());
+        self.patterns.push(pattern.into());
         self
     }
 
+    /// Drop events for paths matching this glob, e.g. `**/node_modules/**`.
+    /// Matched against the full path and takes precedence over `with_pattern`.
+    pub fn with_exclude_pattern(mut self, pattern: impl Into<String>) -> Self {
+        self.exclude_patterns.push(pattern.into());
+        self
+    }
+
+    fn path_filter(&self) -> PathFilter {
+        PathFilter::new(&self.patterns, &self.exclude_patterns)
+    }
+
     pub fn with_recursive(mut self, recursive: bool) -> Self {
         self.recursive = recursive;
         self
     }
 
-    #[allow(dead_code)]
-    fn should_emit_event(&self, path: &PathBuf) -> bool {
-        if let Some(ref pattern) = self.pattern {
-            if let Some(filename) = path.file_name() {
-                if let Some(name) = filename.to_str() {
-                    return glob::Pattern::new(pattern)
-                        .map(|p| p.matches(name))
-                        .unwrap_or(true);
-                }
+    /// Also watch directories that symlinks inside a recursive watch point to.
+    /// Links are resolved when the watcher starts; events for files behind a
+    /// link carry the link target's path.
+    pub fn with_follow_symlinks(mut self, follow: bool) -> Self {
+        self.follow_symlinks = follow;
+        self
+    }
+
+    /// Quiet period used to coalesce bursts of changes to the same path
+    pub fn with_debounce(mut self, debounce: Duration) -> Self {
+        self.debounce = debounce;
+        self
+    }
+
+    fn notice_event(notice: FileNotice, plugin_name: &str) -> Event {
+        let (kind, watcher_path) = match notice {
+            FileNotice::Changed(change, path) => {
+                let watcher_path = path.to_string_lossy().to_string();
+                let kind = match change {
+                    FileChange::Created => EventKind::FileCreated { path },
+                    FileChange::Modified => EventKind::FileModified { path },
+                    FileChange::Deleted => EventKind::FileDeleted { path },
+                };
+                (kind, watcher_path)
             }
-            false
-        } else {
-            true
-        }
+            FileNotice::Renamed { from, to } => {
+                let watcher_path = to.to_string_lossy().to_string();
+                let kind = EventKind::FileRenamed {
+                    old_path: from,
+                    new_path: to,
+                };
+                (kind, watcher_path)
+            }
+        };
+        Event::new(kind, plugin_name).with_metadata("watcher_path", watcher_path)
+    }
+
+    #[allow(dead_code)]
+    fn should_emit_event(&self, path: &Path) -> bool {
+        self.path_filter().allows(path)
     }
 
     #[allow(dead_code)]
@@ -105,45 +490,20 @@ impl EventSourcePlugin for FileWatcherPlugin {
         };
 
         let plugin_name = self.name.clone();
-        let pattern = self.pattern.clone();
+        let filter = self.path_filter();
+        let mut debouncer = ChangeDebouncer::new(self.debounce);
+
+        // The notify callback runs on its own thread; changes are debounced
+        // on a task so quiet paths can be flushed on a timer
+        let (change_sender, mut change_receiver) = tokio::sync::mpsc::unbounded_channel();
 
         let mut watcher = RecommendedWatcher::new(
             move |res: Result<NotifyEvent, notify::Error>| match res {
                 Ok(event) => {
                     debug!("File system event: {:?}", event);
 
-                    for path in &event.paths {
-                        if let Some(ref pat) = pattern {
-                            if let Some(filename) = path.file_name() {
-                                if let Some(name) = filename.to_str() {
-                                    if let Ok(glob_pattern) = glob::Pattern::new(pat) {
-                                        if !glob_pattern.matches(name) {
-                                            continue;
-                                        }
-                                    }
-                                }
-                            }
-                        }
-
-                        let kind = match event.kind {
-                            notify::EventKind::Create(_) => {
-                                EventKind::FileCreated { path: path.clone() }
-                            }
-                            notify::EventKind::Modify(_) => {
-                                EventKind::FileModified { path: path.clone() }
-                            }
-                            notify::EventKind::Remove(_) => {
-                                EventKind::FileDeleted { path: path.clone() }
-                            }
-                            _ => continue,
-                        };
-
-                        let event = Event::new(kind, &plugin_name)
-                            .with_metadata("watcher_path", path.to_string_lossy());
-
-                        if let Err(e) = emitter.try_send(event) {
-                            error!("Failed to send event: {}", e);
-                        }
+                    for change in RawChange::from_notify(&event) {
+                        let _ = change_sender.send(change);
                     }
                 }
                 Err(e) => {
@@ -167,6 +527,71 @@ impl EventSourcePlugin for FileWatcherPlugin {
             info!("Watching path: {:?} (recursive: {})", path, self.recursive);
         }
 
+        self.linked_paths.clear();
+        if self.follow_symlinks && self.recursive {
+            // Walks every watched tree, so keep it off the async runtime
+            let roots = self.paths.clone();
+            let walk_filter = self.path_filter();
+            let targets =
+                tokio::task::spawn_blocking(move || symlink_targets(&roots, &walk_filter))
+                    .await
+                    .unwrap_or_else(|e| {
+                        warn!("Failed to look for symlinks: {}", e);
+                        Vec::new()
+                    });
+            for target in targets {
+                match watcher.watch(&target, RecursiveMode::Recursive) {
+                    Ok(()) => {
+                        info!("Watching symlink target: {:?}", target);
+                        self.linked_paths.push(target);
+                    }
+                    Err(e) => warn!("Failed to watch symlink target {:?}: {}", target, e),
+                }
+            }
+        }
+
+        // Runs until the watcher, and with it the sender, is dropped in stop()
+        tokio::spawn(async move {
+            loop {
+                let received = match debouncer.next_flush() {
+                    Some(deadline) => {
+                        tokio::time::timeout_at(deadline.into(), change_receiver.recv())
+                            .await
+                            .ok()
+                    }
+                    None => Some(change_receiver.recv().await),
+                };
+
+                let mut closed = matches!(received, Some(None));
+                let ready = match received {
+                    Some(Some(change)) => {
+                        let now = Instant::now();
+                        let mut ready = debouncer.add(change, now);
+                        ready.extend(debouncer.drain_expired(now));
+                        ready
+                    }
+                    Some(None) => debouncer.drain_all(),
+                    None => debouncer.drain_expired(Instant::now()),
+                };
+
+                for notice in ready {
+                    if !filter.allows_notice(&notice) {
+                        continue;
+                    }
+
+                    let event = Self::notice_event(notice, &plugin_name);
+                    if emitter.emit(event) == EmitOutcome::Closed {
+                        closed = true;
+                        break;
+                    }
+                }
+
+                if closed {
+                    break;
+                }
+            }
+        });
+
         self.watcher = Some(watcher);
         self.is_running = true;
 
@@ -177,7 +602,7 @@ impl EventSourcePlugin for FileWatcherPlugin {
         if let Some(mut watcher) = self.watcher.take() {
             info!("Stopping file watcher plugin: {}", self.name);
 
-            for path in &self.paths {
+            for path in self.paths.iter().chain(&self.linked_paths) {
                 if let Err(e) = watcher.unwatch(path) {
                     warn!("Failed to unwatch {:?}: {}", path, e);
                 }
@@ -196,7 +621,6 @@ impl EventSourcePlugin for FileWatcherPlugin {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use std::time::Duration;
     use tempfile::TempDir;
     use tokio::fs::File;
     use tokio::io::AsyncWriteExt;
@@ -211,7 +635,10 @@ mod tests {
             FileWatcherPlugin::new("test_watcher", vec![temp_dir.path().to_path_buf()])
                 .with_recursive(false);
 
-        plugin.start(tx).await.expect("Failed to start plugin");
+        plugin
+            .start(tx.into())
+            .await
+            .expect("Failed to start plugin");
 
         // Give watcher time to initialize
         tokio::time::sleep(Duration::from_millis(100)).await;
@@ -240,6 +667,207 @@ mod tests {
         }
     }
 
+    fn changed(change: FileChange, name: &str) -> RawChange {
+        RawChange::Changed(change, PathBuf::from(name))
+    }
+
+    fn notice(change: FileChange, name: &str) -> FileNotice {
+        FileNotice::Changed(change, PathBuf::from(name))
+    }
+
+    fn renamed(from: &str, to: &str) -> FileNotice {
+        FileNotice::Renamed {
+            from: PathBuf::from(from),
+            to: PathBuf::from(to),
+        }
+    }
+
+    #[test]
+    fn test_debounce_coalesces_modifications() {
+        let mut debouncer = ChangeDebouncer::new(Duration::from_millis(300));
+        let start = Instant::now();
+        let at = |ms| start + Duration::from_millis(ms);
+
+        // One save, three writes
+        for ms in [0, 50, 100] {
+            assert!(
+                debouncer
+                    .add(changed(FileChange::Modified, "a.txt"), at(ms))
+                    .is_empty()
+            );
+        }
+        assert_eq!(debouncer.next_flush(), Some(at(400)));
+        assert!(debouncer.drain_expired(at(350)).is_empty());
+        assert_eq!(
+            debouncer.drain_expired(at(400)),
+            vec![notice(FileChange::Modified, "a.txt")]
+        );
+        assert_eq!(debouncer.next_flush(), None);
+
+        // A later save is reported again
+        debouncer.add(changed(FileChange::Modified, "a.txt"), at(1000));
+        assert_eq!(
+            debouncer.drain_all(),
+            vec![notice(FileChange::Modified, "a.txt")]
+        );
+    }
+
+    #[test]
+    fn test_debounce_created_then_modified_is_created() {
+        let mut debouncer = ChangeDebouncer::new(Duration::from_millis(300));
+        let start = Instant::now();
+        let at = |ms| start + Duration::from_millis(ms);
+
+        debouncer.add(changed(FileChange::Created, "a.txt"), at(0));
+        debouncer.add(changed(FileChange::Modified, "a.txt"), at(20));
+        debouncer.add(changed(FileChange::Modified, "b.txt"), at(30));
+        debouncer.add(changed(FileChange::Modified, "a.txt"), at(40));
+
+        assert_eq!(
+            debouncer.drain_expired(at(340)),
+            vec![
+                notice(FileChange::Modified, "b.txt"),
+                notice(FileChange::Created, "a.txt")
+            ]
+        );
+    }
+
+    #[test]
+    fn test_debounce_delete_flushes_pending() {
+        let mut debouncer = ChangeDebouncer::new(Duration::from_millis(300));
+        let start = Instant::now();
+
+        debouncer.add(changed(FileChange::Created, "a.txt"), start);
+        assert_eq!(
+            debouncer.add(changed(FileChange::Deleted, "a.txt"), start),
+            vec![
+                notice(FileChange::Created, "a.txt"),
+                notice(FileChange::Deleted, "a.txt")
+            ]
+        );
+        assert_eq!(
+            debouncer.add(changed(FileChange::Deleted, "b.txt"), start),
+            vec![notice(FileChange::Deleted, "b.txt")]
+        );
+        assert!(debouncer.drain_all().is_empty());
+    }
+
+    #[test]
+    fn test_rename_halves_are_correlated() {
+        let mut debouncer = ChangeDebouncer::new(Duration::from_millis(300));
+        let start = Instant::now();
+        let at = |ms| start + Duration::from_millis(ms);
+
+        let from = RawChange::RenamedFrom(PathBuf::from("inbox.tmp"));
+        assert!(debouncer.add(from, at(0)).is_empty());
+        assert_eq!(debouncer.next_flush(), Some(at(100)));
+
+        let to = RawChange::RenamedTo(PathBuf::from("inbox/report.pdf"));
+        assert_eq!(
+            debouncer.add(to, at(5)),
+            vec![renamed("inbox.tmp", "inbox/report.pdf")]
+        );
+        assert_eq!(debouncer.next_flush(), None);
+
+        // Backends that report both names at once
+        let both = RawChange::Renamed {
+            from: PathBuf::from("a.txt"),
+            to: PathBuf::from("b.txt"),
+        };
+        assert_eq!(debouncer.add(both, at(10)), vec![renamed("a.txt", "b.txt")]);
+    }
+
+    #[test]
+    fn test_rename_correlation_times_out() {
+        let mut debouncer = ChangeDebouncer::new(Duration::from_millis(300));
+        let start = Instant::now();
+        let at = |ms| start + Duration::from_millis(ms);
+
+        // Moved out of the watched tree: only the old name is reported
+        debouncer.add(RawChange::RenamedFrom(PathBuf::from("a.txt")), at(0));
+        assert!(debouncer.drain_expired(at(50)).is_empty());
+        assert_eq!(
+            debouncer.drain_expired(at(100)),
+            vec![notice(FileChange::Deleted, "a.txt")]
+        );
+
+        // Moved into the watched tree: only the new name is reported
+        debouncer.add(RawChange::RenamedTo(PathBuf::from("b.txt")), at(200));
+        assert_eq!(
+            debouncer.drain_expired(at(500)),
+            vec![notice(FileChange::Created, "b.txt")]
+        );
+
+        // A late rename-to does not pair with an expired rename-from
+        debouncer.add(RawChange::RenamedFrom(PathBuf::from("c.txt")), at(1000));
+        assert_eq!(
+            debouncer.add(RawChange::RenamedTo(PathBuf::from("d.txt")), at(1200)),
+            vec![notice(FileChange::Deleted, "c.txt")]
+        );
+        assert_eq!(
+            debouncer.drain_all(),
+            vec![notice(FileChange::Created, "d.txt")]
+        );
+    }
+
+    #[test]
+    fn test_raw_changes_from_notify() {
+        use notify::event::{CreateKind, ModifyKind, RenameMode};
+
+        let event = |kind, paths: &[&str]| {
+            let mut event = NotifyEvent::new(kind);
+            for path in paths {
+                event = event.add_path(PathBuf::from(path));
+            }
+            event
+        };
+
+        assert_eq!(
+            RawChange::from_notify(&event(
+                notify::EventKind::Modify(ModifyKind::Name(RenameMode::Both)),
+                &["a.txt", "b.txt"]
+            )),
+            vec![RawChange::Renamed {
+                from: PathBuf::from("a.txt"),
+                to: PathBuf::from("b.txt")
+            }]
+        );
+        assert_eq!(
+            RawChange::from_notify(&event(
+                notify::EventKind::Modify(ModifyKind::Name(RenameMode::From)),
+                &["a.txt"]
+            )),
+            vec![RawChange::RenamedFrom(PathBuf::from("a.txt"))]
+        );
+        assert_eq!(
+            RawChange::from_notify(&event(
+                notify::EventKind::Create(CreateKind::File),
+                &["a.txt"]
+            )),
+            vec![changed(FileChange::Created, "a.txt")]
+        );
+    }
+
+    #[test]
+    fn test_rename_matches_pattern_on_either_name() {
+        let filter = PathFilter::new(&["*.pdf".to_string()], &[]);
+        let matches = |notice| filter.allows_notice(&notice);
+
+        assert!(matches(renamed("report.tmp", "report.pdf")));
+        assert!(matches(renamed("report.pdf", "report.bak")));
+        assert!(!matches(renamed("a.tmp", "a.txt")));
+        assert!(!matches(notice(FileChange::Modified, "a.txt")));
+
+        let event = FileWatcherPlugin::notice_event(renamed("report.tmp", "report.pdf"), "test");
+        assert_eq!(
+            event.kind,
+            EventKind::FileRenamed {
+                old_path: PathBuf::from("report.tmp"),
+                new_path: PathBuf::from("report.pdf")
+            }
+        );
+    }
+
     #[test]
     fn test_pattern_matching() {
         let temp_dir = TempDir::new().unwrap();
@@ -251,6 +879,80 @@ mod tests {
         assert!(!plugin.should_emit_event(&PathBuf::from("file.log")));
     }
 
+    #[test]
+    fn test_multiple_patterns_match_any() {
+        let temp_dir = TempDir::new().unwrap();
+        let plugin = FileWatcherPlugin::new("test", vec![temp_dir.path().to_path_buf()])
+            .with_pattern("*.txt")
+            .with_pattern("*.log");
+
+        assert!(plugin.should_emit_event(&PathBuf::from("notes.txt")));
+        assert!(plugin.should_emit_event(&PathBuf::from("/var/app/server.log")));
+        assert!(!plugin.should_emit_event(&PathBuf::from("image.png")));
+    }
+
+    #[test]
+    fn test_exclude_takes_precedence() {
+        let temp_dir = TempDir::new().unwrap();
+        let plugin = FileWatcherPlugin::new("test", vec![temp_dir.path().to_path_buf()])
+            .with_pattern("*.js")
+            .with_exclude_pattern("**/node_modules/**")
+            .with_exclude_pattern("**/.git/**");
+
+        assert!(plugin.should_emit_event(Path::new("/src/app/index.js")));
+        // Matches the include, but lives under an excluded directory
+        assert!(!plugin.should_emit_event(Path::new("/src/app/node_modules/lodash/index.js")));
+        assert!(!plugin.should_emit_event(Path::new("/src/app/.git/hooks/pre-commit.js")));
+        assert!(!plugin.should_emit_event(Path::new("/src/app/readme.md")));
+
+        // Excludes alone still let everything else through
+        let plugin = FileWatcherPlugin::new("test", vec![temp_dir.path().to_path_buf()])
+            .with_exclude_pattern("*.tmp");
+        assert!(plugin.should_emit_event(Path::new("/src/notes.txt")));
+        assert!(!plugin.should_emit_event(Path::new("/src/notes.txt.tmp")));
+    }
+
+    #[test]
+    #[cfg_attr(
+        windows,
+        ignore = "creating symlinks needs Developer Mode or elevation"
+    )]
+    fn test_symlink_targets_skip_cycles_and_excludes() {
+        #[cfg(unix)]
+        use std::os::unix::fs::symlink;
+        #[cfg(windows)]
+        use std::os::windows::fs::symlink_dir as symlink;
+
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path().join("watched");
+        let outside = temp_dir.path().join("outside");
+        let skipped = temp_dir.path().join("skipped");
+        std::fs::create_dir_all(root.join("sub")).unwrap();
+        std::fs::create_dir_all(&outside).unwrap();
+        std::fs::create_dir_all(&skipped).unwrap();
+
+        symlink(&outside, root.join("link_out")).unwrap();
+        // Back into the watched tree, from inside and from the followed target
+        symlink(root.join("sub"), root.join("link_in")).unwrap();
+        symlink(&root, outside.join("link_back")).unwrap();
+        // Excluded, as is anything under an excluded directory
+        symlink(&skipped, root.join("cache_link")).unwrap();
+        std::fs::create_dir_all(root.join("node_modules")).unwrap();
+        symlink(&skipped, root.join("node_modules").join("link")).unwrap();
+
+        let filter = PathFilter::new(
+            &[],
+            &["*cache_link".to_string(), "*node_modules".to_string()],
+        );
+        assert_eq!(
+            symlink_targets(std::slice::from_ref(&root), &filter),
+            vec![std::fs::canonicalize(&outside).unwrap()]
+        );
+        assert!(
+            symlink_targets(std::slice::from_ref(&outside.join("missing")), &filter).is_empty()
+        );
+    }
+
     #[test]
     fn test_no_pattern_matches_all() {
         let temp_dir = TempDir::new().unwrap();
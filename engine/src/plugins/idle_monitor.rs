@@ -0,0 +1,255 @@
+use async_trait::async_trait;
+use engine_core::event::{Event, EventKind};
+use engine_core::plugin::{EmitOutcome, EventEmitter, EventSourcePlugin, PluginError};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+use tokio::task::JoinHandle;
+use tracing::{info, warn};
+use windows::Win32::System::SystemInformation::GetTickCount;
+use windows::Win32::UI::Input::KeyboardAndMouse::{GetLastInputInfo, LASTINPUTINFO};
+
+/// Idle time after which `UserIdle` is emitted
+pub const DEFAULT_IDLE_THRESHOLD: Duration = Duration::from_secs(300);
+
+/// How often the last input time is sampled
+pub const DEFAULT_IDLE_POLL_INTERVAL: Duration = Duration::from_secs(5);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum IdleTransition {
+    BecameIdle { idle_for: Duration },
+    BecameActive,
+}
+
+/// Idle/active state machine, fed with the time since the last user input.
+/// Each transition is reported once; staying idle or active reports nothing.
+struct IdleTracker {
+    threshold: Duration,
+    idle: bool,
+}
+
+impl IdleTracker {
+    fn new(threshold: Duration) -> Self {
+        Self {
+            threshold,
+            idle: false,
+        }
+    }
+
+    fn update(&mut self, idle_for: Duration) -> Option<IdleTransition> {
+        match (self.idle, idle_for >= self.threshold) {
+            (false, true) => {
+                self.idle = true;
+                Some(IdleTransition::BecameIdle { idle_for })
+            }
+            (true, false) => {
+                self.idle = false;
+                Some(IdleTransition::BecameActive)
+            }
+            _ => None,
+        }
+    }
+}
+
+/// Emits `UserIdle` once the user has not touched keyboard or mouse for the
+/// threshold, and `UserActive` when input resumes
+pub struct IdleMonitorPlugin {
+    name: String,
+    threshold: Duration,
+    poll_interval: Duration,
+    is_running: Arc<AtomicBool>,
+    poll_task: Option<JoinHandle<()>>,
+}
+
+impl IdleMonitorPlugin {
+    pub fn new(name: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            threshold: DEFAULT_IDLE_THRESHOLD,
+            poll_interval: DEFAULT_IDLE_POLL_INTERVAL,
+            is_running: Arc::new(AtomicBool::new(false)),
+            poll_task: None,
+        }
+    }
+
+    pub fn with_threshold(mut self, threshold: Duration) -> Self {
+        self.threshold = threshold;
+        self
+    }
+
+    pub fn with_poll_interval(mut self, interval: Duration) -> Self {
+        self.poll_interval = interval;
+        self
+    }
+
+    /// Time since the last keyboard or mouse input in this session
+    fn idle_duration() -> Option<Duration> {
+        let mut info = LASTINPUTINFO {
+            cbSize: std::mem::size_of::<LASTINPUTINFO>() as u32,
+            dwTime: 0,
+        };
+
+        unsafe {
+            if !GetLastInputInfo(&mut info).as_bool() {
+                return None;
+            }
+            // Both are milliseconds since boot and wrap after ~49 days
+            let elapsed = GetTickCount().wrapping_sub(info.dwTime);
+            Some(Duration::from_millis(elapsed as u64))
+        }
+    }
+
+    fn transition_event(plugin_name: &str, transition: IdleTransition) -> Event {
+        let kind = match transition {
+            IdleTransition::BecameIdle { idle_for } => EventKind::UserIdle {
+                idle_seconds: idle_for.as_secs(),
+            },
+            IdleTransition::BecameActive => EventKind::UserActive,
+        };
+        Event::new(kind, plugin_name)
+    }
+}
+
+#[async_trait]
+impl EventSourcePlugin for IdleMonitorPlugin {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    async fn start(&mut self, emitter: EventEmitter) -> Result<(), PluginError> {
+        if self.is_running.load(Ordering::SeqCst) {
+            return Ok(());
+        }
+
+        if self.poll_interval.is_zero() {
+            return Err(PluginError::Configuration(
+                "Idle monitor poll interval must be greater than zero".to_string(),
+            ));
+        }
+
+        if Self::idle_duration().is_none() {
+            return Err(PluginError::Initialization(
+                "GetLastInputInfo is unavailable".to_string(),
+            ));
+        }
+
+        info!(
+            "Starting idle monitor plugin: {} (threshold {:?}, polling every {:?})",
+            self.name, self.threshold, self.poll_interval
+        );
+
+        self.is_running.store(true, Ordering::SeqCst);
+
+        let plugin_name = self.name.clone();
+        let is_running = self.is_running.clone();
+        let poll_interval = self.poll_interval;
+        let mut tracker = IdleTracker::new(self.threshold);
+
+        self.poll_task = Some(tokio::spawn(async move {
+            let mut interval = tokio::time::interval(poll_interval);
+
+            while is_running.load(Ordering::SeqCst) {
+                interval.tick().await;
+
+                let Some(idle_for) = Self::idle_duration() else {
+                    warn!("Failed to read last input time");
+                    continue;
+                };
+
+                if let Some(transition) = tracker.update(idle_for) {
+                    let event = Self::transition_event(&plugin_name, transition);
+                    if emitter.emit(event) == EmitOutcome::Closed {
+                        is_running.store(false, Ordering::SeqCst);
+                        break;
+                    }
+                }
+            }
+        }));
+
+        Ok(())
+    }
+
+    async fn stop(&mut self) -> Result<(), PluginError> {
+        info!("Stopping idle monitor plugin: {}", self.name);
+        self.is_running.store(false, Ordering::SeqCst);
+
+        if let Some(task) = self.poll_task.take() {
+            task.abort();
+        }
+
+        Ok(())
+    }
+
+    fn is_running(&self) -> bool {
+        self.is_running.load(Ordering::SeqCst)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_idle_transitions() {
+        let mut tracker = IdleTracker::new(Duration::from_secs(300));
+        let secs = Duration::from_secs;
+
+        // Active user, nothing to report
+        assert_eq!(tracker.update(secs(1)), None);
+        assert_eq!(tracker.update(secs(299)), None);
+
+        // Crossing the threshold reports once
+        assert_eq!(
+            tracker.update(secs(300)),
+            Some(IdleTransition::BecameIdle {
+                idle_for: secs(300)
+            })
+        );
+        assert_eq!(tracker.update(secs(305)), None);
+        assert_eq!(tracker.update(secs(900)), None);
+
+        // Input resets the idle time
+        assert_eq!(tracker.update(secs(0)), Some(IdleTransition::BecameActive));
+        assert_eq!(tracker.update(secs(2)), None);
+
+        // And the cycle repeats
+        assert!(matches!(
+            tracker.update(secs(400)),
+            Some(IdleTransition::BecameIdle { .. })
+        ));
+    }
+
+    #[test]
+    fn test_transition_events() {
+        let idle = IdleMonitorPlugin::transition_event(
+            "idle",
+            IdleTransition::BecameIdle {
+                idle_for: Duration::from_millis(301_500),
+            },
+        );
+        assert!(matches!(
+            idle.kind,
+            EventKind::UserIdle { idle_seconds: 301 }
+        ));
+        assert_eq!(idle.source, "idle");
+
+        let active = IdleMonitorPlugin::transition_event("idle", IdleTransition::BecameActive);
+        assert!(matches!(active.kind, EventKind::UserActive));
+    }
+
+    #[tokio::test]
+    async fn test_idle_plugin_lifecycle() {
+        let (tx, _rx) = tokio::sync::mpsc::channel(10);
+        let mut plugin =
+            IdleMonitorPlugin::new("test_idle").with_poll_interval(Duration::from_millis(50));
+
+        assert!(!plugin.is_running());
+        plugin
+            .start(tx.into())
+            .await
+            .expect("Failed to start plugin");
+        assert!(plugin.is_running());
+        plugin.stop().await.expect("Failed to stop plugin");
+        assert!(!plugin.is_running());
+    }
+}
@@ -1,4 +1,9 @@
+pub mod clipboard_monitor;
+pub mod device_monitor;
 pub mod file_watcher;
+pub mod idle_monitor;
 pub mod process_monitor;
+pub mod process_name_cache;
 pub mod registry_monitor;
+pub mod timer;
 pub mod window_watcher;
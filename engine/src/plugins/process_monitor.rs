@@ -1,21 +1,33 @@
+use crate::plugins::process_name_cache::ProcessNameCache;
 use async_trait::async_trait;
 use engine_core::event::{Event, EventKind, NetworkProtocol};
-use engine_core::plugin::{EventEmitter, EventSourcePlugin, PluginError};
+use engine_core::plugin::{
+    EmitOutcome, EventEmitter, EventSourcePlugin, PluginError, PluginHealth,
+};
+use regex::Regex;
+use std::collections::HashMap;
+use std::net::{Ipv4Addr, Ipv6Addr};
 use std::path::PathBuf;
 use std::sync::Arc;
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::mpsc::{self, Sender};
 use std::thread::{self, JoinHandle};
-use tracing::{error, info};
-use uuid::Uuid;
-use windows::Win32::Foundation::CloseHandle;
+use std::time::Duration;
+use tracing::{error, info, warn};
+use windows::Win32::Foundation::{
+    CloseHandle, ERROR_ALREADY_EXISTS, HLOCAL, LocalFree, PSID, WIN32_ERROR,
+};
+use windows::Win32::Security::{Authorization::ConvertSidToStringSidW, IsValidSid};
 use windows::Win32::System::Diagnostics::Etw::{
     CloseTrace, ControlTraceW, EnableTraceEx2, OpenTraceW, ProcessTrace, StartTraceW,
-    CONTROLTRACE_HANDLE, EVENT_CONTROL_CODE_ENABLE_PROVIDER,
+    CONTROLTRACE_HANDLE, EVENT_CONTROL_CODE_ENABLE_PROVIDER, EVENT_HEADER_EXT_TYPE_SID,
     EVENT_ENABLE_PROPERTY_PROCESS_START_KEY, EVENT_ENABLE_PROPERTY_SID, EVENT_ENABLE_PROPERTY_TS_ID,
     EVENT_TRACE_CONTROL_STOP, EVENT_TRACE_FILE_MODE_NONE, EVENT_TRACE_PROPERTIES,
     EVENT_TRACE_REAL_TIME_MODE, PROCESSTRACE_HANDLE,
 };
+use windows::Win32::System::Diagnostics::ToolHelp::{
+    CreateToolhelp32Snapshot, PROCESSENTRY32W, Process32FirstW, Process32NextW, TH32CS_SNAPPROCESS,
+};
 use windows::Win32::System::Threading::{OpenProcess, PROCESS_QUERY_INFORMATION, QueryFullProcessImageNameW};
 use windows::core::{GUID, PWSTR};
 
@@ -36,9 +48,38 @@ const EVENT_FILE_DELETE: u16 = 65;
 const EVENT_FILE_READ: u16 = 67;
 const EVENT_FILE_WRITE: u16 = 68;
 
-// Event IDs for Microsoft-Windows-Kernel-Network
-const EVENT_NETWORK_CONNECT: u16 = 11;
-const EVENT_NETWORK_DISCONNECT: u16 = 12;
+// Event IDs for Microsoft-Windows-Kernel-Network. Each TCP event has an IPv4
+// and an IPv6 variant, whose payloads differ in the size of the addresses
+const EVENT_TCP_CONNECT_V4: u16 = 12;
+const EVENT_TCP_DISCONNECT_V4: u16 = 13;
+const EVENT_TCP_CONNECT_V6: u16 = 28;
+const EVENT_TCP_DISCONNECT_V6: u16 = 29;
+
+/// Address family of a network event, `AF_INET` or `AF_INET6`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum AddressFamily {
+    Inet,
+    Inet6,
+}
+
+impl AddressFamily {
+    fn address_len(self) -> usize {
+        match self {
+            AddressFamily::Inet => 4,
+            AddressFamily::Inet6 => 16,
+        }
+    }
+}
+
+/// The connection a Kernel-Network TCP event is about
+#[derive(Debug, Clone, PartialEq)]
+struct TcpEndpoints {
+    pid: u32,
+    local_addr: String,
+    local_port: u16,
+    remote_addr: String,
+    remote_port: u16,
+}
 
 #[derive(Debug, Clone)]
 enum EtwEvent {
@@ -98,15 +139,169 @@ enum EtwEvent {
     },
 }
 
+/// A running process as seen by a Toolhelp snapshot
+#[derive(Debug, Clone, PartialEq)]
+struct PolledProcess {
+    pid: u32,
+    parent_pid: u32,
+    name: String,
+}
+
+type ProcessSnapshot = HashMap<u32, PolledProcess>;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum IoDirection {
+    Read,
+    Write,
+}
+
+/// Byte counts accumulated for one `(pid, path, direction)` since `first_seen`
+struct PendingIo {
+    bytes: u64,
+    first_seen: std::time::Instant,
+}
+
+/// Folds bursts of file reads/writes into one event per process, path and
+/// direction, emitted `window` after the first I/O with the summed byte count
+struct IoCoalescer {
+    window: Duration,
+    pending: HashMap<(u32, PathBuf, IoDirection), PendingIo>,
+}
+
+impl IoCoalescer {
+    fn new(window: Duration) -> Self {
+        Self {
+            window,
+            pending: HashMap::new(),
+        }
+    }
+
+    /// Absorb a file I/O event. Any other event is handed straight back.
+    fn add(&mut self, event: EtwEvent, now: std::time::Instant) -> Option<EtwEvent> {
+        let (key, bytes) = match event {
+            EtwEvent::FileRead {
+                pid,
+                path,
+                bytes_read,
+            } => ((pid, path, IoDirection::Read), bytes_read),
+            EtwEvent::FileWrite {
+                pid,
+                path,
+                bytes_written,
+            } => ((pid, path, IoDirection::Write), bytes_written),
+            other => return Some(other),
+        };
+
+        let pending = self.pending.entry(key).or_insert(PendingIo {
+            bytes: 0,
+            first_seen: now,
+        });
+        pending.bytes += bytes;
+        None
+    }
+
+    /// When the oldest pending entry is due to be emitted
+    fn next_flush(&self) -> Option<std::time::Instant> {
+        self.pending
+            .values()
+            .map(|p| p.first_seen + self.window)
+            .min()
+    }
+
+    /// Remove and return the entries whose window has elapsed
+    fn drain_expired(&mut self, now: std::time::Instant) -> Vec<EtwEvent> {
+        let window = self.window;
+        let expired: Vec<_> = self
+            .pending
+            .iter()
+            .filter(|(_, p)| now.duration_since(p.first_seen) >= window)
+            .map(|(key, _)| key.clone())
+            .collect();
+
+        expired
+            .into_iter()
+            .filter_map(|key| {
+                let pending = self.pending.remove(&key)?;
+                let (pid, path, direction) = key;
+                Some(match direction {
+                    IoDirection::Read => EtwEvent::FileRead {
+                        pid,
+                        path,
+                        bytes_read: pending.bytes,
+                    },
+                    IoDirection::Write => EtwEvent::FileWrite {
+                        pid,
+                        path,
+                        bytes_written: pending.bytes,
+                    },
+                })
+            })
+            .collect()
+    }
+}
+
+/// Shortest polling interval accepted, so a zero interval can't busy-loop
+pub const MIN_POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Compiled name and command-line filters applied before events are emitted
+struct EventFilters {
+    name: Option<glob::Pattern>,
+    command_line: Option<Regex>,
+}
+
+impl EventFilters {
+    /// Polled starts carry no command line, so a command-line filter can't be
+    /// honoured while polling. Rather than let every start through, polling is
+    /// refused.
+    fn check_pollable(&self) -> Result<(), PluginError> {
+        match &self.command_line {
+            Some(regex) => Err(PluginError::Configuration(format!(
+                "command line filter '{}' needs ETW (administrator privileges); \
+                 polled process events carry no command line",
+                regex
+            ))),
+            None => Ok(()),
+        }
+    }
+
+    fn allows(&self, event: &Event) -> bool {
+        // Names of processes that already exited resolve to "PID:n"
+        // and are dropped by any name filter
+        let process_name = event.metadata.get("process_name").map(String::as_str);
+        if !ProcessMonitorPlugin::matches_name_filter(
+            self.name.as_ref(),
+            process_name.unwrap_or_default(),
+        ) {
+            return false;
+        }
+
+        match &event.kind {
+            EventKind::ProcessStarted { command_line, .. } => {
+                ProcessMonitorPlugin::matches_command_line_filter(
+                    self.command_line.as_ref(),
+                    command_line,
+                )
+            }
+            _ => true,
+        }
+    }
+}
+
 pub struct ProcessMonitorPlugin {
     name: String,
     filter_name: Option<String>,
+    command_line_filter: Option<String>,
     monitor_threads: bool,
     monitor_files: bool,
     monitor_network: bool,
+    poll_interval: Duration,
+    prefer_polling: bool,
+    io_coalesce: Option<Duration>,
     is_running: Arc<AtomicBool>,
     session_name: String,
     etw_thread: Option<JoinHandle<()>>,
+    /// The polling loop, when ETW isn't used
+    poll_task: Option<tokio::task::JoinHandle<()>>,
     event_sender: Option<Sender<EtwEvent>>,
 }
 
@@ -118,37 +313,56 @@ thread_local! {
 struct EtwCallbackContext {
     sender: Sender<EtwEvent>,
     is_running: Arc<AtomicBool>,
-    #[allow(dead_code)]
-    pid_filter: Option<String>,
+}
+
+/// ETW session name for a plugin instance. It's the same on every run, so a
+/// session orphaned by a crash is found and stopped on the next start instead
+/// of lingering next to a freshly named one.
+pub(crate) fn etw_session_name(prefix: &str, plugin_name: &str) -> String {
+    format!(
+        "{}_{}",
+        prefix,
+        plugin_name.to_lowercase().replace([' ', '-'], "_")
+    )
 }
 
 impl ProcessMonitorPlugin {
     pub fn new(name: impl Into<String>) -> Self {
         let name_str: String = name.into();
-        // Generate unique session name to avoid conflicts
-        let session_name = format!("wee_{}_{}", 
-            name_str.to_lowercase().replace(" ", "_").replace("-", "_"),
-            Uuid::new_v4().simple()
-        );
-        
+        let session_name = etw_session_name("wee", &name_str);
+
         Self {
             name: name_str,
             filter_name: None,
+            command_line_filter: None,
             monitor_threads: false,
             monitor_files: false,
             monitor_network: false,
+            poll_interval: Duration::from_secs(2),
+            prefer_polling: false,
+            io_coalesce: None,
             is_running: Arc::new(AtomicBool::new(false)),
             session_name,
             etw_thread: None,
+            poll_task: None,
             event_sender: None,
         }
     }
 
+    /// Only emit events from processes whose executable name matches `pattern`,
+    /// a case-insensitive glob such as `chrome*.exe`. The `.exe` suffix is optional.
     pub fn with_name_filter(mut self, pattern: impl Into<String>) -> Self {
         self.filter_name = Some(pattern.into());
         self
     }
 
+    /// Only emit `ProcessStarted` events whose command line matches the regex
+    /// `pattern`, e.g. `(?i)powershell.*\s-enc?\s`. Other event types are unaffected.
+    pub fn with_command_line_filter(mut self, pattern: impl Into<String>) -> Self {
+        self.command_line_filter = Some(pattern.into());
+        self
+    }
+
     pub fn with_thread_monitoring(mut self, enabled: bool) -> Self {
         self.monitor_threads = enabled;
         self
@@ -164,11 +378,31 @@ impl ProcessMonitorPlugin {
         self
     }
 
+    /// Merge file reads/writes by the same process on the same path within
+    /// `window` into a single event carrying the total byte count
+    pub fn with_io_coalesce(mut self, window: Duration) -> Self {
+        self.io_coalesce = Some(window);
+        self
+    }
+
+    /// How often to snapshot the process list when ETW isn't used, at most
+    /// every [`MIN_POLL_INTERVAL`]
+    pub fn with_poll_interval(mut self, interval: Duration) -> Self {
+        self.poll_interval = interval.max(MIN_POLL_INTERVAL);
+        self
+    }
+
+    /// Skip ETW and always poll. Polling only reports process start/stop and
+    /// needs no administrator privileges.
+    pub fn with_prefer_polling(mut self, prefer: bool) -> Self {
+        self.prefer_polling = prefer;
+        self
+    }
+
     fn run_etw_session(
         session_name: String,
         sender: Sender<EtwEvent>,
         is_running: Arc<AtomicBool>,
-        pid_filter: Option<String>,
         monitor_threads: bool,
         monitor_files: bool,
         monitor_network: bool,
@@ -198,7 +432,6 @@ impl ProcessMonitorPlugin {
         let context = EtwCallbackContext {
             sender,
             is_running,
-            pid_filter,
         };
 
         // Open trace for processing
@@ -224,21 +457,77 @@ impl ProcessMonitorPlugin {
 
     fn create_etw_session(session_name: &str) -> Result<CONTROLTRACE_HANDLE, String> {
         let name_wide: Vec<u16> = session_name.encode_utf16().chain(std::iter::once(0)).collect();
-        
-        // Calculate total size needed for properties struct
-        let name_len = name_wide.len() * std::mem::size_of::<u16>();
+
+        let mut attempt = 0;
+        loop {
+            match Self::start_trace(&name_wide) {
+                Ok(session_handle) => {
+                    info!("ETW session '{}' created", session_name);
+                    return Ok(session_handle);
+                }
+                Err(e) if Self::should_retry_session_start(&e, attempt) => {
+                    // Left behind by a previous run that didn't shut down cleanly
+                    warn!(
+                        "ETW session '{}' already exists, stopping the stale session",
+                        session_name
+                    );
+                    match Self::stop_session_by_name(&name_wide) {
+                        Ok(()) => info!("Stopped stale ETW session '{}', retrying", session_name),
+                        Err(stop_error) => warn!(
+                            "Failed to stop stale ETW session '{}': {:?}",
+                            session_name, stop_error
+                        ),
+                    }
+                    attempt += 1;
+                }
+                Err(e) => {
+                    let error_code = e.code().0 as u32;
+                    if WIN32_ERROR::from_error(&e) == Some(ERROR_ALREADY_EXISTS) {
+                        return Err(format!(
+                            "ETW session '{}' already exists and could not be cleaned up. Stop it with `logman stop {} -ets`.",
+                            session_name, session_name
+                        ));
+                    }
+                    return Err(format!(
+                        "Failed to create ETW session: 0x{:08X} - {:?}",
+                        error_code, e
+                    ));
+                }
+            }
+        }
+    }
+
+    /// Retry `StartTraceW` once after cleaning up a session with the same name
+    pub(crate) fn should_retry_session_start(error: &windows::core::Error, attempt: u32) -> bool {
+        attempt == 0 && WIN32_ERROR::from_error(error) == Some(ERROR_ALREADY_EXISTS)
+    }
+
+    /// Allocate `EVENT_TRACE_PROPERTIES` followed by room for the session name
+    fn session_properties(name_wide: &[u16]) -> Vec<u8> {
+        let name_len = std::mem::size_of_val(name_wide);
         let properties_size = std::mem::size_of::<EVENT_TRACE_PROPERTIES>() + name_len;
-        
+
         let mut properties_buffer = vec![0u8; properties_size];
         let properties = properties_buffer.as_mut_ptr() as *mut EVENT_TRACE_PROPERTIES;
-        
+
         unsafe {
-            // Initialize properties
             (*properties).Wnode.BufferSize = properties_size as u32;
+            (*properties).LoggerNameOffset = std::mem::size_of::<EVENT_TRACE_PROPERTIES>() as u32;
+        }
+
+        properties_buffer
+    }
+
+    fn start_trace(name_wide: &[u16]) -> windows::core::Result<CONTROLTRACE_HANDLE> {
+        let mut properties_buffer = Self::session_properties(name_wide);
+        let properties = properties_buffer.as_mut_ptr() as *mut EVENT_TRACE_PROPERTIES;
+
+        unsafe {
+            // Initialize properties
             (*properties).Wnode.Guid = GUID::zeroed();
             (*properties).Wnode.ClientContext = 1; // Use query performance counter
             (*properties).Wnode.Flags = 0;
-            
+
             (*properties).BufferSize = 64; // 64KB buffers
             (*properties).MinimumBuffers = 4;
             (*properties).MaximumBuffers = 64;
@@ -246,34 +535,33 @@ impl ProcessMonitorPlugin {
             (*properties).LogFileMode = EVENT_TRACE_REAL_TIME_MODE | EVENT_TRACE_FILE_MODE_NONE;
             (*properties).FlushTimer = 1; // 1 second flush
             (*properties).EnableFlags = windows::Win32::System::Diagnostics::Etw::EVENT_TRACE_FLAG(0);
-            
+
             // Set session name at the offset
-            let name_offset = std::mem::size_of::<EVENT_TRACE_PROPERTIES>();
+            let name_offset = (*properties).LoggerNameOffset as usize;
             let name_ptr = properties_buffer.as_mut_ptr().add(name_offset) as *mut u16;
             std::ptr::copy_nonoverlapping(name_wide.as_ptr(), name_ptr, name_wide.len());
-            (*properties).LoggerNameOffset = name_offset as u32;
-            
+
             let mut session_handle: CONTROLTRACE_HANDLE = std::mem::zeroed();
-            let result = StartTraceW(
+            StartTraceW(
                 &mut session_handle,
                 windows::core::PCWSTR(name_wide.as_ptr()),
                 properties,
-            );
-            
-            match result {
-                Ok(_) => {
-                    info!("ETW session '{}' created", session_name);
-                    Ok(session_handle)
-                }
-                Err(e) => {
-                    let error_code = e.code().0 as u32;
-                    if error_code == 0xB7 { // ERROR_ALREADY_EXISTS
-                        Err(format!("ETW session '{}' already exists. Try restarting or use a different name.", session_name))
-                    } else {
-                        Err(format!("Failed to create ETW session: 0x{:08X} - {:?}", error_code, e))
-                    }
-                }
-            }
+            )?;
+            Ok(session_handle)
+        }
+    }
+
+    pub(crate) fn stop_session_by_name(name_wide: &[u16]) -> windows::core::Result<()> {
+        let mut properties_buffer = Self::session_properties(name_wide);
+        let properties = properties_buffer.as_mut_ptr() as *mut EVENT_TRACE_PROPERTIES;
+
+        unsafe {
+            ControlTraceW(
+                CONTROLTRACE_HANDLE::default(),
+                windows::core::PCWSTR(name_wide.as_ptr()),
+                properties,
+                EVENT_TRACE_CONTROL_STOP,
+            )
         }
     }
 
@@ -376,7 +664,9 @@ impl ProcessMonitorPlugin {
             // Parse based on provider
             if provider_id == KERNEL_PROCESS_PROVIDER {
                 match event_id {
-                    EVENT_PROCESS_START => Self::parse_process_start(data),
+                    EVENT_PROCESS_START => {
+                        Self::parse_process_start(data, Self::extract_user_sid(record))
+                    }
                     EVENT_PROCESS_STOP => Self::parse_process_stop(data),
                     EVENT_THREAD_START => Self::parse_thread_start(data),
                     EVENT_THREAD_STOP => Self::parse_thread_stop(data),
@@ -392,8 +682,14 @@ impl ProcessMonitorPlugin {
                 }
             } else if provider_id == KERNEL_NETWORK_PROVIDER && !data.is_empty() {
                 match event_id {
-                    EVENT_NETWORK_CONNECT => Self::parse_network_connect(data),
-                    EVENT_NETWORK_DISCONNECT => Self::parse_network_disconnect(data),
+                    EVENT_TCP_CONNECT_V4 => Self::parse_network_connect(data, AddressFamily::Inet),
+                    EVENT_TCP_CONNECT_V6 => Self::parse_network_connect(data, AddressFamily::Inet6),
+                    EVENT_TCP_DISCONNECT_V4 => {
+                        Self::parse_network_disconnect(data, AddressFamily::Inet)
+                    }
+                    EVENT_TCP_DISCONNECT_V6 => {
+                        Self::parse_network_disconnect(data, AddressFamily::Inet6)
+                    }
                     _ => None,
                 }
             } else {
@@ -402,7 +698,52 @@ impl ProcessMonitorPlugin {
         }
     }
 
-    fn parse_process_start(data: &[u8]) -> Option<EtwEvent> {
+    /// Find the SID extended data item that `EVENT_ENABLE_PROPERTY_SID` attaches
+    /// to each event and render it as a string SID
+    fn extract_user_sid(
+        record: &windows::Win32::System::Diagnostics::Etw::EVENT_RECORD,
+    ) -> Option<String> {
+        if record.ExtendedData.is_null() {
+            return None;
+        }
+
+        let items = unsafe {
+            std::slice::from_raw_parts(record.ExtendedData, record.ExtendedDataCount as usize)
+        };
+
+        items
+            .iter()
+            .find(|item| item.ExtType as u32 == EVENT_HEADER_EXT_TYPE_SID && item.DataPtr != 0)
+            .and_then(|item| {
+                let sid = unsafe {
+                    std::slice::from_raw_parts(item.DataPtr as *const u8, item.DataSize as usize)
+                };
+                Self::sid_to_string(sid)
+            })
+    }
+
+    /// Convert a binary SID (e.g. `01 01 00..05 12 00 00 00`) to its string form (`S-1-5-18`)
+    fn sid_to_string(sid: &[u8]) -> Option<String> {
+        // Revision, sub-authority count and the 6-byte identifier authority come first
+        if sid.len() < 8 || sid.len() < 8 + 4 * sid[1] as usize {
+            return None;
+        }
+
+        unsafe {
+            let psid = PSID(sid.as_ptr() as *mut _);
+            if !IsValidSid(psid).as_bool() {
+                return None;
+            }
+
+            let mut string_sid = PWSTR::null();
+            ConvertSidToStringSidW(psid, &mut string_sid).ok()?;
+            let result = string_sid.to_string().ok();
+            let _ = LocalFree(HLOCAL(string_sid.0 as *mut _));
+            result
+        }
+    }
+
+    fn parse_process_start(data: &[u8], user_sid: Option<String>) -> Option<EtwEvent> {
         // Process start event layout varies by Windows version
         // Common layout: ProcessId(4), ParentId(4), SessionId(4), ... strings
         if data.len() < 16 {
@@ -422,7 +763,7 @@ impl ProcessMonitorPlugin {
             image_name: image_name.unwrap_or_else(|| format!("PID:{}", pid)),
             command_line: command_line.unwrap_or_default(),
             session_id,
-            user_sid: None, // Would need to parse SID from extended data
+            user_sid,
         })
     }
 
@@ -515,61 +856,49 @@ impl ProcessMonitorPlugin {
         Some(EtwEvent::FileWrite { pid, path, bytes_written: bytes })
     }
 
-    fn parse_network_connect(data: &[u8]) -> Option<EtwEvent> {
-        if data.len() < 32 {
+    /// Read the endpoints of a TCP event. Its payload starts with PID(4),
+    /// size(4), daddr, saddr, dport(2), sport(2); the addresses are 4 or 16
+    /// bytes depending on `family`, and they and the ports are in network
+    /// byte order.
+    fn parse_tcp_endpoints(data: &[u8], family: AddressFamily) -> Option<TcpEndpoints> {
+        let addr_len = family.address_len();
+        let daddr = 8;
+        let saddr = daddr + addr_len;
+        let dport = saddr + addr_len;
+        let sport = dport + 2;
+        if data.len() < sport + 2 {
             return None;
         }
-        
-        let pid = u32::from_le_bytes([data[0], data[1], data[2], data[3]]);
-        let local_port = u16::from_le_bytes([data[8], data[9]]);
-        let remote_port = u16::from_le_bytes([data[10], data[11]]);
-        let protocol = data[12];
-        
-        let local_addr = Self::parse_ip_address(&data[16..32]);
-        let remote_addr = if data.len() >= 48 {
-            Self::parse_ip_address(&data[32..48])
-        } else {
-            "unknown".to_string()
-        };
-        
-        let protocol_enum = match protocol {
-            6 => NetworkProtocol::Tcp,
-            17 => NetworkProtocol::Udp,
-            _ => NetworkProtocol::Other(format!("{}", protocol)),
-        };
-        
+
+        Some(TcpEndpoints {
+            pid: u32::from_le_bytes([data[0], data[1], data[2], data[3]]),
+            local_addr: Self::format_ip_address(&data[saddr..dport], family),
+            local_port: u16::from_be_bytes([data[sport], data[sport + 1]]),
+            remote_addr: Self::format_ip_address(&data[daddr..saddr], family),
+            remote_port: u16::from_be_bytes([data[dport], data[dport + 1]]),
+        })
+    }
+
+    fn parse_network_connect(data: &[u8], family: AddressFamily) -> Option<EtwEvent> {
+        let endpoints = Self::parse_tcp_endpoints(data, family)?;
         Some(EtwEvent::NetworkConnect {
-            pid,
-            local_addr,
-            local_port,
-            remote_addr,
-            remote_port,
-            protocol: protocol_enum,
+            pid: endpoints.pid,
+            local_addr: endpoints.local_addr,
+            local_port: endpoints.local_port,
+            remote_addr: endpoints.remote_addr,
+            remote_port: endpoints.remote_port,
+            protocol: NetworkProtocol::Tcp,
         })
     }
 
-    fn parse_network_disconnect(data: &[u8]) -> Option<EtwEvent> {
-        if data.len() < 32 {
-            return None;
-        }
-        
-        let pid = u32::from_le_bytes([data[0], data[1], data[2], data[3]]);
-        let local_port = u16::from_le_bytes([data[8], data[9]]);
-        let remote_port = u16::from_le_bytes([data[10], data[11]]);
-        
-        let local_addr = Self::parse_ip_address(&data[16..32]);
-        let remote_addr = if data.len() >= 48 {
-            Self::parse_ip_address(&data[32..48])
-        } else {
-            "unknown".to_string()
-        };
-        
+    fn parse_network_disconnect(data: &[u8], family: AddressFamily) -> Option<EtwEvent> {
+        let endpoints = Self::parse_tcp_endpoints(data, family)?;
         Some(EtwEvent::NetworkDisconnect {
-            pid,
-            local_addr,
-            local_port,
-            remote_addr,
-            remote_port,
+            pid: endpoints.pid,
+            local_addr: endpoints.local_addr,
+            local_port: endpoints.local_port,
+            remote_addr: endpoints.remote_addr,
+            remote_port: endpoints.remote_port,
         })
     }
 
@@ -636,27 +965,371 @@ impl ProcessMonitorPlugin {
         }
     }
 
-    fn parse_ip_address(data: &[u8]) -> String {
-        if data.len() >= 16 {
-            // Check if IPv4 mapped to IPv6 (::ffff:x.x.x.x)
-            let is_ipv4_mapped = data[0..10].iter().all(|&b| b == 0) 
-                && data[10] == 0xff && data[11] == 0xff;
-            
-            if is_ipv4_mapped {
-                format!("{}.{}.{}.{}", data[12], data[13], data[14], data[15])
-            } else {
-                // IPv6
-                let segments: Vec<String> = data[..16]
-                    .chunks_exact(2)
-                    .map(|c| format!("{:02x}{:02x}", c[0], c[1]))
-                    .collect();
-                segments.join(":")
+    /// Render an address in network byte order; IPv4-mapped IPv6 addresses
+    /// are shown as plain IPv4
+    fn format_ip_address(data: &[u8], family: AddressFamily) -> String {
+        let formatted = match family {
+            AddressFamily::Inet => {
+                <[u8; 4]>::try_from(data).map(|octets| Ipv4Addr::from(octets).to_string())
             }
-        } else if data.len() >= 4 {
-            format!("{}.{}.{}.{}", data[0], data[1], data[2], data[3])
-        } else {
-            "unknown".to_string()
+            AddressFamily::Inet6 => <[u8; 16]>::try_from(data).map(|octets| {
+                let addr = Ipv6Addr::from(octets);
+                match addr.to_ipv4_mapped() {
+                    Some(v4) => v4.to_string(),
+                    None => addr.to_string(),
+                }
+            }),
+        };
+        formatted.unwrap_or_else(|_| "unknown".to_string())
+    }
+
+    /// Convert a raw ETW event into an engine event, resolving process names by PID
+    fn etw_to_event(etw_event: EtwEvent, plugin_name: &str, names: &mut ProcessNameCache) -> Event {
+        match etw_event {
+            EtwEvent::ProcessStart {
+                pid,
+                parent_pid,
+                image_name,
+                command_line,
+                session_id,
+                user_sid,
+            } => {
+                // A new process may have reused the PID of a cached one
+                names.invalidate(pid);
+
+                Event::new(
+                    EventKind::ProcessStarted {
+                        pid,
+                        parent_pid,
+                        name: image_name.clone(),
+                        path: image_name.clone(),
+                        command_line,
+                        session_id,
+                        user: user_sid.unwrap_or_default(),
+                    },
+                    plugin_name,
+                )
+                .with_metadata("process_name", &image_name)
+                .with_metadata("parent_pid", parent_pid.to_string())
+            }
+            EtwEvent::ProcessStop { pid, exit_code } => {
+                let name = Self::cached_process_name(names, pid);
+                names.invalidate(pid);
+
+                Event::new(
+                    EventKind::ProcessStopped {
+                        pid,
+                        name: name.clone(),
+                        exit_code: Some(exit_code),
+                    },
+                    plugin_name,
+                )
+                .with_metadata("process_name", &name)
+                .with_metadata("exit_code", exit_code.to_string())
+            }
+            EtwEvent::ThreadStart {
+                pid,
+                tid,
+                start_address,
+            } => {
+                let name = Self::cached_process_name(names, pid);
+
+                Event::new(
+                    EventKind::ThreadCreated {
+                        pid,
+                        tid,
+                        start_address,
+                        user_stack: None,
+                    },
+                    plugin_name,
+                )
+                .with_metadata("process_name", &name)
+            }
+            EtwEvent::ThreadStop { pid, tid } => {
+                let name = Self::cached_process_name(names, pid);
+
+                Event::new(EventKind::ThreadDestroyed { pid, tid }, plugin_name)
+                    .with_metadata("process_name", &name)
+            }
+            EtwEvent::FileCreate { pid, path } => {
+                let name = Self::cached_process_name(names, pid);
+
+                Event::new(
+                    EventKind::FileAccessed {
+                        pid,
+                        path: path.clone(),
+                        access_mask: 0,
+                    },
+                    plugin_name,
+                )
+                .with_metadata("process_name", &name)
+                .with_metadata("operation", "create")
+            }
+            EtwEvent::FileDelete { pid, path } => {
+                let name = Self::cached_process_name(names, pid);
+
+                Event::new(
+                    EventKind::FileIoDelete {
+                        pid,
+                        path: path.clone(),
+                    },
+                    plugin_name,
+                )
+                .with_metadata("process_name", &name)
+            }
+            EtwEvent::FileRead {
+                pid,
+                path,
+                bytes_read,
+            } => {
+                let name = Self::cached_process_name(names, pid);
+
+                Event::new(
+                    EventKind::FileIoRead {
+                        pid,
+                        path: path.clone(),
+                        bytes_read,
+                    },
+                    plugin_name,
+                )
+                .with_metadata("process_name", &name)
+                .with_metadata("bytes", bytes_read.to_string())
+            }
+            EtwEvent::FileWrite {
+                pid,
+                path,
+                bytes_written,
+            } => {
+                let name = Self::cached_process_name(names, pid);
+
+                Event::new(
+                    EventKind::FileIoWrite {
+                        pid,
+                        path: path.clone(),
+                        bytes_written,
+                    },
+                    plugin_name,
+                )
+                .with_metadata("process_name", &name)
+                .with_metadata("bytes", bytes_written.to_string())
+            }
+            EtwEvent::NetworkConnect {
+                pid,
+                local_addr,
+                local_port,
+                remote_addr,
+                remote_port,
+                protocol,
+            } => {
+                let name = Self::cached_process_name(names, pid);
+
+                Event::new(
+                    EventKind::NetworkConnectionCreated {
+                        pid,
+                        local_addr: local_addr.clone(),
+                        local_port,
+                        remote_addr: remote_addr.clone(),
+                        remote_port,
+                        protocol: protocol.clone(),
+                    },
+                    plugin_name,
+                )
+                .with_metadata("process_name", &name)
+                .with_metadata("protocol", format!("{:?}", protocol))
+            }
+            EtwEvent::NetworkDisconnect {
+                pid,
+                local_addr,
+                local_port,
+                remote_addr,
+                remote_port,
+            } => {
+                let name = Self::cached_process_name(names, pid);
+
+                Event::new(
+                    EventKind::NetworkConnectionClosed {
+                        pid,
+                        local_addr: local_addr.clone(),
+                        local_port,
+                        remote_addr: remote_addr.clone(),
+                        remote_port,
+                    },
+                    plugin_name,
+                )
+                .with_metadata("process_name", &name)
+            }
+        }
+    }
+
+    /// Take a snapshot of all running processes keyed by PID
+    fn snapshot_processes() -> Result<ProcessSnapshot, String> {
+        let mut processes = HashMap::new();
+
+        unsafe {
+            let snapshot = CreateToolhelp32Snapshot(TH32CS_SNAPPROCESS, 0)
+                .map_err(|e| format!("CreateToolhelp32Snapshot failed: {}", e))?;
+
+            let mut entry = PROCESSENTRY32W {
+                dwSize: std::mem::size_of::<PROCESSENTRY32W>() as u32,
+                ..Default::default()
+            };
+
+            let mut next = Process32FirstW(snapshot, &mut entry);
+            while next.is_ok() {
+                let len = entry
+                    .szExeFile
+                    .iter()
+                    .position(|&c| c == 0)
+                    .unwrap_or(entry.szExeFile.len());
+                processes.insert(
+                    entry.th32ProcessID,
+                    PolledProcess {
+                        pid: entry.th32ProcessID,
+                        parent_pid: entry.th32ParentProcessID,
+                        name: String::from_utf16_lossy(&entry.szExeFile[..len]),
+                    },
+                );
+                next = Process32NextW(snapshot, &mut entry);
+            }
+
+            let _ = CloseHandle(snapshot);
         }
+
+        Ok(processes)
+    }
+
+    /// Compare two snapshots and return the processes that started and stopped
+    /// in between. A PID reused by a different executable counts as both.
+    fn diff_snapshots(
+        previous: &ProcessSnapshot,
+        current: &ProcessSnapshot,
+    ) -> (Vec<PolledProcess>, Vec<PolledProcess>) {
+        let changed = |from: &ProcessSnapshot, to: &ProcessSnapshot| {
+            let mut changed: Vec<PolledProcess> = from
+                .values()
+                .filter(|process| to.get(&process.pid).map(|p| &p.name) != Some(&process.name))
+                .cloned()
+                .collect();
+            changed.sort_by_key(|process| process.pid);
+            changed
+        };
+
+        (changed(current, previous), changed(previous, current))
+    }
+
+    /// Fallback when ETW can't be used: diff Toolhelp snapshots every `poll_interval`
+    fn start_polling(&mut self, emitter: EventEmitter, filters: EventFilters) {
+        let is_running = self.is_running.clone();
+        let plugin_name = self.name.clone();
+        let poll_interval = self.poll_interval;
+
+        self.is_running.store(true, Ordering::SeqCst);
+
+        self.poll_task = Some(tokio::spawn(async move {
+            info!(
+                "Process monitoring active (polling every {:?})",
+                poll_interval
+            );
+
+            let mut known = Self::snapshot_processes().unwrap_or_else(|e| {
+                error!("Failed to snapshot processes: {}", e);
+                HashMap::new()
+            });
+
+            while is_running.load(Ordering::SeqCst) {
+                tokio::time::sleep(poll_interval).await;
+
+                let current = match Self::snapshot_processes() {
+                    Ok(current) => current,
+                    Err(e) => {
+                        error!("Failed to snapshot processes: {}", e);
+                        continue;
+                    }
+                };
+
+                let (started, stopped) = Self::diff_snapshots(&known, &current);
+                let started = started.into_iter().map(|process| {
+                    Event::new(
+                        EventKind::ProcessStarted {
+                            pid: process.pid,
+                            parent_pid: process.parent_pid,
+                            name: process.name.clone(),
+                            path: process.name.clone(),
+                            command_line: String::new(),
+                            session_id: 0,
+                            user: String::new(),
+                        },
+                        &plugin_name,
+                    )
+                    .with_metadata("process_name", &process.name)
+                    .with_metadata("parent_pid", process.parent_pid.to_string())
+                });
+                let stopped = stopped.into_iter().map(|process| {
+                    Event::new(
+                        EventKind::ProcessStopped {
+                            pid: process.pid,
+                            name: process.name.clone(),
+                            exit_code: None,
+                        },
+                        &plugin_name,
+                    )
+                    .with_metadata("process_name", &process.name)
+                });
+
+                for event in started.chain(stopped) {
+                    if !filters.allows(&event) {
+                        continue;
+                    }
+                    if emitter.emit(event) == EmitOutcome::Closed {
+                        is_running.store(false, Ordering::SeqCst);
+                        break;
+                    }
+                }
+
+                known = current;
+            }
+
+            info!("Process polling stopped");
+        }));
+    }
+
+    /// Decide whether an event from `process_name` passes the name filter.
+    /// `process_name` may be a bare file name or a full image path.
+    fn matches_name_filter(filter: Option<&glob::Pattern>, process_name: &str) -> bool {
+        let Some(pattern) = filter else {
+            return true;
+        };
+
+        let options = glob::MatchOptions {
+            case_sensitive: false,
+            ..Default::default()
+        };
+        let file_name = process_name
+            .rsplit(['\\', '/'])
+            .next()
+            .unwrap_or(process_name);
+        let stem = file_name
+            .len()
+            .checked_sub(4)
+            .filter(|&i| {
+                file_name
+                    .get(i..)
+                    .is_some_and(|ext| ext.eq_ignore_ascii_case(".exe"))
+            })
+            .map_or(file_name, |i| &file_name[..i]);
+
+        pattern.matches_with(file_name, options) || pattern.matches_with(stem, options)
+    }
+
+    /// Decide whether a process command line passes the command-line filter
+    fn matches_command_line_filter(filter: Option<&Regex>, command_line: &str) -> bool {
+        filter.is_none_or(|regex| regex.is_match(command_line))
+    }
+
+    fn cached_process_name(names: &mut ProcessNameCache, pid: u32) -> String {
+        names.get_or_resolve(pid, std::time::Instant::now(), |pid| {
+            Self::get_process_name_from_pid(pid).unwrap_or_else(|| format!("PID:{}", pid))
+        })
     }
 
     fn get_process_name_from_pid(pid: u32) -> Option<String> {
@@ -725,13 +1398,38 @@ impl EventSourcePlugin for ProcessMonitorPlugin {
         info!("Starting ETW process monitor plugin: {}", self.name);
         info!("Session name: {}", self.session_name);
 
+        let filters = EventFilters {
+            name: self
+                .filter_name
+                .as_deref()
+                .map(glob::Pattern::new)
+                .transpose()
+                .map_err(|e| {
+                    PluginError::Configuration(format!("Invalid process name filter: {}", e))
+                })?,
+            command_line: self
+                .command_line_filter
+                .as_deref()
+                .map(Regex::new)
+                .transpose()
+                .map_err(|e| {
+                    PluginError::Configuration(format!("Invalid command line filter: {}", e))
+                })?,
+        };
+
+        if self.prefer_polling {
+            filters.check_pollable()?;
+            self.start_polling(emitter, filters);
+            return Ok(());
+        }
+
         let session_name = self.session_name.clone();
         let is_running = self.is_running.clone();
-        let filter_name = self.filter_name.clone();
         let plugin_name = self.name.clone();
         let monitor_threads = self.monitor_threads;
         let monitor_files = self.monitor_files;
         let monitor_network = self.monitor_network;
+        let io_coalesce = self.io_coalesce;
 
         // Create tokio channel for async communication
         let (tokio_sender, mut tokio_receiver) = tokio::sync::mpsc::channel(1000);
@@ -747,7 +1445,6 @@ impl EventSourcePlugin for ProcessMonitorPlugin {
                 session_name,
                 std_sender,
                 is_running_clone,
-                filter_name,
                 monitor_threads,
                 monitor_files,
                 monitor_network,
@@ -773,12 +1470,15 @@ impl EventSourcePlugin for ProcessMonitorPlugin {
 
         // Check if thread is still running
         if self.etw_thread.as_ref().map(|t| t.is_finished()).unwrap_or(true) {
-            self.is_running.store(false, Ordering::SeqCst);
             self.etw_thread = None;
             self.event_sender = None;
-            return Err(PluginError::Initialization(
-                "Failed to start ETW monitoring. Administrator privileges are required for ETW.".to_string()
-            ));
+            warn!(
+                "ETW monitoring unavailable (administrator privileges are required), \
+                 falling back to polling for process start/stop"
+            );
+            filters.check_pollable()?;
+            self.start_polling(emitter, filters);
+            return Ok(());
         }
 
         self.is_running.store(true, Ordering::SeqCst);
@@ -790,9 +1490,24 @@ impl EventSourcePlugin for ProcessMonitorPlugin {
             let mut event_count = 0u64;
             let start_time = std::time::Instant::now();
 
+            let mut coalescer = io_coalesce.map(IoCoalescer::new);
+            let mut names = ProcessNameCache::default();
+
             while is_running.load(Ordering::SeqCst) {
-                match tokio_receiver.recv().await {
-                    Some(etw_event) => {
+                // With coalescing on, wake up in time to flush the oldest pending I/O
+                let flush_at = coalescer.as_ref().and_then(IoCoalescer::next_flush);
+                let received = match flush_at {
+                    Some(deadline) => {
+                        tokio::time::timeout_at(deadline.into(), tokio_receiver.recv())
+                            .await
+                            .ok()
+                    }
+                    None => Some(tokio_receiver.recv().await),
+                };
+
+                let mut ready = Vec::new();
+                match received {
+                    Some(Some(etw_event)) => {
                         event_count += 1;
                         
                         // Log stats every 100 events
@@ -802,162 +1517,34 @@ impl EventSourcePlugin for ProcessMonitorPlugin {
                             info!("Processed {} ETW events ({:.1} events/sec)", event_count, rate);
                         }
 
-                        let event = match etw_event {
-                            EtwEvent::ProcessStart { pid, parent_pid, image_name, command_line, session_id, user_sid } => {
-                                Event::new(
-                                    EventKind::ProcessStarted {
-                                        pid,
-                                        parent_pid,
-                                        name: image_name.clone(),
-                                        path: image_name.clone(),
-                                        command_line,
-                                        session_id,
-                                        user: user_sid.unwrap_or_default(),
-                                    },
-                                    &plugin_name,
-                                )
-                                .with_metadata("process_name", &image_name)
-                                .with_metadata("parent_pid", parent_pid.to_string())
+                        match coalescer.as_mut() {
+                            Some(coalescer) => {
+                                ready.extend(coalescer.add(etw_event, std::time::Instant::now()))
                             }
-                            EtwEvent::ProcessStop { pid, exit_code } => {
-                                let name = Self::get_process_name_from_pid(pid)
-                                    .unwrap_or_else(|| format!("PID:{}", pid));
-
-                                Event::new(
-                                    EventKind::ProcessStopped {
-                                        pid,
-                                        name: name.clone(),
-                                        exit_code: Some(exit_code),
-                                    },
-                                    &plugin_name,
-                                )
-                                .with_metadata("process_name", &name)
-                                .with_metadata("exit_code", exit_code.to_string())
-                            }
-                            EtwEvent::ThreadStart { pid, tid, start_address } => {
-                                let name = Self::get_process_name_from_pid(pid)
-                                    .unwrap_or_else(|| format!("PID:{}", pid));
-
-                                Event::new(
-                                    EventKind::ThreadCreated {
-                                        pid,
-                                        tid,
-                                        start_address,
-                                        user_stack: None,
-                                    },
-                                    &plugin_name,
-                                )
-                                .with_metadata("process_name", &name)
-                            }
-                            EtwEvent::ThreadStop { pid, tid } => {
-                                let name = Self::get_process_name_from_pid(pid)
-                                    .unwrap_or_else(|| format!("PID:{}", pid));
-
-                                Event::new(
-                                    EventKind::ThreadDestroyed { pid, tid },
-                                    &plugin_name,
-                                )
-                                .with_metadata("process_name", &name)
-                            }
-                            EtwEvent::FileCreate { pid, path } => {
-                                let name = Self::get_process_name_from_pid(pid)
-                                    .unwrap_or_else(|| format!("PID:{}", pid));
-
-                                Event::new(
-                                    EventKind::FileAccessed {
-                                        pid,
-                                        path: path.clone(),
-                                        access_mask: 0,
-                                    },
-                                    &plugin_name,
-                                )
-                                .with_metadata("process_name", &name)
-                                .with_metadata("operation", "create")
-                            }
-                            EtwEvent::FileDelete { pid, path } => {
-                                let name = Self::get_process_name_from_pid(pid)
-                                    .unwrap_or_else(|| format!("PID:{}", pid));
-
-                                Event::new(
-                                    EventKind::FileIoDelete { pid, path: path.clone() },
-                                    &plugin_name,
-                                )
-                                .with_metadata("process_name", &name)
-                            }
-                            EtwEvent::FileRead { pid, path, bytes_read } => {
-                                let name = Self::get_process_name_from_pid(pid)
-                                    .unwrap_or_else(|| format!("PID:{}", pid));
-
-                                Event::new(
-                                    EventKind::FileIoRead {
-                                        pid,
-                                        path: path.clone(),
-                                        bytes_read,
-                                    },
-                                    &plugin_name,
-                                )
-                                .with_metadata("process_name", &name)
-                                .with_metadata("bytes", bytes_read.to_string())
-                            }
-                            EtwEvent::FileWrite { pid, path, bytes_written } => {
-                                let name = Self::get_process_name_from_pid(pid)
-                                    .unwrap_or_else(|| format!("PID:{}", pid));
-
-                                Event::new(
-                                    EventKind::FileIoWrite {
-                                        pid,
-                                        path: path.clone(),
-                                        bytes_written,
-                                    },
-                                    &plugin_name,
-                                )
-                                .with_metadata("process_name", &name)
-                                .with_metadata("bytes", bytes_written.to_string())
-                            }
-                            EtwEvent::NetworkConnect { pid, local_addr, local_port, remote_addr, remote_port, protocol } => {
-                                let name = Self::get_process_name_from_pid(pid)
-                                    .unwrap_or_else(|| format!("PID:{}", pid));
-
-                                Event::new(
-                                    EventKind::NetworkConnectionCreated {
-                                        pid,
-                                        local_addr: local_addr.clone(),
-                                        local_port,
-                                        remote_addr: remote_addr.clone(),
-                                        remote_port,
-                                        protocol: protocol.clone(),
-                                    },
-                                    &plugin_name,
-                                )
-                                .with_metadata("process_name", &name)
-                                .with_metadata("protocol", format!("{:?}", protocol))
-                            }
-                            EtwEvent::NetworkDisconnect { pid, local_addr, local_port, remote_addr, remote_port } => {
-                                let name = Self::get_process_name_from_pid(pid)
-                                    .unwrap_or_else(|| format!("PID:{}", pid));
-
-                                Event::new(
-                                    EventKind::NetworkConnectionClosed {
-                                        pid,
-                                        local_addr: local_addr.clone(),
-                                        local_port,
-                                        remote_addr: remote_addr.clone(),
-                                        remote_port,
-                                    },
-                                    &plugin_name,
-                                )
-                                .with_metadata("process_name", &name)
-                            }
-                        };
-
-                        if let Err(e) = emitter.try_send(event) {
-                            error!("Failed to send event: {}", e);
+                            None => ready.push(etw_event),
                         }
                     }
-                    None => {
+                    Some(None) => {
                         // Channel closed
                         break;
                     }
+                    None => {}
+                }
+
+                if let Some(coalescer) = coalescer.as_mut() {
+                    ready.extend(coalescer.drain_expired(std::time::Instant::now()));
+                }
+
+                for etw_event in ready {
+                    let event = Self::etw_to_event(etw_event, &plugin_name, &mut names);
+                    if !filters.allows(&event) {
+                        continue;
+                    }
+
+                    if emitter.emit(event) == EmitOutcome::Closed {
+                        is_running.store(false, Ordering::SeqCst);
+                        break;
+                    }
                 }
             }
 
@@ -982,6 +1569,8 @@ impl EventSourcePlugin for ProcessMonitorPlugin {
         if let Some(thread) = self.etw_thread.take() {
             let _ = thread.join();
         }
+        // The polling loop notices `is_running` on its next pass
+        self.poll_task = None;
         
         info!("ETW process monitor stopped");
         Ok(())
@@ -990,6 +1579,27 @@ impl EventSourcePlugin for ProcessMonitorPlugin {
     fn is_running(&self) -> bool {
         self.is_running.load(Ordering::SeqCst)
     }
+
+    fn health(&self) -> PluginHealth {
+        if !self.is_running() {
+            return PluginHealth::Stopped;
+        }
+        if self
+            .etw_thread
+            .as_ref()
+            .is_some_and(|thread| thread.is_finished())
+        {
+            PluginHealth::Failed("ETW session ended".to_string())
+        } else if self
+            .poll_task
+            .as_ref()
+            .is_some_and(|task| task.is_finished())
+        {
+            PluginHealth::Failed("Process polling ended".to_string())
+        } else {
+            PluginHealth::Healthy
+        }
+    }
 }
 
 #[cfg(test)]
@@ -1006,7 +1616,7 @@ mod tests {
         assert!(!plugin.is_running());
 
         // Note: This test requires admin privileges
-        let result = plugin.start(tx).await;
+        let result = plugin.start(tx.into()).await;
         
         if result.is_ok() {
             assert!(plugin.is_running());
@@ -1020,15 +1630,110 @@ mod tests {
 
     #[test]
     fn test_ip_address_parsing() {
-        // IPv4
-        let ipv4_mapped = vec![0u8, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0xff, 0xff, 192, 168, 1, 1];
-        assert_eq!(ProcessMonitorPlugin::parse_ip_address(&ipv4_mapped), "192.168.1.1");
-        
-        // IPv6
-        let ipv6 = vec![0x20u8, 0x01, 0x0d, 0xb8, 0x00, 0x00, 0x00, 0x00, 
-                        0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x01];
-        let result = ProcessMonitorPlugin::parse_ip_address(&ipv6);
-        assert!(result.contains(":"));
+        let ipv4 = [192u8, 168, 1, 1];
+        assert_eq!(
+            ProcessMonitorPlugin::format_ip_address(&ipv4, AddressFamily::Inet),
+            "192.168.1.1"
+        );
+
+        let ipv4_mapped = [0u8, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0xff, 0xff, 192, 168, 1, 1];
+        assert_eq!(
+            ProcessMonitorPlugin::format_ip_address(&ipv4_mapped, AddressFamily::Inet6),
+            "192.168.1.1"
+        );
+
+        let ipv6 = [
+            0x20u8, 0x01, 0x0d, 0xb8, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+            0x00, 0x01,
+        ];
+        assert_eq!(
+            ProcessMonitorPlugin::format_ip_address(&ipv6, AddressFamily::Inet6),
+            "2001:db8::1"
+        );
+
+        // An address of the wrong size for its family
+        assert_eq!(
+            ProcessMonitorPlugin::format_ip_address(&ipv4, AddressFamily::Inet6),
+            "unknown"
+        );
+    }
+
+    /// A TCP event payload: PID, size, daddr, saddr, dport, sport, then the
+    /// rest of the template (mss, window sizes, ...) which isn't read
+    fn tcp_record(pid: u32, daddr: &[u8], saddr: &[u8], dport: u16, sport: u16) -> Vec<u8> {
+        let mut data = Vec::new();
+        data.extend_from_slice(&pid.to_le_bytes());
+        data.extend_from_slice(&512u32.to_le_bytes());
+        data.extend_from_slice(daddr);
+        data.extend_from_slice(saddr);
+        data.extend_from_slice(&dport.to_be_bytes());
+        data.extend_from_slice(&sport.to_be_bytes());
+        data.extend_from_slice(&[0u8; 24]);
+        data
+    }
+
+    #[test]
+    fn test_parse_ipv4_connect() {
+        let data = tcp_record(4321, &[93, 184, 216, 34], &[10, 0, 0, 5], 443, 50123);
+
+        let endpoints =
+            ProcessMonitorPlugin::parse_tcp_endpoints(&data, AddressFamily::Inet).unwrap();
+        assert_eq!(
+            endpoints,
+            TcpEndpoints {
+                pid: 4321,
+                local_addr: "10.0.0.5".to_string(),
+                local_port: 50123,
+                remote_addr: "93.184.216.34".to_string(),
+                remote_port: 443,
+            }
+        );
+
+        match ProcessMonitorPlugin::parse_network_connect(&data, AddressFamily::Inet) {
+            Some(EtwEvent::NetworkConnect { protocol, .. }) => {
+                assert_eq!(protocol, NetworkProtocol::Tcp)
+            }
+            other => panic!("Expected a connect event, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_parse_ipv6_connect() {
+        let remote: [u8; 16] = "2606:4700::1111".parse::<Ipv6Addr>().unwrap().octets();
+        let local: [u8; 16] = "fe80::1c2d:3e4f".parse::<Ipv6Addr>().unwrap().octets();
+        let data = tcp_record(4321, &remote, &local, 8443, 61000);
+
+        let endpoints =
+            ProcessMonitorPlugin::parse_tcp_endpoints(&data, AddressFamily::Inet6).unwrap();
+        assert_eq!(
+            endpoints,
+            TcpEndpoints {
+                pid: 4321,
+                local_addr: "fe80::1c2d:3e4f".to_string(),
+                local_port: 61000,
+                remote_addr: "2606:4700::1111".to_string(),
+                remote_port: 8443,
+            }
+        );
+
+        // Read with the IPv4 layout the same bytes give different endpoints,
+        // which is why the family has to come from the event id
+        let as_ipv4 =
+            ProcessMonitorPlugin::parse_tcp_endpoints(&data, AddressFamily::Inet).unwrap();
+        assert_ne!(as_ipv4.remote_addr, endpoints.remote_addr);
+
+        match ProcessMonitorPlugin::parse_network_disconnect(&data, AddressFamily::Inet6) {
+            Some(EtwEvent::NetworkDisconnect { remote_port, .. }) => assert_eq!(remote_port, 8443),
+            other => panic!("Expected a disconnect event, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_parse_truncated_tcp_record() {
+        // Long enough for the IPv4 layout but not the IPv6 one
+        let data = tcp_record(1, &[127, 0, 0, 1], &[127, 0, 0, 1], 80, 1234)[..20].to_vec();
+        assert!(ProcessMonitorPlugin::parse_tcp_endpoints(&data, AddressFamily::Inet).is_some());
+        assert!(ProcessMonitorPlugin::parse_tcp_endpoints(&data, AddressFamily::Inet6).is_none());
     }
 
     #[test]
@@ -1055,13 +1760,372 @@ mod tests {
     fn test_builder_methods() {
         let plugin = ProcessMonitorPlugin::new("test")
             .with_name_filter("chrome")
+            .with_command_line_filter("--incognito")
             .with_thread_monitoring(true)
             .with_file_monitoring(true)
-            .with_network_monitoring(true);
-
+            .with_network_monitoring(true)
+            .with_poll_interval(Duration::from_secs(5))
+            .with_prefer_polling(true)
+            .with_io_coalesce(Duration::from_millis(500));
+
+        assert_eq!(plugin.io_coalesce, Some(Duration::from_millis(500)));
+        assert_eq!(plugin.poll_interval, Duration::from_secs(5));
+        assert!(plugin.prefer_polling);
         assert!(plugin.monitor_threads);
         assert!(plugin.monitor_files);
         assert!(plugin.monitor_network);
         assert_eq!(plugin.filter_name, Some("chrome".to_string()));
+        assert_eq!(plugin.command_line_filter, Some("--incognito".to_string()));
+
+        // A zero interval would busy-loop
+        let zero = ProcessMonitorPlugin::new("test").with_poll_interval(Duration::ZERO);
+        assert_eq!(zero.poll_interval, MIN_POLL_INTERVAL);
+    }
+
+    #[tokio::test]
+    async fn test_polling_health_reports_dead_loop() {
+        let (tx, _rx) = tokio::sync::mpsc::channel(100);
+        let mut plugin = ProcessMonitorPlugin::new("test_polling_health").with_prefer_polling(true);
+
+        plugin
+            .start(tx.into())
+            .await
+            .expect("Failed to start plugin");
+        assert_eq!(plugin.health(), PluginHealth::Healthy);
+
+        // Stands in for the loop panicking while the plugin still claims to run
+        plugin.poll_task.as_ref().unwrap().abort();
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        assert!(matches!(plugin.health(), PluginHealth::Failed(_)));
+
+        plugin.stop().await.expect("Failed to stop plugin");
+        assert_eq!(plugin.health(), PluginHealth::Stopped);
+    }
+
+    fn polled(entries: &[(u32, &str)]) -> ProcessSnapshot {
+        entries
+            .iter()
+            .map(|(pid, name)| {
+                (
+                    *pid,
+                    PolledProcess {
+                        pid: *pid,
+                        parent_pid: 4,
+                        name: name.to_string(),
+                    },
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_snapshot_diff() {
+        let previous = polled(&[
+            (100, "explorer.exe"),
+            (200, "notepad.exe"),
+            (300, "chrome.exe"),
+        ]);
+        let current = polled(&[
+            (100, "explorer.exe"),
+            (300, "chrome.exe"),
+            (400, "calc.exe"),
+        ]);
+
+        let (started, stopped) = ProcessMonitorPlugin::diff_snapshots(&previous, &current);
+        assert_eq!(
+            started,
+            vec![PolledProcess {
+                pid: 400,
+                parent_pid: 4,
+                name: "calc.exe".to_string()
+            }]
+        );
+        assert_eq!(
+            stopped,
+            vec![PolledProcess {
+                pid: 200,
+                parent_pid: 4,
+                name: "notepad.exe".to_string()
+            }]
+        );
+
+        let (started, stopped) = ProcessMonitorPlugin::diff_snapshots(&current, &current);
+        assert!(started.is_empty());
+        assert!(stopped.is_empty());
+    }
+
+    #[test]
+    fn test_snapshot_diff_reused_pid() {
+        let previous = polled(&[(500, "setup.exe")]);
+        let current = polled(&[(500, "game.exe")]);
+
+        let (started, stopped) = ProcessMonitorPlugin::diff_snapshots(&previous, &current);
+        assert_eq!(started.len(), 1);
+        assert_eq!(started[0].name, "game.exe");
+        assert_eq!(stopped.len(), 1);
+        assert_eq!(stopped[0].name, "setup.exe");
+    }
+
+    #[test]
+    fn test_io_coalescer_sums_bytes_per_pid_and_path() {
+        let start = std::time::Instant::now();
+        let mut coalescer = IoCoalescer::new(Duration::from_millis(500));
+        let read = |pid, path: &str, bytes_read| EtwEvent::FileRead {
+            pid,
+            path: PathBuf::from(path),
+            bytes_read,
+        };
+
+        assert!(coalescer.add(read(10, "C:\\a.log", 100), start).is_none());
+        assert!(
+            coalescer
+                .add(
+                    read(10, "C:\\a.log", 50),
+                    start + Duration::from_millis(100)
+                )
+                .is_none()
+        );
+        assert!(
+            coalescer
+                .add(read(10, "C:\\b.log", 7), start + Duration::from_millis(200))
+                .is_none()
+        );
+        assert!(
+            coalescer
+                .add(read(11, "C:\\a.log", 1), start + Duration::from_millis(200))
+                .is_none()
+        );
+        assert!(
+            coalescer
+                .add(
+                    EtwEvent::FileWrite {
+                        pid: 10,
+                        path: PathBuf::from("C:\\a.log"),
+                        bytes_written: 9
+                    },
+                    start
+                )
+                .is_none()
+        );
+        assert!(matches!(
+            coalescer.add(
+                EtwEvent::ProcessStop {
+                    pid: 10,
+                    exit_code: 0
+                },
+                start
+            ),
+            Some(EtwEvent::ProcessStop { .. })
+        ));
+
+        assert!(
+            coalescer
+                .drain_expired(start + Duration::from_millis(400))
+                .is_empty()
+        );
+        assert_eq!(
+            coalescer.next_flush(),
+            Some(start + Duration::from_millis(500))
+        );
+
+        let flushed = coalescer.drain_expired(start + Duration::from_millis(500));
+        assert_eq!(flushed.len(), 2);
+        assert!(flushed.iter().any(|e| matches!(
+            e,
+            EtwEvent::FileRead {
+                pid: 10,
+                bytes_read: 150,
+                ..
+            }
+        )));
+        assert!(flushed.iter().any(|e| matches!(
+            e,
+            EtwEvent::FileWrite {
+                pid: 10,
+                bytes_written: 9,
+                ..
+            }
+        )));
+
+        let flushed = coalescer.drain_expired(start + Duration::from_millis(700));
+        assert_eq!(flushed.len(), 2);
+        assert!(flushed.iter().any(|e| matches!(e, EtwEvent::FileRead { pid: 10, bytes_read: 7, path } if path == &PathBuf::from("C:\\b.log"))));
+        assert!(flushed.iter().any(|e| matches!(
+            e,
+            EtwEvent::FileRead {
+                pid: 11,
+                bytes_read: 1,
+                ..
+            }
+        )));
+        assert_eq!(coalescer.next_flush(), None);
+    }
+
+    #[test]
+    fn test_sid_to_string() {
+        // S-1-5-18 (LocalSystem): revision 1, one sub-authority, NT authority 5, RID 18
+        let local_system = [1u8, 1, 0, 0, 0, 0, 0, 5, 18, 0, 0, 0];
+        assert_eq!(
+            ProcessMonitorPlugin::sid_to_string(&local_system),
+            Some("S-1-5-18".to_string())
+        );
+
+        // S-1-5-21-1-2-3-1001: a domain/local user account
+        let mut user = vec![1u8, 5, 0, 0, 0, 0, 0, 5];
+        for sub_authority in [21u32, 1, 2, 3, 1001] {
+            user.extend_from_slice(&sub_authority.to_le_bytes());
+        }
+        assert_eq!(
+            ProcessMonitorPlugin::sid_to_string(&user),
+            Some("S-1-5-21-1-2-3-1001".to_string())
+        );
+
+        // Truncated: claims two sub-authorities but only carries one
+        let truncated = [1u8, 2, 0, 0, 0, 0, 0, 5, 18, 0, 0, 0];
+        assert_eq!(ProcessMonitorPlugin::sid_to_string(&truncated), None);
+    }
+
+    #[test]
+    fn test_session_name_is_stable_across_instances() {
+        let first = ProcessMonitorPlugin::new("Process Monitor-1");
+        let second = ProcessMonitorPlugin::new("Process Monitor-1");
+        assert_eq!(first.session_name, "wee_process_monitor_1");
+        assert_eq!(first.session_name, second.session_name);
+        assert_ne!(
+            first.session_name,
+            ProcessMonitorPlugin::new("other").session_name
+        );
+    }
+
+    #[test]
+    fn test_session_start_retry_decision() {
+        let already_exists = windows::core::Error::from(ERROR_ALREADY_EXISTS.to_hresult());
+        assert!(ProcessMonitorPlugin::should_retry_session_start(
+            &already_exists,
+            0
+        ));
+        assert!(!ProcessMonitorPlugin::should_retry_session_start(
+            &already_exists,
+            1
+        ));
+
+        let access_denied = windows::core::Error::from(
+            windows::Win32::Foundation::ERROR_ACCESS_DENIED.to_hresult(),
+        );
+        assert!(!ProcessMonitorPlugin::should_retry_session_start(
+            &access_denied,
+            0
+        ));
+    }
+
+    #[test]
+    fn test_command_line_filter_decision() {
+        let encoded =
+            Regex::new(r"(?i)powershell(\.exe)?\s.*-e(nc(odedcommand)?)?\s+[A-Za-z0-9+/=]{16,}")
+                .unwrap();
+        assert!(ProcessMonitorPlugin::matches_command_line_filter(
+            Some(&encoded),
+            "powershell.exe -NoProfile -enc SQBFAFgAIAAoAE4AZQB3AC0ATwBiAGoAZQBjAHQAKQA="
+        ));
+        assert!(ProcessMonitorPlugin::matches_command_line_filter(
+            Some(&encoded),
+            "PowerShell -EncodedCommand ZQBjAGgAbwAgAGgAaQA="
+        ));
+        assert!(!ProcessMonitorPlugin::matches_command_line_filter(
+            Some(&encoded),
+            "powershell.exe -File C:\\scripts\\backup.ps1"
+        ));
+        assert!(!ProcessMonitorPlugin::matches_command_line_filter(
+            Some(&encoded),
+            ""
+        ));
+        assert!(ProcessMonitorPlugin::matches_command_line_filter(
+            None, "anything"
+        ));
+    }
+
+    #[test]
+    fn test_event_filters_only_check_command_line_of_process_starts() {
+        let filters = EventFilters {
+            name: None,
+            command_line: Some(Regex::new("-enc").unwrap()),
+        };
+        let started = |command_line: &str| {
+            Event::new(
+                EventKind::ProcessStarted {
+                    pid: 1,
+                    parent_pid: 0,
+                    name: "powershell.exe".to_string(),
+                    path: "powershell.exe".to_string(),
+                    command_line: command_line.to_string(),
+                    session_id: 1,
+                    user: String::new(),
+                },
+                "test",
+            )
+        };
+
+        assert!(filters.allows(&started("powershell -enc AAAA")));
+        assert!(!filters.allows(&started("powershell -File x.ps1")));
+        assert!(filters.allows(&Event::new(
+            EventKind::ProcessStopped {
+                pid: 1,
+                name: "powershell.exe".to_string(),
+                exit_code: Some(0)
+            },
+            "test"
+        )));
+
+        // Polled starts have no command line, so polling is refused rather
+        // than letting every start through
+        assert!(matches!(
+            filters.check_pollable(),
+            Err(PluginError::Configuration(_))
+        ));
+        let name_only = EventFilters {
+            name: None,
+            command_line: None,
+        };
+        assert!(name_only.check_pollable().is_ok());
+    }
+
+    #[test]
+    fn test_name_filter_decision() {
+        let chrome = glob::Pattern::new("chrome").unwrap();
+        assert!(ProcessMonitorPlugin::matches_name_filter(
+            Some(&chrome),
+            "chrome.exe"
+        ));
+        assert!(ProcessMonitorPlugin::matches_name_filter(
+            Some(&chrome),
+            "CHROME.EXE"
+        ));
+        assert!(ProcessMonitorPlugin::matches_name_filter(
+            Some(&chrome),
+            r"\Device\HarddiskVolume3\Program Files\Google\Chrome\chrome.exe"
+        ));
+        assert!(!ProcessMonitorPlugin::matches_name_filter(
+            Some(&chrome),
+            "notepad.exe"
+        ));
+        assert!(!ProcessMonitorPlugin::matches_name_filter(
+            Some(&chrome),
+            "PID:42"
+        ));
+
+        let glob = glob::Pattern::new("note*.exe").unwrap();
+        assert!(ProcessMonitorPlugin::matches_name_filter(
+            Some(&glob),
+            "notepad.exe"
+        ));
+        assert!(!ProcessMonitorPlugin::matches_name_filter(
+            Some(&glob),
+            "chrome.exe"
+        ));
+
+        assert!(ProcessMonitorPlugin::matches_name_filter(
+            None,
+            "anything.exe"
+        ));
     }
 }
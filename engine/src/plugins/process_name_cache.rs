@@ -0,0 +1,142 @@
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+/// How long a resolved name is trusted. Kept short because Windows reuses PIDs.
+pub const DEFAULT_NAME_TTL: Duration = Duration::from_secs(5);
+
+/// Maximum number of PIDs remembered at once
+pub const DEFAULT_NAME_CAPACITY: usize = 256;
+
+struct CachedName {
+    name: String,
+    resolved_at: Instant,
+    last_used: Instant,
+}
+
+/// Time-bounded LRU cache of PID -> process name lookups, so a burst of
+/// events from one process only opens a process handle once
+pub struct ProcessNameCache {
+    ttl: Duration,
+    capacity: usize,
+    entries: HashMap<u32, CachedName>,
+}
+
+impl ProcessNameCache {
+    pub fn new(ttl: Duration, capacity: usize) -> Self {
+        Self {
+            ttl,
+            capacity,
+            entries: HashMap::new(),
+        }
+    }
+
+    /// Return the cached name for `pid`, calling `resolve` on a miss or once
+    /// the entry is older than the TTL
+    pub fn get_or_resolve(
+        &mut self,
+        pid: u32,
+        now: Instant,
+        resolve: impl FnOnce(u32) -> String,
+    ) -> String {
+        if let Some(entry) = self.entries.get_mut(&pid)
+            && now.duration_since(entry.resolved_at) < self.ttl
+        {
+            entry.last_used = now;
+            return entry.name.clone();
+        }
+
+        let name = resolve(pid);
+        if !self.entries.contains_key(&pid) && self.entries.len() >= self.capacity {
+            self.evict_least_recently_used();
+        }
+        self.entries.insert(
+            pid,
+            CachedName {
+                name: name.clone(),
+                resolved_at: now,
+                last_used: now,
+            },
+        );
+        name
+    }
+
+    /// Forget `pid`, e.g. once its process has exited
+    pub fn invalidate(&mut self, pid: u32) {
+        self.entries.remove(&pid);
+    }
+
+    fn evict_least_recently_used(&mut self) {
+        if let Some(pid) = self
+            .entries
+            .iter()
+            .min_by_key(|(_, entry)| entry.last_used)
+            .map(|(pid, _)| *pid)
+        {
+            self.entries.remove(&pid);
+        }
+    }
+}
+
+impl Default for ProcessNameCache {
+    fn default() -> Self {
+        Self::new(DEFAULT_NAME_TTL, DEFAULT_NAME_CAPACITY)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn resolver(calls: &mut Vec<u32>) -> impl FnOnce(u32) -> String + '_ {
+        |pid| {
+            calls.push(pid);
+            format!("proc{}.exe", pid)
+        }
+    }
+
+    #[test]
+    fn test_cache_hit_miss_and_expiry() {
+        let mut cache = ProcessNameCache::new(Duration::from_secs(5), 16);
+        let start = Instant::now();
+        let mut calls = Vec::new();
+
+        // Miss, then hit within the TTL
+        assert_eq!(
+            cache.get_or_resolve(10, start, resolver(&mut calls)),
+            "proc10.exe"
+        );
+        assert_eq!(
+            cache.get_or_resolve(10, start + Duration::from_secs(1), resolver(&mut calls)),
+            "proc10.exe"
+        );
+        assert_eq!(calls, vec![10]);
+
+        // Expired entries are resolved again
+        cache.get_or_resolve(10, start + Duration::from_secs(6), resolver(&mut calls));
+        assert_eq!(calls, vec![10, 10]);
+
+        // Invalidated entries are resolved again
+        cache.invalidate(10);
+        cache.get_or_resolve(10, start + Duration::from_secs(7), resolver(&mut calls));
+        assert_eq!(calls, vec![10, 10, 10]);
+    }
+
+    #[test]
+    fn test_cache_evicts_least_recently_used() {
+        let mut cache = ProcessNameCache::new(Duration::from_secs(60), 2);
+        let start = Instant::now();
+        let mut calls = Vec::new();
+
+        cache.get_or_resolve(1, start, resolver(&mut calls));
+        cache.get_or_resolve(2, start + Duration::from_secs(1), resolver(&mut calls));
+        // Touch 1 so 2 becomes the least recently used
+        cache.get_or_resolve(1, start + Duration::from_secs(2), resolver(&mut calls));
+        cache.get_or_resolve(3, start + Duration::from_secs(3), resolver(&mut calls));
+        assert_eq!(calls, vec![1, 2, 3]);
+
+        cache.get_or_resolve(1, start + Duration::from_secs(4), resolver(&mut calls));
+        assert_eq!(calls, vec![1, 2, 3]);
+        cache.get_or_resolve(2, start + Duration::from_secs(5), resolver(&mut calls));
+        assert_eq!(calls, vec![1, 2, 3, 2]);
+    }
+}
@@ -1,13 +1,16 @@
+use crate::plugins::process_monitor::{ProcessMonitorPlugin, etw_session_name};
+use crate::plugins::process_name_cache::ProcessNameCache;
 use async_trait::async_trait;
 use engine_core::event::{Event, EventKind, RegistryChangeType};
-use engine_core::plugin::{EventEmitter, EventSourcePlugin, PluginError};
+use engine_core::plugin::{
+    EmitOutcome, EventEmitter, EventSourcePlugin, PluginError, PluginHealth,
+};
 use std::collections::HashSet;
 use std::sync::Arc;
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::mpsc::{self, Sender};
 use std::thread::{self, JoinHandle};
-use tracing::{error, info};
-use uuid::Uuid;
+use tracing::{error, info, warn};
 use windows::Win32::System::Diagnostics::Etw::{
     CloseTrace, ControlTraceW, EnableTraceEx2, OpenTraceW, ProcessTrace, StartTraceW,
     CONTROLTRACE_HANDLE, EVENT_CONTROL_CODE_ENABLE_PROVIDER,
@@ -85,6 +88,28 @@ pub struct RegistryKeyConfig {
     pub root: RegistryRoot,
     pub path: String,
     pub watch_tree: bool,
+    /// Case-insensitive glob on the value name; only value set/delete events
+    /// are filtered, key-level events always pass
+    pub value_name_filter: Option<String>,
+    /// `value_name_filter` compiled by `with_value_filter`; `None` for an
+    /// invalid pattern, which `start` rejects
+    value_pattern: Option<glob::Pattern>,
+}
+
+impl RegistryKeyConfig {
+    fn matches_value(&self, value_name: Option<&str>) -> bool {
+        let (Some(pattern), Some(value_name)) = (&self.value_pattern, value_name) else {
+            return true;
+        };
+
+        pattern.matches_with(
+            value_name,
+            glob::MatchOptions {
+                case_sensitive: false,
+                ..Default::default()
+            },
+        )
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -132,12 +157,8 @@ struct EtwCallbackContext {
 impl RegistryMonitorPlugin {
     pub fn new(name: impl Into<String>) -> Self {
         let name_str: String = name.into();
-        // Generate unique session name to avoid conflicts
-        let session_name = format!("wee_reg_{}_{}", 
-            name_str.to_lowercase().replace(" ", "_").replace("-", "_"),
-            Uuid::new_v4().simple()
-        );
-        
+        let session_name = etw_session_name("wee_reg", &name_str);
+
         Self {
             name: name_str,
             keys: Vec::new(),
@@ -153,6 +174,8 @@ impl RegistryMonitorPlugin {
             root,
             path: path.into(),
             watch_tree: false,
+            value_name_filter: None,
+            value_pattern: None,
         });
         self
     }
@@ -162,10 +185,41 @@ impl RegistryMonitorPlugin {
             root,
             path: path.into(),
             watch_tree: true,
+            value_name_filter: None,
+            value_pattern: None,
         });
         self
     }
 
+    /// Only report values whose name matches `pattern` under the key added by
+    /// the preceding `watch_key`/`watch_key_recursive` call
+    pub fn with_value_filter(mut self, pattern: impl Into<String>) -> Self {
+        if let Some(key) = self.keys.last_mut() {
+            let pattern = pattern.into();
+            key.value_pattern = glob::Pattern::new(&pattern).ok();
+            key.value_name_filter = Some(pattern);
+        }
+        self
+    }
+
+    /// Reject value filters that aren't valid globs rather than matching
+    /// nothing, or everything, at runtime
+    fn check_value_filters(&self) -> Result<(), PluginError> {
+        for key in &self.keys {
+            if let Some(filter) = &key.value_name_filter
+                && key.value_pattern.is_none()
+            {
+                let error = glob::Pattern::new(filter).err().map(|e| e.to_string());
+                return Err(PluginError::Configuration(format!(
+                    "Invalid value name filter '{}': {}",
+                    filter,
+                    error.unwrap_or_default()
+                )));
+            }
+        }
+        Ok(())
+    }
+
     fn build_key_filters(&self) -> HashSet<String> {
         let mut filters = HashSet::new();
         for key in &self.keys {
@@ -182,23 +236,34 @@ impl RegistryMonitorPlugin {
 
     #[allow(dead_code)]
     fn should_emit_event(&self, key_path: &str) -> bool {
-        if self.keys.is_empty() {
+        Self::key_matches(&self.keys, key_path, None)
+    }
+
+    /// Whether `key_path` (and `value_name`, for value events) falls under one
+    /// of the watched `keys`. Everything passes when nothing is watched.
+    fn key_matches(keys: &[RegistryKeyConfig], key_path: &str, value_name: Option<&str>) -> bool {
+        if keys.is_empty() {
             return true; // No filters, emit all
         }
 
         let key_lower = key_path.to_lowercase();
         
-        for config in &self.keys {
+        for config in keys {
             let filter_path = format!("{}\\{}", config.root.to_full_name(), config.path).to_lowercase();
             
             if config.watch_tree {
                 // For recursive watching, check if the event path starts with the filter path
-                if key_lower.starts_with(&filter_path) || filter_path.starts_with(&key_lower) {
+                if (key_lower.starts_with(&filter_path) || filter_path.starts_with(&key_lower))
+                    && config.matches_value(value_name)
+                {
                     return true;
                 }
             } else {
                 // Exact match or direct child
-                if key_lower == filter_path || key_lower.starts_with(&format!("{}\\", filter_path)) {
+                if (key_lower == filter_path
+                    || key_lower.starts_with(&format!("{}\\", filter_path)))
+                    && config.matches_value(value_name)
+                {
                     return true;
                 }
             }
@@ -207,6 +272,107 @@ impl RegistryMonitorPlugin {
         false
     }
 
+    /// Turn an ETW registry event into an engine event if its key is watched.
+    /// `resolve_process_name` opens a process handle, so it only runs for
+    /// events that pass the key filter.
+    fn to_emitted_event(
+        etw_event: &EtwEvent,
+        keys: &[RegistryKeyConfig],
+        plugin_name: &str,
+        resolve_process_name: impl FnOnce(u32) -> String,
+    ) -> Option<Event> {
+        let (process_id, key_path, value_name) = match etw_event {
+            EtwEvent::RegistryKeyCreated {
+                process_id,
+                key_path,
+                ..
+            }
+            | EtwEvent::RegistryKeyDeleted {
+                process_id,
+                key_path,
+                ..
+            } => (*process_id, key_path, None),
+            EtwEvent::RegistryValueSet {
+                process_id,
+                key_path,
+                value_name,
+                ..
+            }
+            | EtwEvent::RegistryValueDeleted {
+                process_id,
+                key_path,
+                value_name,
+                ..
+            } => (*process_id, key_path, Some(value_name.as_str())),
+            // Don't emit events for open operations to reduce noise
+            EtwEvent::RegistryKeyOpened { .. } => return None,
+        };
+
+        if !Self::key_matches(keys, key_path, value_name) {
+            return None;
+        }
+
+        let proc_name = resolve_process_name(process_id);
+        let root = Self::extract_root_from_path(key_path);
+
+        let event = match etw_event {
+            EtwEvent::RegistryKeyCreated { status, .. } => Event::new(
+                EventKind::RegistryChanged {
+                    root,
+                    key: key_path.clone(),
+                    value_name: None,
+                    change_type: RegistryChangeType::Created,
+                },
+                plugin_name,
+            )
+            .with_metadata("status", status.to_string()),
+            EtwEvent::RegistryKeyDeleted { status, .. } => Event::new(
+                EventKind::RegistryChanged {
+                    root,
+                    key: key_path.clone(),
+                    value_name: None,
+                    change_type: RegistryChangeType::Deleted,
+                },
+                plugin_name,
+            )
+            .with_metadata("status", status.to_string()),
+            EtwEvent::RegistryValueSet {
+                value_name,
+                data_type,
+                data_size,
+                ..
+            } => Event::new(
+                EventKind::RegistryChanged {
+                    root,
+                    key: key_path.clone(),
+                    value_name: Some(value_name.clone()),
+                    change_type: RegistryChangeType::Modified,
+                },
+                plugin_name,
+            )
+            .with_metadata("value_name", value_name)
+            .with_metadata("data_type", data_type.to_string())
+            .with_metadata("data_size", data_size.to_string()),
+            EtwEvent::RegistryValueDeleted { value_name, .. } => Event::new(
+                EventKind::RegistryChanged {
+                    root,
+                    key: key_path.clone(),
+                    value_name: Some(value_name.clone()),
+                    change_type: RegistryChangeType::Deleted,
+                },
+                plugin_name,
+            )
+            .with_metadata("value_name", value_name),
+            EtwEvent::RegistryKeyOpened { .. } => return None,
+        };
+
+        Some(
+            event
+                .with_metadata("process_name", &proc_name)
+                .with_metadata("process_id", process_id.to_string()),
+        )
+    }
+
     fn run_etw_session(
         session_name: String,
         sender: Sender<EtwEvent>,
@@ -252,8 +418,48 @@ impl RegistryMonitorPlugin {
 
     fn create_etw_session(session_name: &str) -> Result<CONTROLTRACE_HANDLE, String> {
         let name_wide: Vec<u16> = session_name.encode_utf16().chain(std::iter::once(0)).collect();
-        
-        let name_len = name_wide.len() * std::mem::size_of::<u16>();
+
+        let mut attempt = 0;
+        loop {
+            match Self::start_trace(&name_wide) {
+                Ok(session_handle) => {
+                    info!("ETW registry session '{}' created", session_name);
+                    return Ok(session_handle);
+                }
+                Err(e) if ProcessMonitorPlugin::should_retry_session_start(&e, attempt) => {
+                    // Left behind by a previous run that didn't shut down cleanly
+                    warn!(
+                        "ETW registry session '{}' already exists, stopping the stale session",
+                        session_name
+                    );
+                    if let Err(stop_error) = ProcessMonitorPlugin::stop_session_by_name(&name_wide)
+                    {
+                        warn!(
+                            "Failed to stop stale ETW registry session '{}': {:?}",
+                            session_name, stop_error
+                        );
+                    }
+                    attempt += 1;
+                }
+                Err(e) => {
+                    let error_code = e.code().0 as u32;
+                    if error_code == 0xB7 {
+                        return Err(format!(
+                            "ETW registry session '{}' already exists and could not be cleaned up. Stop it with `logman stop {} -ets`.",
+                            session_name, session_name
+                        ));
+                    }
+                    return Err(format!(
+                        "Failed to create ETW registry session: 0x{:08X}",
+                        error_code
+                    ));
+                }
+            }
+        }
+    }
+
+    fn start_trace(name_wide: &[u16]) -> windows::core::Result<CONTROLTRACE_HANDLE> {
+        let name_len = std::mem::size_of_val(name_wide);
         let properties_size = std::mem::size_of::<EVENT_TRACE_PROPERTIES>() + name_len;
         
         let mut properties_buffer = vec![0u8; properties_size];
@@ -279,26 +485,12 @@ impl RegistryMonitorPlugin {
             (*properties).LoggerNameOffset = name_offset as u32;
             
             let mut session_handle: CONTROLTRACE_HANDLE = std::mem::zeroed();
-            let result = StartTraceW(
+            StartTraceW(
                 &mut session_handle,
                 windows::core::PCWSTR(name_wide.as_ptr()),
                 properties,
-            );
-            
-            match result {
-                Ok(_) => {
-                    info!("ETW registry session '{}' created", session_name);
-                    Ok(session_handle)
-                }
-                Err(e) => {
-                    let error_code = e.code().0 as u32;
-                    if error_code == 0xB7 {
-                        Err(format!("ETW registry session '{}' already exists", session_name))
-                    } else {
-                        Err(format!("Failed to create ETW registry session: 0x{:08X}", error_code))
-                    }
-                }
-            }
+            )?;
+            Ok(session_handle)
         }
     }
 
@@ -608,6 +800,7 @@ impl EventSourcePlugin for RegistryMonitorPlugin {
                 "No registry keys specified to watch".to_string(),
             ));
         }
+        self.check_value_filters()?;
 
         info!("Starting ETW registry monitor plugin: {}", self.name);
         info!("Session name: {}", self.session_name);
@@ -665,27 +858,7 @@ impl EventSourcePlugin for RegistryMonitorPlugin {
 
         self.is_running.store(true, Ordering::SeqCst);
 
-        // Build filter function
-        let keys_clone = self.keys.clone();
-        let should_emit_fn = move |key_path: &str| -> bool {
-            if keys_clone.is_empty() {
-                return true;
-            }
-            let key_lower = key_path.to_lowercase();
-            for config in &keys_clone {
-                let filter_path = format!("{}\\{}", config.root.to_full_name(), config.path).to_lowercase();
-                if config.watch_tree {
-                    if key_lower.starts_with(&filter_path) || filter_path.starts_with(&key_lower) {
-                        return true;
-                    }
-                } else {
-                    if key_lower == filter_path || key_lower.starts_with(&format!("{}\\", filter_path)) {
-                        return true;
-                    }
-                }
-            }
-            false
-        };
+        let keys = self.keys.clone();
 
         // Spawn async task to process events
         tokio::spawn(async move {
@@ -693,6 +866,7 @@ impl EventSourcePlugin for RegistryMonitorPlugin {
             
             let mut event_count = 0u64;
             let start_time = std::time::Instant::now();
+            let mut names = ProcessNameCache::default();
 
             while is_running.load(Ordering::SeqCst) {
                 match tokio_receiver.recv().await {
@@ -706,102 +880,19 @@ impl EventSourcePlugin for RegistryMonitorPlugin {
                             info!("Processed {} ETW registry events ({:.1} events/sec)", event_count, rate);
                         }
 
-                        let (should_emit, event) = match &etw_event {
-                            EtwEvent::RegistryKeyCreated { process_id, key_path, status, .. } => {
-                                let should = should_emit_fn(key_path);
-                                let proc_name = Self::get_process_name_from_pid(*process_id)
-                                    .unwrap_or_else(|| format!("PID:{}", process_id));
-                                
-                                let ev = Event::new(
-                                    EventKind::RegistryChanged {
-                                        root: Self::extract_root_from_path(key_path),
-                                        key: key_path.clone(),
-                                        value_name: None,
-                                        change_type: RegistryChangeType::Created,
-                                    },
-                                    &plugin_name,
-                                )
-                                .with_metadata("process_name", &proc_name)
-                                .with_metadata("process_id", process_id.to_string())
-                                .with_metadata("status", status.to_string());
-                                
-                                (should, ev)
-                            }
-                            EtwEvent::RegistryKeyDeleted { process_id, key_path, status, .. } => {
-                                let should = should_emit_fn(key_path);
-                                let proc_name = Self::get_process_name_from_pid(*process_id)
-                                    .unwrap_or_else(|| format!("PID:{}", process_id));
-                                
-                                let ev = Event::new(
-                                    EventKind::RegistryChanged {
-                                        root: Self::extract_root_from_path(key_path),
-                                        key: key_path.clone(),
-                                        value_name: None,
-                                        change_type: RegistryChangeType::Deleted,
-                                    },
-                                    &plugin_name,
-                                )
-                                .with_metadata("process_name", &proc_name)
-                                .with_metadata("process_id", process_id.to_string())
-                                .with_metadata("status", status.to_string());
-                                
-                                (should, ev)
-                            }
-                            EtwEvent::RegistryValueSet { process_id, key_path, value_name, data_type, data_size, .. } => {
-                                let should = should_emit_fn(key_path);
-                                let proc_name = Self::get_process_name_from_pid(*process_id)
-                                    .unwrap_or_else(|| format!("PID:{}", process_id));
-                                
-                                let ev = Event::new(
-                                    EventKind::RegistryChanged {
-                                        root: Self::extract_root_from_path(key_path),
-                                        key: key_path.clone(),
-                                        value_name: Some(value_name.clone()),
-                                        change_type: RegistryChangeType::Modified,
-                                    },
-                                    &plugin_name,
-                                )
-                                .with_metadata("process_name", &proc_name)
-                                .with_metadata("process_id", process_id.to_string())
-                                .with_metadata("value_name", value_name)
-                                .with_metadata("data_type", data_type.to_string())
-                                .with_metadata("data_size", data_size.to_string());
-                                
-                                (should, ev)
-                            }
-                            EtwEvent::RegistryValueDeleted { process_id, key_path, value_name, .. } => {
-                                let should = should_emit_fn(key_path);
-                                let proc_name = Self::get_process_name_from_pid(*process_id)
-                                    .unwrap_or_else(|| format!("PID:{}", process_id));
-                                
-                                let ev = Event::new(
-                                    EventKind::RegistryChanged {
-                                        root: Self::extract_root_from_path(key_path),
-                                        key: key_path.clone(),
-                                        value_name: Some(value_name.clone()),
-                                        change_type: RegistryChangeType::Deleted,
-                                    },
-                                    &plugin_name,
-                                )
-                                .with_metadata("process_name", &proc_name)
-                                .with_metadata("process_id", process_id.to_string())
-                                .with_metadata("value_name", value_name);
-                                
-                                (should, ev)
-                            }
-                            EtwEvent::RegistryKeyOpened { process_id: _, key_path: _, desired_access: _, .. } => {
-                                // Don't emit events for open operations to reduce noise
-                                (false, Event::new(
-                                    EventKind::TimerTick, // Dummy event, will be filtered out
-                                    &plugin_name,
-                                ))
-                            }
-                        };
-
-                        if should_emit {
-                            if let Err(e) = emitter.try_send(event) {
-                                error!("Failed to send registry event: {}", e);
-                            }
+                        let event =
+                            Self::to_emitted_event(&etw_event, &keys, &plugin_name, |pid| {
+                                names.get_or_resolve(pid, std::time::Instant::now(), |pid| {
+                                    Self::get_process_name_from_pid(pid)
+                                        .unwrap_or_else(|| format!("PID:{}", pid))
+                                })
+                            });
+
+                        if let Some(event) = event
+                            && emitter.emit(event) == EmitOutcome::Closed
+                        {
+                            is_running.store(false, Ordering::SeqCst);
+                            break;
                         }
                     }
                     None => {
@@ -840,6 +931,18 @@ impl EventSourcePlugin for RegistryMonitorPlugin {
     fn is_running(&self) -> bool {
         self.is_running.load(Ordering::SeqCst)
     }
+
+    fn health(&self) -> PluginHealth {
+        if !self.is_running() {
+            return PluginHealth::Stopped;
+        }
+        match &self.etw_thread {
+            Some(thread) if thread.is_finished() => {
+                PluginHealth::Failed("ETW session ended".to_string())
+            }
+            _ => PluginHealth::Healthy,
+        }
+    }
 }
 
 impl RegistryMonitorPlugin {
@@ -864,7 +967,6 @@ impl RegistryMonitorPlugin {
 mod tests {
     use super::*;
     use engine_core::plugin::EventSourcePlugin;
-    use tracing::warn;
 
     #[tokio::test]
     async fn test_registry_plugin_lifecycle() {
@@ -875,7 +977,7 @@ mod tests {
         assert!(!plugin.is_running());
 
         // Note: This test requires admin privileges
-        let result = plugin.start(tx).await;
+        let result = plugin.start(tx.into()).await;
         
         if result.is_ok() {
             assert!(plugin.is_running());
@@ -928,6 +1030,131 @@ mod tests {
         assert!(!plugin.should_emit_event("HKEY_CURRENT_USER\\Software\\Firefox"));
     }
 
+    #[test]
+    fn test_key_and_value_filtering() {
+        let plugin = RegistryMonitorPlugin::new("test")
+            .watch_key(
+                RegistryRoot::HKEY_CURRENT_USER,
+                "Software\\Microsoft\\Windows\\CurrentVersion\\Run",
+            )
+            .with_value_filter("OneDrive*")
+            .watch_key_recursive(RegistryRoot::HKEY_LOCAL_MACHINE, "SYSTEM");
+        let run = "HKEY_CURRENT_USER\\Software\\Microsoft\\Windows\\CurrentVersion\\Run";
+
+        assert_eq!(
+            plugin.keys[0].value_name_filter.as_deref(),
+            Some("OneDrive*")
+        );
+        assert_eq!(plugin.keys[1].value_name_filter, None);
+
+        // Matching value under the filtered key, case-insensitively
+        assert!(RegistryMonitorPlugin::key_matches(
+            &plugin.keys,
+            run,
+            Some("OneDrive")
+        ));
+        assert!(RegistryMonitorPlugin::key_matches(
+            &plugin.keys,
+            run,
+            Some("onedrivesetup")
+        ));
+
+        // Other values under the same key are dropped
+        assert!(!RegistryMonitorPlugin::key_matches(
+            &plugin.keys,
+            run,
+            Some("Discord")
+        ));
+
+        // Key-level events carry no value name and still pass
+        assert!(RegistryMonitorPlugin::key_matches(&plugin.keys, run, None));
+
+        // Keys without a value filter accept every value
+        assert!(RegistryMonitorPlugin::key_matches(
+            &plugin.keys,
+            "HKEY_LOCAL_MACHINE\\SYSTEM\\CurrentControlSet",
+            Some("Anything")
+        ));
+
+        // A matching value name doesn't rescue an unwatched key
+        assert!(!RegistryMonitorPlugin::key_matches(
+            &plugin.keys,
+            "HKEY_CURRENT_USER\\Software\\Other",
+            Some("OneDrive")
+        ));
+    }
+
+    #[test]
+    fn test_invalid_value_filter_is_rejected() {
+        let valid = RegistryMonitorPlugin::new("test")
+            .watch_key(RegistryRoot::HKEY_CURRENT_USER, "Software\\Test")
+            .with_value_filter("One*");
+        assert!(valid.check_value_filters().is_ok());
+
+        let invalid = RegistryMonitorPlugin::new("test")
+            .watch_key(RegistryRoot::HKEY_CURRENT_USER, "Software\\Test")
+            .with_value_filter("[One");
+        assert!(matches!(
+            invalid.check_value_filters(),
+            Err(PluginError::Configuration(_))
+        ));
+    }
+
+    #[test]
+    fn test_filtered_events_skip_process_name_resolution() {
+        let keys = vec![RegistryKeyConfig {
+            root: RegistryRoot::HKEY_CURRENT_USER,
+            path: "Software\\Chrome".to_string(),
+            watch_tree: false,
+            value_name_filter: None,
+            value_pattern: None,
+        }];
+        let set_value = |key_path: &str| EtwEvent::RegistryValueSet {
+            process_id: 42,
+            thread_id: 1,
+            key_path: key_path.to_string(),
+            value_name: "Path".to_string(),
+            data_type: 1,
+            data_size: 8,
+        };
+
+        let unwatched = set_value("HKEY_CURRENT_USER\\Software\\Firefox");
+        let event = RegistryMonitorPlugin::to_emitted_event(&unwatched, &keys, "test", |_| {
+            panic!("process name resolved for a filtered-out event")
+        });
+        assert!(event.is_none());
+
+        let opened = EtwEvent::RegistryKeyOpened {
+            process_id: 42,
+            thread_id: 1,
+            key_path: "HKEY_CURRENT_USER\\Software\\Chrome".to_string(),
+            desired_access: 0,
+        };
+        assert!(
+            RegistryMonitorPlugin::to_emitted_event(&opened, &keys, "test", |_| {
+                panic!("process name resolved for an ignored event")
+            })
+            .is_none()
+        );
+
+        let mut resolved = Vec::new();
+        let watched = set_value("HKEY_CURRENT_USER\\Software\\Chrome");
+        let event = RegistryMonitorPlugin::to_emitted_event(&watched, &keys, "test", |pid| {
+            resolved.push(pid);
+            "chrome.exe".to_string()
+        })
+        .expect("watched key should be emitted");
+        assert_eq!(resolved, vec![42]);
+        assert_eq!(
+            event.metadata.get("process_name").map(String::as_str),
+            Some("chrome.exe")
+        );
+        assert_eq!(
+            event.metadata.get("value_name").map(String::as_str),
+            Some("Path")
+        );
+    }
+
     #[test]
     fn test_unicode_string_extraction() {
         // Create test data with UTF-16LE string
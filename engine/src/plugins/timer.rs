@@ -0,0 +1,174 @@
+use async_trait::async_trait;
+use engine_core::event::{Event, EventKind};
+use engine_core::plugin::{EmitOutcome, EventEmitter, EventSourcePlugin, PluginError};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+use tokio::task::JoinHandle;
+use tokio::time::{Instant, MissedTickBehavior};
+use tracing::info;
+
+/// Emits a `TimerTick` event every `interval`. The first tick fires one full
+/// interval after start, not immediately.
+pub struct TimerPlugin {
+    name: String,
+    interval: Duration,
+    is_running: Arc<AtomicBool>,
+    tick_task: Option<JoinHandle<()>>,
+}
+
+impl TimerPlugin {
+    pub fn new(name: impl Into<String>, interval: Duration) -> Self {
+        Self {
+            name: name.into(),
+            interval,
+            is_running: Arc::new(AtomicBool::new(false)),
+            tick_task: None,
+        }
+    }
+
+    fn tick_event(plugin_name: &str, interval: Duration, tick_count: u64) -> Event {
+        Event::new(EventKind::TimerTick, plugin_name)
+            .with_metadata("interval_seconds", interval.as_secs().to_string())
+            .with_metadata("tick_count", tick_count.to_string())
+    }
+}
+
+#[async_trait]
+impl EventSourcePlugin for TimerPlugin {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    async fn start(&mut self, emitter: EventEmitter) -> Result<(), PluginError> {
+        if self.is_running.load(Ordering::SeqCst) {
+            return Ok(());
+        }
+
+        if self.interval.is_zero() {
+            return Err(PluginError::Configuration(
+                "Timer interval must be greater than zero".to_string(),
+            ));
+        }
+
+        info!(
+            "Starting timer plugin: {} (every {:?})",
+            self.name, self.interval
+        );
+
+        self.is_running.store(true, Ordering::SeqCst);
+
+        let plugin_name = self.name.clone();
+        let is_running = self.is_running.clone();
+        let period = self.interval;
+
+        self.tick_task = Some(tokio::spawn(async move {
+            let mut interval = tokio::time::interval_at(Instant::now() + period, period);
+            // After a stall (e.g. sleep/resume), tick once rather than in a burst
+            interval.set_missed_tick_behavior(MissedTickBehavior::Delay);
+            let mut tick_count: u64 = 0;
+
+            while is_running.load(Ordering::SeqCst) {
+                interval.tick().await;
+                tick_count += 1;
+
+                let event = Self::tick_event(&plugin_name, period, tick_count);
+                if emitter.emit(event) == EmitOutcome::Closed {
+                    is_running.store(false, Ordering::SeqCst);
+                    break;
+                }
+            }
+        }));
+
+        Ok(())
+    }
+
+    async fn stop(&mut self) -> Result<(), PluginError> {
+        info!("Stopping timer plugin: {}", self.name);
+        self.is_running.store(false, Ordering::SeqCst);
+
+        if let Some(task) = self.tick_task.take() {
+            task.abort();
+            let _ = task.await;
+        }
+
+        Ok(())
+    }
+
+    fn is_running(&self) -> bool {
+        self.is_running.load(Ordering::SeqCst)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tick_event_metadata() {
+        let event = TimerPlugin::tick_event("hourly", Duration::from_secs(3600), 42);
+        assert!(matches!(event.kind, EventKind::TimerTick));
+        assert_eq!(event.source, "hourly");
+        assert_eq!(event.metadata["interval_seconds"], "3600");
+        assert_eq!(event.metadata["tick_count"], "42");
+    }
+
+    #[tokio::test]
+    async fn test_timer_tick_cadence() {
+        let (tx, mut rx) = tokio::sync::mpsc::channel(10);
+        let interval = Duration::from_millis(50);
+        let mut plugin = TimerPlugin::new("test_timer", interval);
+
+        let started = Instant::now();
+        plugin
+            .start(tx.into())
+            .await
+            .expect("Failed to start plugin");
+
+        for expected in 1..=3u64 {
+            let event = tokio::time::timeout(Duration::from_secs(2), rx.recv())
+                .await
+                .expect("Timed out waiting for tick")
+                .expect("Channel closed");
+            assert!(matches!(event.kind, EventKind::TimerTick));
+            assert_eq!(event.metadata["tick_count"], expected.to_string());
+        }
+
+        // Three ticks can't arrive before three intervals have passed
+        assert!(started.elapsed() >= interval * 3);
+
+        plugin.stop().await.expect("Failed to stop plugin");
+    }
+
+    #[tokio::test]
+    async fn test_timer_stops_cleanly() {
+        let (tx, mut rx) = tokio::sync::mpsc::channel(10);
+        let mut plugin = TimerPlugin::new("test_timer", Duration::from_millis(20));
+
+        assert!(!plugin.is_running());
+        plugin
+            .start(tx.into())
+            .await
+            .expect("Failed to start plugin");
+        assert!(plugin.is_running());
+        plugin.stop().await.expect("Failed to stop plugin");
+        assert!(!plugin.is_running());
+
+        // The tick task owned the only sender, so the channel closes once it is gone
+        while rx.try_recv().is_ok() {}
+        let closed = tokio::time::timeout(Duration::from_secs(1), rx.recv()).await;
+        assert!(matches!(closed, Ok(None)));
+    }
+
+    #[tokio::test]
+    async fn test_zero_interval_is_rejected() {
+        let (tx, _rx) = tokio::sync::mpsc::channel(10);
+        let mut plugin = TimerPlugin::new("test_timer", Duration::ZERO);
+
+        assert!(matches!(
+            plugin.start(tx.into()).await,
+            Err(PluginError::Configuration(_))
+        ));
+        assert!(!plugin.is_running());
+    }
+}
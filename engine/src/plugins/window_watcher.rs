@@ -1,20 +1,22 @@
 use async_trait::async_trait;
 use engine_core::event::{Event, EventKind};
-use engine_core::plugin::{EventEmitter, EventSourcePlugin, PluginError};
+use engine_core::plugin::{EventEmitter, EventSourcePlugin, PluginError, PluginHealth};
 use regex::Regex;
+use std::collections::{HashMap, HashSet};
 use std::sync::Arc;
-use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::atomic::{AtomicBool, AtomicU32, Ordering};
 use std::sync::mpsc::{self, Sender};
 use std::thread::{self, JoinHandle};
-use std::time::Duration;
+use std::time::{Duration, Instant};
 use tracing::{error, info, warn};
-use windows::Win32::Foundation::HWND;
-use windows::Win32::UI::Accessibility::{SetWinEventHook, UnhookWinEvent};
-use windows::Win32::UI::WindowsAndMessaging::{DispatchMessageW, GetMessageW, MSG, GetWindowThreadProcessId};
-use windows::Win32::UI::WindowsAndMessaging::{EVENT_SYSTEM_FOREGROUND, EVENT_OBJECT_CREATE, EVENT_OBJECT_DESTROY, WINEVENT_OUTOFCONTEXT, WINEVENT_SKIPOWNPROCESS};
+use windows::Win32::Foundation::{HWND, RECT};
+use windows::Win32::UI::Accessibility::{HWINEVENTHOOK, SetWinEventHook, UnhookWinEvent};
+use windows::Win32::Foundation::{BOOL, LPARAM, WPARAM};
+use windows::Win32::UI::WindowsAndMessaging::{DispatchMessageW, EnumWindows, GetMessageW, PostThreadMessageW, MSG, WM_QUIT, GetWindowThreadProcessId, GetWindowRect, GetWindowTextLengthW, IsWindowVisible, GetAncestor, GA_ROOT};
+use windows::Win32::UI::WindowsAndMessaging::{EVENT_SYSTEM_FOREGROUND, EVENT_OBJECT_CREATE, EVENT_OBJECT_DESTROY, EVENT_OBJECT_LOCATIONCHANGE, EVENT_OBJECT_NAMECHANGE, EVENT_OBJECT_SHOW, EVENT_SYSTEM_MINIMIZESTART, EVENT_SYSTEM_MINIMIZEEND, OBJID_WINDOW, CHILDID_SELF, WINEVENT_OUTOFCONTEXT, WINEVENT_SKIPOWNPROCESS};
 use windows::core::PWSTR;
 use windows::Win32::System::Threading::{
-    OpenProcess, PROCESS_QUERY_INFORMATION, PROCESS_VM_READ, QueryFullProcessImageNameW, PROCESS_NAME_FORMAT,
+    GetCurrentThreadId, OpenProcess, PROCESS_QUERY_INFORMATION, PROCESS_VM_READ, QueryFullProcessImageNameW, PROCESS_NAME_FORMAT,
 };
 
 #[derive(Debug, Clone)]
@@ -35,15 +37,176 @@ enum WindowEvent {
         hwnd: HWND,
         title: Option<String>,
     },
+    Minimized {
+        hwnd: HWND,
+        title: String,
+        process_name: String,
+        process_id: u32,
+    },
+    Restored {
+        hwnd: HWND,
+        title: String,
+        process_name: String,
+        process_id: u32,
+    },
+    Moved {
+        hwnd: HWND,
+        bounds: WindowBounds,
+    },
+}
+
+/// Screen position and size of a window, as reported by `GetWindowRect`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct WindowBounds {
+    x: i32,
+    y: i32,
+    width: i32,
+    height: i32,
+}
+
+impl From<RECT> for WindowBounds {
+    fn from(rect: RECT) -> Self {
+        Self {
+            x: rect.left,
+            y: rect.top,
+            width: rect.right - rect.left,
+            height: rect.bottom - rect.top,
+        }
+    }
+}
+
+/// What the hook callback knows about the object behind a create/destroy event
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct WindowAttributes {
+    /// The event is about the window itself (`OBJID_WINDOW`/`CHILDID_SELF`),
+    /// not a control or accessible object inside it
+    is_window_object: bool,
+    /// `GetAncestor(hwnd, GA_ROOT) == hwnd`
+    is_root: bool,
+    has_title: bool,
+    is_visible: bool,
+}
+
+impl WindowAttributes {
+    fn query(hwnd: HWND, id_object: i32, id_child: i32) -> Self {
+        let is_window_object = id_object == OBJID_WINDOW.0 && id_child == CHILDID_SELF as i32;
+        unsafe {
+            Self {
+                is_window_object,
+                is_root: GetAncestor(hwnd, GA_ROOT).0 == hwnd.0,
+                has_title: GetWindowTextLengthW(hwnd) > 0,
+                is_visible: IsWindowVisible(hwnd).as_bool(),
+            }
+        }
+    }
+
+    /// Real application windows, as opposed to child controls, tooltips and
+    /// menu popups, which are untitled and usually not yet visible
+    fn is_top_level(&self) -> bool {
+        self.is_window_object && self.is_root && (self.has_title || self.is_visible)
+    }
+}
+
+/// Top-level windows, either open when the watcher started or seen being
+/// created, shown or titled since. A destroyed window can no longer be
+/// queried, so whether to report its destruction is decided from this record.
+#[derive(Debug, Default)]
+struct TopLevelWindows {
+    hwnds: HashSet<isize>,
+}
+
+impl TopLevelWindows {
+    /// Whether a create should be reported; top-level windows are remembered
+    fn created(&mut self, hwnd: isize, attributes: &WindowAttributes) -> bool {
+        if attributes.is_top_level() {
+            self.hwnds.insert(hwnd);
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Record a window that is top-level now, though it may not have been when
+    /// it was created (hidden and untitled until shown or named). Returns
+    /// whether it's newly recorded, i.e. its creation hasn't been reported yet.
+    fn seen(&mut self, hwnd: isize, attributes: &WindowAttributes) -> bool {
+        attributes.is_top_level() && self.hwnds.insert(hwnd)
+    }
+
+    /// Whether a destroy should be reported: only for the window itself, and
+    /// only if it was recorded as top-level
+    fn destroyed(&mut self, hwnd: isize, is_window_object: bool) -> bool {
+        is_window_object && self.hwnds.remove(&hwnd)
+    }
+}
+
+/// Default quiet period before a move/resize is reported
+pub const DEFAULT_MOVE_DEBOUNCE: Duration = Duration::from_millis(250);
+
+/// Trailing-edge debounce for location changes. Dragging a window fires
+/// `EVENT_OBJECT_LOCATIONCHANGE` for every intermediate position, so only the
+/// final bounds are reported once a window has been still for `window`.
+struct MoveDebouncer {
+    window: Duration,
+    pending: HashMap<isize, (WindowBounds, Instant)>,
+}
+
+impl MoveDebouncer {
+    fn new(window: Duration) -> Self {
+        Self {
+            window,
+            pending: HashMap::new(),
+        }
+    }
+
+    fn record(&mut self, hwnd: isize, bounds: WindowBounds, now: Instant) {
+        self.pending.insert(hwnd, (bounds, now));
+    }
+
+    /// Drop a pending move, e.g. because the window was destroyed
+    fn forget(&mut self, hwnd: isize) {
+        self.pending.remove(&hwnd);
+    }
+
+    /// Remove and return the windows that have not moved for the debounce window
+    fn drain_settled(&mut self, now: Instant) -> Vec<(isize, WindowBounds)> {
+        let mut settled: Vec<_> = self
+            .pending
+            .iter()
+            .filter(|(_, (_, last_seen))| now.duration_since(*last_seen) >= self.window)
+            .map(|(hwnd, (bounds, _))| (*hwnd, *bounds))
+            .collect();
+        for (hwnd, _) in &settled {
+            self.pending.remove(hwnd);
+        }
+        settled.sort_by_key(|(hwnd, _)| *hwnd);
+        settled
+    }
+
+    /// When the earliest pending move settles, if any
+    fn next_flush(&self) -> Option<Instant> {
+        self.pending
+            .values()
+            .map(|(_, last_seen)| *last_seen + self.window)
+            .min()
+    }
 }
 
 pub struct WindowEventPlugin {
     name: String,
     is_running: Arc<AtomicBool>,
-    previous_hwnd: Arc<tokio::sync::Mutex<Option<HWND>>>,
+    /// The focused window, when it gained focus and whether it passes the filters
+    previous_focus: Arc<tokio::sync::Mutex<Option<(HWND, Instant, bool)>>>,
     title_filter: Option<Regex>,
     process_filter: Option<Regex>,
+    emit_unfocus: bool,
+    top_level_only: bool,
+    track_location: bool,
+    move_debounce: Duration,
     hook_thread: Option<JoinHandle<()>>,
+    /// Win32 thread id of the message loop, so `stop()` can post `WM_QUIT` to it
+    hook_thread_id: Arc<AtomicU32>,
+    forward_task: Option<tokio::task::JoinHandle<()>>,
     event_sender: Option<Sender<WindowEvent>>,
 }
 
@@ -52,10 +215,16 @@ impl WindowEventPlugin {
         Self {
             name: name.into(),
             is_running: Arc::new(AtomicBool::new(false)),
-            previous_hwnd: Arc::new(tokio::sync::Mutex::new(None)),
+            previous_focus: Arc::new(tokio::sync::Mutex::new(None)),
             title_filter: None,
             process_filter: None,
+            emit_unfocus: true,
+            top_level_only: true,
+            track_location: false,
+            move_debounce: DEFAULT_MOVE_DEBOUNCE,
             hook_thread: None,
+            hook_thread_id: Arc::new(AtomicU32::new(0)),
+            forward_task: None,
             event_sender: None,
         }
     }
@@ -78,6 +247,162 @@ impl WindowEventPlugin {
         self
     }
 
+    /// Whether to synthesize `WindowUnfocused` for the previously focused
+    /// window on every focus change (on by default)
+    pub fn with_emit_unfocus(mut self, enabled: bool) -> Self {
+        self.emit_unfocus = enabled;
+        self
+    }
+
+    /// Only report `WindowCreated`/`WindowDestroyed` for top-level windows,
+    /// skipping child controls, tooltips and menus (on by default)
+    pub fn with_top_level_only(mut self, enabled: bool) -> Self {
+        self.top_level_only = enabled;
+        self
+    }
+
+    /// Also hook `EVENT_OBJECT_LOCATIONCHANGE` and emit `WindowMoved` when a
+    /// top-level window is moved or resized (off by default)
+    pub fn with_location_tracking(mut self, enabled: bool) -> Self {
+        self.track_location = enabled;
+        self
+    }
+
+    /// How long a window must stay put before its move is reported
+    pub fn with_move_debounce(mut self, debounce: Duration) -> Self {
+        self.move_debounce = debounce;
+        self
+    }
+
+    /// Build a `WindowMinimized` (or, with `minimized = false`, `WindowRestored`)
+    /// event carrying the same metadata as focus events
+    fn minimize_event(
+        plugin_name: &str,
+        hwnd: HWND,
+        minimized: bool,
+        title: &str,
+        process_name: &str,
+        process_id: u32,
+    ) -> Event {
+        let hwnd = hwnd.0;
+        let title_owned = title.to_string();
+        let kind = if minimized {
+            EventKind::WindowMinimized {
+                hwnd,
+                title: title_owned,
+            }
+        } else {
+            EventKind::WindowRestored {
+                hwnd,
+                title: title_owned,
+            }
+        };
+
+        Event::new(kind, plugin_name)
+            .with_metadata("window_title", title)
+            .with_metadata("process_id", process_id.to_string())
+            .with_metadata("process_name", process_name)
+    }
+
+    fn moved_event(
+        plugin_name: &str,
+        hwnd: isize,
+        bounds: WindowBounds,
+        title: &str,
+        process_name: &str,
+        process_id: u32,
+    ) -> Event {
+        Event::new(
+            EventKind::WindowMoved {
+                hwnd,
+                x: bounds.x,
+                y: bounds.y,
+                width: bounds.width,
+                height: bounds.height,
+            },
+            plugin_name,
+        )
+        .with_metadata("window_title", title)
+        .with_metadata("process_id", process_id.to_string())
+        .with_metadata("process_name", process_name)
+    }
+
+    /// Build the events for a focus change: a synthetic unfocus for the
+    /// previously focused window (when `emit_unfocus` is set), then the focus
+    /// event itself. Windows repeats `EVENT_SYSTEM_FOREGROUND` for a window
+    /// that already has focus; those repeats produce no events.
+    ///
+    /// The unfocus event's `focus_duration_ms` is how long the previous window
+    /// had focus, up to `now`.
+    ///
+    /// Every foreground change is tracked, but windows that don't pass the
+    /// filters (`passes_filters`) get neither a focus nor an unfocus event.
+    #[allow(clippy::too_many_arguments)]
+    fn focus_change_events(
+        plugin_name: &str,
+        hwnd: HWND,
+        title: &str,
+        process_name: &str,
+        process_id: u32,
+        passes_filters: bool,
+        previous: &mut Option<(HWND, Instant, bool)>,
+        now: Instant,
+        emit_unfocus: bool,
+        previous_title: impl FnOnce(HWND) -> Option<String>,
+    ) -> Vec<Event> {
+        let mut events = Vec::new();
+
+        if let Some((prev_hwnd, focused_at, prev_passes)) =
+            previous.replace((hwnd, now, passes_filters))
+        {
+            if prev_hwnd.0 == hwnd.0 {
+                // Still the same stretch of focus
+                *previous = Some((prev_hwnd, focused_at, prev_passes));
+                return events;
+            }
+
+            if emit_unfocus && prev_passes {
+                if let Some(prev_title) = previous_title(prev_hwnd) {
+                    events.push(
+                        Event::new(
+                            EventKind::WindowUnfocused {
+                                hwnd: prev_hwnd.0 as isize,
+                                title: prev_title.clone(),
+                            },
+                            plugin_name,
+                        )
+                        .with_metadata("window_title", &prev_title)
+                        .with_metadata(
+                            "focus_duration_ms",
+                            now.saturating_duration_since(focused_at)
+                                .as_millis()
+                                .to_string(),
+                        ),
+                    );
+                }
+            }
+        }
+
+        if !passes_filters {
+            return events;
+        }
+
+        events.push(
+            Event::new(
+                EventKind::WindowFocused {
+                    hwnd: hwnd.0 as isize,
+                    title: title.to_string(),
+                },
+                plugin_name,
+            )
+            .with_metadata("window_title", title)
+            .with_metadata("process_id", process_id.to_string())
+            .with_metadata("process_name", process_name),
+        );
+
+        events
+    }
+
     fn get_window_info(hwnd: HWND) -> Option<(String, u32, String)> {
         if hwnd.0 == 0 {
             return None;
@@ -144,62 +469,70 @@ impl WindowEventPlugin {
     fn run_message_loop(
         event_sender: Sender<WindowEvent>,
         is_running: Arc<AtomicBool>,
+        track_location: bool,
+        top_level_only: bool,
+        thread_id: Arc<AtomicU32>,
     ) -> Result<(), String> {
-        // Create hooks for different window events
-        let foreground_hook = unsafe {
-            SetWinEventHook(
-                EVENT_SYSTEM_FOREGROUND,
-                EVENT_SYSTEM_FOREGROUND,
-                None,
-                Some(win_event_callback),
-                0,
-                0,
-                WINEVENT_OUTOFCONTEXT | WINEVENT_SKIPOWNPROCESS,
-            )
-        };
+        thread_id.store(unsafe { GetCurrentThreadId() }, Ordering::SeqCst);
 
-        let create_hook = unsafe {
-            SetWinEventHook(
-                EVENT_OBJECT_CREATE,
-                EVENT_OBJECT_CREATE,
-                None,
-                Some(win_event_callback),
-                0,
-                0,
-                WINEVENT_OUTOFCONTEXT | WINEVENT_SKIPOWNPROCESS,
-            )
-        };
+        // Create hooks for different window events
+        let mut hooked_events = vec![
+            EVENT_SYSTEM_FOREGROUND,
+            EVENT_OBJECT_CREATE,
+            EVENT_OBJECT_DESTROY,
+            EVENT_SYSTEM_MINIMIZESTART,
+            EVENT_SYSTEM_MINIMIZEEND,
+        ];
+        if track_location {
+            hooked_events.push(EVENT_OBJECT_LOCATIONCHANGE);
+        }
+        // Windows created hidden and untitled only become top-level later
+        if top_level_only {
+            hooked_events.push(EVENT_OBJECT_SHOW);
+            hooked_events.push(EVENT_OBJECT_NAMECHANGE);
+        }
 
-        let destroy_hook = unsafe {
-            SetWinEventHook(
-                EVENT_OBJECT_DESTROY,
-                EVENT_OBJECT_DESTROY,
-                None,
-                Some(win_event_callback),
-                0,
-                0,
-                WINEVENT_OUTOFCONTEXT | WINEVENT_SKIPOWNPROCESS,
-            )
-        };
+        let hooks: Vec<HWINEVENTHOOK> = hooked_events
+            .iter()
+            .map(|&event| unsafe {
+                SetWinEventHook(
+                    event,
+                    event,
+                    None,
+                    Some(win_event_callback),
+                    0,
+                    0,
+                    WINEVENT_OUTOFCONTEXT | WINEVENT_SKIPOWNPROCESS,
+                )
+            })
+            .collect();
 
-        if foreground_hook.0 == 0 || create_hook.0 == 0 || destroy_hook.0 == 0 {
+        if hooks.iter().any(|hook| hook.0 == 0) {
             unsafe {
-                if foreground_hook.0 != 0 { let _ = UnhookWinEvent(foreground_hook); }
-                if create_hook.0 != 0 { let _ = UnhookWinEvent(create_hook); }
-                if destroy_hook.0 != 0 { let _ = UnhookWinEvent(destroy_hook); }
+                for hook in hooks.into_iter().filter(|hook| hook.0 != 0) {
+                    let _ = UnhookWinEvent(hook);
+                }
             }
             return Err("Failed to set one or more Windows event hooks".to_string());
         }
 
         // Store hooks in thread-local storage for cleanup
         let _ = HOOKS.with(|h| {
-            *h.borrow_mut() = Some((foreground_hook, create_hook, destroy_hook));
+            *h.borrow_mut() = Some(hooks);
         });
 
         // Store sender for callback to use
         let _ = EVENT_SENDER.with(|s| {
             *s.borrow_mut() = Some(event_sender);
         });
+        TOP_LEVEL_ONLY.with(|t| t.set(top_level_only));
+        TOP_LEVEL_WINDOWS.with(|w| *w.borrow_mut() = TopLevelWindows::default());
+        if top_level_only {
+            // So windows already open are reported when they're closed
+            if let Err(e) = unsafe { EnumWindows(Some(record_existing_window), LPARAM(0)) } {
+                warn!("Failed to list open windows: {}", e);
+            }
+        }
 
         info!("Window event hooks installed, starting message loop");
 
@@ -216,16 +549,14 @@ impl WindowEventPlugin {
         }
 
         // Cleanup hooks
-        unsafe {
-            let _ = UnhookWinEvent(foreground_hook);
-            let _ = UnhookWinEvent(create_hook);
-            let _ = UnhookWinEvent(destroy_hook);
+        if let Some(hooks) = HOOKS.with(|h| h.borrow_mut().take()) {
+            unsafe {
+                for hook in hooks {
+                    let _ = UnhookWinEvent(hook);
+                }
+            }
         }
 
-        let _ = HOOKS.with(|h| {
-            *h.borrow_mut() = None;
-        });
-
         let _ = EVENT_SENDER.with(|s| {
             *s.borrow_mut() = None;
         });
@@ -236,16 +567,24 @@ impl WindowEventPlugin {
 
 // Thread-local storage for hooks and sender
 thread_local! {
-    static HOOKS: std::cell::RefCell<Option<(windows::Win32::UI::Accessibility::HWINEVENTHOOK, windows::Win32::UI::Accessibility::HWINEVENTHOOK, windows::Win32::UI::Accessibility::HWINEVENTHOOK)>> = std::cell::RefCell::new(None);
+    static HOOKS: std::cell::RefCell<Option<Vec<HWINEVENTHOOK>>> = std::cell::RefCell::new(None);
     static EVENT_SENDER: std::cell::RefCell<Option<Sender<WindowEvent>>> = std::cell::RefCell::new(None);
+    static TOP_LEVEL_ONLY: std::cell::Cell<bool> = const { std::cell::Cell::new(true) };
+    static TOP_LEVEL_WINDOWS: std::cell::RefCell<TopLevelWindows> = std::cell::RefCell::new(TopLevelWindows::default());
+}
+
+unsafe extern "system" fn record_existing_window(hwnd: HWND, _: LPARAM) -> BOOL {
+    let attributes = WindowAttributes::query(hwnd, OBJID_WINDOW.0, CHILDID_SELF as i32);
+    TOP_LEVEL_WINDOWS.with(|w| w.borrow_mut().seen(hwnd.0, &attributes));
+    true.into()
 }
 
 unsafe extern "system" fn win_event_callback(
-    _hook: windows::Win32::UI::Accessibility::HWINEVENTHOOK,
+    _hook: HWINEVENTHOOK,
     event: u32,
     hwnd: HWND,
-    _id_object: i32,
-    _id_child: i32,
+    id_object: i32,
+    id_child: i32,
     _id_event_thread: u32,
     _dwms_event_time: u32,
 ) {
@@ -257,8 +596,50 @@ unsafe extern "system" fn win_event_callback(
 
     let event_type = match event {
         EVENT_SYSTEM_FOREGROUND => "focus",
-        EVENT_OBJECT_CREATE => "create",
-        EVENT_OBJECT_DESTROY => "destroy",
+        EVENT_OBJECT_CREATE => {
+            if TOP_LEVEL_ONLY.with(|t| t.get()) {
+                let attributes = WindowAttributes::query(hwnd, id_object, id_child);
+                if !TOP_LEVEL_WINDOWS.with(|w| w.borrow_mut().created(hwnd.0, &attributes)) {
+                    return;
+                }
+            }
+            "create"
+        }
+        // The window is already gone, so it can't be queried like on create
+        EVENT_OBJECT_DESTROY => {
+            if TOP_LEVEL_ONLY.with(|t| t.get()) {
+                let is_window_object =
+                    id_object == OBJID_WINDOW.0 && id_child == CHILDID_SELF as i32;
+                let recorded =
+                    TOP_LEVEL_WINDOWS.with(|w| w.borrow_mut().destroyed(hwnd.0, is_window_object));
+                if !recorded {
+                    return;
+                }
+            }
+            "destroy"
+        }
+        // Only hooked with top_level_only; reported as the window's creation
+        // the first time it turns out to be top-level
+        EVENT_OBJECT_SHOW | EVENT_OBJECT_NAMECHANGE => {
+            let attributes = WindowAttributes::query(hwnd, id_object, id_child);
+            if !TOP_LEVEL_WINDOWS.with(|w| w.borrow_mut().seen(hwnd.0, &attributes)) {
+                return;
+            }
+            "create"
+        }
+        EVENT_SYSTEM_MINIMIZESTART => "minimize",
+        EVENT_SYSTEM_MINIMIZEEND => "restore",
+        // Location changes also fire for carets, cursors and child controls;
+        // only top-level windows themselves are interesting
+        EVENT_OBJECT_LOCATIONCHANGE => {
+            if id_object != OBJID_WINDOW.0 || id_child != CHILDID_SELF as i32 {
+                return;
+            }
+            if unsafe { GetAncestor(hwnd, GA_ROOT) }.0 != hwnd.0 {
+                return;
+            }
+            "move"
+        }
         _ => return,
     };
 
@@ -297,6 +678,40 @@ unsafe extern "system" fn win_event_callback(
                         title,
                     })
                 }
+                "minimize" | "restore" => {
+                    if let Some((title, process_id, process_name)) =
+                        WindowEventPlugin::get_window_info(hwnd)
+                    {
+                        if event_type == "minimize" {
+                            Some(WindowEvent::Minimized {
+                                hwnd,
+                                title,
+                                process_name,
+                                process_id,
+                            })
+                        } else {
+                            Some(WindowEvent::Restored {
+                                hwnd,
+                                title,
+                                process_name,
+                                process_id,
+                            })
+                        }
+                    } else {
+                        None
+                    }
+                }
+                "move" => {
+                    let mut rect = RECT::default();
+                    if unsafe { GetWindowRect(hwnd, &mut rect) }.is_ok() {
+                        Some(WindowEvent::Moved {
+                            hwnd,
+                            bounds: WindowBounds::from(rect),
+                        })
+                    } else {
+                        None
+                    }
+                }
                 _ => None,
             };
 
@@ -322,24 +737,48 @@ impl EventSourcePlugin for WindowEventPlugin {
 
         let plugin_name = self.name.clone();
         let is_running = self.is_running.clone();
-        let previous_hwnd = self.previous_hwnd.clone();
+        let previous_focus = self.previous_focus.clone();
         let title_filter = self.title_filter.clone();
         let process_filter = self.process_filter.clone();
+        let emit_unfocus = self.emit_unfocus;
+        let track_location = self.track_location;
+        let top_level_only = self.top_level_only;
+        let mut moves = MoveDebouncer::new(self.move_debounce);
 
         self.is_running.store(true, Ordering::SeqCst);
 
-        // Create channel for thread communication
+        // Create tokio channel for async communication
+        let (tokio_sender, mut tokio_receiver) = tokio::sync::mpsc::channel(1000);
+
+        // Create std channel for hook thread to tokio bridge
         let (event_sender, event_receiver) = mpsc::channel::<WindowEvent>();
         self.event_sender = Some(event_sender.clone());
 
         // Spawn dedicated thread for Windows message loop
         let is_running_clone = is_running.clone();
+        let thread_id = self.hook_thread_id.clone();
         let hook_thread = thread::spawn(move || {
-            if let Err(e) = Self::run_message_loop(event_sender, is_running_clone) {
+            if let Err(e) = Self::run_message_loop(
+                event_sender,
+                is_running_clone,
+                track_location,
+                top_level_only,
+                thread_id,
+            ) {
                 error!("Window event hook thread failed: {}", e);
             }
         });
 
+        // Spawn bridge thread to forward from std channel to tokio channel. It
+        // exits once the hook thread and `stop()` have dropped their senders.
+        thread::spawn(move || {
+            while let Ok(window_event) = event_receiver.recv() {
+                if tokio_sender.blocking_send(window_event).is_err() {
+                    break;
+                }
+            }
+        });
+
         self.hook_thread = Some(hook_thread);
 
         // Give the hook a moment to register
@@ -356,75 +795,72 @@ impl EventSourcePlugin for WindowEventPlugin {
         }
 
         // Spawn async task to process events from the thread
-        tokio::spawn(async move {
+        let forward_task = tokio::spawn(async move {
             info!("Window event monitoring active (real-time via SetWinEventHook)");
 
+            let passes_filters = |title: &str, process_name: &str| {
+                title_filter
+                    .as_ref()
+                    .is_none_or(|regex| regex.is_match(title))
+                    && process_filter
+                        .as_ref()
+                        .is_none_or(|regex| regex.is_match(process_name))
+            };
+
             while is_running.load(Ordering::SeqCst) {
-                match event_receiver.try_recv() {
-                    Ok(window_event) => {
+                if emitter.is_closed() {
+                    is_running.store(false, Ordering::SeqCst);
+                    break;
+                }
+
+                // Wake up in time to report the oldest pending move
+                let received = match moves.next_flush() {
+                    Some(deadline) => {
+                        tokio::time::timeout_at(deadline.into(), tokio_receiver.recv())
+                            .await
+                            .ok()
+                    }
+                    None => Some(tokio_receiver.recv().await),
+                };
+
+                for (hwnd, bounds) in moves.drain_settled(Instant::now()) {
+                    let Some((title, process_id, process_name)) =
+                        WindowEventPlugin::get_window_info(HWND(hwnd))
+                    else {
+                        continue;
+                    };
+                    if passes_filters(&title, &process_name) {
+                        emitter.emit(Self::moved_event(
+                            &plugin_name,
+                            hwnd,
+                            bounds,
+                            &title,
+                            &process_name,
+                            process_id,
+                        ));
+                    }
+                }
+
+                match received {
+                    Some(Some(window_event)) => {
                         match window_event {
                             WindowEvent::Focused { hwnd, title, process_name, process_id } => {
-                                // Check filters
-                                let passes = if let Some(ref title_regex) = title_filter {
-                                    title_regex.is_match(&title)
-                                } else {
-                                    true
-                                } && if let Some(ref process_regex) = process_filter {
-                                    process_regex.is_match(&process_name)
-                                } else {
-                                    true
+                                let passes = passes_filters(&title, &process_name);
+                                let window_title = |prev: HWND| {
+                                    WindowEventPlugin::get_window_info(prev).map(|(t, _, _)| t)
                                 };
+                                let mut prev_guard = previous_focus.lock().await;
+                                let events = Self::focus_change_events(
+                                    &plugin_name, hwnd, &title, &process_name, process_id, passes,
+                                    &mut prev_guard, Instant::now(), emit_unfocus, window_title,
+                                );
 
-                                if !passes {
-                                    continue;
-                                }
-
-                                // Send unfocus event for previous window
-                                let mut prev_guard = previous_hwnd.lock().await;
-                                if let Some(prev_hwnd) = *prev_guard {
-                                    if prev_hwnd.0 != hwnd.0 {
-                                        if let Some((prev_title, _, _)) = WindowEventPlugin::get_window_info(prev_hwnd) {
-                                            let unfocus_event = Event::new(
-                                                EventKind::WindowUnfocused {
-                                                    hwnd: prev_hwnd.0 as isize,
-                                                    title: prev_title.clone(),
-                                                },
-                                                &plugin_name,
-                                            ).with_metadata("window_title", &prev_title);
-                                            
-                                            let _ = emitter.try_send(unfocus_event);
-                                        }
-                                    }
+                                for event in events {
+                                    emitter.emit(event);
                                 }
-
-                                // Send focus event for new window
-                                let focus_event = Event::new(
-                                    EventKind::WindowFocused {
-                                        hwnd: hwnd.0 as isize,
-                                        title: title.clone(),
-                                    },
-                                    &plugin_name,
-                                )
-                                .with_metadata("window_title", &title)
-                                .with_metadata("process_id", process_id.to_string())
-                                .with_metadata("process_name", &process_name);
-                                
-                                let _ = emitter.try_send(focus_event);
-                                *prev_guard = Some(hwnd);
                             }
                             WindowEvent::Created { hwnd, title, process_name, process_id } => {
-                                // Check filters
-                                let passes = if let Some(ref title_regex) = title_filter {
-                                    title_regex.is_match(&title)
-                                } else {
-                                    true
-                                } && if let Some(ref process_regex) = process_filter {
-                                    process_regex.is_match(&process_name)
-                                } else {
-                                    true
-                                };
-
-                                if !passes {
+                                if !passes_filters(&title, &process_name) {
                                     continue;
                                 }
 
@@ -439,9 +875,10 @@ impl EventSourcePlugin for WindowEventPlugin {
                                 .with_metadata("window_title", &title)
                                 .with_metadata("process_name", &process_name);
                                 
-                                let _ = emitter.try_send(create_event);
+                                emitter.emit(create_event);
                             }
                             WindowEvent::Destroyed { hwnd, title } => {
+                                moves.forget(hwnd.0);
                                 let destroyed_event = Event::new(
                                     EventKind::WindowDestroyed {
                                         hwnd: hwnd.0 as isize,
@@ -455,21 +892,38 @@ impl EventSourcePlugin for WindowEventPlugin {
                                     destroyed_event
                                 };
                                 
-                                let _ = emitter.try_send(destroyed_event);
+                                emitter.emit(destroyed_event);
+                            }
+                            WindowEvent::Minimized { hwnd, title, process_name, process_id } => {
+                                if passes_filters(&title, &process_name) {
+                                    emitter.emit(Self::minimize_event(
+                                        &plugin_name, hwnd, true, &title, &process_name, process_id,
+                                    ));
+                                }
+                            }
+                            WindowEvent::Restored { hwnd, title, process_name, process_id } => {
+                                if passes_filters(&title, &process_name) {
+                                    emitter.emit(Self::minimize_event(
+                                        &plugin_name, hwnd, false, &title, &process_name, process_id,
+                                    ));
+                                }
+                            }
+                            WindowEvent::Moved { hwnd, bounds } => {
+                                moves.record(hwnd.0, bounds, Instant::now());
                             }
                         }
                     }
-                    Err(mpsc::TryRecvError::Empty) => {
-                        tokio::time::sleep(Duration::from_millis(1)).await;
-                    }
-                    Err(mpsc::TryRecvError::Disconnected) => {
+                    Some(None) => {
+                        // Channel closed
                         break;
                     }
+                    None => {}
                 }
             }
 
             info!("Window event processing stopped");
         });
+        self.forward_task = Some(forward_task);
 
         info!("Window event monitoring active");
         Ok(())
@@ -481,18 +935,41 @@ impl EventSourcePlugin for WindowEventPlugin {
         
         // Signal sender to drop (which will cause thread to exit)
         self.event_sender = None;
-        
+
+        // GetMessageW blocks until the next message, so wake the loop up
+        let thread_id = self.hook_thread_id.swap(0, Ordering::SeqCst);
+        if thread_id != 0 {
+            let _ = unsafe { PostThreadMessageW(thread_id, WM_QUIT, WPARAM(0), LPARAM(0)) };
+        }
+
         // Wait for thread to finish
         if let Some(thread) = self.hook_thread.take() {
             let _ = thread.join();
         }
-        
+
+        // With every sender gone the bridge closes the channel and the task ends
+        if let Some(task) = self.forward_task.take() {
+            let _ = task.await;
+        }
+
         Ok(())
     }
 
     fn is_running(&self) -> bool {
         self.is_running.load(Ordering::SeqCst)
     }
+
+    fn health(&self) -> PluginHealth {
+        if !self.is_running() {
+            return PluginHealth::Stopped;
+        }
+        match &self.hook_thread {
+            Some(thread) if thread.is_finished() => {
+                PluginHealth::Failed("Window hook thread exited".to_string())
+            }
+            _ => PluginHealth::Healthy,
+        }
+    }
 }
 
 #[cfg(test)]
@@ -507,13 +984,412 @@ mod tests {
 
         assert!(!plugin.is_running());
 
-        plugin.start(tx).await.expect("Failed to start plugin");
+        plugin
+            .start(tx.into())
+            .await
+            .expect("Failed to start plugin");
         assert!(plugin.is_running());
 
         plugin.stop().await.expect("Failed to stop plugin");
         assert!(!plugin.is_running());
     }
 
+    #[tokio::test]
+    async fn test_window_plugin_stops_promptly() {
+        let (tx, mut rx) = tokio::sync::mpsc::channel(1000);
+        let mut plugin = WindowEventPlugin::new("test_window").with_location_tracking(true);
+        plugin
+            .start(tx.into())
+            .await
+            .expect("Failed to start plugin");
+
+        let started = Instant::now();
+        tokio::time::timeout(Duration::from_secs(2), plugin.stop())
+            .await
+            .expect("stop() did not return")
+            .expect("Failed to stop plugin");
+        assert!(started.elapsed() < Duration::from_secs(2));
+
+        // The forwarding task has exited and dropped the emitter
+        while rx.try_recv().is_ok() {}
+        assert!(matches!(
+            rx.try_recv(),
+            Err(tokio::sync::mpsc::error::TryRecvError::Disconnected)
+        ));
+    }
+
+    /// Focus `hwnds` in turn, one second apart
+    fn focus_sequence(hwnds: &[isize], emit_unfocus: bool) -> Vec<Event> {
+        filtered_focus_sequence(hwnds, &[], emit_unfocus)
+    }
+
+    /// Like `focus_sequence`, with the `filtered` windows failing the filters
+    fn filtered_focus_sequence(
+        hwnds: &[isize],
+        filtered: &[isize],
+        emit_unfocus: bool,
+    ) -> Vec<Event> {
+        let start = Instant::now();
+        let mut previous = None;
+        let mut events = Vec::new();
+        for (i, &hwnd) in hwnds.iter().enumerate() {
+            events.extend(WindowEventPlugin::focus_change_events(
+                "test",
+                HWND(hwnd),
+                "Window",
+                "app.exe",
+                42,
+                !filtered.contains(&hwnd),
+                &mut previous,
+                start + Duration::from_secs(i as u64),
+                emit_unfocus,
+                |prev| Some(format!("Window {}", prev.0)),
+            ));
+        }
+        events
+    }
+
+    #[test]
+    fn test_unfocus_carries_focus_duration() {
+        // 1 focused, refocused (a repeat), then 2 and back to 1
+        let events = focus_sequence(&[1, 1, 2, 1], true);
+        let durations: Vec<_> = events
+            .iter()
+            .filter_map(|e| match &e.kind {
+                EventKind::WindowUnfocused { hwnd, .. } => {
+                    Some((*hwnd, e.metadata["focus_duration_ms"].as_str()))
+                }
+                _ => None,
+            })
+            .collect();
+
+        // The repeat doesn't restart window 1's time in focus
+        assert_eq!(durations, vec![(1, "2000"), (2, "1000")]);
+        assert!(
+            events
+                .iter()
+                .filter(|e| matches!(e.kind, EventKind::WindowFocused { .. }))
+                .all(|e| !e.metadata.contains_key("focus_duration_ms"))
+        );
+    }
+
+    #[test]
+    fn test_focus_change_emits_unfocus_by_default() {
+        let plugin = WindowEventPlugin::new("test");
+        assert!(plugin.emit_unfocus);
+
+        let events = focus_sequence(&[1, 2, 1], true);
+        let unfocused: Vec<_> = events
+            .iter()
+            .filter_map(|e| match &e.kind {
+                EventKind::WindowUnfocused { hwnd, .. } => Some(*hwnd),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(events.len(), 5);
+        assert_eq!(unfocused, vec![1, 2]);
+    }
+
+    #[test]
+    fn test_focus_change_without_unfocus() {
+        let plugin = WindowEventPlugin::new("test").with_emit_unfocus(false);
+        assert!(!plugin.emit_unfocus);
+
+        let events = focus_sequence(&[1, 2, 1], false);
+        assert_eq!(events.len(), 3);
+        assert!(
+            events
+                .iter()
+                .all(|e| matches!(e.kind, EventKind::WindowFocused { .. }))
+        );
+    }
+
+    #[test]
+    fn test_repeated_focus_is_forwarded_once() {
+        for emit_unfocus in [true, false] {
+            let events = focus_sequence(&[1, 1], emit_unfocus);
+            assert_eq!(events.len(), 1);
+            assert!(matches!(
+                events[0].kind,
+                EventKind::WindowFocused { hwnd: 1, .. }
+            ));
+        }
+
+        // A repeat after switching away still emits the focus change
+        let events = focus_sequence(&[1, 1, 2, 2, 1], true);
+        let focused: Vec<_> = events
+            .iter()
+            .filter_map(|e| match &e.kind {
+                EventKind::WindowFocused { hwnd, .. } => Some(*hwnd),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(focused, vec![1, 2, 1]);
+    }
+
+    #[test]
+    fn test_filtered_window_ends_focus_stretch() {
+        // 1 focused, then filtered-out 2 for three seconds, then back to 1
+        let events = filtered_focus_sequence(&[1, 2, 2, 2, 1], &[2], true);
+        let kinds: Vec<_> = events
+            .iter()
+            .map(|e| match &e.kind {
+                EventKind::WindowFocused { hwnd, .. } => ("focused", *hwnd),
+                EventKind::WindowUnfocused { hwnd, .. } => ("unfocused", *hwnd),
+                other => panic!("unexpected event {other:?}"),
+            })
+            .collect();
+
+        // Window 1 loses focus to 2 and gets it back, but 2 itself is silent
+        assert_eq!(
+            kinds,
+            vec![("focused", 1), ("unfocused", 1), ("focused", 1)]
+        );
+        assert_eq!(events[1].metadata["focus_duration_ms"], "1000");
+    }
+
+    #[test]
+    fn test_top_level_predicate() {
+        let app_window = WindowAttributes {
+            is_window_object: true,
+            is_root: true,
+            has_title: true,
+            is_visible: true,
+        };
+        assert!(app_window.is_top_level());
+
+        // Titled but still hidden (e.g. created before being shown), or
+        // untitled but visible, both count
+        assert!(
+            WindowAttributes {
+                is_visible: false,
+                ..app_window
+            }
+            .is_top_level()
+        );
+        assert!(
+            WindowAttributes {
+                has_title: false,
+                ..app_window
+            }
+            .is_top_level()
+        );
+
+        // Tooltips and menu popups: untitled and hidden
+        assert!(
+            !WindowAttributes {
+                has_title: false,
+                is_visible: false,
+                ..app_window
+            }
+            .is_top_level()
+        );
+        // Child controls
+        assert!(
+            !WindowAttributes {
+                is_root: false,
+                ..app_window
+            }
+            .is_top_level()
+        );
+        // Accessible objects inside a window
+        assert!(
+            !WindowAttributes {
+                is_window_object: false,
+                ..app_window
+            }
+            .is_top_level()
+        );
+
+        assert!(WindowEventPlugin::new("test").top_level_only);
+        assert!(
+            !WindowEventPlugin::new("test")
+                .with_top_level_only(false)
+                .top_level_only
+        );
+    }
+
+    #[test]
+    fn test_destroy_reported_only_for_recorded_top_level_windows() {
+        let app_window = WindowAttributes {
+            is_window_object: true,
+            is_root: true,
+            has_title: true,
+            is_visible: true,
+        };
+        let tooltip = WindowAttributes {
+            has_title: false,
+            is_visible: false,
+            ..app_window
+        };
+        let mut windows = TopLevelWindows::default();
+
+        assert!(windows.created(1, &app_window));
+        assert!(!windows.created(2, &tooltip));
+
+        // A control inside the window going away isn't the window itself
+        assert!(!windows.destroyed(1, false));
+        assert!(windows.destroyed(1, true));
+        // Reported once, and never for windows that weren't top-level
+        assert!(!windows.destroyed(1, true));
+        assert!(!windows.destroyed(2, true));
+        // Nor for windows that were never recorded
+        assert!(!windows.destroyed(3, true));
+    }
+
+    #[test]
+    fn test_window_recorded_once_it_becomes_top_level() {
+        let app_window = WindowAttributes {
+            is_window_object: true,
+            is_root: true,
+            has_title: true,
+            is_visible: true,
+        };
+        let hidden = WindowAttributes {
+            has_title: false,
+            is_visible: false,
+            ..app_window
+        };
+        let mut windows = TopLevelWindows::default();
+
+        // Created hidden and untitled, then shown
+        assert!(!windows.created(1, &hidden));
+        assert!(!windows.seen(1, &hidden));
+        assert!(windows.seen(1, &app_window));
+        // Only the first time counts, including for windows seen being created
+        assert!(!windows.seen(1, &app_window));
+        assert!(windows.created(2, &app_window));
+        assert!(!windows.seen(2, &app_window));
+
+        assert!(windows.destroyed(1, true));
+        assert!(windows.destroyed(2, true));
+    }
+
+    #[test]
+    fn test_location_tracking_is_opt_in() {
+        let plugin = WindowEventPlugin::new("test");
+        assert!(!plugin.track_location);
+        assert_eq!(plugin.move_debounce, DEFAULT_MOVE_DEBOUNCE);
+
+        let plugin = plugin
+            .with_location_tracking(true)
+            .with_move_debounce(Duration::from_millis(100));
+        assert!(plugin.track_location);
+        assert_eq!(plugin.move_debounce, Duration::from_millis(100));
+    }
+
+    #[test]
+    fn test_moved_event_from_rect() {
+        let rect = RECT {
+            left: 100,
+            top: 50,
+            right: 900,
+            bottom: 650,
+        };
+        let event = WindowEventPlugin::moved_event(
+            "test",
+            7,
+            WindowBounds::from(rect),
+            "Untitled - Notepad",
+            "notepad.exe",
+            42,
+        );
+
+        match event.kind {
+            EventKind::WindowMoved {
+                hwnd,
+                x,
+                y,
+                width,
+                height,
+            } => {
+                assert_eq!((hwnd, x, y, width, height), (7, 100, 50, 800, 600));
+            }
+            other => panic!("expected WindowMoved, got {:?}", other),
+        }
+        assert_eq!(
+            event.metadata.get("window_title").map(String::as_str),
+            Some("Untitled - Notepad")
+        );
+        assert_eq!(
+            event.metadata.get("process_name").map(String::as_str),
+            Some("notepad.exe")
+        );
+    }
+
+    #[test]
+    fn test_minimize_and_restore_events_carry_metadata() {
+        let minimized = WindowEventPlugin::minimize_event(
+            "test",
+            HWND(7),
+            true,
+            "Inbox - Outlook",
+            "OUTLOOK.EXE",
+            42,
+        );
+        let restored = WindowEventPlugin::minimize_event(
+            "test",
+            HWND(7),
+            false,
+            "Inbox - Outlook",
+            "OUTLOOK.EXE",
+            42,
+        );
+
+        assert!(matches!(
+            &minimized.kind,
+            EventKind::WindowMinimized { hwnd: 7, title } if title == "Inbox - Outlook"
+        ));
+        assert!(matches!(
+            &restored.kind,
+            EventKind::WindowRestored { hwnd: 7, title } if title == "Inbox - Outlook"
+        ));
+        for event in [&minimized, &restored] {
+            assert_eq!(event.source, "test");
+            assert_eq!(
+                event.metadata.get("window_title").map(String::as_str),
+                Some("Inbox - Outlook")
+            );
+            assert_eq!(
+                event.metadata.get("process_name").map(String::as_str),
+                Some("OUTLOOK.EXE")
+            );
+            assert_eq!(
+                event.metadata.get("process_id").map(String::as_str),
+                Some("42")
+            );
+        }
+    }
+
+    #[test]
+    fn test_move_debounce_reports_final_bounds() {
+        let mut moves = MoveDebouncer::new(Duration::from_millis(250));
+        let start = Instant::now();
+        let at = |ms| start + Duration::from_millis(ms);
+        let bounds = |x| WindowBounds {
+            x,
+            y: 0,
+            width: 800,
+            height: 600,
+        };
+
+        // A drag: many intermediate positions in quick succession
+        for (i, x) in [10, 20, 30, 40].into_iter().enumerate() {
+            moves.record(1, bounds(x), at(i as u64 * 50));
+        }
+        moves.record(2, bounds(500), at(100));
+        assert!(moves.drain_settled(at(300)).is_empty());
+
+        assert_eq!(moves.drain_settled(at(350)), vec![(2, bounds(500))]);
+        assert_eq!(moves.drain_settled(at(400)), vec![(1, bounds(40))]);
+        assert!(moves.drain_settled(at(1000)).is_empty());
+
+        // Destroyed windows never report their pending move
+        moves.record(3, bounds(0), at(1000));
+        moves.forget(3);
+        assert!(moves.drain_settled(at(2000)).is_empty());
+    }
+
     #[test]
     fn test_title_filter() {
         let plugin = WindowEventPlugin::new("test")
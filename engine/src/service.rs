@@ -1,8 +1,9 @@
+use engine_core::log_rotation;
 use std::fs::{File, OpenOptions};
-use std::io::Write;
+use std::io::{self, Write};
 use std::path::PathBuf;
 use std::sync::atomic::{AtomicBool, Ordering};
-use std::sync::Arc;
+use std::sync::{Arc, Mutex};
 use std::thread;
 use std::time::Duration;
 use tokio::sync::mpsc;
@@ -10,7 +11,10 @@ use tracing::info;
 use tracing_subscriber::prelude::*;
 use windows_service::{
     define_windows_service,
-    service::{ServiceAccess, ServiceControl, ServiceControlAccept, ServiceExitCode, ServiceStatus},
+    service::{
+        ServiceAccess, ServiceControl, ServiceControlAccept, ServiceExitCode, ServiceState,
+        ServiceStatus,
+    },
     service::{ServiceInfo, ServiceStartType, ServiceType},
     service_control_handler::{self, ServiceControlHandlerResult},
     service_dispatcher,
@@ -20,10 +24,194 @@ use windows_service::{
 const SERVICE_NAME: &str = "WinEventEngine";
 const SERVICE_DISPLAY_NAME: &str = "Windows Event Automation Engine";
 
+/// The service log, shared by `log_to_file` and the tracing layer. Opened
+/// with the config defaults and resized once the config has been loaded.
+static SERVICE_LOG: Mutex<Option<RotatingLog>> = Mutex::new(None);
+
 fn log_to_file(msg: &str) {
-    let log_path = get_service_log_path();
-    if let Ok(mut file) = OpenOptions::new().create(true).append(true).open(&log_path) {
-        let _ = writeln!(file, "{}", msg);
+    let _ = with_service_log(|log| log.write_all(format!("{}\n", msg).as_bytes()));
+}
+
+fn with_service_log<T>(f: impl FnOnce(&mut RotatingLog) -> io::Result<T>) -> io::Result<T> {
+    let mut log = SERVICE_LOG.lock().unwrap_or_else(|e| e.into_inner());
+    let log = log.get_or_insert_with(|| {
+        let defaults = crate::config::EngineConfig::default();
+        RotatingLog::open(
+            get_service_log_path(),
+            defaults.service_log_max_bytes,
+            defaults.service_log_max_files,
+        )
+    });
+    f(log)
+}
+
+/// Lets the tracing layer write through [`SERVICE_LOG`]
+struct ServiceLogWriter;
+
+impl Write for ServiceLogWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        with_service_log(|log| log.write(buf))
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        with_service_log(|log| log.flush())
+    }
+}
+
+/// Appends to `path`, first rolling it to `path.1` (and older files up to
+/// `path.<max_files>`) when a write would take it past `max_bytes`
+struct RotatingLog {
+    path: PathBuf,
+    max_bytes: u64,
+    max_files: usize,
+    file: Option<File>,
+    size: u64,
+}
+
+impl RotatingLog {
+    fn open(path: PathBuf, max_bytes: u64, max_files: usize) -> Self {
+        let mut log = Self {
+            path,
+            max_bytes,
+            max_files,
+            file: None,
+            size: 0,
+        };
+        if let Err(e) = log.reopen() {
+            eprintln!("Failed to open log file {:?}: {}", log.path, e);
+        }
+        log
+    }
+
+    fn reopen(&mut self) -> io::Result<()> {
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)?;
+        self.size = file.metadata()?.len();
+        self.file = Some(file);
+        Ok(())
+    }
+
+    fn rotate(&mut self) -> io::Result<()> {
+        // Windows won't rename a file that is still open
+        self.file = None;
+        log_rotation::rotate_files(&self.path, self.max_files)?;
+        self.reopen()
+    }
+}
+
+impl Write for RotatingLog {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if log_rotation::should_rotate(self.size, buf.len(), self.max_bytes)
+            && let Err(e) = self.rotate()
+        {
+            eprintln!("Failed to rotate log file {:?}: {}", self.path, e);
+        }
+
+        let file = match &mut self.file {
+            Some(file) => file,
+            None => {
+                self.reopen()?;
+                self.file.as_mut().ok_or(io::ErrorKind::NotFound)?
+            }
+        };
+        let written = file.write(buf)?;
+        self.size += written as u64;
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        match &mut self.file {
+            Some(file) => file.flush(),
+            None => Ok(()),
+        }
+    }
+}
+
+/// Service operations the CLI can request; implemented by
+/// [`ServiceManagerHandle`] and mocked in tests
+pub trait ServiceManagement {
+    fn install(&self, exe_path: &str) -> Result<(), ServiceError>;
+    fn uninstall(&self) -> Result<(), ServiceError>;
+    fn start(&self) -> Result<(), ServiceError>;
+    fn stop(&self) -> Result<(), ServiceError>;
+    fn status(&self) -> Result<ServiceState, ServiceError>;
+}
+
+/// A service operation selected on the command line
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ServiceCommand {
+    Install,
+    Uninstall,
+    Start,
+    Stop,
+    Status,
+}
+
+/// Carry out `command`, returning the message to show the user
+pub fn run_service_command(
+    manager: &dyn ServiceManagement,
+    command: ServiceCommand,
+    exe_path: &str,
+) -> Result<String, ServiceError> {
+    match command {
+        ServiceCommand::Install => {
+            manager.install(exe_path)?;
+            Ok("Service installed successfully. Use --start-service to start it.".to_string())
+        }
+        ServiceCommand::Uninstall => {
+            // The SCM only removes a service once it has stopped
+            if matches!(manager.status(), Ok(state) if state != ServiceState::Stopped) {
+                manager.stop()?;
+            }
+            manager.uninstall()?;
+            Ok("Service uninstalled successfully.".to_string())
+        }
+        ServiceCommand::Start => {
+            manager.start()?;
+            Ok("Service start requested.".to_string())
+        }
+        ServiceCommand::Stop => {
+            manager.stop()?;
+            Ok("Service stop requested.".to_string())
+        }
+        ServiceCommand::Status => {
+            let state = manager.status()?;
+            Ok(format!("{}: {}", SERVICE_NAME, state_name(state)))
+        }
+    }
+}
+
+/// The state to report to the SCM once `control` has been handled, given
+/// whether the engine was paused beforehand
+fn state_after_control(control: &ServiceControl, paused: bool) -> ServiceState {
+    match control {
+        ServiceControl::Stop => ServiceState::StopPending,
+        ServiceControl::Pause => ServiceState::Paused,
+        ServiceControl::Continue => ServiceState::Running,
+        _ => running_state(paused),
+    }
+}
+
+/// The state of a service whose engine is up
+fn running_state(paused: bool) -> ServiceState {
+    if paused {
+        ServiceState::Paused
+    } else {
+        ServiceState::Running
+    }
+}
+
+fn state_name(state: ServiceState) -> &'static str {
+    match state {
+        ServiceState::Stopped => "stopped",
+        ServiceState::StartPending => "starting",
+        ServiceState::StopPending => "stopping",
+        ServiceState::Running => "running",
+        ServiceState::ContinuePending => "resuming",
+        ServiceState::PausePending => "pausing",
+        ServiceState::Paused => "paused",
     }
 }
 
@@ -32,16 +220,25 @@ pub struct ServiceManagerHandle {
 }
 
 impl ServiceManagerHandle {
+    /// Connect with the rights needed to install the service (requires admin)
     pub fn new() -> Result<Self, ServiceError> {
-        let manager = ServiceManager::local_computer(
-            None::<&str>,
-            ServiceManagerAccess::CONNECT | ServiceManagerAccess::CREATE_SERVICE,
-        )
-        .map_err(|e| ServiceError::OpenScManager(e.to_string()))?;
+        Self::with_access(ServiceManagerAccess::CONNECT | ServiceManagerAccess::CREATE_SERVICE)
+    }
+
+    /// Connect without install rights, enough for controlling an existing service
+    pub fn connect() -> Result<Self, ServiceError> {
+        Self::with_access(ServiceManagerAccess::CONNECT)
+    }
+
+    fn with_access(access: ServiceManagerAccess) -> Result<Self, ServiceError> {
+        let manager = ServiceManager::local_computer(None::<&str>, access)
+            .map_err(|e| ServiceError::OpenScManager(e.to_string()))?;
         Ok(Self { manager })
     }
+}
 
-    pub fn install(&self, exe_path: &str) -> Result<(), ServiceError> {
+impl ServiceManagement for ServiceManagerHandle {
+    fn install(&self, exe_path: &str) -> Result<(), ServiceError> {
         let service_info = ServiceInfo {
             name: SERVICE_NAME.into(),
             display_name: SERVICE_DISPLAY_NAME.into(),
@@ -63,7 +260,7 @@ impl ServiceManagerHandle {
         Ok(())
     }
 
-    pub fn uninstall(&self) -> Result<(), ServiceError> {
+    fn uninstall(&self) -> Result<(), ServiceError> {
         let service = self
             .manager
             .open_service(SERVICE_NAME, ServiceAccess::DELETE)
@@ -76,6 +273,41 @@ impl ServiceManagerHandle {
         info!("Service uninstalled successfully");
         Ok(())
     }
+
+    fn start(&self) -> Result<(), ServiceError> {
+        let service = self
+            .manager
+            .open_service(SERVICE_NAME, ServiceAccess::START)
+            .map_err(|e| ServiceError::Start(e.to_string()))?;
+
+        service
+            .start::<&str>(&[])
+            .map_err(|e| ServiceError::Start(e.to_string()))
+    }
+
+    fn stop(&self) -> Result<(), ServiceError> {
+        let service = self
+            .manager
+            .open_service(SERVICE_NAME, ServiceAccess::STOP)
+            .map_err(|e| ServiceError::Stop(e.to_string()))?;
+
+        service
+            .stop()
+            .map(|_| ())
+            .map_err(|e| ServiceError::Stop(e.to_string()))
+    }
+
+    fn status(&self) -> Result<ServiceState, ServiceError> {
+        let service = self
+            .manager
+            .open_service(SERVICE_NAME, ServiceAccess::QUERY_STATUS)
+            .map_err(|e| ServiceError::Query(e.to_string()))?;
+
+        service
+            .query_status()
+            .map(|status| status.current_state)
+            .map_err(|e| ServiceError::Query(e.to_string()))
+    }
 }
 
 define_windows_service!(ffi_service_main, service_main);
@@ -84,18 +316,9 @@ static STOP_FLAG: AtomicBool = AtomicBool::new(false);
 
 fn service_main(_arguments: Vec<std::ffi::OsString>) {
     log_to_file("Service starting...");
-    
-    let log_path = get_service_log_path();
-    let log_file = match File::create(&log_path) {
-        Ok(f) => f,
-        Err(e) => {
-            eprintln!("Failed to create log file: {}", e);
-            return;
-        }
-    };
 
     let file_layer = tracing_subscriber::fmt::layer()
-        .with_writer(std::sync::Mutex::new(log_file))
+        .with_writer(|| ServiceLogWriter)
         .with_ansi(false);
 
     let _ = tracing_subscriber::registry()
@@ -121,6 +344,12 @@ fn service_main(_arguments: Vec<std::ffi::OsString>) {
         crate::config::Config::default()
     };
 
+    let _ = with_service_log(|log| {
+        log.max_bytes = config.engine.service_log_max_bytes;
+        log.max_files = config.engine.service_log_max_files;
+        Ok(())
+    });
+
     log_to_file("Creating engine...");
     let mut engine = crate::engine::Engine::new(config, Some(config_path));
 
@@ -129,6 +358,9 @@ fn service_main(_arguments: Vec<std::ffi::OsString>) {
     
     let (shutdown_tx, mut shutdown_rx) = mpsc::channel::<()>(1);
     
+    let pause_flag = engine.pause_flag();
+    let handler_pause_flag = pause_flag.clone();
+
     let status_handle = match service_control_handler::register(SERVICE_NAME, move |control_event| {
         match control_event {
             ServiceControl::Stop => {
@@ -137,6 +369,14 @@ fn service_main(_arguments: Vec<std::ffi::OsString>) {
                 let _ = shutdown_tx.try_send(());
                 ServiceControlHandlerResult::NoError
             }
+            ServiceControl::Pause | ServiceControl::Continue => {
+                let paused = handler_pause_flag.load(Ordering::Relaxed);
+                let state = state_after_control(&control_event, paused);
+                log_to_file(&format!("{:?} control received", control_event));
+                // The main thread notices the change and reports it to the SCM
+                handler_pause_flag.store(state == ServiceState::Paused, Ordering::Relaxed);
+                ServiceControlHandlerResult::NoError
+            }
             ServiceControl::Interrogate => {
                 ServiceControlHandlerResult::NoError
             }
@@ -164,17 +404,29 @@ fn service_main(_arguments: Vec<std::ffi::OsString>) {
             }
             
             log_to_file("Engine running successfully");
-            
-            tokio::select! {
-                _ = async {
-                    while !stop_flag_clone.load(Ordering::Relaxed) {
-                        tokio::time::sleep(Duration::from_millis(100)).await;
+
+            // Periodically look for sources that died without being stopped
+            let mut plugin_check = engine.plugin_check_timer();
+            loop {
+                tokio::select! {
+                    _ = async {
+                        while !stop_flag_clone.load(Ordering::Relaxed) {
+                            tokio::time::sleep(Duration::from_millis(100)).await;
+                        }
+                    } => {
+                        log_to_file("Stop flag detected in engine");
+                        break;
+                    }
+                    _ = shutdown_rx.recv() => {
+                        log_to_file("Shutdown signal received");
+                        break;
+                    }
+                    _ = crate::engine::next_plugin_check(&mut plugin_check) => {
+                        let failed = engine.check_plugin_health().await;
+                        if !failed.is_empty() {
+                            log_to_file(&format!("Plugins found dead: {}", failed.join(", ")));
+                        }
                     }
-                } => {
-                    log_to_file("Stop flag detected in engine");
-                }
-                _ = shutdown_rx.recv() => {
-                    log_to_file("Shutdown signal received");
                 }
             }
             
@@ -189,20 +441,34 @@ fn service_main(_arguments: Vec<std::ffi::OsString>) {
     log_to_file("Service running - engine initialized");
     
     if let Some(handle) = status_handle {
+        let mut reported_paused = pause_flag.load(Ordering::Relaxed);
         let status = ServiceStatus {
             service_type: ServiceType::OWN_PROCESS,
-            controls_accepted: ServiceControlAccept::STOP,
-            current_state: windows_service::service::ServiceState::Running,
+            controls_accepted: ServiceControlAccept::STOP | ServiceControlAccept::PAUSE_CONTINUE,
+            current_state: running_state(reported_paused),
             exit_code: ServiceExitCode::Win32(0),
             checkpoint: 0,
             wait_hint: Duration::from_secs(60),
             process_id: Some(std::process::id()),
         };
         
-        let _ = handle.set_service_status(status);
+        let _ = handle.set_service_status(status.clone());
         log_to_file("Service status set to RUNNING");
         
         while !STOP_FLAG.load(Ordering::Relaxed) {
+            let paused = pause_flag.load(Ordering::Relaxed);
+            if paused != reported_paused {
+                let state = running_state(paused);
+                let _ = handle.set_service_status(ServiceStatus {
+                    current_state: state,
+                    ..status.clone()
+                });
+                log_to_file(&format!(
+                    "Service status set to {}",
+                    state_name(state).to_uppercase()
+                ));
+                reported_paused = paused;
+            }
             thread::sleep(Duration::from_millis(100));
         }
         
@@ -269,6 +535,8 @@ pub enum ServiceError {
     Install(String),
     Uninstall(String),
     Start(String),
+    Stop(String),
+    Query(String),
     Config(String),
 }
 
@@ -279,9 +547,158 @@ impl std::fmt::Display for ServiceError {
             ServiceError::Install(msg) => write!(f, "Failed to install service: {}", msg),
             ServiceError::Uninstall(msg) => write!(f, "Failed to uninstall service: {}", msg),
             ServiceError::Start(msg) => write!(f, "Failed to start service: {}", msg),
+            ServiceError::Stop(msg) => write!(f, "Failed to stop service: {}", msg),
+            ServiceError::Query(msg) => write!(f, "Failed to query service status: {}", msg),
             ServiceError::Config(msg) => write!(f, "Service configuration error: {}", msg),
         }
     }
 }
 
 impl std::error::Error for ServiceError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::{Cell, RefCell};
+
+    /// Records the calls made to it, reporting `state` as the service status
+    struct MockManager {
+        state: Cell<ServiceState>,
+        calls: RefCell<Vec<String>>,
+    }
+
+    impl MockManager {
+        fn new(state: ServiceState) -> Self {
+            Self {
+                state: Cell::new(state),
+                calls: RefCell::new(Vec::new()),
+            }
+        }
+
+        fn calls(&self) -> Vec<String> {
+            self.calls.borrow().clone()
+        }
+    }
+
+    impl ServiceManagement for MockManager {
+        fn install(&self, exe_path: &str) -> Result<(), ServiceError> {
+            self.calls
+                .borrow_mut()
+                .push(format!("install {}", exe_path));
+            Ok(())
+        }
+
+        fn uninstall(&self) -> Result<(), ServiceError> {
+            self.calls.borrow_mut().push("uninstall".to_string());
+            Ok(())
+        }
+
+        fn start(&self) -> Result<(), ServiceError> {
+            self.calls.borrow_mut().push("start".to_string());
+            if self.state.get() == ServiceState::Running {
+                return Err(ServiceError::Start("already running".to_string()));
+            }
+            self.state.set(ServiceState::Running);
+            Ok(())
+        }
+
+        fn stop(&self) -> Result<(), ServiceError> {
+            self.calls.borrow_mut().push("stop".to_string());
+            self.state.set(ServiceState::Stopped);
+            Ok(())
+        }
+
+        fn status(&self) -> Result<ServiceState, ServiceError> {
+            Ok(self.state.get())
+        }
+    }
+
+    #[test]
+    fn test_service_commands_dispatch() {
+        let manager = MockManager::new(ServiceState::Stopped);
+        let exe = "C:/engine.exe";
+
+        run_service_command(&manager, ServiceCommand::Install, exe).unwrap();
+        run_service_command(&manager, ServiceCommand::Start, exe).unwrap();
+        assert_eq!(
+            run_service_command(&manager, ServiceCommand::Status, exe).unwrap(),
+            "WinEventEngine: running"
+        );
+        run_service_command(&manager, ServiceCommand::Stop, exe).unwrap();
+
+        assert_eq!(
+            manager.calls(),
+            vec!["install C:/engine.exe", "start", "stop"]
+        );
+    }
+
+    #[test]
+    fn test_uninstall_stops_running_service() {
+        let manager = MockManager::new(ServiceState::Running);
+        run_service_command(&manager, ServiceCommand::Uninstall, "").unwrap();
+        assert_eq!(manager.calls(), vec!["stop", "uninstall"]);
+
+        let manager = MockManager::new(ServiceState::Stopped);
+        run_service_command(&manager, ServiceCommand::Uninstall, "").unwrap();
+        assert_eq!(manager.calls(), vec!["uninstall"]);
+    }
+
+    #[test]
+    fn test_service_command_errors_are_returned() {
+        let manager = MockManager::new(ServiceState::Running);
+        let err = run_service_command(&manager, ServiceCommand::Start, "").unwrap_err();
+        assert_eq!(err.to_string(), "Failed to start service: already running");
+    }
+
+    #[test]
+    fn test_state_after_control() {
+        assert_eq!(
+            state_after_control(&ServiceControl::Pause, false),
+            ServiceState::Paused
+        );
+        assert_eq!(
+            state_after_control(&ServiceControl::Continue, true),
+            ServiceState::Running
+        );
+        assert_eq!(
+            state_after_control(&ServiceControl::Stop, true),
+            ServiceState::StopPending
+        );
+        // Pausing twice, or continuing while running, is harmless
+        assert_eq!(
+            state_after_control(&ServiceControl::Pause, true),
+            ServiceState::Paused
+        );
+        assert_eq!(
+            state_after_control(&ServiceControl::Continue, false),
+            ServiceState::Running
+        );
+        // Interrogation reports the current state unchanged
+        assert_eq!(
+            state_after_control(&ServiceControl::Interrogate, true),
+            ServiceState::Paused
+        );
+        assert_eq!(
+            state_after_control(&ServiceControl::Interrogate, false),
+            ServiceState::Running
+        );
+    }
+
+    #[test]
+    fn test_rotating_log_keeps_max_files() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let path = dir.path().join("service.log");
+        let mut log = RotatingLog::open(path.clone(), 10, 2);
+
+        for line in ["first\n", "second\n", "third\n", "fourth\n"] {
+            log.write_all(line.as_bytes()).unwrap();
+        }
+        log.flush().unwrap();
+
+        let read = |name: &str| std::fs::read_to_string(dir.path().join(name)).unwrap();
+        assert_eq!(read("service.log"), "fourth\n");
+        assert_eq!(read("service.log.1"), "third\n");
+        assert_eq!(read("service.log.2"), "second\n");
+        assert!(!dir.path().join("service.log.3").exists());
+    }
+}
@@ -0,0 +1,237 @@
+use async_trait::async_trait;
+use engine_core::event::Event;
+use engine_core::log_rotation::{self, DEFAULT_MAX_BYTES, DEFAULT_MAX_FILES};
+use engine_core::sink::{EventSink, SinkError};
+use serde::Serialize;
+use std::fs::{File, OpenOptions};
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+use tracing::info;
+
+/// One line of the file: the event plus the wall-clock time it was written
+#[derive(Serialize)]
+struct Record<'a> {
+    timestamp: String,
+    #[serde(flatten)]
+    event: &'a Event,
+}
+
+/// Appends every event to `path` as one JSON object per line. Once the file
+/// would grow past `max_bytes` it is renamed to `path.1` (shifting older files
+/// up to `path.<max_files>`, the oldest being deleted) and a new file started.
+pub struct JsonlFileSink {
+    name: String,
+    path: PathBuf,
+    max_bytes: u64,
+    max_files: usize,
+    writer: Option<BufWriter<File>>,
+    written: u64,
+}
+
+impl JsonlFileSink {
+    /// Open (or create) `path` for appending
+    pub fn new(name: impl Into<String>, path: impl Into<PathBuf>) -> Result<Self, SinkError> {
+        let path = path.into();
+        if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+            std::fs::create_dir_all(parent).map_err(|e| io_error(parent, e))?;
+        }
+
+        let (writer, written) = Self::open(&path)?;
+        Ok(Self {
+            name: name.into(),
+            path,
+            max_bytes: DEFAULT_MAX_BYTES,
+            max_files: DEFAULT_MAX_FILES,
+            writer: Some(writer),
+            written,
+        })
+    }
+
+    pub fn with_max_bytes(mut self, max_bytes: u64) -> Self {
+        self.max_bytes = max_bytes;
+        self
+    }
+
+    /// Rotated files to keep; 0 discards the old file on rotation
+    pub fn with_max_files(mut self, max_files: usize) -> Self {
+        self.max_files = max_files;
+        self
+    }
+
+    fn open(path: &Path) -> Result<(BufWriter<File>, u64), SinkError> {
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .map_err(|e| io_error(path, e))?;
+        let written = file.metadata().map_err(|e| io_error(path, e))?.len();
+        Ok((BufWriter::new(file), written))
+    }
+
+    fn rotate(&mut self) -> Result<(), SinkError> {
+        // Windows won't rename a file that is still open
+        if let Some(mut writer) = self.writer.take() {
+            writer.flush().map_err(|e| io_error(&self.path, e))?;
+        }
+
+        log_rotation::rotate_files(&self.path, self.max_files)
+            .map_err(|e| io_error(&self.path, e))?;
+
+        info!("Rotated event sink '{}' file {:?}", self.name, self.path);
+        let (writer, written) = Self::open(&self.path)?;
+        self.writer = Some(writer);
+        self.written = written;
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl EventSink for JsonlFileSink {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    async fn write(&mut self, event: &Event) -> Result<(), SinkError> {
+        let record = Record {
+            timestamp: timestamp_now(),
+            event,
+        };
+        let mut line =
+            serde_json::to_vec(&record).map_err(|e| SinkError::Serialization(e.to_string()))?;
+        line.push(b'\n');
+
+        if log_rotation::should_rotate(self.written, line.len(), self.max_bytes) {
+            self.rotate()?;
+        }
+
+        // A failed rotation leaves no writer; try to reopen on the next event
+        if self.writer.is_none() {
+            let (writer, written) = Self::open(&self.path)?;
+            self.writer = Some(writer);
+            self.written = written;
+        }
+
+        if let Some(writer) = self.writer.as_mut() {
+            writer
+                .write_all(&line)
+                .map_err(|e| io_error(&self.path, e))?;
+        }
+        self.written += line.len() as u64;
+        Ok(())
+    }
+
+    async fn flush(&mut self) -> Result<(), SinkError> {
+        match self.writer.as_mut() {
+            Some(writer) => writer.flush().map_err(|e| io_error(&self.path, e)),
+            None => Ok(()),
+        }
+    }
+}
+
+/// UTC with fixed millisecond precision, so every line's timestamp has the same length
+fn timestamp_now() -> String {
+    chrono::Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Millis, true)
+}
+
+fn io_error(path: &Path, e: std::io::Error) -> SinkError {
+    SinkError::Io(format!("{}: {}", path.display(), e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use engine_core::event::{EventKind, NetworkProtocol};
+    use tempfile::TempDir;
+
+    fn read_lines(path: &Path) -> Vec<serde_json::Value> {
+        std::fs::read_to_string(path)
+            .unwrap()
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn test_events_written_as_json_lines() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("audit").join("events.jsonl");
+        let mut sink = JsonlFileSink::new("audit", &path).unwrap();
+
+        let tick = Event::new(EventKind::TimerTick, "timer").with_metadata("tick_count", "1");
+        let connection = Event::new(
+            EventKind::NetworkConnectionCreated {
+                pid: 42,
+                local_addr: "127.0.0.1".into(),
+                local_port: 50000,
+                remote_addr: "10.0.0.1".into(),
+                remote_port: 443,
+                protocol: NetworkProtocol::Tcp,
+            },
+            "process_monitor",
+        );
+        sink.write(&tick).await.unwrap();
+        sink.write(&connection).await.unwrap();
+        sink.flush().await.unwrap();
+
+        let lines = read_lines(&path);
+        assert_eq!(lines.len(), 2);
+
+        assert_eq!(lines[0]["id"], tick.id.to_string());
+        assert_eq!(lines[0]["source"], "timer");
+        assert_eq!(lines[0]["kind"]["type"], "timer_tick");
+        assert_eq!(lines[0]["metadata"]["tick_count"], "1");
+        let timestamp = lines[0]["timestamp"].as_str().unwrap();
+        assert!(chrono::DateTime::parse_from_rfc3339(timestamp).is_ok());
+
+        assert_eq!(lines[1]["kind"]["type"], "network_connection_created");
+        assert_eq!(lines[1]["kind"]["remote_port"], 443);
+        assert_eq!(lines[1]["kind"]["protocol"], "tcp");
+    }
+
+    #[tokio::test]
+    async fn test_reopening_appends() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("events.jsonl");
+
+        for _ in 0..2 {
+            let mut sink = JsonlFileSink::new("audit", &path).unwrap();
+            sink.write(&Event::new(EventKind::TimerTick, "timer"))
+                .await
+                .unwrap();
+            sink.flush().await.unwrap();
+        }
+
+        assert_eq!(read_lines(&path).len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_rotates_by_size() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("events.jsonl");
+        let event = Event::new(EventKind::TimerTick, "timer");
+
+        // Room for two lines per file
+        let line_len = serde_json::to_vec(&Record {
+            timestamp: timestamp_now(),
+            event: &event,
+        })
+        .unwrap()
+        .len() as u64
+            + 1;
+        let mut sink = JsonlFileSink::new("audit", &path)
+            .unwrap()
+            .with_max_bytes(line_len * 2)
+            .with_max_files(2);
+
+        for _ in 0..7 {
+            sink.write(&event).await.unwrap();
+        }
+        sink.flush().await.unwrap();
+
+        // 7 lines: the oldest file rotated away, then 2 + 2 + 1
+        assert_eq!(read_lines(&log_rotation::rotated_path(&path, 2)).len(), 2);
+        assert_eq!(read_lines(&log_rotation::rotated_path(&path, 1)).len(), 2);
+        assert_eq!(read_lines(&path).len(), 1);
+        assert!(!log_rotation::rotated_path(&path, 3).exists());
+    }
+}
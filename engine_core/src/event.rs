@@ -1,14 +1,26 @@
+use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::path::PathBuf;
 use std::time::Instant;
 use uuid::Uuid;
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Event {
     pub id: Uuid,
+    /// Position in the engine's event stream, assigned as the event enters the
+    /// bus; 0 until then. Seeded from the clock at startup, so it keeps
+    /// increasing across restarts (see [`EventSequence::starting_now`]).
+    ///
+    /// [`EventSequence::starting_now`]: crate::plugin::EventSequence::starting_now
+    #[serde(default)]
+    pub seq: u64,
+    /// Monotonic, so only meaningful within this process; not serialized, and
+    /// set to the time of deserialization
+    #[serde(skip, default = "Instant::now")]
     pub timestamp: Instant,
     pub kind: EventKind,
     pub source: String,
+    #[serde(default)]
     pub metadata: HashMap<String, String>,
 }
 
@@ -16,6 +28,7 @@ impl Event {
     pub fn new(kind: EventKind, source: impl Into<String>) -> Self {
         Self {
             id: Uuid::new_v4(),
+            seq: 0,
             timestamp: Instant::now(),
             kind,
             source: source.into(),
@@ -29,7 +42,8 @@ impl Event {
     }
 }
 
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
 pub enum EventKind {
     // File System Events
     FileCreated {
@@ -63,6 +77,21 @@ pub enum EventKind {
         hwnd: isize,
         title: String,
     },
+    WindowMinimized {
+        hwnd: isize,
+        title: String,
+    },
+    WindowRestored {
+        hwnd: isize,
+        title: String,
+    },
+    WindowMoved {
+        hwnd: isize,
+        x: i32,
+        y: i32,
+        width: i32,
+        height: i32,
+    },
 
     // Process Events
     ProcessStarted {
@@ -138,18 +167,155 @@ pub enum EventKind {
         change_type: RegistryChangeType,
     },
 
+    // User Presence Events
+    UserIdle {
+        idle_seconds: u64,
+    },
+    UserActive,
+
+    // Clipboard Events
+    /// `formats` lists every format the new contents are available in;
+    /// `text_preview` is only captured when the source opts in
+    ClipboardChanged {
+        formats: Vec<String>,
+        text_preview: Option<String>,
+    },
+
+    // Device Events
+    /// `device_id` is the device instance id, e.g.
+    /// `USB\VID_0781&PID_5581\4C530001231120115142`
+    DeviceArrived {
+        device_id: String,
+        description: Option<String>,
+    },
+    DeviceRemoved {
+        device_id: String,
+        description: Option<String>,
+    },
+
     // Timer (for testing/scheduled tasks)
     TimerTick,
 }
 
-#[derive(Debug, Clone, PartialEq, Eq)]
+/// Field-less counterpart of [`EventKind`], for grouping events by variant
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum EventKindTag {
+    FileCreated,
+    FileModified,
+    FileDeleted,
+    FileRenamed,
+    WindowCreated,
+    WindowDestroyed,
+    WindowFocused,
+    WindowUnfocused,
+    WindowMinimized,
+    WindowRestored,
+    WindowMoved,
+    ProcessStarted,
+    ProcessStopped,
+    ThreadCreated,
+    ThreadDestroyed,
+    FileAccessed,
+    FileIoRead,
+    FileIoWrite,
+    FileIoDelete,
+    NetworkConnectionCreated,
+    NetworkConnectionClosed,
+    RegistryChanged,
+    UserIdle,
+    UserActive,
+    ClipboardChanged,
+    DeviceArrived,
+    DeviceRemoved,
+    TimerTick,
+}
+
+impl EventKind {
+    pub fn tag(&self) -> EventKindTag {
+        match self {
+            EventKind::FileCreated { .. } => EventKindTag::FileCreated,
+            EventKind::FileModified { .. } => EventKindTag::FileModified,
+            EventKind::FileDeleted { .. } => EventKindTag::FileDeleted,
+            EventKind::FileRenamed { .. } => EventKindTag::FileRenamed,
+            EventKind::WindowCreated { .. } => EventKindTag::WindowCreated,
+            EventKind::WindowDestroyed { .. } => EventKindTag::WindowDestroyed,
+            EventKind::WindowFocused { .. } => EventKindTag::WindowFocused,
+            EventKind::WindowUnfocused { .. } => EventKindTag::WindowUnfocused,
+            EventKind::WindowMinimized { .. } => EventKindTag::WindowMinimized,
+            EventKind::WindowRestored { .. } => EventKindTag::WindowRestored,
+            EventKind::WindowMoved { .. } => EventKindTag::WindowMoved,
+            EventKind::ProcessStarted { .. } => EventKindTag::ProcessStarted,
+            EventKind::ProcessStopped { .. } => EventKindTag::ProcessStopped,
+            EventKind::ThreadCreated { .. } => EventKindTag::ThreadCreated,
+            EventKind::ThreadDestroyed { .. } => EventKindTag::ThreadDestroyed,
+            EventKind::FileAccessed { .. } => EventKindTag::FileAccessed,
+            EventKind::FileIoRead { .. } => EventKindTag::FileIoRead,
+            EventKind::FileIoWrite { .. } => EventKindTag::FileIoWrite,
+            EventKind::FileIoDelete { .. } => EventKindTag::FileIoDelete,
+            EventKind::NetworkConnectionCreated { .. } => EventKindTag::NetworkConnectionCreated,
+            EventKind::NetworkConnectionClosed { .. } => EventKindTag::NetworkConnectionClosed,
+            EventKind::RegistryChanged { .. } => EventKindTag::RegistryChanged,
+            EventKind::UserIdle { .. } => EventKindTag::UserIdle,
+            EventKind::UserActive => EventKindTag::UserActive,
+            EventKind::ClipboardChanged { .. } => EventKindTag::ClipboardChanged,
+            EventKind::DeviceArrived { .. } => EventKindTag::DeviceArrived,
+            EventKind::DeviceRemoved { .. } => EventKindTag::DeviceRemoved,
+            EventKind::TimerTick => EventKindTag::TimerTick,
+        }
+    }
+
+    /// Every field name some variant serializes, e.g. `title` or `pid`
+    pub const FIELD_NAMES: &[&str] = &[
+        "path",
+        "old_path",
+        "new_path",
+        "hwnd",
+        "title",
+        "process_id",
+        "x",
+        "y",
+        "width",
+        "height",
+        "pid",
+        "parent_pid",
+        "name",
+        "command_line",
+        "session_id",
+        "user",
+        "exit_code",
+        "tid",
+        "start_address",
+        "user_stack",
+        "access_mask",
+        "bytes_read",
+        "bytes_written",
+        "local_addr",
+        "local_port",
+        "remote_addr",
+        "remote_port",
+        "protocol",
+        "root",
+        "key",
+        "value_name",
+        "change_type",
+        "idle_seconds",
+        "formats",
+        "text_preview",
+        "device_id",
+        "description",
+    ];
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
 pub enum RegistryChangeType {
     Created,
     Modified,
     Deleted,
 }
 
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
 pub enum NetworkProtocol {
     Tcp,
     Udp,
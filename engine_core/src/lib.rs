@@ -1,5 +1,7 @@
 pub mod event;
+pub mod log_rotation;
 pub mod plugin;
+pub mod sink;
 
 #[cfg(test)]
 mod tests {
@@ -33,4 +35,75 @@ mod tests {
         assert_eq!(event.metadata.get("key1"), Some(&"value1".to_string()));
         assert_eq!(event.metadata.get("key2"), Some(&"value2".to_string()));
     }
+
+    #[test]
+    fn test_event_serde_round_trip() {
+        let events = vec![
+            Event::new(
+                EventKind::FileRenamed {
+                    old_path: PathBuf::from("C:/Data/a.txt"),
+                    new_path: PathBuf::from("C:/Data/b.txt"),
+                },
+                "file_watcher",
+            )
+            .with_metadata("user", "alice"),
+            Event::new(
+                EventKind::NetworkConnectionCreated {
+                    pid: 42,
+                    local_addr: "127.0.0.1".into(),
+                    local_port: 50000,
+                    remote_addr: "10.0.0.1".into(),
+                    remote_port: 443,
+                    protocol: NetworkProtocol::Other("quic".into()),
+                },
+                "process_monitor",
+            ),
+            Event::new(
+                EventKind::RegistryChanged {
+                    root: "HKCU".into(),
+                    key: "Software\\Run".into(),
+                    value_name: None,
+                    change_type: RegistryChangeType::Modified,
+                },
+                "registry_monitor",
+            ),
+            Event::new(EventKind::TimerTick, "timer"),
+        ];
+
+        for event in events {
+            let json = serde_json::to_string(&event).unwrap();
+            let parsed: Event = serde_json::from_str(&json).unwrap();
+            assert_eq!(parsed.id, event.id);
+            assert_eq!(parsed.kind, event.kind);
+            assert_eq!(parsed.source, event.source);
+            assert_eq!(parsed.metadata, event.metadata);
+        }
+    }
+
+    #[test]
+    fn test_event_kind_tagged_representation() {
+        let kind = EventKind::FileCreated {
+            path: PathBuf::from("C:/Data/a.txt"),
+        };
+        assert_eq!(
+            serde_json::to_value(&kind).unwrap(),
+            serde_json::json!({ "type": "file_created", "path": "C:/Data/a.txt" })
+        );
+        assert_eq!(
+            serde_json::to_value(EventKind::UserActive).unwrap(),
+            serde_json::json!({ "type": "user_active" })
+        );
+        assert_eq!(
+            serde_json::to_value(NetworkProtocol::Tcp).unwrap(),
+            serde_json::json!("tcp")
+        );
+
+        // Metadata may be omitted
+        let event: Event = serde_json::from_str(
+            r#"{"id":"67e55044-10b1-426f-9247-bb680e5fe0c8","kind":{"type":"timer_tick"},"source":"timer"}"#,
+        )
+        .unwrap();
+        assert_eq!(event.kind, EventKind::TimerTick);
+        assert!(event.metadata.is_empty());
+    }
 }
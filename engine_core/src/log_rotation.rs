@@ -0,0 +1,80 @@
+//! Size-based rotation for the log files written by the engine, its sinks and
+//! its actions
+
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// Size a log may reach before it is rolled, unless configured otherwise
+pub const DEFAULT_MAX_BYTES: u64 = 10 * 1024 * 1024;
+/// Rolled files kept alongside the live log, unless configured otherwise
+pub const DEFAULT_MAX_FILES: usize = 5;
+
+/// Whether a log already `current_size` bytes long must be rolled before
+/// `incoming` more bytes are written. An empty log is never rolled, so a single
+/// oversized write still lands somewhere.
+pub fn should_rotate(current_size: u64, incoming: usize, max_bytes: u64) -> bool {
+    current_size > 0 && current_size + incoming as u64 > max_bytes
+}
+
+/// `path` with `.<index>` appended, e.g. `events.jsonl.2`
+pub fn rotated_path(path: &Path, index: usize) -> PathBuf {
+    let mut name = path.as_os_str().to_owned();
+    name.push(format!(".{}", index));
+    PathBuf::from(name)
+}
+
+/// Move `path` to `path.1`, `path.1` to `path.2` and so on, dropping whatever
+/// was in `path.<max_files>`. With `max_files` of 0 the log is just deleted.
+/// The caller must have closed `path` first; Windows won't rename an open file.
+pub fn rotate_files(path: &Path, max_files: usize) -> io::Result<()> {
+    if max_files == 0 {
+        return std::fs::remove_file(path);
+    }
+
+    for index in (1..max_files).rev() {
+        let from = rotated_path(path, index);
+        if from.exists() {
+            std::fs::rename(&from, rotated_path(path, index + 1))?;
+        }
+    }
+    std::fs::rename(path, rotated_path(path, 1))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_should_rotate() {
+        let max = 1000;
+        assert!(!should_rotate(0, 10, max));
+        assert!(!should_rotate(990, 10, max));
+        assert!(should_rotate(991, 10, max));
+        assert!(should_rotate(5000, 1, max));
+        // A write bigger than the cap still goes into an empty log
+        assert!(!should_rotate(0, 5000, max));
+    }
+
+    #[test]
+    fn test_rotate_files_shifts_and_drops_oldest() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("app.log");
+        let read = |path: &Path| std::fs::read_to_string(path).unwrap();
+
+        for contents in ["one", "two", "three"] {
+            std::fs::write(&path, contents).unwrap();
+            rotate_files(&path, 2).unwrap();
+        }
+
+        assert!(!path.exists());
+        assert_eq!(read(&rotated_path(&path, 1)), "three");
+        assert_eq!(read(&rotated_path(&path, 2)), "two");
+        assert!(!rotated_path(&path, 3).exists());
+
+        std::fs::write(&path, "four").unwrap();
+        rotate_files(&path, 0).unwrap();
+        assert!(!path.exists());
+        assert_eq!(read(&rotated_path(&path, 1)), "three");
+    }
+}
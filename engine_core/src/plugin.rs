@@ -1,9 +1,156 @@
 use crate::event::Event;
 use async_trait::async_trait;
 use std::fmt;
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 use tokio::sync::mpsc::Sender;
+use tokio::sync::mpsc::error::TrySendError;
+use tracing::warn;
 
-pub type EventEmitter = Sender<Event>;
+/// Minimum time between "channel full" warnings from one emitter
+const DROP_WARNING_INTERVAL: Duration = Duration::from_secs(10);
+
+/// Called with every event dropped because the channel was full
+pub type DropHook = Arc<dyn Fn(&Event) + Send + Sync>;
+
+/// What happened to an event passed to [`EventEmitter::emit`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EmitOutcome {
+    Sent,
+    /// The channel was full and the event was discarded
+    Dropped,
+    /// The engine has gone away; the plugin should stop producing events
+    Closed,
+}
+
+/// Numbers events in the order they are queued, shared by every emitter
+/// feeding one channel. Dropped events leave a gap.
+#[derive(Debug, Default)]
+pub struct EventSequence {
+    last: Mutex<u64>,
+}
+
+impl EventSequence {
+    /// A sequence whose first number is `last + 1`
+    pub fn starting_after(last: u64) -> Self {
+        Self {
+            last: Mutex::new(last),
+        }
+    }
+
+    /// A sequence seeded from the clock, in microseconds since the Unix
+    /// epoch, so numbers keep increasing across restarts as long as fewer
+    /// than a million events a second are numbered on average and the clock
+    /// isn't set back
+    pub fn starting_now() -> Self {
+        let micros = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |elapsed| elapsed.as_micros() as u64);
+        Self::starting_after(micros)
+    }
+
+    /// Hand out the next number
+    pub fn next(&self) -> u64 {
+        let mut last = self.lock();
+        *last += 1;
+        *last
+    }
+
+    fn lock(&self) -> MutexGuard<'_, u64> {
+        self.last.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+#[derive(Default)]
+struct DropWarnings {
+    last_warning: Option<Instant>,
+    suppressed: u64,
+}
+
+/// The channel a plugin emits events into. Never blocks: when the engine falls
+/// behind, events are dropped, reported to the drop hook and logged at most
+/// once per [`DROP_WARNING_INTERVAL`].
+#[derive(Clone)]
+pub struct EventEmitter {
+    sender: Sender<Event>,
+    sequence: Option<Arc<EventSequence>>,
+    on_drop: Option<DropHook>,
+    warnings: Arc<Mutex<DropWarnings>>,
+}
+
+impl EventEmitter {
+    pub fn new(sender: Sender<Event>) -> Self {
+        Self {
+            sender,
+            sequence: None,
+            on_drop: None,
+            warnings: Arc::new(Mutex::new(DropWarnings::default())),
+        }
+    }
+
+    /// Set each emitted event's `seq` from `sequence`
+    pub fn with_sequence(mut self, sequence: Arc<EventSequence>) -> Self {
+        self.sequence = Some(sequence);
+        self
+    }
+
+    pub fn with_drop_hook(mut self, hook: DropHook) -> Self {
+        self.on_drop = Some(hook);
+        self
+    }
+
+    pub fn emit(&self, mut event: Event) -> EmitOutcome {
+        // Held until the event is queued, so the channel stays in `seq` order
+        // when several emitters share one sequence
+        let mut last_seq = self.sequence.as_ref().map(|sequence| sequence.lock());
+        if let Some(last) = last_seq.as_deref_mut() {
+            *last += 1;
+            event.seq = *last;
+        }
+        let sent = self.sender.try_send(event);
+        drop(last_seq);
+
+        match sent {
+            Ok(()) => EmitOutcome::Sent,
+            Err(TrySendError::Full(event)) => {
+                if let Some(hook) = &self.on_drop {
+                    hook(&event);
+                }
+                self.warn_dropped(&event, Instant::now());
+                EmitOutcome::Dropped
+            }
+            Err(TrySendError::Closed(_)) => EmitOutcome::Closed,
+        }
+    }
+
+    pub fn is_closed(&self) -> bool {
+        self.sender.is_closed()
+    }
+
+    fn warn_dropped(&self, event: &Event, now: Instant) {
+        let mut warnings = self.warnings.lock().unwrap_or_else(|e| e.into_inner());
+        let due = warnings
+            .last_warning
+            .is_none_or(|last| now.duration_since(last) >= DROP_WARNING_INTERVAL);
+
+        if due {
+            warn!(
+                "Event channel full, dropping events from {} ({} more dropped since the last warning)",
+                event.source, warnings.suppressed
+            );
+            warnings.last_warning = Some(now);
+            warnings.suppressed = 0;
+        } else {
+            warnings.suppressed += 1;
+        }
+    }
+}
+
+impl From<Sender<Event>> for EventEmitter {
+    fn from(sender: Sender<Event>) -> Self {
+        Self::new(sender)
+    }
+}
 
 #[derive(Debug, Clone)]
 pub enum PluginError {
@@ -24,6 +171,15 @@ impl fmt::Display for PluginError {
 
 impl std::error::Error for PluginError {}
 
+/// Liveness of an event source, as reported by [`EventSourcePlugin::health`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PluginHealth {
+    Healthy,
+    Stopped,
+    /// The plugin was never stopped, but whatever produced its events has died
+    Failed(String),
+}
+
 #[async_trait]
 pub trait EventSourcePlugin: Send + Sync {
     fn name(&self) -> &str;
@@ -37,4 +193,88 @@ pub trait EventSourcePlugin: Send + Sync {
     fn is_running(&self) -> bool {
         false
     }
+
+    /// Plugins backed by a thread or OS session should override this to report
+    /// `Failed` once it has exited, since `is_running` only tracks start/stop
+    fn health(&self) -> PluginHealth {
+        if self.is_running() {
+            PluginHealth::Healthy
+        } else {
+            PluginHealth::Stopped
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::event::EventKind;
+    use std::sync::atomic::{AtomicU64, Ordering};
+
+    #[test]
+    fn test_full_channel_reports_drops() {
+        let (tx, mut rx) = tokio::sync::mpsc::channel(2);
+        let dropped = Arc::new(AtomicU64::new(0));
+        let counter = dropped.clone();
+        let emitter = EventEmitter::new(tx).with_drop_hook(Arc::new(move |_| {
+            counter.fetch_add(1, Ordering::Relaxed);
+        }));
+
+        let outcomes: Vec<_> = (0..5)
+            .map(|_| emitter.emit(Event::new(EventKind::TimerTick, "test")))
+            .collect();
+        assert_eq!(
+            outcomes,
+            vec![
+                EmitOutcome::Sent,
+                EmitOutcome::Sent,
+                EmitOutcome::Dropped,
+                EmitOutcome::Dropped,
+                EmitOutcome::Dropped,
+            ]
+        );
+        assert_eq!(dropped.load(Ordering::Relaxed), 3);
+
+        // Only the first drop in the interval is logged
+        assert_eq!(emitter.warnings.lock().unwrap().suppressed, 2);
+
+        rx.close();
+        while rx.try_recv().is_ok() {}
+        assert!(emitter.is_closed());
+        assert_eq!(
+            emitter.emit(Event::new(EventKind::TimerTick, "test")),
+            EmitOutcome::Closed
+        );
+        assert_eq!(dropped.load(Ordering::Relaxed), 3);
+    }
+
+    #[test]
+    fn test_sequence_continues_across_restarts() {
+        let before = EventSequence::starting_now();
+        let last = (0..100).map(|_| before.next()).last().unwrap();
+
+        std::thread::sleep(Duration::from_millis(2));
+        let after = EventSequence::starting_now();
+        assert!(after.next() > last);
+    }
+
+    #[test]
+    fn test_emitters_share_a_sequence() {
+        let (tx, mut rx) = tokio::sync::mpsc::channel(3);
+        let sequence = Arc::new(EventSequence::starting_after(41));
+        let first = EventEmitter::new(tx.clone()).with_sequence(sequence.clone());
+        let second = EventEmitter::new(tx).with_sequence(sequence.clone());
+
+        first.emit(Event::new(EventKind::TimerTick, "first"));
+        second.emit(Event::new(EventKind::TimerTick, "second"));
+        first.emit(Event::new(EventKind::TimerTick, "first"));
+        // Dropped, leaving a gap
+        second.emit(Event::new(EventKind::TimerTick, "second"));
+
+        let seqs: Vec<u64> = std::iter::from_fn(|| rx.try_recv().ok())
+            .map(|event| event.seq)
+            .collect();
+        assert_eq!(seqs, vec![42, 43, 44]);
+        assert_eq!(sequence.next(), 46);
+    }
 }
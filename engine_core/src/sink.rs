@@ -0,0 +1,35 @@
+use crate::event::Event;
+use async_trait::async_trait;
+use std::fmt;
+
+#[derive(Debug, Clone)]
+pub enum SinkError {
+    Io(String),
+    Serialization(String),
+}
+
+impl fmt::Display for SinkError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SinkError::Io(msg) => write!(f, "Sink I/O error: {}", msg),
+            SinkError::Serialization(msg) => write!(f, "Sink serialization error: {}", msg),
+        }
+    }
+}
+
+impl std::error::Error for SinkError {}
+
+/// A consumer that is handed every event the engine receives, whether or not
+/// any rule matches it
+#[async_trait]
+pub trait EventSink: Send {
+    fn name(&self) -> &str;
+
+    async fn write(&mut self, event: &Event) -> Result<(), SinkError>;
+
+    /// Called whenever the sink has caught up with the event stream, and once
+    /// more before it is dropped
+    async fn flush(&mut self) -> Result<(), SinkError> {
+        Ok(())
+    }
+}
@@ -1,6 +1,8 @@
 pub mod server;
 
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
 use std::sync::atomic::{AtomicU64, Ordering};
 use std::time::{Duration, Instant};
 
@@ -8,7 +10,27 @@ use chrono::{DateTime, Utc};
 use dashmap::DashMap;
 use serde::{Deserialize, Serialize};
 use tokio::sync::{broadcast, RwLock};
-use tracing::{debug, info};
+use tracing::{debug, error, info};
+
+/// How an action run ended, recorded as the `status` label of
+/// `actions_executed_total`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ActionStatus {
+    Success,
+    Error,
+    /// The action returned without doing anything, e.g. in a dry run
+    Skipped,
+}
+
+impl ActionStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ActionStatus::Success => "success",
+            ActionStatus::Error => "error",
+            ActionStatus::Skipped => "skipped",
+        }
+    }
+}
 
 /// Real-time metric update events for WebSocket broadcast
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -42,6 +64,17 @@ pub enum MetricUpdate {
         timestamp: DateTime<Utc>,
         action_name: String,
         success: bool,
+        /// Set when the action chose not to run, e.g. a dry run or an open
+        /// circuit; `success` is false then too
+        #[serde(default)]
+        skipped: bool,
+    },
+
+    /// An event was dropped before reaching the rule engine
+    #[serde(rename = "event_dropped")]
+    EventDropped {
+        timestamp: DateTime<Utc>,
+        reason: String,
     },
 
     /// Periodic full metrics snapshot
@@ -58,12 +91,29 @@ pub enum MetricUpdate {
     },
 }
 
+impl MetricUpdate {
+    /// The `type` this update is serialized with, e.g. `rule_matched`
+    pub fn type_name(&self) -> &'static str {
+        match self {
+            MetricUpdate::EventReceived { .. } => "event_received",
+            MetricUpdate::RuleEvaluated { .. } => "rule_evaluated",
+            MetricUpdate::RuleMatched { .. } => "rule_matched",
+            MetricUpdate::ActionExecuted { .. } => "action_executed",
+            MetricUpdate::EventDropped { .. } => "event_dropped",
+            MetricUpdate::Snapshot(_) => "snapshot",
+            MetricUpdate::Health { .. } => "health",
+        }
+    }
+}
+
 /// Default retention period for regular metrics (1 hour)
 const DEFAULT_RETENTION_SECONDS: u64 = 3600;
 /// Extended retention period for error-level metrics (24 hours)
 const ERROR_RETENTION_SECONDS: u64 = 86400;
 /// Cleanup interval (5 minutes)
 const CLEANUP_INTERVAL_SECONDS: u64 = 300;
+/// Window used for derived rate gauges (1 minute)
+const RATE_WINDOW_SECONDS: u64 = 60;
 
 /// Metric value types
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -118,6 +168,10 @@ pub struct MetricsCollector {
     error_retention_seconds: u64,
     /// Cleanup task handle
     cleanup_handle: RwLock<Option<tokio::task::JoinHandle<()>>>,
+    /// Snapshot persistence path and flush interval
+    persistence: Option<(PathBuf, Duration)>,
+    /// Persistence task handle
+    persistence_handle: RwLock<Option<tokio::task::JoinHandle<()>>>,
     /// Broadcast channel for real-time metric updates
     update_tx: broadcast::Sender<MetricUpdate>,
 }
@@ -150,6 +204,8 @@ impl MetricsCollector {
             retention_seconds,
             error_retention_seconds,
             cleanup_handle: RwLock::new(None),
+            persistence: None,
+            persistence_handle: RwLock::new(None),
             update_tx,
         };
 
@@ -220,16 +276,40 @@ impl MetricsCollector {
             "Engine uptime in seconds",
             false,
         );
+        collector.register_metadata(
+            "events_per_second",
+            MetricType::Gauge,
+            "Events processed per second over the last minute",
+            false,
+        );
         collector.register_metadata(
             "config_reload_total",
             MetricType::Counter,
             "Total configuration reloads",
             false,
         );
+        collector.register_metadata(
+            "active_plugins",
+            MetricType::Gauge,
+            "Event source plugins currently loaded",
+            false,
+        );
+        collector.register_metadata(
+            "active_rules",
+            MetricType::Gauge,
+            "Rules currently loaded",
+            false,
+        );
 
         collector
     }
 
+    /// Periodically flush snapshots to `path` as JSON every `interval`
+    pub fn with_persistence(mut self, path: impl Into<PathBuf>, interval: Duration) -> Self {
+        self.persistence = Some((path.into(), interval));
+        self
+    }
+
     /// Start the background cleanup task
     pub async fn start_cleanup_task(&self) {
         let mut handle = self.cleanup_handle.write().await;
@@ -284,6 +364,101 @@ impl MetricsCollector {
         }
     }
 
+    /// Start the background snapshot persistence task (no-op without `with_persistence`)
+    pub async fn start_persistence_task(self: &Arc<Self>) {
+        let Some((path, flush_interval)) = self.persistence.clone() else {
+            return;
+        };
+
+        let mut handle = self.persistence_handle.write().await;
+        if handle.is_none() {
+            let collector = Arc::clone(self);
+
+            *handle = Some(tokio::spawn(async move {
+                let mut interval = tokio::time::interval(flush_interval);
+                // The first tick completes immediately; skip it so we don't flush an empty snapshot
+                interval.tick().await;
+
+                loop {
+                    interval.tick().await;
+
+                    if let Err(e) = collector.save_snapshot(&path) {
+                        error!("Failed to persist metrics snapshot to {:?}: {}", path, e);
+                    } else {
+                        debug!("Metrics snapshot persisted to {:?}", path);
+                    }
+                }
+            }));
+
+            info!("Metrics persistence task started ({:?})", self.persistence);
+        }
+    }
+
+    /// Stop the persistence task, flushing a final snapshot
+    pub async fn stop_persistence_task(&self) {
+        let mut handle = self.persistence_handle.write().await;
+        if let Some(h) = handle.take() {
+            h.abort();
+
+            if let Some((path, _)) = &self.persistence
+                && let Err(e) = self.save_snapshot(path)
+            {
+                error!("Failed to persist final metrics snapshot: {}", e);
+            }
+
+            info!("Metrics persistence task stopped");
+        }
+    }
+
+    /// Write the current snapshot to `path` as JSON
+    pub fn save_snapshot(&self, path: &Path) -> std::io::Result<()> {
+        let json = serde_json::to_vec_pretty(&self.get_snapshot())?;
+
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+
+        // Write to a temporary file first so a crash mid-write can't corrupt the snapshot
+        let tmp_path = path.with_extension("json.tmp");
+        std::fs::write(&tmp_path, json)?;
+        std::fs::rename(&tmp_path, path)
+    }
+
+    /// Restore counters, gauges and histogram samples from a persisted snapshot
+    ///
+    /// Gauges and histogram samples older than the retention window are discarded.
+    pub fn load_from_snapshot(&self, path: &Path) -> std::io::Result<()> {
+        let contents = std::fs::read(path)?;
+        let snapshot: MetricsSnapshot = serde_json::from_slice(&contents)?;
+
+        for (key, value) in snapshot.counters {
+            self.counters.insert(key, AtomicU64::new(value));
+        }
+
+        let cutoff = Utc::now() - chrono::Duration::seconds(self.retention_seconds as i64);
+        if snapshot.timestamp > cutoff {
+            for (key, value) in snapshot.gauges {
+                // Uptime always reflects the current process
+                if key == "engine_uptime_seconds" {
+                    continue;
+                }
+                self.gauges.insert(key, AtomicU64::new(value.to_bits()));
+            }
+
+            for (key, values) in snapshot.histograms {
+                let samples = values
+                    .into_iter()
+                    .map(|v| (snapshot.timestamp, (v * 1_000_000_000.0) as u64));
+                self.histograms.entry(key).or_default().extend(samples);
+            }
+        } else {
+            debug!("Snapshot older than retention window, skipping gauges and histograms");
+        }
+
+        info!("Metrics restored from snapshot {:?}", path);
+        Ok(())
+    }
+
     fn register_metadata(&self, name: &str, metric_type: MetricType, description: &str, is_error_metric: bool) {
         self.metadata.insert(
             name.to_string(),
@@ -301,7 +476,7 @@ impl MetricsCollector {
         } else {
             let mut label_parts: Vec<String> = labels
                 .iter()
-                .map(|(k, v)| format!("{}={}", k, v))
+                .map(|(k, v)| format!("{}={}", encode_label_part(k), encode_label_part(v)))
                 .collect();
             label_parts.sort();
             format!("{}:{{{}}}", name, label_parts.join(","))
@@ -382,6 +557,103 @@ impl MetricsCollector {
             .map(|g| f64::from_bits(g.load(Ordering::Relaxed)))
     }
 
+    /// Get a gauge's recorded values within the retention window, oldest first
+    pub fn get_gauge_series(
+        &self,
+        name: &str,
+        labels: &HashMap<String, String>,
+    ) -> Vec<(DateTime<Utc>, f64)> {
+        let key = Self::build_key(name, labels);
+        let cutoff = Utc::now() - chrono::Duration::seconds(self.retention_seconds as i64);
+
+        self.gauge_samples
+            .get(&key)
+            .map(|samples| {
+                samples
+                    .iter()
+                    .filter(|(ts, _)| *ts > cutoff)
+                    .map(|(ts, bits)| (*ts, f64::from_bits(*bits)))
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    /// Get min/max/avg of a gauge over the retention window, or `None` if it
+    /// has no values in the window
+    pub fn get_gauge_stats(
+        &self,
+        name: &str,
+        labels: &HashMap<String, String>,
+    ) -> Option<GaugeStats> {
+        let series = self.get_gauge_series(name, labels);
+        if series.is_empty() {
+            return None;
+        }
+
+        let count = series.len() as u64;
+        let sum: f64 = series.iter().map(|(_, value)| value).sum();
+        let min = series.iter().fold(f64::INFINITY, |a, (_, b)| a.min(*b));
+        let max = series.iter().fold(f64::NEG_INFINITY, |a, (_, b)| a.max(*b));
+
+        Some(GaugeStats {
+            count,
+            min,
+            max,
+            avg: sum / count as f64,
+        })
+    }
+
+    /// Get the per-second rate of a counter over a trailing window
+    ///
+    /// Sums the increments recorded within `window_seconds` and divides by the window.
+    /// Returns `Some(0.0)` when fewer than two samples fall inside the window.
+    pub fn get_counter_rate(
+        &self,
+        name: &str,
+        labels: &HashMap<String, String>,
+        window_seconds: u64,
+    ) -> Option<f64> {
+        let key = Self::build_key(name, labels);
+        self.counter_samples
+            .get(&key)
+            .map(|samples| Self::rate_from_samples(samples.iter(), window_seconds))
+    }
+
+    /// Get the per-second rate of a counter summed across all of its label sets
+    fn get_aggregate_counter_rate(&self, name: &str, window_seconds: u64) -> f64 {
+        let prefix = format!("{}:{{", name);
+        let samples: Vec<(DateTime<Utc>, u64)> = self
+            .counter_samples
+            .iter()
+            .filter(|entry| entry.key() == name || entry.key().starts_with(&prefix))
+            .flat_map(|entry| entry.value().clone())
+            .collect();
+
+        Self::rate_from_samples(samples.iter(), window_seconds)
+    }
+
+    fn rate_from_samples<'a>(
+        samples: impl Iterator<Item = &'a (DateTime<Utc>, u64)>,
+        window_seconds: u64,
+    ) -> f64 {
+        if window_seconds == 0 {
+            return 0.0;
+        }
+
+        let cutoff = Utc::now() - chrono::Duration::seconds(window_seconds as i64);
+        let (count, total) = samples
+            .filter(|(ts, _)| *ts > cutoff)
+            .fold((0usize, 0u64), |(count, total), (_, value)| {
+                (count + 1, total + value)
+            });
+
+        if count < 2 {
+            return 0.0;
+        }
+
+        total as f64 / window_seconds as f64
+    }
+
     /// Get histogram statistics (count, sum, avg, min, max) for the retention window
     pub fn get_histogram_stats(
         &self,
@@ -448,6 +720,12 @@ impl MetricsCollector {
             self.get_uptime_seconds(),
         );
 
+        // Derived event rate from the sliding window samples
+        gauges.insert(
+            "events_per_second".to_string(),
+            self.get_aggregate_counter_rate("events_total", RATE_WINDOW_SECONDS),
+        );
+
         let mut histograms = HashMap::new();
         for entry in self.histograms.iter() {
             let key = entry.key();
@@ -474,16 +752,21 @@ impl MetricsCollector {
     /// Get Prometheus-formatted metrics
     pub fn get_prometheus_format(&self) -> String {
         let snapshot = self.get_snapshot();
-        let mut output = String::new();
+        // Every label set of a metric goes under a single HELP/TYPE header
+        let mut families: BTreeMap<String, PrometheusFamily> = BTreeMap::new();
 
         // Counters
         for (key, value) in &snapshot.counters {
             let (name, labels) = self.parse_key(key);
             if let Some(meta) = self.metadata.get(&name) {
                 if meta.metric_type == MetricType::Counter {
-                    output.push_str(&format!("# HELP {} {}\n", name, meta.description));
-                    output.push_str(&format!("# TYPE {} counter\n", name));
-                    output.push_str(&format!("{}{} {}\n", name, self.format_labels(&labels), value));
+                    let name = sanitize_metric_name(&name);
+                    let labels = self.format_labels(&labels);
+                    families
+                        .entry(name.clone())
+                        .or_insert_with(|| PrometheusFamily::new(&meta.description, "counter"))
+                        .samples
+                        .push(format!("{}{} {}", name, labels, value));
                 }
             }
         }
@@ -491,9 +774,13 @@ impl MetricsCollector {
         // Gauges
         for (key, value) in &snapshot.gauges {
             let (name, labels) = self.parse_key(key);
-            output.push_str(&format!("# HELP {} {}\n", name, name));
-            output.push_str(&format!("# TYPE {} gauge\n", name));
-            output.push_str(&format!("{}{} {}\n", name, self.format_labels(&labels), value));
+            let name = sanitize_metric_name(&name);
+            let labels = self.format_labels(&labels);
+            families
+                .entry(name.clone())
+                .or_insert_with(|| PrometheusFamily::new(&name, "gauge"))
+                .samples
+                .push(format!("{}{} {}", name, labels, value));
         }
 
         // Histograms - output summary stats
@@ -503,42 +790,51 @@ impl MetricsCollector {
                 if meta.metric_type == MetricType::Histogram && !values.is_empty() {
                     let count = values.len() as u64;
                     let sum: f64 = values.iter().sum();
-                    
-                    output.push_str(&format!("# HELP {} {}\n", name, meta.description));
-                    output.push_str(&format!("# TYPE {} summary\n", name));
-                    output.push_str(&format!(
-                        "{}_sum{} {}\n",
-                        name,
-                        self.format_labels(&labels),
-                        sum
-                    ));
-                    output.push_str(&format!(
-                        "{}_count{} {}\n",
-                        name,
-                        self.format_labels(&labels),
-                        count
-                    ));
+
+                    let name = sanitize_metric_name(&name);
+                    let labels = self.format_labels(&labels);
+                    let family = families
+                        .entry(name.clone())
+                        .or_insert_with(|| PrometheusFamily::new(&meta.description, "summary"));
+                    family
+                        .samples
+                        .push(format!("{}_sum{} {}", name, labels, sum));
+                    family
+                        .samples
+                        .push(format!("{}_count{} {}", name, labels, count));
                 }
             }
         }
 
+        let mut output = String::new();
+        for (name, mut family) in families {
+            family.samples.sort();
+            output.push_str(&format!("# HELP {} {}\n", name, family.help));
+            output.push_str(&format!("# TYPE {} {}\n", name, family.metric_type));
+            for sample in family.samples {
+                output.push_str(&sample);
+                output.push('\n');
+            }
+        }
+
         output
     }
 
     fn parse_key(&self, key: &str) -> (String, HashMap<String, String>) {
-        if let Some(pos) = key.find(':') {
+        // Keys are `name` or `name:{k=v,...}` with label parts encoded by `build_key`
+        if let Some(pos) = key.find(":{")
+            && key.ends_with('}')
+        {
             let name = &key[..pos];
             let labels_str = &key[pos + 2..key.len() - 1]; // Remove :{ and }
             let mut labels = HashMap::new();
-            
+
             for part in labels_str.split(',') {
-                if let Some(eq_pos) = part.find('=') {
-                    let k = part[..eq_pos].to_string();
-                    let v = part[eq_pos + 1..].to_string();
-                    labels.insert(k, v);
+                if let Some((k, v)) = part.split_once('=') {
+                    labels.insert(decode_label_part(k), decode_label_part(v));
                 }
             }
-            
+
             (name.to_string(), labels)
         } else {
             (key.to_string(), HashMap::new())
@@ -551,7 +847,7 @@ impl MetricsCollector {
         } else {
             let parts: Vec<String> = labels
                 .iter()
-                .map(|(k, v)| format!("{}=\"{}\"", k, v))
+                .map(|(k, v)| format!("{}=\"{}\"", sanitize_label_name(k), escape_label_value(v)))
                 .collect();
             format!("{{{}}}", parts.join(","))
         }
@@ -579,6 +875,16 @@ impl MetricsCollector {
         });
     }
 
+    /// Record a dropped event and broadcast the update
+    pub fn record_event_dropped_with_broadcast(&self, reason: &str) {
+        record_event_dropped(self);
+
+        self.broadcast(MetricUpdate::EventDropped {
+            timestamp: Utc::now(),
+            reason: reason.to_string(),
+        });
+    }
+
     /// Record a rule match and broadcast the update
     pub fn record_rule_match_with_broadcast(&self, rule_name: &str) {
         record_rule_match(self, rule_name);
@@ -603,15 +909,16 @@ impl MetricsCollector {
     pub fn record_action_execution_with_broadcast(
         &self,
         action_name: &str,
-        success: bool,
+        status: ActionStatus,
         duration: Duration,
     ) {
-        record_action_execution(self, action_name, success, duration);
+        record_action_execution(self, action_name, status, duration);
 
         self.broadcast(MetricUpdate::ActionExecuted {
             timestamp: Utc::now(),
             action_name: action_name.to_string(),
-            success,
+            success: status == ActionStatus::Success,
+            skipped: status == ActionStatus::Skipped,
         });
     }
 
@@ -620,13 +927,113 @@ impl MetricsCollector {
         record_config_reload(self, success);
 
         // Also broadcast a health update with system status
+        let status = |name| self.get_gauge(name, &HashMap::new()).unwrap_or(0.0) as usize;
         self.broadcast(MetricUpdate::Health {
             timestamp: Utc::now(),
             uptime_seconds: self.get_uptime_seconds(),
-            active_plugins: 0,  // Will be updated from snapshot
-            active_rules: 0,    // Will be updated from snapshot
+            active_plugins: status("active_plugins"),
+            active_rules: status("active_rules"),
         });
     }
+
+    /// Store the engine's current plugin and rule counts, reported in health updates
+    pub fn set_system_status(&self, active_plugins: usize, active_rules: usize) {
+        self.set_gauge("active_plugins", HashMap::new(), active_plugins as f64);
+        self.set_gauge("active_rules", HashMap::new(), active_rules as f64);
+    }
+}
+
+/// One metric in the Prometheus output: its header and every label set's samples
+struct PrometheusFamily {
+    help: String,
+    metric_type: &'static str,
+    samples: Vec<String>,
+}
+
+impl PrometheusFamily {
+    fn new(help: &str, metric_type: &'static str) -> Self {
+        Self {
+            help: help.to_string(),
+            metric_type,
+            samples: Vec::new(),
+        }
+    }
+}
+
+/// Make `name` a valid Prometheus name, `[a-zA-Z_:][a-zA-Z0-9_:]*` (or without
+/// colons for label names), by replacing anything else with `_`
+fn sanitize_name(name: &str, allow_colon: bool) -> String {
+    let valid = |c: char| c.is_ascii_alphanumeric() || c == '_' || (allow_colon && c == ':');
+    let mut sanitized: String = name
+        .chars()
+        .map(|c| if valid(c) { c } else { '_' })
+        .collect();
+    // Names can't start with a digit, so keep it behind a prefix rather than lose it
+    if sanitized.is_empty() || sanitized.starts_with(|c: char| c.is_ascii_digit()) {
+        sanitized.insert(0, '_');
+    }
+    sanitized
+}
+
+fn sanitize_metric_name(name: &str) -> String {
+    sanitize_name(name, true)
+}
+
+fn sanitize_label_name(name: &str) -> String {
+    sanitize_name(name, false)
+}
+
+/// Escape a label value for the text exposition format
+fn escape_label_value(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            '\\' => escaped.push_str("\\\\"),
+            '"' => escaped.push_str("\\\""),
+            '\n' => escaped.push_str("\\n"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// Percent-encode the characters that delimit label blocks in metric keys
+fn encode_label_part(part: &str) -> String {
+    let mut encoded = String::with_capacity(part.len());
+    for c in part.chars() {
+        match c {
+            '%' => encoded.push_str("%25"),
+            ':' => encoded.push_str("%3A"),
+            ',' => encoded.push_str("%2C"),
+            '=' => encoded.push_str("%3D"),
+            '{' => encoded.push_str("%7B"),
+            '}' => encoded.push_str("%7D"),
+            _ => encoded.push(c),
+        }
+    }
+    encoded
+}
+
+/// Reverse `encode_label_part`
+fn decode_label_part(part: &str) -> String {
+    let mut decoded = String::with_capacity(part.len());
+    let mut rest = part;
+    while let Some(pos) = rest.find('%') {
+        decoded.push_str(&rest[..pos]);
+        let escape = rest.get(pos + 1..pos + 3);
+        match escape.and_then(|hex| u8::from_str_radix(hex, 16).ok()) {
+            Some(byte) => {
+                decoded.push(byte as char);
+                rest = &rest[pos + 3..];
+            }
+            None => {
+                decoded.push('%');
+                rest = &rest[pos + 1..];
+            }
+        }
+    }
+    decoded.push_str(rest);
+    decoded
 }
 
 /// Histogram statistics
@@ -639,6 +1046,15 @@ pub struct HistogramStats {
     pub max: f64,
 }
 
+/// Gauge statistics over the retention window
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct GaugeStats {
+    pub count: u64,
+    pub min: f64,
+    pub max: f64,
+    pub avg: f64,
+}
+
 /// Metrics snapshot for export
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct MetricsSnapshot {
@@ -701,12 +1117,12 @@ pub fn record_rule_match_duration(metrics: &MetricsCollector, rule_name: &str, d
 pub fn record_action_execution(
     metrics: &MetricsCollector,
     action_name: &str,
-    success: bool,
+    status: ActionStatus,
     duration: Duration,
 ) {
     let mut labels = HashMap::new();
     labels.insert("action".to_string(), action_name.to_string());
-    labels.insert("status".to_string(), if success { "success".to_string() } else { "error".to_string() });
+    labels.insert("status".to_string(), status.as_str().to_string());
     metrics.increment_counter("actions_executed_total", labels.clone(), 1);
     
     metrics.record_histogram(
@@ -716,6 +1132,13 @@ pub fn record_action_execution(
     );
 }
 
+/// Record an action the engine stopped waiting for
+pub fn record_action_timeout(metrics: &MetricsCollector, action_name: &str) {
+    let mut labels = HashMap::new();
+    labels.insert("action".to_string(), action_name.to_string());
+    metrics.increment_counter("actions_timed_out_total", labels, 1);
+}
+
 /// Record events generated by a plugin
 pub fn record_plugin_event(metrics: &MetricsCollector, plugin: &str, event_type: &str) {
     let mut labels = HashMap::new();
@@ -784,6 +1207,66 @@ mod tests {
         assert!(stats.avg > 0.19 && stats.avg < 0.21);
     }
 
+    #[tokio::test]
+    async fn test_gauge_series() {
+        let metrics = MetricsCollector::new();
+        let mut labels = HashMap::new();
+        labels.insert("queue".to_string(), "actions".to_string());
+
+        for depth in [3.0, 7.5, 1.0, 4.5] {
+            metrics.set_gauge("queue_depth", labels.clone(), depth);
+            tokio::time::sleep(Duration::from_millis(5)).await;
+        }
+
+        let series = metrics.get_gauge_series("queue_depth", &labels);
+        let values: Vec<f64> = series.iter().map(|(_, value)| *value).collect();
+        assert_eq!(values, [3.0, 7.5, 1.0, 4.5]);
+        assert!(series.windows(2).all(|pair| pair[0].0 < pair[1].0));
+
+        let stats = metrics.get_gauge_stats("queue_depth", &labels).unwrap();
+        assert_eq!(stats.count, 4);
+        assert_eq!(stats.min, 1.0);
+        assert_eq!(stats.max, 7.5);
+        assert_eq!(stats.avg, 4.0);
+
+        // Other label sets and unknown gauges have no history
+        assert!(
+            metrics
+                .get_gauge_series("queue_depth", &HashMap::new())
+                .is_empty()
+        );
+        assert!(
+            metrics
+                .get_gauge_stats("missing", &HashMap::new())
+                .is_none()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_gauge_series_excludes_expired_samples() {
+        let metrics = MetricsCollector::with_retention(60, 60);
+        let key = MetricsCollector::build_key("queue_depth", &HashMap::new());
+        let now = Utc::now();
+        metrics.gauge_samples.insert(
+            key,
+            vec![
+                (now - chrono::Duration::seconds(120), 100.0f64.to_bits()),
+                (now - chrono::Duration::seconds(10), 2.0f64.to_bits()),
+            ],
+        );
+
+        let series = metrics.get_gauge_series("queue_depth", &HashMap::new());
+        assert_eq!(series.len(), 1);
+        assert_eq!(series[0].1, 2.0);
+        assert_eq!(
+            metrics
+                .get_gauge_stats("queue_depth", &HashMap::new())
+                .unwrap()
+                .max,
+            2.0
+        );
+    }
+
     #[tokio::test]
     async fn test_prometheus_format() {
         let metrics = MetricsCollector::new();
@@ -798,6 +1281,266 @@ mod tests {
         assert!(output.contains("engine_uptime_seconds"));
     }
 
+    #[tokio::test]
+    async fn test_prometheus_format_one_header_per_metric() {
+        let metrics = MetricsCollector::new();
+        for plugin in ["file_watcher", "timer"] {
+            let mut labels = HashMap::new();
+            labels.insert("plugin".to_string(), plugin.to_string());
+            metrics.increment_counter("events_total", labels, 1);
+        }
+
+        let output = metrics.get_prometheus_format();
+        let lines: Vec<&str> = output.lines().collect();
+        let help = lines
+            .iter()
+            .position(|line| line.starts_with("# HELP events_total "))
+            .unwrap();
+        assert_eq!(
+            lines
+                .iter()
+                .filter(|line| line.starts_with("# HELP events_total "))
+                .count(),
+            1
+        );
+        assert_eq!(
+            lines
+                .iter()
+                .filter(|line| line.starts_with("# TYPE events_total "))
+                .count(),
+            1
+        );
+
+        // Both label sets follow the header
+        assert_eq!(lines[help + 1], "# TYPE events_total counter");
+        assert_eq!(
+            &lines[help + 2..help + 4],
+            [
+                r#"events_total{plugin="file_watcher"} 1"#,
+                r#"events_total{plugin="timer"} 1"#
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_counter_rate() {
+        let metrics = MetricsCollector::new();
+        let mut labels = HashMap::new();
+        labels.insert("plugin".to_string(), "test".to_string());
+        let key = MetricsCollector::build_key("events_total", &labels);
+
+        let now = Utc::now();
+        metrics.counter_samples.insert(
+            key,
+            vec![
+                (now - chrono::Duration::seconds(120), 100),
+                (now - chrono::Duration::seconds(30), 20),
+                (now - chrono::Duration::seconds(10), 10),
+            ],
+        );
+
+        // Only the two samples inside the 60s window count
+        let rate = metrics.get_counter_rate("events_total", &labels, 60);
+        assert_eq!(rate, Some(0.5));
+
+        let snapshot = metrics.get_snapshot();
+        assert_eq!(snapshot.gauges.get("events_per_second"), Some(&0.5));
+
+        assert_eq!(
+            metrics.get_counter_rate("events_total", &HashMap::new(), 60),
+            None
+        );
+    }
+
+    #[tokio::test]
+    async fn test_counter_rate_single_sample() {
+        let metrics = MetricsCollector::new();
+        let labels = HashMap::new();
+
+        metrics.increment_counter("events_total", labels.clone(), 5);
+
+        assert_eq!(
+            metrics.get_counter_rate("events_total", &labels, 60),
+            Some(0.0)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_snapshot_persistence_round_trip() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("metrics.json");
+
+        let metrics = MetricsCollector::new();
+        let mut labels = HashMap::new();
+        labels.insert("plugin".to_string(), "test".to_string());
+        metrics.increment_counter("events_total", labels.clone(), 7);
+        metrics.set_gauge("queue_depth", HashMap::new(), 3.5);
+        metrics.record_histogram("test_histogram", HashMap::new(), 0.25);
+
+        metrics.save_snapshot(&path).unwrap();
+
+        let restored = MetricsCollector::new();
+        restored.load_from_snapshot(&path).unwrap();
+
+        assert_eq!(restored.get_counter("events_total", &labels), Some(7));
+        assert_eq!(
+            restored.get_gauge("queue_depth", &HashMap::new()),
+            Some(3.5)
+        );
+
+        let stats = restored
+            .get_histogram_stats("test_histogram", &HashMap::new())
+            .unwrap();
+        assert_eq!(stats.count, 1);
+        assert!((stats.sum - 0.25).abs() < 1e-6);
+    }
+
+    #[tokio::test]
+    async fn test_snapshot_load_skips_expired_samples() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("metrics.json");
+
+        let metrics = MetricsCollector::new();
+        metrics.increment_counter("events_total", HashMap::new(), 4);
+        metrics.set_gauge("queue_depth", HashMap::new(), 1.0);
+
+        let mut snapshot = metrics.get_snapshot();
+        snapshot.timestamp = Utc::now() - chrono::Duration::hours(2);
+        std::fs::write(&path, serde_json::to_string(&snapshot).unwrap()).unwrap();
+
+        let restored = MetricsCollector::new();
+        restored.load_from_snapshot(&path).unwrap();
+
+        assert_eq!(
+            restored.get_counter("events_total", &HashMap::new()),
+            Some(4)
+        );
+        assert_eq!(restored.get_gauge("queue_depth", &HashMap::new()), None);
+    }
+
+    #[tokio::test]
+    async fn test_event_dropped_broadcast() {
+        let metrics = MetricsCollector::new();
+        let mut updates = metrics.subscribe();
+
+        metrics.record_event_dropped_with_broadcast("event buffer full");
+
+        assert_eq!(
+            metrics.get_counter("events_dropped_total", &HashMap::new()),
+            Some(1)
+        );
+
+        let update = updates.recv().await.unwrap();
+        let json = serde_json::to_string(&update).unwrap();
+        assert!(json.contains("\"type\":\"event_dropped\""));
+
+        match serde_json::from_str::<MetricUpdate>(&json).unwrap() {
+            MetricUpdate::EventDropped { reason, .. } => assert_eq!(reason, "event buffer full"),
+            other => panic!("Expected EventDropped, got {:?}", other),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_health_broadcast_reports_system_status() {
+        let metrics = MetricsCollector::new();
+        let mut updates = metrics.subscribe();
+
+        metrics.set_system_status(3, 7);
+        metrics.record_config_reload_with_broadcast(true);
+
+        assert_eq!(
+            metrics.get_gauge("active_plugins", &HashMap::new()),
+            Some(3.0)
+        );
+        assert_eq!(
+            metrics.get_gauge("active_rules", &HashMap::new()),
+            Some(7.0)
+        );
+
+        match updates.recv().await.unwrap() {
+            MetricUpdate::Health {
+                active_plugins,
+                active_rules,
+                ..
+            } => {
+                assert_eq!(active_plugins, 3);
+                assert_eq!(active_rules, 7);
+            }
+            other => panic!("Expected Health, got {:?}", other),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_key_round_trip_with_delimiters_in_labels() {
+        let metrics = MetricsCollector::new();
+        let mut labels = HashMap::new();
+        labels.insert("title".to_string(), r"C:\path,a=b {x}".to_string());
+        labels.insert("plugin".to_string(), "100%".to_string());
+
+        let key = MetricsCollector::build_key("events_total", &labels);
+        let (name, parsed) = metrics.parse_key(&key);
+
+        assert_eq!(name, "events_total");
+        assert_eq!(parsed, labels);
+
+        metrics.increment_counter("events_total", labels.clone(), 1);
+        let output = metrics.get_prometheus_format();
+        assert!(output.contains(r#"title="C:\\path,a=b {x}""#));
+    }
+
+    #[tokio::test]
+    async fn test_prometheus_format_sanitizes_names() {
+        let metrics = MetricsCollector::new();
+        let mut labels = HashMap::new();
+        labels.insert("rule name".to_string(), "backup".to_string());
+        labels.insert("2nd-label".to_string(), "x".to_string());
+
+        metrics.set_gauge("custom.queue-depth (items)", labels, 3.0);
+        metrics.set_gauge("5xx_responses", HashMap::new(), 1.0);
+        metrics.set_gauge("namespace:requests", HashMap::new(), 2.0);
+
+        let output = metrics.get_prometheus_format();
+        assert!(output.contains("# TYPE custom_queue_depth__items_ gauge\n"));
+        assert!(output.contains(r#"rule_name="backup""#));
+        assert!(output.contains(r#"_2nd_label="x""#));
+        assert!(output.contains("_5xx_responses 1\n"));
+        // Colons are allowed in metric names, but not in label names
+        assert!(output.contains("namespace:requests 2\n"));
+        assert_eq!(sanitize_label_name("namespace:label"), "namespace_label");
+        assert_eq!(sanitize_metric_name(""), "_");
+
+        let valid_name = |name: &str| {
+            name.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_' || c == ':')
+                && name
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == ':')
+        };
+        for line in output.lines().filter(|line| !line.starts_with('#')) {
+            let name = line.split(['{', ' ']).next().unwrap();
+            assert!(valid_name(name), "invalid metric name in {:?}", line);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_prometheus_format_escapes_label_values() {
+        let metrics = MetricsCollector::new();
+        let mut labels = HashMap::new();
+        labels.insert("title".to_string(), "say \"hi\"\nC:\\temp".to_string());
+
+        metrics.set_gauge("window_count", labels, 1.0);
+
+        let output = metrics.get_prometheus_format();
+        assert!(output.contains(r#"window_count{title="say \"hi\"\nC:\\temp"} 1"#));
+        // The value stays on one line
+        assert_eq!(
+            output
+                .lines()
+                .filter(|line| line.starts_with("window_count"))
+                .count(),
+            1
+        );
+    }
+
     #[tokio::test]
     async fn test_concurrent_access() {
         use std::sync::Arc;
@@ -1,38 +1,71 @@
+use std::collections::HashSet;
 use std::net::SocketAddr;
 use std::sync::Arc;
 
 use axum::{
     extract::{
         ws::{Message, WebSocket, WebSocketUpgrade},
-        State,
+        Path, State,
     },
+    http::StatusCode,
     response::{Html, Json},
-    routing::get,
+    routing::{get, post},
     Router,
 };
-use futures::{sink::SinkExt, stream::StreamExt};
+use futures::{
+    sink::{Sink, SinkExt},
+    stream::{Stream, StreamExt},
+};
 use serde::{Deserialize, Serialize};
 use tokio::net::TcpListener;
 use tokio::time::{interval, Duration};
-use tracing::{error, info};
+use tracing::{debug, error, info};
 
 use crate::{MetricUpdate, MetricsCollector, MetricsSnapshot};
 
+/// A loaded rule as reported by `/api/rules`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RuleInfo {
+    pub name: String,
+    pub description: Option<String>,
+    pub enabled: bool,
+}
+
+/// The engine's live rule list, for the server to list and toggle rules
+pub trait RuleRegistry: Send + Sync {
+    fn rules(&self) -> Vec<RuleInfo>;
+
+    /// Enable or disable a rule by name, returning it as updated, or `None`
+    /// if there is no such rule
+    fn set_rule_enabled(&self, name: &str, enabled: bool) -> Option<RuleInfo>;
+}
+
 /// HTTP server for serving metrics with WebSocket support
 pub struct MetricsServer {
     collector: Arc<MetricsCollector>,
+    rules: Option<Arc<dyn RuleRegistry>>,
     port: u16,
 }
 
 impl MetricsServer {
     /// Create a new metrics server
     pub fn new(collector: Arc<MetricsCollector>, port: u16) -> Self {
-        Self { collector, port }
+        Self {
+            collector,
+            rules: None,
+            port,
+        }
+    }
+
+    /// Serve `/api/rules` from `rules`
+    pub fn with_rules(mut self, rules: Arc<dyn RuleRegistry>) -> Self {
+        self.rules = Some(rules);
+        self
     }
 
     /// Start the HTTP server
     pub async fn start(&self) -> Result<(), Box<dyn std::error::Error>> {
-        let app = Router::new()
+        let mut app = Router::new()
             .route("/", get(root_handler))
             .route("/metrics", get(metrics_handler))
             .route("/api/snapshot", get(snapshot_handler))
@@ -40,6 +73,10 @@ impl MetricsServer {
             .route("/ws", get(websocket_handler))
             .with_state(self.collector.clone());
 
+        if let Some(rules) = &self.rules {
+            app = app.merge(rules_router(rules.clone()));
+        }
+
         let addr = SocketAddr::from(([127, 0, 0, 1], self.port));
         info!("Starting metrics server on http://{}", addr);
         info!("WebSocket endpoint available at ws://{}/ws", addr);
@@ -61,10 +98,19 @@ async fn websocket_handler(
 
 /// Handle WebSocket connection
 async fn handle_socket(socket: WebSocket, collector: Arc<MetricsCollector>) {
-    let (mut sender, mut receiver) = socket.split();
+    let (sender, receiver) = socket.split();
+    run_socket(sender, receiver, collector).await;
+}
 
+/// Stream updates to one client until it disconnects
+async fn run_socket<S, R, E>(mut sender: S, mut receiver: R, collector: Arc<MetricsCollector>)
+where
+    S: Sink<Message> + Unpin,
+    R: Stream<Item = Result<Message, E>> + Unpin,
+{
     // Subscribe to metric updates
     let mut updates = collector.subscribe();
+    let mut subscription = Subscription::default();
 
     // Send initial snapshot
     let initial_snapshot = MetricUpdate::Snapshot(collector.get_snapshot());
@@ -81,6 +127,9 @@ async fn handle_socket(socket: WebSocket, collector: Arc<MetricsCollector>) {
         tokio::select! {
             // Receive broadcast updates from metrics collector
             Ok(update) = updates.recv() => {
+                if !subscription.wants(&update) {
+                    continue;
+                }
                 match serde_json::to_string(&update) {
                     Ok(json) => {
                         if sender.send(Message::Text(json)).await.is_err() {
@@ -96,6 +145,9 @@ async fn handle_socket(socket: WebSocket, collector: Arc<MetricsCollector>) {
             // Send periodic snapshots
             _ = snapshot_interval.tick() => {
                 let snapshot = MetricUpdate::Snapshot(collector.get_snapshot());
+                if !subscription.wants(&snapshot) {
+                    continue;
+                }
                 if let Ok(json) = serde_json::to_string(&snapshot) {
                     if sender.send(Message::Text(json)).await.is_err() {
                         break;
@@ -116,7 +168,7 @@ async fn handle_socket(socket: WebSocket, collector: Arc<MetricsCollector>) {
                         }
                     }
                     Message::Text(text) => {
-                        handle_client_command(&text, &collector).await;
+                        handle_client_command(&text, &collector, &mut subscription).await;
                     }
                     _ => {}
                 }
@@ -128,19 +180,71 @@ async fn handle_socket(socket: WebSocket, collector: Arc<MetricsCollector>) {
 }
 
 /// Handle optional client commands via WebSocket
-async fn handle_client_command(text: &str, _collector: &MetricsCollector) {
-    if let Ok(cmd) = serde_json::from_str::<ClientCommand>(text) {
-        match cmd {
-            ClientCommand::Ping => {
-                // Pong sent automatically by protocol
-            }
+async fn handle_client_command(
+    text: &str,
+    _collector: &MetricsCollector,
+    subscription: &mut Subscription,
+) {
+    match parse_client_command(text) {
+        Ok(ClientCommand::Ping) => {
+            // Pong sent automatically by protocol
+        }
+        Ok(ClientCommand::Subscribe { types }) => {
+            info!("WebSocket client subscribed to {:?}", types);
+            *subscription = Subscription::to(types);
+        }
+        Err(e) => {
+            // Any page on the machine can connect, so don't let it fill the log
+            debug!("Ignoring unrecognized WebSocket command {:?}: {}", text, e);
         }
     }
 }
 
+/// Parse a client command, also accepting the older untagged form of ping
+/// (`"Ping"`) that clients written before commands were tagged still send
+fn parse_client_command(text: &str) -> serde_json::Result<ClientCommand> {
+    serde_json::from_str(text).or_else(|e| match serde_json::from_str(text) {
+        Ok(LegacyCommand::Ping) => Ok(ClientCommand::Ping),
+        Err(_) => Err(e),
+    })
+}
+
+/// Commands a client can send, e.g. `{"type": "subscribe", "types": ["rule_matched"]}`
 #[derive(Debug, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
 enum ClientCommand {
     Ping,
+    /// Only receive updates of these types from now on; an empty list
+    /// restores the default of receiving everything
+    Subscribe {
+        types: Vec<String>,
+    },
+}
+
+/// Commands as they were sent before they were tagged, e.g. `"Ping"`
+#[derive(Debug, Deserialize)]
+enum LegacyCommand {
+    Ping,
+}
+
+/// The update types one client receives; everything until it subscribes
+#[derive(Debug, Default)]
+struct Subscription {
+    types: Option<HashSet<String>>,
+}
+
+impl Subscription {
+    fn to(types: Vec<String>) -> Self {
+        Self {
+            types: (!types.is_empty()).then(|| types.into_iter().collect()),
+        }
+    }
+
+    fn wants(&self, update: &MetricUpdate) -> bool {
+        self.types
+            .as_ref()
+            .is_none_or(|types| types.contains(update.type_name()))
+    }
 }
 
 /// Root handler - full dashboard HTML
@@ -265,6 +369,14 @@ const DASHBOARD_HTML: &str = r#"<!DOCTYPE html>
             border-bottom: none;
         }
 
+        .event-item.error {
+            border-left: 3px solid #ef4444;
+        }
+
+        .event-item.error .event-type {
+            background: #7f1d1d;
+        }
+
         .event-time {
             color: #64748b;
             font-size: 0.75rem;
@@ -526,12 +638,20 @@ const DASHBOARD_HTML: &str = r#"<!DOCTYPE html>
                     addEventToLog('event', `Event from ${data.data.source}`, data.data.event_type);
                     break;
 
+                case 'event_dropped':
+                    addEventToLog('event', 'Event dropped', data.data.reason, 'error');
+                    break;
+
                 case 'rule_matched':
                     lastSecondMatches++;
                     addEventToLog('rule', `Rule matched: ${data.data.rule_name}`, 'match');
                     break;
 
                 case 'action_executed':
+                    if (data.data.skipped) {
+                        addEventToLog('action', `Action: ${data.data.action_name}`, 'skipped');
+                        break;
+                    }
                     if (data.data.success) {
                         actionSuccessCount++;
                     } else {
@@ -548,7 +668,7 @@ const DASHBOARD_HTML: &str = r#"<!DOCTYPE html>
         }
 
         // Add event to live log
-        function addEventToLog(type, message, detail) {
+        function addEventToLog(type, message, detail, level) {
             const log = document.getElementById('eventLog');
 
             if (log.children.length === 1 && log.children[0].style.textAlign === 'center') {
@@ -557,6 +677,9 @@ const DASHBOARD_HTML: &str = r#"<!DOCTYPE html>
 
             const item = document.createElement('div');
             item.className = 'event-item';
+            if (level === 'error') {
+                item.classList.add('error');
+            }
             item.dataset.type = type;
 
             const time = new Date().toLocaleTimeString();
@@ -665,6 +788,39 @@ async fn snapshot_handler(State(collector): State<Arc<MetricsCollector>>) -> Jso
     Json(collector.get_snapshot())
 }
 
+fn rules_router(rules: Arc<dyn RuleRegistry>) -> Router {
+    Router::new()
+        .route("/api/rules", get(rules_handler))
+        .route("/api/rules/:name", post(set_rule_enabled_handler))
+        .with_state(rules)
+}
+
+/// Loaded rules handler
+async fn rules_handler(State(rules): State<Arc<dyn RuleRegistry>>) -> Json<Vec<RuleInfo>> {
+    Json(rules.rules())
+}
+
+/// Body of `POST /api/rules/{name}`
+#[derive(Debug, Deserialize)]
+struct SetRuleEnabled {
+    enabled: bool,
+}
+
+/// Rule toggle handler
+async fn set_rule_enabled_handler(
+    State(rules): State<Arc<dyn RuleRegistry>>,
+    Path(name): Path<String>,
+    Json(request): Json<SetRuleEnabled>,
+) -> Result<Json<RuleInfo>, (StatusCode, Json<serde_json::Value>)> {
+    match rules.set_rule_enabled(&name, request.enabled) {
+        Some(rule) => Ok(Json(rule)),
+        None => Err((
+            StatusCode::NOT_FOUND,
+            Json(serde_json::json!({ "error": format!("no rule named '{}'", name) })),
+        )),
+    }
+}
+
 /// Health check handler
 async fn health_handler() -> Json<HealthResponse> {
     Json(HealthResponse {
@@ -683,6 +839,7 @@ struct HealthResponse {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::ActionStatus;
 
     #[tokio::test]
     async fn test_metrics_server_creation() {
@@ -697,4 +854,174 @@ mod tests {
         let response = health_handler().await;
         assert_eq!(response.status, "healthy");
     }
+
+    /// Rules held in memory, standing in for the engine
+    struct TestRules(std::sync::Mutex<Vec<RuleInfo>>);
+
+    impl RuleRegistry for TestRules {
+        fn rules(&self) -> Vec<RuleInfo> {
+            self.0.lock().unwrap().clone()
+        }
+
+        fn set_rule_enabled(&self, name: &str, enabled: bool) -> Option<RuleInfo> {
+            let mut rules = self.0.lock().unwrap();
+            let rule = rules.iter_mut().find(|rule| rule.name == name)?;
+            rule.enabled = enabled;
+            Some(rule.clone())
+        }
+    }
+
+    fn test_rules() -> Arc<dyn RuleRegistry> {
+        Arc::new(TestRules(std::sync::Mutex::new(vec![
+            RuleInfo {
+                name: "backup".to_string(),
+                description: Some("Back up new documents".to_string()),
+                enabled: true,
+            },
+            RuleInfo {
+                name: "cleanup".to_string(),
+                description: None,
+                enabled: false,
+            },
+        ])))
+    }
+
+    #[tokio::test]
+    async fn test_rules_handler_serialization() {
+        let Json(rules) = rules_handler(State(test_rules())).await;
+
+        assert_eq!(
+            serde_json::to_value(&rules).unwrap(),
+            serde_json::json!([
+                { "name": "backup", "description": "Back up new documents", "enabled": true },
+                { "name": "cleanup", "description": null, "enabled": false }
+            ])
+        );
+    }
+
+    #[tokio::test]
+    async fn test_set_rule_enabled_handler() {
+        let rules = test_rules();
+
+        let Json(rule) = set_rule_enabled_handler(
+            State(rules.clone()),
+            Path("backup".to_string()),
+            Json(SetRuleEnabled { enabled: false }),
+        )
+        .await
+        .unwrap();
+        assert_eq!(rule.name, "backup");
+        assert!(!rule.enabled);
+
+        // The change is visible to the next listing
+        let Json(listed) = rules_handler(State(rules.clone())).await;
+        assert!(listed.iter().all(|rule| !rule.enabled));
+
+        let missing = set_rule_enabled_handler(
+            State(rules),
+            Path("nope".to_string()),
+            Json(SetRuleEnabled { enabled: true }),
+        )
+        .await;
+        assert!(matches!(missing, Err((StatusCode::NOT_FOUND, _))));
+    }
+
+    /// The `type` of the next message the client receives
+    async fn next_update_type(
+        outgoing: &mut futures::channel::mpsc::UnboundedReceiver<Message>,
+    ) -> Option<String> {
+        let message = tokio::time::timeout(Duration::from_millis(200), outgoing.next())
+            .await
+            .ok()??;
+        let Message::Text(json) = message else {
+            return None;
+        };
+        let value: serde_json::Value = serde_json::from_str(&json).unwrap();
+        value["type"].as_str().map(str::to_string)
+    }
+
+    #[test]
+    fn test_subscription_filters_by_type() {
+        let matched = MetricUpdate::RuleMatched {
+            timestamp: chrono::Utc::now(),
+            rule_name: "backup".to_string(),
+        };
+        let evaluated = MetricUpdate::RuleEvaluated {
+            timestamp: chrono::Utc::now(),
+            rule_name: "backup".to_string(),
+        };
+
+        let everything = Subscription::default();
+        assert!(everything.wants(&matched) && everything.wants(&evaluated));
+
+        let subscription = Subscription::to(vec!["rule_matched".to_string()]);
+        assert!(subscription.wants(&matched));
+        assert!(!subscription.wants(&evaluated));
+
+        // An empty list goes back to everything
+        assert!(Subscription::to(Vec::new()).wants(&evaluated));
+    }
+
+    #[test]
+    fn test_parse_client_command_accepts_both_ping_forms() {
+        assert!(matches!(
+            parse_client_command(r#"{"type": "ping"}"#),
+            Ok(ClientCommand::Ping)
+        ));
+        assert!(matches!(
+            parse_client_command(r#""Ping""#),
+            Ok(ClientCommand::Ping)
+        ));
+        assert!(matches!(
+            parse_client_command(r#"{"type": "subscribe", "types": []}"#),
+            Ok(ClientCommand::Subscribe { types }) if types.is_empty()
+        ));
+        assert!(parse_client_command("hello").is_err());
+    }
+
+    #[tokio::test]
+    async fn test_subscribe_command_filters_delivery() {
+        let collector = Arc::new(MetricsCollector::new());
+        let (incoming_tx, incoming_rx) = futures::channel::mpsc::unbounded::<Result<Message, ()>>();
+        let (outgoing_tx, mut outgoing_rx) = futures::channel::mpsc::unbounded();
+        let client = tokio::spawn(run_socket(outgoing_tx, incoming_rx, collector.clone()));
+
+        // Initial snapshot, then the first tick of the periodic one
+        assert_eq!(
+            next_update_type(&mut outgoing_rx).await.as_deref(),
+            Some("snapshot")
+        );
+        assert_eq!(
+            next_update_type(&mut outgoing_rx).await.as_deref(),
+            Some("snapshot")
+        );
+
+        let command = r#"{"type": "subscribe", "types": ["rule_matched", "action_executed"]}"#;
+        incoming_tx
+            .unbounded_send(Ok(Message::Text(command.to_string())))
+            .unwrap();
+        tokio::time::sleep(Duration::from_millis(50)).await;
+
+        collector.record_event_with_broadcast("timer", "TimerTick");
+        collector.record_rule_evaluation_with_broadcast("backup");
+        collector.record_rule_match_with_broadcast("backup");
+        collector.record_action_execution_with_broadcast(
+            "run_backup",
+            ActionStatus::Success,
+            Duration::from_millis(5),
+        );
+        collector.record_event_dropped_with_broadcast("queue full");
+
+        assert_eq!(
+            next_update_type(&mut outgoing_rx).await.as_deref(),
+            Some("rule_matched")
+        );
+        assert_eq!(
+            next_update_type(&mut outgoing_rx).await.as_deref(),
+            Some("action_executed")
+        );
+        assert_eq!(next_update_type(&mut outgoing_rx).await, None);
+
+        client.abort();
+    }
 }
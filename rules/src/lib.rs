@@ -1,9 +1,20 @@
-use engine_core::event::{Event, EventKind};
+use chrono::{Datelike, Duration as ChronoDuration, NaiveDateTime, NaiveTime, Weekday};
+use engine_core::event::{Event, EventKind, EventKindTag, NetworkProtocol};
+use regex::Regex;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
 
 pub trait RuleMatcher: Send + Sync {
     fn matches(&self, event: &Event) -> bool;
     fn description(&self) -> String;
     fn clone_box(&self) -> Box<dyn RuleMatcher>;
+
+    /// The event kinds this matcher can possibly match, or `None` if it may
+    /// match any kind. Used by [`RuleSet`] to skip rules that cannot apply.
+    fn event_kinds(&self) -> Option<Vec<EventKindTag>> {
+        None
+    }
 }
 
 impl Clone for Box<dyn RuleMatcher> {
@@ -52,6 +63,83 @@ impl Rule {
         }
         self.matcher.matches(event)
     }
+
+    pub fn event_kinds(&self) -> Option<Vec<EventKindTag>> {
+        self.matcher.event_kinds()
+    }
+}
+
+/// An ordered list of rules, indexed by the event kinds each rule can match so
+/// that an event is only evaluated against rules that might apply to it
+#[derive(Debug, Clone, Default)]
+pub struct RuleSet {
+    rules: Vec<Rule>,
+    indexed: bool,
+    by_kind: HashMap<EventKindTag, Vec<usize>>,
+    any_kind: Vec<usize>,
+}
+
+impl RuleSet {
+    pub fn new() -> Self {
+        Self {
+            indexed: true,
+            ..Default::default()
+        }
+    }
+
+    /// A rule set that evaluates every rule against every event
+    pub fn unindexed() -> Self {
+        Self::default()
+    }
+
+    pub fn push(&mut self, rule: Rule) {
+        let position = self.rules.len();
+        match rule.event_kinds() {
+            Some(kinds) => {
+                for kind in kinds {
+                    let positions = self.by_kind.entry(kind).or_default();
+                    if positions.last() != Some(&position) {
+                        positions.push(position);
+                    }
+                }
+            }
+            None => self.any_kind.push(position),
+        }
+        self.rules.push(rule);
+    }
+
+    /// Rules that might match an event of `kind`, in the order they were added
+    pub fn candidates(&self, kind: &EventKind) -> Vec<&Rule> {
+        if !self.indexed {
+            return self.rules.iter().collect();
+        }
+
+        let specific = self.by_kind.get(&kind.tag()).map_or(&[][..], Vec::as_slice);
+        let mut positions = Vec::with_capacity(specific.len() + self.any_kind.len());
+        positions.extend_from_slice(specific);
+        positions.extend_from_slice(&self.any_kind);
+        positions.sort_unstable();
+
+        positions.into_iter().map(|i| &self.rules[i]).collect()
+    }
+
+    pub fn iter(&self) -> std::slice::Iter<'_, Rule> {
+        self.rules.iter()
+    }
+
+    /// Mutable access for toggling rules; a rule's matcher must not be replaced,
+    /// or the index goes stale
+    pub fn iter_mut(&mut self) -> std::slice::IterMut<'_, Rule> {
+        self.rules.iter_mut()
+    }
+
+    pub fn len(&self) -> usize {
+        self.rules.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.rules.is_empty()
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -71,12 +159,17 @@ impl RuleMatcher for EventKindMatcher {
     fn clone_box(&self) -> Box<dyn RuleMatcher> {
         Box::new(self.clone())
     }
+
+    fn event_kinds(&self) -> Option<Vec<EventKindTag>> {
+        Some(vec![self.kind.tag()])
+    }
 }
 
 #[derive(Debug, Clone)]
 pub struct WindowMatcher {
     pub event_type: WindowEventType,
     pub title_contains: Option<String>,
+    pub title_regex: Option<Regex>,
     pub process_name: Option<String>,
 }
 
@@ -86,6 +179,9 @@ pub enum WindowEventType {
     Unfocused,
     Created,
     Destroyed,
+    Minimized,
+    Restored,
+    Moved,
 }
 
 impl RuleMatcher for WindowMatcher {
@@ -115,6 +211,35 @@ impl RuleMatcher for WindowMatcher {
             EventKind::WindowDestroyed { hwnd: _ } => {
                 (WindowEventType::Destroyed, String::new(), String::new())
             }
+            EventKind::WindowMinimized { hwnd: _, title } => {
+                let process_name = event
+                    .metadata
+                    .get("process_name")
+                    .cloned()
+                    .unwrap_or_default();
+                (WindowEventType::Minimized, title.clone(), process_name)
+            }
+            EventKind::WindowRestored { hwnd: _, title } => {
+                let process_name = event
+                    .metadata
+                    .get("process_name")
+                    .cloned()
+                    .unwrap_or_default();
+                (WindowEventType::Restored, title.clone(), process_name)
+            }
+            EventKind::WindowMoved { .. } => {
+                let title = event
+                    .metadata
+                    .get("window_title")
+                    .cloned()
+                    .unwrap_or_default();
+                let process_name = event
+                    .metadata
+                    .get("process_name")
+                    .cloned()
+                    .unwrap_or_default();
+                (WindowEventType::Moved, title, process_name)
+            }
             _ => return false,
         };
 
@@ -128,6 +253,12 @@ impl RuleMatcher for WindowMatcher {
             }
         }
 
+        if let Some(ref title_regex) = self.title_regex
+            && !title_regex.is_match(&title)
+        {
+            return false;
+        }
+
         if let Some(ref process_filter) = self.process_name {
             if !process_name
                 .to_lowercase()
@@ -145,6 +276,9 @@ impl RuleMatcher for WindowMatcher {
         if let Some(ref title) = self.title_contains {
             desc.push_str(&format!(" with title containing '{}'", title));
         }
+        if let Some(ref regex) = self.title_regex {
+            desc.push_str(&format!(" with title matching /{}/", regex.as_str()));
+        }
         if let Some(ref process) = self.process_name {
             desc.push_str(&format!(" from process '{}'", process));
         }
@@ -154,6 +288,19 @@ impl RuleMatcher for WindowMatcher {
     fn clone_box(&self) -> Box<dyn RuleMatcher> {
         Box::new(self.clone())
     }
+
+    fn event_kinds(&self) -> Option<Vec<EventKindTag>> {
+        let kind = match self.event_type {
+            WindowEventType::Focused => EventKindTag::WindowFocused,
+            WindowEventType::Unfocused => EventKindTag::WindowUnfocused,
+            WindowEventType::Created => EventKindTag::WindowCreated,
+            WindowEventType::Destroyed => EventKindTag::WindowDestroyed,
+            WindowEventType::Minimized => EventKindTag::WindowMinimized,
+            WindowEventType::Restored => EventKindTag::WindowRestored,
+            WindowEventType::Moved => EventKindTag::WindowMoved,
+        };
+        Some(vec![kind])
+    }
 }
 
 #[derive(Debug)]
@@ -184,6 +331,7 @@ pub enum FileEventType {
     Created,
     Modified,
     Deleted,
+    Renamed,
     Any,
 }
 
@@ -193,6 +341,8 @@ impl RuleMatcher for FilePatternMatcher {
             EventKind::FileCreated { path } => (FileEventType::Created, path),
             EventKind::FileModified { path } => (FileEventType::Modified, path),
             EventKind::FileDeleted { path } => (FileEventType::Deleted, path),
+            // Patterns apply to the new name, e.g. "moved into the inbox"
+            EventKind::FileRenamed { new_path, .. } => (FileEventType::Renamed, new_path),
             _ => return false,
         };
 
@@ -232,6 +382,21 @@ impl RuleMatcher for FilePatternMatcher {
     fn clone_box(&self) -> Box<dyn RuleMatcher> {
         Box::new(self.clone())
     }
+
+    fn event_kinds(&self) -> Option<Vec<EventKindTag>> {
+        Some(match self.event_type {
+            FileEventType::Created => vec![EventKindTag::FileCreated],
+            FileEventType::Modified => vec![EventKindTag::FileModified],
+            FileEventType::Deleted => vec![EventKindTag::FileDeleted],
+            FileEventType::Renamed => vec![EventKindTag::FileRenamed],
+            FileEventType::Any => vec![
+                EventKindTag::FileCreated,
+                EventKindTag::FileModified,
+                EventKindTag::FileDeleted,
+                EventKindTag::FileRenamed,
+            ],
+        })
+    }
 }
 
 impl FilePatternMatcher {
@@ -259,6 +424,14 @@ impl FilePatternMatcher {
         }
     }
 
+    pub fn renamed() -> Self {
+        Self {
+            event_type: FileEventType::Renamed,
+            path_pattern: None,
+            file_pattern: None,
+        }
+    }
+
     pub fn any() -> Self {
         Self {
             event_type: FileEventType::Any,
@@ -278,166 +451,1662 @@ impl FilePatternMatcher {
     }
 }
 
-#[derive(Debug)]
-pub struct CompositeMatcher {
-    pub matchers: Vec<Box<dyn RuleMatcher>>,
-    pub operator: MatchOperator,
-}
-
-impl Clone for CompositeMatcher {
-    fn clone(&self) -> Self {
-        Self {
-            matchers: self.matchers.iter().map(|m| m.clone_box()).collect(),
-            operator: self.operator,
-        }
-    }
+/// Matches ETW file I/O events, optionally requiring a minimum transfer size
+#[derive(Debug, Clone)]
+pub struct FileIoMatcher {
+    pub operation: FileIoOperation,
+    pub min_bytes: Option<u64>,
+    pub path_pattern: Option<glob::Pattern>,
 }
 
-#[derive(Debug, Clone, Copy)]
-pub enum MatchOperator {
-    And,
-    Or,
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FileIoOperation {
+    Read,
+    Write,
+    Any,
 }
 
-impl RuleMatcher for CompositeMatcher {
+impl RuleMatcher for FileIoMatcher {
     fn matches(&self, event: &Event) -> bool {
-        match self.operator {
-            MatchOperator::And => self.matchers.iter().all(|m| m.matches(event)),
-            MatchOperator::Or => self.matchers.iter().any(|m| m.matches(event)),
+        let (operation, path, bytes) = match &event.kind {
+            EventKind::FileIoRead {
+                path, bytes_read, ..
+            } => (FileIoOperation::Read, path, *bytes_read),
+            EventKind::FileIoWrite {
+                path,
+                bytes_written,
+                ..
+            } => (FileIoOperation::Write, path, *bytes_written),
+            _ => return false,
+        };
+
+        if self.operation != FileIoOperation::Any && self.operation != operation {
+            return false;
+        }
+
+        if let Some(min_bytes) = self.min_bytes
+            && bytes < min_bytes
+        {
+            return false;
+        }
+
+        if let Some(ref pattern) = self.path_pattern
+            && !path.to_str().is_some_and(|p| pattern.matches(p))
+        {
+            return false;
         }
+
+        true
     }
 
     fn description(&self) -> String {
-        let op_str = match self.operator {
-            MatchOperator::And => "AND",
-            MatchOperator::Or => "OR",
-        };
-        format!(
-            "({})",
-            self.matchers
-                .iter()
-                .map(|m| m.description())
-                .collect::<Vec<_>>()
-                .join(&format!(" {} ", op_str))
-        )
+        let mut desc = format!("File I/O {:?} event", self.operation);
+        if let Some(min_bytes) = self.min_bytes {
+            desc.push_str(&format!(" of at least {} bytes", min_bytes));
+        }
+        if let Some(ref pattern) = self.path_pattern {
+            desc.push_str(&format!(" on '{}'", pattern.as_str()));
+        }
+        desc
     }
 
     fn clone_box(&self) -> Box<dyn RuleMatcher> {
         Box::new(self.clone())
     }
+
+    fn event_kinds(&self) -> Option<Vec<EventKindTag>> {
+        Some(match self.operation {
+            FileIoOperation::Read => vec![EventKindTag::FileIoRead],
+            FileIoOperation::Write => vec![EventKindTag::FileIoWrite],
+            FileIoOperation::Any => vec![EventKindTag::FileIoRead, EventKindTag::FileIoWrite],
+        })
+    }
 }
 
-fn matches_event_kind(expected: &EventKind, actual: &EventKind) -> bool {
-    match (expected, actual) {
-        (EventKind::TimerTick, EventKind::TimerTick) => true,
-        (EventKind::FileCreated { path: p1 }, EventKind::FileCreated { path: p2 }) => p1 == p2,
-        (EventKind::FileModified { path: p1 }, EventKind::FileModified { path: p2 }) => p1 == p2,
-        (EventKind::FileDeleted { path: p1 }, EventKind::FileDeleted { path: p2 }) => p1 == p2,
-        (EventKind::WindowFocused { .. }, EventKind::WindowFocused { .. }) => true,
-        (EventKind::WindowUnfocused { .. }, EventKind::WindowUnfocused { .. }) => true,
-        (EventKind::WindowCreated { .. }, EventKind::WindowCreated { .. }) => true,
-        (EventKind::WindowDestroyed { .. }, EventKind::WindowDestroyed { .. }) => true,
-        _ => false,
+impl FileIoMatcher {
+    pub fn new(operation: FileIoOperation) -> Self {
+        Self {
+            operation,
+            min_bytes: None,
+            path_pattern: None,
+        }
+    }
+
+    pub fn with_min_bytes(mut self, min_bytes: u64) -> Self {
+        self.min_bytes = Some(min_bytes);
+        self
+    }
+
+    pub fn with_path_pattern(mut self, pattern: &str) -> Result<Self, glob::PatternError> {
+        self.path_pattern = Some(glob::Pattern::new(pattern)?);
+        Ok(self)
     }
 }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use engine_core::event::Event;
-    use std::path::PathBuf;
+#[derive(Debug, Clone)]
+pub struct ProcessMatcher {
+    pub event_type: ProcessEventType,
+    pub name_pattern: Option<glob::Pattern>,
+    pub command_line_contains: Option<String>,
+    /// Only match stop events whose exit code is known and non-zero
+    pub exit_code_nonzero: bool,
+}
 
-    #[test]
-    fn test_simple_event_kind_matcher() {
-        let matcher = EventKindMatcher {
-            kind: EventKind::TimerTick,
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ProcessEventType {
+    Started,
+    Stopped,
+    Any,
+}
+
+impl RuleMatcher for ProcessMatcher {
+    fn matches(&self, event: &Event) -> bool {
+        let (event_type, name, command_line, exit_code) = match &event.kind {
+            EventKind::ProcessStarted {
+                name, command_line, ..
+            } => (ProcessEventType::Started, name, Some(command_line), None),
+            EventKind::ProcessStopped {
+                name, exit_code, ..
+            } => (ProcessEventType::Stopped, name, None, *exit_code),
+            _ => return false,
         };
-        let event = Event::new(EventKind::TimerTick, "test");
-        assert!(matcher.matches(&event));
 
-        let event2 = Event::new(
-            EventKind::FileCreated {
-                path: PathBuf::from("test.txt"),
-            },
-            "test",
-        );
-        assert!(!matcher.matches(&event2));
-    }
+        if self.event_type != ProcessEventType::Any && self.event_type != event_type {
+            return false;
+        }
 
-    #[test]
-    fn test_file_pattern_matcher() {
-        let matcher = FilePatternMatcher::created()
-            .with_file_pattern("*.txt")
-            .unwrap();
+        if let Some(ref pattern) = self.name_pattern
+            && !pattern.matches_with(
+                name,
+                glob::MatchOptions {
+                    case_sensitive: false,
+                    ..Default::default()
+                },
+            )
+        {
+            return false;
+        }
 
-        let event = Event::new(
-            EventKind::FileCreated {
-                path: PathBuf::from("/tmp/test.txt"),
-            },
-            "test",
-        );
-        assert!(matcher.matches(&event));
+        if let Some(ref filter) = self.command_line_contains {
+            // Stop events carry no command line, so a command-line filter never matches them
+            match command_line {
+                Some(cmd) if cmd.to_lowercase().contains(&filter.to_lowercase()) => {}
+                _ => return false,
+            }
+        }
 
-        let event2 = Event::new(
-            EventKind::FileCreated {
-                path: PathBuf::from("/tmp/test.log"),
-            },
-            "test",
-        );
-        assert!(!matcher.matches(&event2));
+        // Start events and stops with an unknown exit code never count as failures
+        if self.exit_code_nonzero && !matches!(exit_code, Some(code) if code != 0) {
+            return false;
+        }
 
-        let event3 = Event::new(
-            EventKind::FileModified {
-                path: PathBuf::from("/tmp/test.txt"),
-            },
-            "test",
-        );
-        assert!(!matcher.matches(&event3));
+        true
     }
 
-    #[test]
-    fn test_composite_matcher_and() {
-        let matcher1 = Box::new(FilePatternMatcher::created()) as Box<dyn RuleMatcher>;
-        let matcher2 = Box::new(
-            FilePatternMatcher::created()
-                .with_file_pattern("*.log")
-                .unwrap(),
-        );
+    fn description(&self) -> String {
+        let mut desc = format!("Process {:?} event", self.event_type);
+        if let Some(ref pattern) = self.name_pattern {
+            desc.push_str(&format!(" with name matching '{}'", pattern.as_str()));
+        }
+        if let Some(ref cmd) = self.command_line_contains {
+            desc.push_str(&format!(" with command line containing '{}'", cmd));
+        }
+        if self.exit_code_nonzero {
+            desc.push_str(" with a non-zero exit code");
+        }
+        desc
+    }
 
-        let composite = CompositeMatcher {
-            matchers: vec![matcher1, matcher2],
-            operator: MatchOperator::And,
-        };
+    fn clone_box(&self) -> Box<dyn RuleMatcher> {
+        Box::new(self.clone())
+    }
 
-        let event = Event::new(
-            EventKind::FileCreated {
-                path: PathBuf::from("/tmp/app.log"),
-            },
-            "test",
-        );
-        assert!(composite.matches(&event));
+    fn event_kinds(&self) -> Option<Vec<EventKindTag>> {
+        if self.exit_code_nonzero {
+            return Some(vec![EventKindTag::ProcessStopped]);
+        }
+        Some(match self.event_type {
+            ProcessEventType::Started => vec![EventKindTag::ProcessStarted],
+            ProcessEventType::Stopped => vec![EventKindTag::ProcessStopped],
+            ProcessEventType::Any => {
+                vec![EventKindTag::ProcessStarted, EventKindTag::ProcessStopped]
+            }
+        })
+    }
+}
 
-        let event2 = Event::new(
-            EventKind::FileCreated {
-                path: PathBuf::from("/tmp/app.txt"),
-            },
-            "test",
-        );
-        assert!(!composite.matches(&event2));
+impl ProcessMatcher {
+    pub fn started() -> Self {
+        Self {
+            event_type: ProcessEventType::Started,
+            name_pattern: None,
+            command_line_contains: None,
+            exit_code_nonzero: false,
+        }
     }
 
-    #[test]
-    fn test_rule_with_disabled() {
-        let matcher = Box::new(EventKindMatcher {
-            kind: EventKind::TimerTick,
-        });
-        let rule = Rule::new("test_rule", matcher).with_enabled(false);
+    pub fn stopped() -> Self {
+        Self {
+            event_type: ProcessEventType::Stopped,
+            name_pattern: None,
+            command_line_contains: None,
+            exit_code_nonzero: false,
+        }
+    }
 
-        let event = Event::new(EventKind::TimerTick, "test");
-        assert!(!rule.matches(&event));
+    pub fn any() -> Self {
+        Self {
+            event_type: ProcessEventType::Any,
+            name_pattern: None,
+            command_line_contains: None,
+            exit_code_nonzero: false,
+        }
     }
 
-    #[test]
+    pub fn with_name_pattern(mut self, pattern: &str) -> Result<Self, glob::PatternError> {
+        self.name_pattern = Some(glob::Pattern::new(pattern)?);
+        Ok(self)
+    }
+
+    pub fn with_command_line_contains(mut self, needle: &str) -> Self {
+        self.command_line_contains = Some(needle.to_string());
+        self
+    }
+
+    /// Only match processes that exited with a non-zero code, e.g. to detect crashes
+    pub fn with_exit_code_nonzero(mut self, nonzero: bool) -> Self {
+        self.exit_code_nonzero = nonzero;
+        self
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct NetworkMatcher {
+    pub event_type: ConnectionEventType,
+    pub remote_port: Option<u16>,
+    pub remote_addr_prefix: Option<String>,
+    pub protocol: Option<NetworkProtocol>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConnectionEventType {
+    Connected,
+    Disconnected,
+    Any,
+}
+
+impl RuleMatcher for NetworkMatcher {
+    fn matches(&self, event: &Event) -> bool {
+        let (event_type, remote_addr, remote_port, protocol) = match &event.kind {
+            EventKind::NetworkConnectionCreated {
+                remote_addr,
+                remote_port,
+                protocol,
+                ..
+            } => (
+                ConnectionEventType::Connected,
+                remote_addr,
+                *remote_port,
+                Some(protocol),
+            ),
+            EventKind::NetworkConnectionClosed {
+                remote_addr,
+                remote_port,
+                ..
+            } => (
+                ConnectionEventType::Disconnected,
+                remote_addr,
+                *remote_port,
+                None,
+            ),
+            _ => return false,
+        };
+
+        if self.event_type != ConnectionEventType::Any && self.event_type != event_type {
+            return false;
+        }
+
+        if let Some(port) = self.remote_port
+            && port != remote_port
+        {
+            return false;
+        }
+
+        if let Some(ref prefix) = self.remote_addr_prefix
+            && !remote_addr.starts_with(prefix.as_str())
+        {
+            return false;
+        }
+
+        // Close events carry no protocol, so a protocol filter never matches them
+        if self.protocol.is_some() && self.protocol.as_ref() != protocol {
+            return false;
+        }
+
+        true
+    }
+
+    fn description(&self) -> String {
+        let mut desc = format!("Network {:?} event", self.event_type);
+        if let Some(ref protocol) = self.protocol {
+            desc.push_str(&format!(" over {:?}", protocol));
+        }
+        if let Some(ref prefix) = self.remote_addr_prefix {
+            desc.push_str(&format!(" to '{}*'", prefix));
+        }
+        if let Some(port) = self.remote_port {
+            desc.push_str(&format!(" on port {}", port));
+        }
+        desc
+    }
+
+    fn clone_box(&self) -> Box<dyn RuleMatcher> {
+        Box::new(self.clone())
+    }
+
+    fn event_kinds(&self) -> Option<Vec<EventKindTag>> {
+        Some(match self.event_type {
+            ConnectionEventType::Connected => vec![EventKindTag::NetworkConnectionCreated],
+            ConnectionEventType::Disconnected => vec![EventKindTag::NetworkConnectionClosed],
+            ConnectionEventType::Any => vec![
+                EventKindTag::NetworkConnectionCreated,
+                EventKindTag::NetworkConnectionClosed,
+            ],
+        })
+    }
+}
+
+impl NetworkMatcher {
+    pub fn connected() -> Self {
+        Self {
+            event_type: ConnectionEventType::Connected,
+            remote_port: None,
+            remote_addr_prefix: None,
+            protocol: None,
+        }
+    }
+
+    pub fn disconnected() -> Self {
+        Self {
+            event_type: ConnectionEventType::Disconnected,
+            remote_port: None,
+            remote_addr_prefix: None,
+            protocol: None,
+        }
+    }
+
+    pub fn any() -> Self {
+        Self {
+            event_type: ConnectionEventType::Any,
+            remote_port: None,
+            remote_addr_prefix: None,
+            protocol: None,
+        }
+    }
+
+    pub fn with_remote_port(mut self, port: u16) -> Self {
+        self.remote_port = Some(port);
+        self
+    }
+
+    pub fn with_remote_addr_prefix(mut self, prefix: &str) -> Self {
+        self.remote_addr_prefix = Some(prefix.to_string());
+        self
+    }
+
+    pub fn with_protocol(mut self, protocol: NetworkProtocol) -> Self {
+        self.protocol = Some(protocol);
+        self
+    }
+}
+
+/// Matches on a single key in the event's metadata map
+#[derive(Debug, Clone)]
+pub struct MetadataMatcher {
+    pub key: String,
+    pub condition: MetadataCondition,
+}
+
+#[derive(Debug, Clone)]
+pub enum MetadataCondition {
+    Equals(String),
+    Contains(String),
+    Regex(Regex),
+    Exists,
+}
+
+impl MetadataMatcher {
+    pub fn new(key: impl Into<String>, condition: MetadataCondition) -> Self {
+        Self {
+            key: key.into(),
+            condition,
+        }
+    }
+}
+
+impl RuleMatcher for MetadataMatcher {
+    fn matches(&self, event: &Event) -> bool {
+        let Some(value) = event.metadata.get(&self.key) else {
+            return false;
+        };
+
+        match &self.condition {
+            MetadataCondition::Equals(expected) => value == expected,
+            MetadataCondition::Contains(needle) => value.contains(needle.as_str()),
+            MetadataCondition::Regex(regex) => regex.is_match(value),
+            MetadataCondition::Exists => true,
+        }
+    }
+
+    fn description(&self) -> String {
+        match &self.condition {
+            MetadataCondition::Equals(expected) => {
+                format!("Metadata '{}' equals '{}'", self.key, expected)
+            }
+            MetadataCondition::Contains(needle) => {
+                format!("Metadata '{}' containing '{}'", self.key, needle)
+            }
+            MetadataCondition::Regex(regex) => {
+                format!("Metadata '{}' matching /{}/", self.key, regex.as_str())
+            }
+            MetadataCondition::Exists => format!("Metadata '{}' exists", self.key),
+        }
+    }
+
+    fn clone_box(&self) -> Box<dyn RuleMatcher> {
+        Box::new(self.clone())
+    }
+}
+
+#[derive(Debug)]
+pub struct CompositeMatcher {
+    pub matchers: Vec<Box<dyn RuleMatcher>>,
+    pub operator: MatchOperator,
+}
+
+impl Clone for CompositeMatcher {
+    fn clone(&self) -> Self {
+        Self {
+            matchers: self.matchers.iter().map(|m| m.clone_box()).collect(),
+            operator: self.operator,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum MatchOperator {
+    And,
+    Or,
+}
+
+impl RuleMatcher for CompositeMatcher {
+    fn matches(&self, event: &Event) -> bool {
+        match self.operator {
+            MatchOperator::And => self.matchers.iter().all(|m| m.matches(event)),
+            MatchOperator::Or => self.matchers.iter().any(|m| m.matches(event)),
+        }
+    }
+
+    fn description(&self) -> String {
+        let op_str = match self.operator {
+            MatchOperator::And => "AND",
+            MatchOperator::Or => "OR",
+        };
+        format!(
+            "({})",
+            self.matchers
+                .iter()
+                .map(|m| m.description())
+                .collect::<Vec<_>>()
+                .join(&format!(" {} ", op_str))
+        )
+    }
+
+    fn clone_box(&self) -> Box<dyn RuleMatcher> {
+        Box::new(self.clone())
+    }
+
+    fn event_kinds(&self) -> Option<Vec<EventKindTag>> {
+        let mut nested = self.matchers.iter().map(|m| m.event_kinds());
+        match self.operator {
+            // Only kinds every restricted matcher allows can satisfy all of them
+            MatchOperator::And => nested.fold(None, |acc, kinds| match (acc, kinds) {
+                (None, kinds) => kinds,
+                (acc, None) => acc,
+                (Some(acc), Some(kinds)) => {
+                    Some(acc.into_iter().filter(|k| kinds.contains(k)).collect())
+                }
+            }),
+            // Any unrestricted branch means any kind can match
+            MatchOperator::Or => nested.try_fold(Vec::new(), |mut acc, kinds| {
+                for kind in kinds? {
+                    if !acc.contains(&kind) {
+                        acc.push(kind);
+                    }
+                }
+                Some(acc)
+            }),
+        }
+    }
+}
+
+/// Negates a single inner matcher
+#[derive(Debug, Clone)]
+pub struct NotMatcher {
+    pub matcher: Box<dyn RuleMatcher>,
+}
+
+impl NotMatcher {
+    pub fn new(matcher: Box<dyn RuleMatcher>) -> Self {
+        Self { matcher }
+    }
+}
+
+impl RuleMatcher for NotMatcher {
+    fn matches(&self, event: &Event) -> bool {
+        !self.matcher.matches(event)
+    }
+
+    fn description(&self) -> String {
+        format!("NOT ({})", self.matcher.description())
+    }
+
+    fn clone_box(&self) -> Box<dyn RuleMatcher> {
+        Box::new(self.clone())
+    }
+}
+
+/// Reports a match from the inner matcher at most once per `interval`
+#[derive(Debug)]
+pub struct ThrottleMatcher {
+    pub matcher: Box<dyn RuleMatcher>,
+    pub interval: Duration,
+    last_match: Mutex<Option<Instant>>,
+}
+
+impl ThrottleMatcher {
+    pub fn new(matcher: Box<dyn RuleMatcher>, interval: Duration) -> Self {
+        Self {
+            matcher,
+            interval,
+            last_match: Mutex::new(None),
+        }
+    }
+}
+
+impl Clone for ThrottleMatcher {
+    fn clone(&self) -> Self {
+        // A clone starts with a fresh window rather than sharing the original's timer
+        Self::new(self.matcher.clone_box(), self.interval)
+    }
+}
+
+impl RuleMatcher for ThrottleMatcher {
+    fn matches(&self, event: &Event) -> bool {
+        if !self.matcher.matches(event) {
+            return false;
+        }
+
+        let now = Instant::now();
+        let mut last_match = self.last_match.lock().unwrap();
+        if let Some(last) = *last_match
+            && now.duration_since(last) < self.interval
+        {
+            return false;
+        }
+        *last_match = Some(now);
+        true
+    }
+
+    fn description(&self) -> String {
+        format!(
+            "{} (at most once per {:?})",
+            self.matcher.description(),
+            self.interval
+        )
+    }
+
+    fn clone_box(&self) -> Box<dyn RuleMatcher> {
+        Box::new(self.clone())
+    }
+
+    fn event_kinds(&self) -> Option<Vec<EventKindTag>> {
+        self.matcher.event_kinds()
+    }
+}
+
+/// Reports a match from the inner matcher only if no event with the same
+/// signature matched within `window`.
+///
+/// The signature is built from `key`: `source`, `kind` (the variant name),
+/// `metadata.<key>`, or the name of one of the event kind's fields such as
+/// `title` or `path`; parts the event doesn't have are empty. An empty `key`
+/// uses the source and the whole event kind, so only identical events are
+/// suppressed.
+#[derive(Debug)]
+pub struct DedupMatcher {
+    pub matcher: Box<dyn RuleMatcher>,
+    pub window: Duration,
+    pub key: Vec<String>,
+    seen: Mutex<HashMap<String, Instant>>,
+}
+
+impl DedupMatcher {
+    pub fn new(matcher: Box<dyn RuleMatcher>, window: Duration, key: Vec<String>) -> Self {
+        Self {
+            matcher,
+            window,
+            key,
+            seen: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// The signature `event` is deduplicated by
+    pub fn signature(&self, event: &Event) -> String {
+        if self.key.is_empty() {
+            return format!("{}|{:?}", event.source, event.kind);
+        }
+
+        // Only serialize the kind if a key part needs one of its fields
+        let mut kind_fields = None;
+        let parts: Vec<String> = self
+            .key
+            .iter()
+            .map(|part| match part.as_str() {
+                "source" => event.source.clone(),
+                "kind" => format!("{:?}", event.kind.tag()),
+                _ => {
+                    if let Some(key) = part.strip_prefix("metadata.") {
+                        return event.metadata.get(key).cloned().unwrap_or_default();
+                    }
+                    let fields =
+                        kind_fields.get_or_insert_with(|| serde_json::to_value(&event.kind).ok());
+                    match fields.as_ref().and_then(|fields| fields.get(part)) {
+                        Some(serde_json::Value::String(value)) => value.clone(),
+                        Some(serde_json::Value::Null) | None => String::new(),
+                        Some(value) => value.to_string(),
+                    }
+                }
+            })
+            .collect();
+        parts.join("|")
+    }
+}
+
+impl Clone for DedupMatcher {
+    fn clone(&self) -> Self {
+        // Like ThrottleMatcher, a clone starts without any remembered signatures
+        Self::new(self.matcher.clone_box(), self.window, self.key.clone())
+    }
+}
+
+impl RuleMatcher for DedupMatcher {
+    fn matches(&self, event: &Event) -> bool {
+        if !self.matcher.matches(event) {
+            return false;
+        }
+
+        let signature = self.signature(event);
+        let now = Instant::now();
+        let mut seen = self.seen.lock().unwrap();
+        // Forget expired signatures so the map only holds the current window
+        seen.retain(|_, last| now.duration_since(*last) < self.window);
+        if seen.contains_key(&signature) {
+            return false;
+        }
+        seen.insert(signature, now);
+        true
+    }
+
+    fn description(&self) -> String {
+        let key = if self.key.is_empty() {
+            "identical events".to_string()
+        } else {
+            self.key.join("+")
+        };
+        format!(
+            "{} (once per {} within {:?})",
+            self.matcher.description(),
+            key,
+            self.window
+        )
+    }
+
+    fn clone_box(&self) -> Box<dyn RuleMatcher> {
+        Box::new(self.clone())
+    }
+
+    fn event_kinds(&self) -> Option<Vec<EventKindTag>> {
+        self.matcher.event_kinds()
+    }
+}
+
+/// Source of the current local time for time-based matchers
+pub trait Clock: Send + Sync + std::fmt::Debug {
+    fn now(&self) -> NaiveDateTime;
+}
+
+/// Reads the system's local wall-clock time
+#[derive(Debug, Clone, Copy, Default)]
+pub struct LocalClock;
+
+impl Clock for LocalClock {
+    fn now(&self) -> NaiveDateTime {
+        chrono::Local::now().naive_local()
+    }
+}
+
+/// Only lets the inner matcher fire during a time-of-day window on selected weekdays.
+///
+/// `Event` only carries a monotonic `Instant`, so the wall-clock time is read
+/// from the configured [`Clock`] (local time by default) when `matches` is
+/// called. The window includes `start` and excludes `end`. If `end` is earlier
+/// than `start` the window crosses midnight, and the hours after midnight
+/// count towards the weekday on which the window opened, so 22:00–02:00 on
+/// Friday also covers Saturday 01:00.
+#[derive(Debug, Clone)]
+pub struct TimeWindowMatcher {
+    pub matcher: Box<dyn RuleMatcher>,
+    pub start: NaiveTime,
+    pub end: NaiveTime,
+    pub weekdays: Vec<Weekday>,
+    clock: Arc<dyn Clock>,
+}
+
+impl TimeWindowMatcher {
+    pub fn new(matcher: Box<dyn RuleMatcher>, start: NaiveTime, end: NaiveTime) -> Self {
+        Self {
+            matcher,
+            start,
+            end,
+            weekdays: vec![
+                Weekday::Mon,
+                Weekday::Tue,
+                Weekday::Wed,
+                Weekday::Thu,
+                Weekday::Fri,
+                Weekday::Sat,
+                Weekday::Sun,
+            ],
+            clock: Arc::new(LocalClock),
+        }
+    }
+
+    pub fn with_weekdays(mut self, weekdays: Vec<Weekday>) -> Self {
+        self.weekdays = weekdays;
+        self
+    }
+
+    pub fn with_clock(mut self, clock: Arc<dyn Clock>) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    fn in_window(&self, now: NaiveDateTime) -> bool {
+        let time = now.time();
+        let opened_on = if self.start <= self.end {
+            if time < self.start || time >= self.end {
+                return false;
+            }
+            now.weekday()
+        } else if time >= self.start {
+            now.weekday()
+        } else if time < self.end {
+            (now - ChronoDuration::days(1)).weekday()
+        } else {
+            return false;
+        };
+
+        self.weekdays.contains(&opened_on)
+    }
+}
+
+impl RuleMatcher for TimeWindowMatcher {
+    fn matches(&self, event: &Event) -> bool {
+        self.in_window(self.clock.now()) && self.matcher.matches(event)
+    }
+
+    fn description(&self) -> String {
+        let days = self
+            .weekdays
+            .iter()
+            .map(|d| d.to_string())
+            .collect::<Vec<_>>()
+            .join(",");
+        format!(
+            "{} between {}-{} on {}",
+            self.matcher.description(),
+            self.start.format("%H:%M"),
+            self.end.format("%H:%M"),
+            days
+        )
+    }
+
+    fn clone_box(&self) -> Box<dyn RuleMatcher> {
+        Box::new(self.clone())
+    }
+
+    fn event_kinds(&self) -> Option<Vec<EventKindTag>> {
+        self.matcher.event_kinds()
+    }
+}
+
+/// Compares event kinds by variant only. The engine builds `EventKindMatcher`s
+/// with placeholder field values (zero pids, empty names), so fields are not
+/// compared, except that a file event with a non-empty expected path must
+/// match that path exactly.
+fn matches_event_kind(expected: &EventKind, actual: &EventKind) -> bool {
+    if std::mem::discriminant(expected) != std::mem::discriminant(actual) {
+        return false;
+    }
+
+    match (expected, actual) {
+        (EventKind::FileCreated { path: p1 }, EventKind::FileCreated { path: p2 })
+        | (EventKind::FileModified { path: p1 }, EventKind::FileModified { path: p2 })
+        | (EventKind::FileDeleted { path: p1 }, EventKind::FileDeleted { path: p2 }) => {
+            p1.as_os_str().is_empty() || p1 == p2
+        }
+        _ => true,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use engine_core::event::Event;
+    use std::path::PathBuf;
+
+    #[test]
+    fn test_simple_event_kind_matcher() {
+        let matcher = EventKindMatcher {
+            kind: EventKind::TimerTick,
+        };
+        let event = Event::new(EventKind::TimerTick, "test");
+        assert!(matcher.matches(&event));
+
+        let event2 = Event::new(
+            EventKind::FileCreated {
+                path: PathBuf::from("test.txt"),
+            },
+            "test",
+        );
+        assert!(!matcher.matches(&event2));
+    }
+
+    #[test]
+    fn test_file_pattern_matcher() {
+        let matcher = FilePatternMatcher::created()
+            .with_file_pattern("*.txt")
+            .unwrap();
+
+        let event = Event::new(
+            EventKind::FileCreated {
+                path: PathBuf::from("/tmp/test.txt"),
+            },
+            "test",
+        );
+        assert!(matcher.matches(&event));
+
+        let event2 = Event::new(
+            EventKind::FileCreated {
+                path: PathBuf::from("/tmp/test.log"),
+            },
+            "test",
+        );
+        assert!(!matcher.matches(&event2));
+
+        let event3 = Event::new(
+            EventKind::FileModified {
+                path: PathBuf::from("/tmp/test.txt"),
+            },
+            "test",
+        );
+        assert!(!matcher.matches(&event3));
+    }
+
+    #[test]
+    fn test_file_pattern_matcher_renamed_uses_new_path() {
+        let matcher = FilePatternMatcher::renamed()
+            .with_path_pattern("C:/Inbox/*")
+            .unwrap();
+
+        let moved_in = Event::new(
+            EventKind::FileRenamed {
+                old_path: PathBuf::from("C:/Downloads/report.pdf"),
+                new_path: PathBuf::from("C:/Inbox/report.pdf"),
+            },
+            "test",
+        );
+        assert!(matcher.matches(&moved_in));
+
+        let moved_out = Event::new(
+            EventKind::FileRenamed {
+                old_path: PathBuf::from("C:/Inbox/report.pdf"),
+                new_path: PathBuf::from("C:/Archive/report.pdf"),
+            },
+            "test",
+        );
+        assert!(!matcher.matches(&moved_out));
+
+        let created = Event::new(
+            EventKind::FileCreated {
+                path: PathBuf::from("C:/Inbox/report.pdf"),
+            },
+            "test",
+        );
+        assert!(!matcher.matches(&created));
+    }
+
+    #[test]
+    fn test_composite_matcher_and() {
+        let matcher1 = Box::new(FilePatternMatcher::created()) as Box<dyn RuleMatcher>;
+        let matcher2 = Box::new(
+            FilePatternMatcher::created()
+                .with_file_pattern("*.log")
+                .unwrap(),
+        );
+
+        let composite = CompositeMatcher {
+            matchers: vec![matcher1, matcher2],
+            operator: MatchOperator::And,
+        };
+
+        let event = Event::new(
+            EventKind::FileCreated {
+                path: PathBuf::from("/tmp/app.log"),
+            },
+            "test",
+        );
+        assert!(composite.matches(&event));
+
+        let event2 = Event::new(
+            EventKind::FileCreated {
+                path: PathBuf::from("/tmp/app.txt"),
+            },
+            "test",
+        );
+        assert!(!composite.matches(&event2));
+    }
+
+    #[test]
+    fn test_not_matcher_inside_and() {
+        // created AND NOT *.log
+        let composite = CompositeMatcher {
+            matchers: vec![
+                Box::new(FilePatternMatcher::created()),
+                Box::new(NotMatcher::new(Box::new(
+                    FilePatternMatcher::any()
+                        .with_file_pattern("*.log")
+                        .unwrap(),
+                ))),
+            ],
+            operator: MatchOperator::And,
+        };
+
+        let txt = Event::new(
+            EventKind::FileCreated {
+                path: PathBuf::from("/tmp/app.txt"),
+            },
+            "test",
+        );
+        let log = Event::new(
+            EventKind::FileCreated {
+                path: PathBuf::from("/tmp/app.log"),
+            },
+            "test",
+        );
+        assert!(composite.matches(&txt));
+        assert!(!composite.matches(&log));
+
+        // NOT (A OR B) behaves like (NOT A) AND (NOT B)
+        let not_or = NotMatcher::new(Box::new(CompositeMatcher {
+            matchers: vec![
+                Box::new(
+                    FilePatternMatcher::any()
+                        .with_file_pattern("*.log")
+                        .unwrap(),
+                ),
+                Box::new(
+                    FilePatternMatcher::any()
+                        .with_file_pattern("*.tmp")
+                        .unwrap(),
+                ),
+            ],
+            operator: MatchOperator::Or,
+        }));
+        let and_not = CompositeMatcher {
+            matchers: vec![
+                Box::new(NotMatcher::new(Box::new(
+                    FilePatternMatcher::any()
+                        .with_file_pattern("*.log")
+                        .unwrap(),
+                ))),
+                Box::new(NotMatcher::new(Box::new(
+                    FilePatternMatcher::any()
+                        .with_file_pattern("*.tmp")
+                        .unwrap(),
+                ))),
+            ],
+            operator: MatchOperator::And,
+        };
+        for name in ["/tmp/a.log", "/tmp/a.tmp", "/tmp/a.txt"] {
+            let event = Event::new(
+                EventKind::FileModified {
+                    path: PathBuf::from(name),
+                },
+                "test",
+            );
+            assert_eq!(not_or.matches(&event), and_not.matches(&event), "{}", name);
+        }
+    }
+
+    #[test]
+    fn test_not_matcher_description_and_clone() {
+        let matcher = NotMatcher::new(Box::new(EventKindMatcher {
+            kind: EventKind::TimerTick,
+        }));
+        assert!(matcher.description().starts_with("NOT ("));
+
+        let cloned = matcher.clone_box();
+        assert_eq!(cloned.description(), matcher.description());
+        assert!(!cloned.matches(&Event::new(EventKind::TimerTick, "test")));
+    }
+
+    fn focused(title: &str) -> Event {
+        Event::new(
+            EventKind::WindowFocused {
+                hwnd: 1,
+                title: title.to_string(),
+            },
+            "test",
+        )
+    }
+
+    #[test]
+    fn test_window_matcher_title_regex() {
+        let anchored = WindowMatcher {
+            event_type: WindowEventType::Focused,
+            title_contains: None,
+            title_regex: Some(Regex::new("^Untitled - Notepad$").unwrap()),
+            process_name: None,
+        };
+        assert!(anchored.matches(&focused("Untitled - Notepad")));
+        assert!(!anchored.matches(&focused("*Untitled - Notepad")));
+        assert!(anchored.description().contains("/^Untitled - Notepad$/"));
+
+        let alternation = WindowMatcher {
+            event_type: WindowEventType::Focused,
+            title_contains: None,
+            title_regex: Some(Regex::new("(Chrome|Firefox)$").unwrap()),
+            process_name: None,
+        };
+        assert!(alternation.matches(&focused("Docs - Google Chrome")));
+        assert!(alternation.matches(&focused("Docs - Mozilla Firefox")));
+        assert!(!alternation.matches(&focused("Docs - Microsoft Edge")));
+    }
+
+    #[test]
+    fn test_window_matcher_contains_and_regex_both_required() {
+        let matcher = WindowMatcher {
+            event_type: WindowEventType::Focused,
+            title_contains: Some("report".to_string()),
+            title_regex: Some(Regex::new(r"\.xlsx").unwrap()),
+            process_name: None,
+        };
+        assert!(matcher.matches(&focused("Q3 Report.xlsx - Excel")));
+        assert!(!matcher.matches(&focused("Q3 Report.docx - Word")));
+        assert!(!matcher.matches(&focused("Budget.xlsx - Excel")));
+    }
+
+    #[test]
+    fn test_window_matcher_moved() {
+        let moved = Event::new(
+            EventKind::WindowMoved {
+                hwnd: 1,
+                x: 0,
+                y: 0,
+                width: 800,
+                height: 600,
+            },
+            "test",
+        )
+        .with_metadata("window_title", "Untitled - Notepad")
+        .with_metadata("process_name", "notepad.exe");
+
+        let matcher = WindowMatcher {
+            event_type: WindowEventType::Moved,
+            title_contains: None,
+            title_regex: None,
+            process_name: Some("notepad".to_string()),
+        };
+        assert!(matcher.matches(&moved));
+        assert!(!matcher.matches(&focused("Untitled - Notepad")));
+
+        let other_process = WindowMatcher {
+            process_name: Some("chrome".to_string()),
+            ..matcher
+        };
+        assert!(!other_process.matches(&moved));
+    }
+
+    #[test]
+    fn test_window_matcher_minimized_and_restored() {
+        let minimized = Event::new(
+            EventKind::WindowMinimized {
+                hwnd: 1,
+                title: "Inbox - Outlook".to_string(),
+            },
+            "test",
+        )
+        .with_metadata("process_name", "OUTLOOK.EXE");
+        let restored = Event::new(
+            EventKind::WindowRestored {
+                hwnd: 1,
+                title: "Inbox - Outlook".to_string(),
+            },
+            "test",
+        );
+
+        let matcher = WindowMatcher {
+            event_type: WindowEventType::Minimized,
+            title_contains: Some("inbox".to_string()),
+            title_regex: None,
+            process_name: Some("outlook".to_string()),
+        };
+        assert!(matcher.matches(&minimized));
+        assert!(!matcher.matches(&restored));
+
+        let restored_matcher = WindowMatcher {
+            event_type: WindowEventType::Restored,
+            title_contains: None,
+            title_regex: None,
+            process_name: None,
+        };
+        assert!(restored_matcher.matches(&restored));
+        assert!(!restored_matcher.matches(&minimized));
+    }
+
+    fn process_started(name: &str, command_line: &str) -> Event {
+        Event::new(
+            EventKind::ProcessStarted {
+                pid: 100,
+                parent_pid: 4,
+                name: name.to_string(),
+                path: format!("C:\\Windows\\{}", name),
+                command_line: command_line.to_string(),
+                session_id: 1,
+                user: "SYSTEM".to_string(),
+            },
+            "test",
+        )
+    }
+
+    #[test]
+    fn test_process_matcher_name_glob() {
+        let matcher = ProcessMatcher::started()
+            .with_name_pattern("note*.exe")
+            .unwrap();
+
+        assert!(matcher.matches(&process_started("notepad.exe", "notepad.exe")));
+        assert!(matcher.matches(&process_started("Notepad.EXE", "notepad.exe")));
+        assert!(!matcher.matches(&process_started("calc.exe", "calc.exe")));
+
+        let stopped = Event::new(
+            EventKind::ProcessStopped {
+                pid: 100,
+                name: "notepad.exe".to_string(),
+                exit_code: Some(0),
+            },
+            "test",
+        );
+        assert!(!matcher.matches(&stopped));
+        assert!(
+            ProcessMatcher::stopped()
+                .with_name_pattern("notepad.exe")
+                .unwrap()
+                .matches(&stopped)
+        );
+    }
+
+    #[test]
+    fn test_process_matcher_command_line() {
+        let matcher = ProcessMatcher::any().with_command_line_contains("--headless");
+
+        assert!(matcher.matches(&process_started(
+            "chrome.exe",
+            "chrome.exe --HEADLESS --disable-gpu"
+        )));
+        assert!(!matcher.matches(&process_started("chrome.exe", "chrome.exe")));
+
+        let stopped = Event::new(
+            EventKind::ProcessStopped {
+                pid: 100,
+                name: "chrome.exe".to_string(),
+                exit_code: None,
+            },
+            "test",
+        );
+        assert!(!matcher.matches(&stopped));
+        assert_eq!(
+            matcher.clone_box().description(),
+            "Process Any event with command line containing '--headless'"
+        );
+    }
+
+    #[test]
+    fn test_process_matcher_exit_code_nonzero() {
+        let stopped = |exit_code| {
+            Event::new(
+                EventKind::ProcessStopped {
+                    pid: 100,
+                    name: "backup.exe".to_string(),
+                    exit_code,
+                },
+                "test",
+            )
+        };
+        let matcher = ProcessMatcher::stopped().with_exit_code_nonzero(true);
+
+        assert!(matcher.matches(&stopped(Some(1))));
+        assert!(matcher.matches(&stopped(Some(0xC0000005))));
+        assert!(!matcher.matches(&stopped(Some(0))));
+        // Polling can't tell how a process exited
+        assert!(!matcher.matches(&stopped(None)));
+
+        // Without the flag every stop matches
+        assert!(ProcessMatcher::stopped().matches(&stopped(Some(0))));
+        assert!(ProcessMatcher::stopped().matches(&stopped(None)));
+    }
+
+    #[test]
+    fn test_process_matcher_exit_code_nonzero_ignores_starts() {
+        let matcher = ProcessMatcher::any().with_exit_code_nonzero(true);
+
+        assert!(!matcher.matches(&process_started("backup.exe", "backup.exe")));
+        assert_eq!(
+            matcher.event_kinds(),
+            Some(vec![EventKindTag::ProcessStopped])
+        );
+        assert_eq!(
+            matcher.description(),
+            "Process Any event with a non-zero exit code"
+        );
+    }
+
+    fn with_metadata(pairs: &[(&str, &str)]) -> Event {
+        let mut event = Event::new(EventKind::TimerTick, "test");
+        for (key, value) in pairs {
+            event = event.with_metadata(*key, *value);
+        }
+        event
+    }
+
+    #[test]
+    fn test_metadata_matcher_equals() {
+        let matcher = MetadataMatcher::new("exit_code", MetadataCondition::Equals("0".into()));
+        assert!(matcher.matches(&with_metadata(&[("exit_code", "0")])));
+        assert!(!matcher.matches(&with_metadata(&[("exit_code", "3")])));
+        assert!(!matcher.matches(&with_metadata(&[])));
+
+        // "exit_code != 0" expressed by negating an Exists + Equals pair
+        let crashed = CompositeMatcher {
+            matchers: vec![
+                Box::new(MetadataMatcher::new("exit_code", MetadataCondition::Exists)),
+                Box::new(NotMatcher::new(Box::new(matcher))),
+            ],
+            operator: MatchOperator::And,
+        };
+        assert!(crashed.matches(&with_metadata(&[("exit_code", "3")])));
+        assert!(!crashed.matches(&with_metadata(&[("exit_code", "0")])));
+        assert!(!crashed.matches(&with_metadata(&[])));
+    }
+
+    #[test]
+    fn test_metadata_matcher_contains() {
+        let matcher =
+            MetadataMatcher::new("process_name", MetadataCondition::Contains("chrome".into()));
+        assert!(matcher.matches(&with_metadata(&[("process_name", "chrome.exe")])));
+        assert!(!matcher.matches(&with_metadata(&[("process_name", "firefox.exe")])));
+        assert_eq!(
+            matcher.description(),
+            "Metadata 'process_name' containing 'chrome'"
+        );
+    }
+
+    #[test]
+    fn test_metadata_matcher_regex() {
+        let matcher = MetadataMatcher::new(
+            "bytes",
+            MetadataCondition::Regex(Regex::new(r"^\d{4,}$").unwrap()),
+        );
+        assert!(matcher.matches(&with_metadata(&[("bytes", "65536")])));
+        assert!(!matcher.matches(&with_metadata(&[("bytes", "512")])));
+        assert!(!matcher.matches(&with_metadata(&[("bytes", "n/a")])));
+        assert_eq!(
+            matcher.description(),
+            r"Metadata 'bytes' matching /^\d{4,}$/"
+        );
+    }
+
+    #[test]
+    fn test_metadata_matcher_exists() {
+        let matcher = MetadataMatcher::new("user", MetadataCondition::Exists);
+        assert!(matcher.matches(&with_metadata(&[("user", "")])));
+        assert!(!matcher.matches(&with_metadata(&[("session", "1")])));
+        assert_eq!(matcher.clone_box().description(), "Metadata 'user' exists");
+    }
+
+    #[test]
+    fn test_throttle_matcher_limits_rate() {
+        let matcher = ThrottleMatcher::new(
+            Box::new(EventKindMatcher {
+                kind: EventKind::TimerTick,
+            }),
+            Duration::from_millis(200),
+        );
+        let tick = Event::new(EventKind::TimerTick, "test");
+
+        let fired = (0..50).filter(|_| matcher.matches(&tick)).count();
+        assert_eq!(fired, 1);
+
+        // Non-matching events never consume the window
+        let other = Event::new(
+            EventKind::FileCreated {
+                path: PathBuf::from("a.txt"),
+            },
+            "test",
+        );
+        assert!(!matcher.matches(&other));
+
+        // A clone resets the timer
+        let cloned = matcher.clone_box();
+        assert!(cloned.matches(&tick));
+        assert!(!cloned.matches(&tick));
+
+        std::thread::sleep(Duration::from_millis(250));
+        assert!(matcher.matches(&tick));
+        assert!(!matcher.matches(&tick));
+    }
+
+    #[test]
+    fn test_dedup_matcher_suppresses_same_signature() {
+        let matcher = DedupMatcher::new(
+            Box::new(WindowMatcher {
+                event_type: WindowEventType::Focused,
+                title_contains: None,
+                title_regex: None,
+                process_name: None,
+            }),
+            Duration::from_millis(200),
+            vec!["source".into(), "kind".into(), "title".into()],
+        );
+        assert_eq!(
+            matcher.signature(&focused("Editor")),
+            "test|WindowFocused|Editor"
+        );
+
+        assert!(matcher.matches(&focused("Editor")));
+        assert!(!matcher.matches(&focused("Editor")));
+        // A different title is a different signature
+        assert!(matcher.matches(&focused("Terminal")));
+        assert!(!matcher.matches(&focused("Terminal")));
+
+        std::thread::sleep(Duration::from_millis(250));
+        assert!(matcher.matches(&focused("Editor")));
+        assert!(!matcher.matches(&focused("Editor")));
+    }
+
+    #[test]
+    fn test_dedup_matcher_signature_parts() {
+        let matcher = DedupMatcher::new(
+            Box::new(EventKindMatcher {
+                kind: EventKind::TimerTick,
+            }),
+            Duration::from_secs(60),
+            vec!["hwnd".into(), "metadata.user".into(), "missing".into()],
+        );
+        let event = focused("Editor").with_metadata("user", "alice");
+        assert_eq!(matcher.signature(&event), "1|alice|");
+
+        // Without a key only identical events share a signature
+        let matcher = DedupMatcher::new(
+            Box::new(WindowMatcher {
+                event_type: WindowEventType::Focused,
+                title_contains: None,
+                title_regex: None,
+                process_name: None,
+            }),
+            Duration::from_secs(60),
+            Vec::new(),
+        );
+        assert!(matcher.matches(&focused("Editor")));
+        assert!(matcher.matches(&focused("Terminal")));
+        assert!(!matcher.matches(&focused("Editor")));
+        assert_eq!(
+            matcher.description(),
+            format!(
+                "{} (once per identical events within 60s)",
+                matcher.matcher.description()
+            )
+        );
+    }
+
+    fn connection(remote_addr: &str, remote_port: u16, protocol: NetworkProtocol) -> Event {
+        Event::new(
+            EventKind::NetworkConnectionCreated {
+                pid: 100,
+                local_addr: "10.0.0.5".to_string(),
+                local_port: 50123,
+                remote_addr: remote_addr.to_string(),
+                remote_port,
+                protocol,
+            },
+            "test",
+        )
+    }
+
+    #[test]
+    fn test_network_matcher_port_and_protocol() {
+        let matcher = NetworkMatcher::connected()
+            .with_remote_port(443)
+            .with_protocol(NetworkProtocol::Tcp);
+
+        assert!(matcher.matches(&connection("1.1.1.1", 443, NetworkProtocol::Tcp)));
+        assert!(!matcher.matches(&connection("1.1.1.1", 80, NetworkProtocol::Tcp)));
+        assert!(!matcher.matches(&connection("1.1.1.1", 443, NetworkProtocol::Udp)));
+        assert_eq!(
+            matcher.description(),
+            "Network Connected event over Tcp on port 443"
+        );
+
+        let closed = Event::new(
+            EventKind::NetworkConnectionClosed {
+                pid: 100,
+                local_addr: "10.0.0.5".to_string(),
+                local_port: 50123,
+                remote_addr: "1.1.1.1".to_string(),
+                remote_port: 443,
+            },
+            "test",
+        );
+        assert!(!matcher.matches(&closed));
+        assert!(NetworkMatcher::any().with_remote_port(443).matches(&closed));
+        assert!(
+            !NetworkMatcher::any()
+                .with_protocol(NetworkProtocol::Tcp)
+                .matches(&closed)
+        );
+    }
+
+    #[test]
+    fn test_network_matcher_addr_prefix() {
+        let matcher = NetworkMatcher::any().with_remote_addr_prefix("192.168.");
+        assert!(matcher.matches(&connection("192.168.1.10", 53, NetworkProtocol::Udp)));
+        assert!(!matcher.matches(&connection("10.0.0.1", 53, NetworkProtocol::Udp)));
+    }
+
+    fn file_write(path: &str, bytes_written: u64) -> Event {
+        Event::new(
+            EventKind::FileIoWrite {
+                pid: 100,
+                path: PathBuf::from(path),
+                bytes_written,
+            },
+            "test",
+        )
+    }
+
+    #[test]
+    fn test_file_io_matcher_threshold_boundary() {
+        let matcher = FileIoMatcher::new(FileIoOperation::Write).with_min_bytes(1_048_576);
+
+        assert!(matcher.matches(&file_write("/data/out.bin", 1_048_576)));
+        assert!(!matcher.matches(&file_write("/data/out.bin", 1_048_575)));
+        assert!(matcher.matches(&file_write("/data/out.bin", 5_000_000)));
+
+        let read = Event::new(
+            EventKind::FileIoRead {
+                pid: 100,
+                path: PathBuf::from("/data/out.bin"),
+                bytes_read: 5_000_000,
+            },
+            "test",
+        );
+        assert!(!matcher.matches(&read));
+        assert!(
+            FileIoMatcher::new(FileIoOperation::Any)
+                .with_min_bytes(1_048_576)
+                .matches(&read)
+        );
+    }
+
+    #[test]
+    fn test_file_io_matcher_path_pattern() {
+        let matcher = FileIoMatcher::new(FileIoOperation::Any)
+            .with_path_pattern("/mnt/usb/**")
+            .unwrap();
+
+        assert!(matcher.matches(&file_write("/mnt/usb/dump/a.zip", 10)));
+        assert!(!matcher.matches(&file_write("/home/user/a.zip", 10)));
+        assert_eq!(matcher.description(), "File I/O Any event on '/mnt/usb/**'");
+    }
+
+    #[derive(Debug)]
+    struct FixedClock(Mutex<NaiveDateTime>);
+
+    impl FixedClock {
+        fn at(datetime: &str) -> Arc<Self> {
+            Arc::new(Self(Mutex::new(
+                NaiveDateTime::parse_from_str(datetime, "%Y-%m-%d %H:%M").unwrap(),
+            )))
+        }
+
+        fn set(&self, datetime: &str) {
+            *self.0.lock().unwrap() =
+                NaiveDateTime::parse_from_str(datetime, "%Y-%m-%d %H:%M").unwrap();
+        }
+    }
+
+    impl Clock for FixedClock {
+        fn now(&self) -> NaiveDateTime {
+            *self.0.lock().unwrap()
+        }
+    }
+
+    fn tick_matcher() -> Box<dyn RuleMatcher> {
+        Box::new(EventKindMatcher {
+            kind: EventKind::TimerTick,
+        })
+    }
+
+    #[test]
+    fn test_time_window_matcher_business_hours() {
+        // 2024-01-01 is a Monday
+        let clock = FixedClock::at("2024-01-01 09:00");
+        let matcher = TimeWindowMatcher::new(
+            tick_matcher(),
+            NaiveTime::from_hms_opt(9, 0, 0).unwrap(),
+            NaiveTime::from_hms_opt(17, 0, 0).unwrap(),
+        )
+        .with_weekdays(vec![
+            Weekday::Mon,
+            Weekday::Tue,
+            Weekday::Wed,
+            Weekday::Thu,
+            Weekday::Fri,
+        ])
+        .with_clock(clock.clone());
+        let tick = Event::new(EventKind::TimerTick, "test");
+
+        assert!(matcher.matches(&tick));
+        clock.set("2024-01-01 16:59");
+        assert!(matcher.matches(&tick));
+        clock.set("2024-01-01 17:00");
+        assert!(!matcher.matches(&tick));
+        clock.set("2024-01-01 08:59");
+        assert!(!matcher.matches(&tick));
+
+        // Saturday, inside the hours but not on a selected weekday
+        clock.set("2024-01-06 12:00");
+        assert!(!matcher.matches(&tick));
+    }
+
+    #[test]
+    fn test_time_window_matcher_crosses_midnight() {
+        // 2024-01-05 is a Friday
+        let clock = FixedClock::at("2024-01-05 23:30");
+        let matcher = TimeWindowMatcher::new(
+            tick_matcher(),
+            NaiveTime::from_hms_opt(22, 0, 0).unwrap(),
+            NaiveTime::from_hms_opt(2, 0, 0).unwrap(),
+        )
+        .with_weekdays(vec![Weekday::Fri])
+        .with_clock(clock.clone());
+        let tick = Event::new(EventKind::TimerTick, "test");
+
+        assert!(matcher.matches(&tick));
+        // Saturday 01:00 still belongs to Friday's window
+        clock.set("2024-01-06 01:00");
+        assert!(matcher.matches(&tick));
+        clock.set("2024-01-06 02:00");
+        assert!(!matcher.matches(&tick));
+        clock.set("2024-01-05 12:00");
+        assert!(!matcher.matches(&tick));
+        // Friday 01:00 belongs to Thursday's window, which is not selected
+        clock.set("2024-01-05 01:00");
+        assert!(!matcher.matches(&tick));
+
+        assert_eq!(
+            matcher.description(),
+            "Event kind matches TimerTick between 22:00-02:00 on Fri"
+        );
+    }
+
+    #[test]
+    fn test_event_kind_matcher_ignores_placeholder_fields() {
+        let matcher = EventKindMatcher {
+            kind: EventKind::ProcessStarted {
+                pid: 0,
+                parent_pid: 0,
+                name: String::new(),
+                path: String::new(),
+                command_line: String::new(),
+                session_id: 0,
+                user: String::new(),
+            },
+        };
+        assert!(matcher.matches(&process_started("notepad.exe", "notepad.exe")));
+
+        let stopped = Event::new(
+            EventKind::ProcessStopped {
+                pid: 100,
+                name: "notepad.exe".to_string(),
+                exit_code: Some(0),
+            },
+            "test",
+        );
+        assert!(!matcher.matches(&stopped));
+    }
+
+    #[test]
+    fn test_event_kind_matcher_file_paths() {
+        let any_path = EventKindMatcher {
+            kind: EventKind::FileCreated {
+                path: PathBuf::new(),
+            },
+        };
+        let exact_path = EventKindMatcher {
+            kind: EventKind::FileCreated {
+                path: PathBuf::from("/tmp/a.txt"),
+            },
+        };
+        let created = |path: &str| {
+            Event::new(
+                EventKind::FileCreated {
+                    path: PathBuf::from(path),
+                },
+                "test",
+            )
+        };
+
+        assert!(any_path.matches(&created("/tmp/a.txt")));
+        assert!(any_path.matches(&created("/var/b.log")));
+        assert!(exact_path.matches(&created("/tmp/a.txt")));
+        assert!(!exact_path.matches(&created("/var/b.log")));
+    }
+
+    #[test]
+    fn test_rule_with_disabled() {
+        let matcher = Box::new(EventKindMatcher {
+            kind: EventKind::TimerTick,
+        });
+        let rule = Rule::new("test_rule", matcher).with_enabled(false);
+
+        let event = Event::new(EventKind::TimerTick, "test");
+        assert!(!rule.matches(&event));
+    }
+
+    #[test]
     fn test_rule_with_description() {
         let matcher = Box::new(EventKindMatcher {
             kind: EventKind::TimerTick,
@@ -446,4 +2115,133 @@ mod tests {
 
         assert_eq!(rule.description, Some("A test rule".to_string()));
     }
+
+    fn kind_rule(name: &str, kind: EventKind) -> Rule {
+        Rule::new(name, Box::new(EventKindMatcher { kind }))
+    }
+
+    fn mixed_rule_set(mut set: RuleSet) -> RuleSet {
+        set.push(kind_rule(
+            "file",
+            EventKind::FileCreated {
+                path: PathBuf::new(),
+            },
+        ));
+        set.push(kind_rule("timer", EventKind::TimerTick));
+        set.push(Rule::new(
+            "any_with_user",
+            Box::new(MetadataMatcher::new("user", MetadataCondition::Exists)),
+        ));
+        set.push(Rule::new(
+            "process_start",
+            Box::new(ProcessMatcher {
+                event_type: ProcessEventType::Started,
+                name_pattern: None,
+                command_line_contains: None,
+                exit_code_nonzero: false,
+            }),
+        ));
+        set.push(Rule::new(
+            "timer_or_file",
+            Box::new(CompositeMatcher {
+                matchers: vec![
+                    Box::new(EventKindMatcher {
+                        kind: EventKind::TimerTick,
+                    }),
+                    Box::new(EventKindMatcher {
+                        kind: EventKind::FileCreated {
+                            path: PathBuf::new(),
+                        },
+                    }),
+                ],
+                operator: MatchOperator::Or,
+            }),
+        ));
+        set
+    }
+
+    #[test]
+    fn test_composite_event_kinds() {
+        let timer = || -> Box<dyn RuleMatcher> {
+            Box::new(EventKindMatcher {
+                kind: EventKind::TimerTick,
+            })
+        };
+        let any = || -> Box<dyn RuleMatcher> {
+            Box::new(MetadataMatcher::new("user", MetadataCondition::Exists))
+        };
+
+        let and = CompositeMatcher {
+            matchers: vec![any(), timer()],
+            operator: MatchOperator::And,
+        };
+        assert_eq!(and.event_kinds(), Some(vec![EventKindTag::TimerTick]));
+
+        let or = CompositeMatcher {
+            matchers: vec![timer(), any()],
+            operator: MatchOperator::Or,
+        };
+        assert_eq!(or.event_kinds(), None);
+
+        assert_eq!(NotMatcher::new(timer()).event_kinds(), None);
+    }
+
+    #[test]
+    fn test_rule_set_index_skips_irrelevant_rules() {
+        let indexed = mixed_rule_set(RuleSet::new());
+        let unindexed = mixed_rule_set(RuleSet::unindexed());
+
+        let events = vec![
+            Event::new(EventKind::TimerTick, "test"),
+            Event::new(
+                EventKind::FileCreated {
+                    path: PathBuf::from("/tmp/a.txt"),
+                },
+                "test",
+            ),
+            Event::new(
+                EventKind::ProcessStarted {
+                    pid: 1,
+                    parent_pid: 0,
+                    name: "app.exe".into(),
+                    path: String::new(),
+                    command_line: String::new(),
+                    session_id: 0,
+                    user: String::new(),
+                },
+                "test",
+            )
+            .with_metadata("user", "alice"),
+            Event::new(EventKind::WindowDestroyed { hwnd: 1 }, "test"),
+        ];
+
+        let mut indexed_evaluations = 0;
+        let mut unindexed_evaluations = 0;
+        for event in &events {
+            let fast = indexed.candidates(&event.kind);
+            let slow = unindexed.candidates(&event.kind);
+            indexed_evaluations += fast.len();
+            unindexed_evaluations += slow.len();
+
+            let names = |rules: Vec<&Rule>| -> Vec<String> {
+                rules
+                    .into_iter()
+                    .filter(|rule| rule.matches(event))
+                    .map(|rule| rule.name.clone())
+                    .collect()
+            };
+            assert_eq!(names(fast), names(slow));
+        }
+
+        assert_eq!(unindexed_evaluations, events.len() * indexed.len());
+        // timer: 3, file: 3, process: 2, window: only the metadata rule
+        assert_eq!(indexed_evaluations, 9);
+
+        let timer_rules: Vec<_> = indexed
+            .candidates(&EventKind::TimerTick)
+            .into_iter()
+            .map(|rule| rule.name.as_str())
+            .collect();
+        assert_eq!(timer_rules, vec!["timer", "any_with_user", "timer_or_file"]);
+    }
 }